@@ -190,6 +190,8 @@ fn substitute_macros(input: &str) -> String {
         ("[RUNNING]", "     Running"),
         ("[COMPILING]", "   Compiling"),
         ("[CHECKING]", "    Checking"),
+        ("[COMPARING]", "   Comparing"),
+        ("[COMPATIBLE]", "  Compatible"),
         ("[COMPLETED]", "   Completed"),
         ("[CREATED]", "     Created"),
         ("[CREATING]", "    Creating"),
@@ -218,6 +220,8 @@ fn substitute_macros(input: &str) -> String {
         ("[UPLOADING]", "   Uploading"),
         ("[UPLOADED]", "    Uploaded"),
         ("[VERIFYING]", "   Verifying"),
+        ("[VERIFIED]", "    Verified"),
+        ("[VENDORING]", "   Vendoring"),
         ("[ARCHIVING]", "   Archiving"),
         ("[INSTALLING]", "  Installing"),
         ("[REPLACING]", "   Replacing"),
@@ -229,12 +233,16 @@ fn substitute_macros(input: &str) -> String {
         ("[IGNORED]", "     Ignored"),
         ("[INSTALLED]", "   Installed"),
         ("[REPLACED]", "    Replaced"),
+        ("[REUSING]", "     Reusing"),
+        ("[STORING]", "     Storing"),
         ("[BUILDING]", "    Building"),
         ("[LOGIN]", "       Login"),
         ("[LOGOUT]", "      Logout"),
         ("[YANK]", "        Yank"),
         ("[OWNER]", "       Owner"),
         ("[MIGRATING]", "   Migrating"),
+        ("[MIRRORING]", "   Mirroring"),
+        ("[MIRRORED]", "    Mirrored"),
         ("[EXECUTABLE]", "  Executable"),
         ("[SKIPPING]", "    Skipping"),
         ("[WAITING]", "     Waiting"),
@@ -114,6 +114,7 @@ pub fn resolve_with_global_context_raw(
                     QueryKind::Exact => dep.matches(summary),
                     QueryKind::Alternatives => true,
                     QueryKind::Normalized => true,
+                    QueryKind::RejectedVersions => false,
                 };
                 if matched {
                     self.used.insert(summary.package_id());
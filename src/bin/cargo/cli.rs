@@ -445,6 +445,9 @@ fn configure_gctx(
 
 enum Exec {
     Builtin(commands::Exec),
+    /// `cargo script`, which leaves the name to an external `cargo-script`
+    /// unless `-Zscript` is passed.
+    Script,
     Manifest(String),
     External(String),
 }
@@ -461,6 +464,8 @@ impl Exec {
     fn infer(cmd: &str) -> CargoResult<Self> {
         if let Some(exec) = commands::builtin_exec(cmd) {
             Ok(Self::Builtin(exec))
+        } else if cmd == "script" {
+            Ok(Self::Script)
         } else if commands::run::is_manifest_command(cmd) {
             Ok(Self::Manifest(cmd.to_owned()))
         } else {
@@ -472,6 +477,23 @@ impl Exec {
     fn exec(self, gctx: &mut GlobalContext, subcommand_args: &ArgMatches) -> CliResult {
         match self {
             Self::Builtin(exec) => exec(gctx, subcommand_args),
+            Self::Script => {
+                let script = gctx.cli_unstable().script;
+                if !script && super::find_external_subcommand(gctx, "script").is_some() {
+                    return Self::External("script".to_owned()).exec(gctx, subcommand_args);
+                }
+                gctx.cli_unstable()
+                    .fail_if_stable_command(gctx, "script", 12207, "script", script)?;
+                let mut args = vec![OsString::from("script")];
+                args.extend(
+                    subcommand_args
+                        .get_many::<OsString>("")
+                        .unwrap_or_default()
+                        .cloned(),
+                );
+                let args = commands::script::cli().try_get_matches_from(args)?;
+                commands::script::exec(gctx, &args)
+            }
             Self::Manifest(cmd) => {
                 let ext_path = super::find_external_subcommand(gctx, &cmd);
                 if !gctx.cli_unstable().script && ext_path.is_some() {
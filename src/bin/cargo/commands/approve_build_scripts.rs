@@ -0,0 +1,37 @@
+use crate::command_prelude::*;
+use cargo::ops::{self, ApproveBuildScriptsOptions};
+
+pub fn cli() -> Command {
+    subcommand("approve-build-scripts")
+        .about("Allow dependencies to run build scripts and proc-macros (unstable)")
+        .arg(
+            Arg::new("spec")
+                .value_name("SPEC")
+                .action(ArgAction::Append)
+                .help("Packages to approve (all that need to be approved by default)"),
+        )
+        .arg_dry_run("Show what would be approved without recording it")
+        .arg_silent_suggestion()
+        .arg_manifest_path()
+}
+
+pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
+    gctx.cli_unstable().fail_if_stable_command(
+        gctx,
+        "approve-build-scripts",
+        None,
+        "build-script-allowlist",
+        gctx.cli_unstable().build_script_allowlist,
+    )?;
+    let ws = args.workspace(gctx)?;
+    let opts = ApproveBuildScriptsOptions {
+        specs: args
+            .get_many::<String>("spec")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        dry_run: args.dry_run(),
+    };
+    ops::approve_build_scripts(&ws, &opts)?;
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::command_prelude::*;
+use anyhow::bail;
+use cargo::core::dependency::DepKind;
+use cargo::ops::tree::{self, EdgeKind};
+use cargo::ops::{self, AuditPolicyOptions};
+use cargo::util::CargoResult;
+use std::collections::HashSet;
+
+pub fn cli() -> Command {
+    subcommand("audit-policy")
+        .about("Check the dependency graph against the `[policy]` configuration (unstable)")
+        .arg_silent_suggestion()
+        .arg(
+            multi_opt(
+                "edges",
+                "KINDS",
+                "The kinds of dependencies to check (normal, build, dev, all)",
+            )
+            .short('e'),
+        )
+        .arg(
+            opt("message-format", "Output representation")
+                .value_name("FMT")
+                .value_parser(["human", "json"])
+                .default_value("human"),
+        )
+        .arg_package_spec_no_all(
+            "Package to check the dependencies of",
+            "Check the dependencies of all packages in the workspace",
+            "Exclude specific workspace members",
+        )
+        .arg_features()
+        .arg_target_triple(
+            "Only check dependencies for the given target-triple (default all targets)",
+        )
+        .arg_manifest_path()
+}
+
+pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
+    gctx.cli_unstable().fail_if_stable_command(
+        gctx,
+        "audit-policy",
+        None,
+        "unstable-options",
+        gctx.cli_unstable().unstable_options,
+    )?;
+    // The audit only looks at what is locked and cached locally.
+    gctx.set_offline();
+
+    let targets = args.targets()?;
+    let target = if targets.is_empty() {
+        tree::Target::All
+    } else {
+        tree::Target::from_cli(targets)
+    };
+
+    let edge_kinds = parse_edge_kinds(args)?;
+
+    let ws = args.workspace(gctx)?;
+    let opts = AuditPolicyOptions {
+        cli_features: args.cli_features()?,
+        packages: args.packages_from_flags()?,
+        target,
+        edge_kinds,
+        json: args.get_one::<String>("message-format").map(String::as_str) == Some("json"),
+    };
+    ops::audit_policy(&ws, &opts)?;
+    Ok(())
+}
+
+/// Parses the `--edges` option, following all kinds of dependencies by default.
+fn parse_edge_kinds(args: &ArgMatches) -> CargoResult<HashSet<EdgeKind>> {
+    let mut edge_kinds = HashSet::new();
+    for kind in args
+        .get_many::<String>("edges")
+        .into_iter()
+        .flatten()
+        .flat_map(|e| e.split(','))
+    {
+        match kind {
+            "normal" => edge_kinds.insert(EdgeKind::Dep(DepKind::Normal)),
+            "build" => edge_kinds.insert(EdgeKind::Dep(DepKind::Build)),
+            "dev" => edge_kinds.insert(EdgeKind::Dep(DepKind::Development)),
+            "all" => {
+                edge_kinds.extend([
+                    EdgeKind::Dep(DepKind::Normal),
+                    EdgeKind::Dep(DepKind::Build),
+                    EdgeKind::Dep(DepKind::Development),
+                ]);
+                true
+            }
+            k => bail!(
+                "unknown edge kind `{k}`, valid values are \"normal\", \"build\", \"dev\", or \"all\""
+            ),
+        };
+    }
+    if edge_kinds.is_empty() {
+        edge_kinds.extend([
+            EdgeKind::Dep(DepKind::Normal),
+            EdgeKind::Dep(DepKind::Build),
+            EdgeKind::Dep(DepKind::Development),
+        ]);
+    }
+    Ok(edge_kinds)
+}
@@ -37,6 +37,7 @@ pub fn cli() -> Command {
         .arg_out_dir()
         .arg_build_plan()
         .arg_unit_graph()
+        .arg_explain_rebuild()
         .arg_timings()
         .arg_manifest_path()
         .arg_ignore_rust_version()
@@ -3,6 +3,7 @@ use crate::util::cache_lock::CacheLockMode;
 use cargo::core::gc::Gc;
 use cargo::core::gc::{parse_human_size, parse_time_span, GcOpts};
 use cargo::core::global_cache_tracker::GlobalCacheTracker;
+use cargo::core::resolver::CliFeatures;
 use cargo::ops::CleanContext;
 use cargo::ops::{self, CleanOptions};
 use cargo::util::print_available_packages;
@@ -20,6 +21,23 @@ pub fn cli() -> Command {
         .arg_target_dir()
         .arg_manifest_path()
         .arg_dry_run("Display what would be deleted without deleting anything")
+        .arg(
+            flag(
+                "stale",
+                "Only clean artifacts not used by the current workspace (unstable)",
+            )
+            .conflicts_with_all(["package", "doc"]),
+        )
+        .arg(
+            opt(
+                "max-age",
+                "Only clean artifacts not used since the given age (unstable)",
+            )
+            .value_name("DURATION")
+            .value_parser(parse_time_span)
+            .conflicts_with_all(["package", "doc"]),
+        )
+        .arg_features()
         .args_conflicts_with_subcommands(true)
         .subcommand(
             subcommand("gc")
@@ -142,6 +160,24 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
         print_available_packages(&ws)?;
     }
 
+    let stale = args.flag("stale");
+    let max_age = args.get_one::<Duration>("max-age").copied();
+    if stale || max_age.is_some() {
+        let flag = if stale { "--stale" } else { "--max-age" };
+        gctx.cli_unstable().fail_if_stable_opt_custom_z(
+            flag,
+            None,
+            "clean-stale",
+            gctx.cli_unstable().clean_stale,
+        )?;
+    }
+    let cli_features = args.cli_features()?;
+    if !stale && cli_features != CliFeatures::new_all(false) {
+        return Err(
+            anyhow::format_err!("feature selection flags can only be used with `--stale`").into(),
+        );
+    }
+
     let opts = CleanOptions {
         gctx,
         spec: values(args, "package"),
@@ -150,6 +186,9 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
         profile_specified: args.contains_id("profile") || args.flag("release"),
         doc: args.flag("doc"),
         dry_run: args.dry_run(),
+        stale,
+        cli_features,
+        max_age,
     };
     ops::clean(&ws, &opts)?;
     Ok(())
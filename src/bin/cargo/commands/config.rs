@@ -28,6 +28,9 @@ pub fn cli() -> Command {
                         .default_value("yes"),
                 ),
         )
+        .subcommand(
+            subcommand("check").about("Check the configuration for keys that Cargo doesn't know"),
+        )
 }
 
 pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
@@ -48,6 +51,9 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
             };
             cargo_config::get(gctx, &opts)?;
         }
+        Some(("check", _args)) => {
+            cargo_config::check(gctx)?;
+        }
         Some((cmd, _)) => {
             unreachable!("unexpected command {}", cmd)
         }
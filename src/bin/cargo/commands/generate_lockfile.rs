@@ -7,6 +7,20 @@ pub fn cli() -> Command {
         .about("Generate the lockfile for a package")
         .arg_silent_suggestion()
         .arg_manifest_path()
+        .arg(flag(
+            "lock",
+            "Keep the lockfile of a script next to it (unstable)",
+        ))
+        .arg(
+            opt(
+                "merge",
+                "Merge the lockfiles of a merge conflict into OURS, as a git merge driver (unstable)",
+            )
+            .value_names(["BASE", "OURS", "THEIRS"])
+            .num_args(3)
+            .value_parser(clap::value_parser!(std::path::PathBuf))
+            .conflicts_with("lock"),
+        )
         .arg_ignore_rust_version_with_help(
             "Ignore `rust-version` specification in packages (unstable)",
         )
@@ -24,7 +38,36 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
             gctx.cli_unstable().msrv_policy,
         )?;
     }
-    let ws = args.workspace(gctx)?;
+    let mut ws = args.workspace(gctx)?;
+    if args.flag("lock") {
+        gctx.cli_unstable().fail_if_stable_opt_custom_z(
+            "--lock",
+            12207,
+            "script",
+            gctx.cli_unstable().script,
+        )?;
+        if !ws.root_maybe().is_embedded() {
+            return Err(anyhow::format_err!(
+                "the `--lock` flag can only be used with a script, \
+                 whose lockfile is otherwise kept in its target directory"
+            )
+            .into());
+        }
+        ws.set_script_lock(true);
+    }
+    if let Some(merge) = args.get_many::<std::path::PathBuf>("merge") {
+        gctx.cli_unstable().fail_if_stable_opt_custom_z(
+            "--merge",
+            None,
+            "lockfile-merge",
+            gctx.cli_unstable().lockfile_merge,
+        )?;
+        let [base, ours, theirs] = merge.collect::<Vec<_>>()[..] else {
+            unreachable!("clap requires 3 values");
+        };
+        ops::merge_lockfiles(&ws, base, ours, theirs)?;
+        return Ok(());
+    }
     ops::generate_lockfile(&ws)?;
     Ok(())
 }
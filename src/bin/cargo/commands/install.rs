@@ -67,6 +67,31 @@ pub fn cli() -> Command {
                 .value_name("PATH")
                 .conflicts_with_all(&["git", "index", "registry"]),
         )
+        .arg(
+            opt(
+                "from",
+                "Install the tools listed in a file, pinned by a lockfile next to it (unstable)",
+            )
+            .value_name("FILE")
+            .value_parser(clap::value_parser!(std::path::PathBuf))
+            .conflicts_with_all(&[
+                "crate",
+                "version",
+                "git",
+                "path",
+                "index",
+                "registry",
+                "list",
+                "no-track",
+                "features",
+                "all-features",
+                "no-default-features",
+                "bin",
+                "bins",
+                "example",
+                "examples",
+            ]),
+        )
         .arg(opt("root", "Directory to install packages into").value_name("DIR"))
         .arg(flag("force", "Force overwriting existing crates or binaries").short('f'))
         .arg(flag("no-track", "Do not save tracking information"))
@@ -198,7 +223,15 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
     compile_opts.build_config.requested_profile =
         args.get_profile_name(gctx, "release", ProfileChecking::Custom)?;
 
-    if args.flag("list") {
+    if let Some(from) = args.get_one::<std::path::PathBuf>("from") {
+        gctx.cli_unstable().fail_if_stable_opt_custom_z(
+            "--from",
+            None,
+            "install-from",
+            gctx.cli_unstable().install_from,
+        )?;
+        ops::install_from(gctx, root, from, &compile_opts, args.flag("force"))?;
+    } else if args.flag("list") {
         ops::install_list(root, gctx)?;
     } else {
         ops::install(
@@ -3,6 +3,8 @@ use crate::command_prelude::*;
 pub fn builtin() -> Vec<Command> {
     vec![
         add::cli(),
+        approve_build_scripts::cli(),
+        audit_policy::cli(),
         bench::cli(),
         build::cli(),
         check::cli(),
@@ -26,6 +28,7 @@ pub fn builtin() -> Vec<Command> {
         pkgid::cli(),
         publish::cli(),
         read_manifest::cli(),
+        registry::cli(),
         remove::cli(),
         report::cli(),
         run::cli(),
@@ -48,6 +51,8 @@ pub type Exec = fn(&mut GlobalContext, &ArgMatches) -> CliResult;
 pub fn builtin_exec(cmd: &str) -> Option<Exec> {
     let f = match cmd {
         "add" => add::exec,
+        "approve-build-scripts" => approve_build_scripts::exec,
+        "audit-policy" => audit_policy::exec,
         "bench" => bench::exec,
         "build" => build::exec,
         "check" => check::exec,
@@ -71,6 +76,7 @@ pub fn builtin_exec(cmd: &str) -> Option<Exec> {
         "pkgid" => pkgid::exec,
         "publish" => publish::exec,
         "read-manifest" => read_manifest::exec,
+        "registry" => registry::exec,
         "remove" => remove::exec,
         "report" => report::exec,
         "run" => run::exec,
@@ -91,6 +97,8 @@ pub fn builtin_exec(cmd: &str) -> Option<Exec> {
 }
 
 pub mod add;
+pub mod approve_build_scripts;
+pub mod audit_policy;
 pub mod bench;
 pub mod build;
 pub mod check;
@@ -114,11 +122,13 @@ pub mod package;
 pub mod pkgid;
 pub mod publish;
 pub mod read_manifest;
+pub mod registry;
 pub mod remove;
 pub mod report;
 pub mod run;
 pub mod rustc;
 pub mod rustdoc;
+pub mod script;
 pub mod search;
 pub mod test;
 pub mod tree;
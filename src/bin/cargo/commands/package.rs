@@ -24,6 +24,10 @@ pub fn cli() -> Command {
             "allow-dirty",
             "Allow dirty working directories to be packaged",
         ))
+        .arg(flag(
+            "semver-check",
+            "Check the changes to the public API against SemVer (unstable)",
+        ))
         .arg_silent_suggestion()
         .arg_package_spec_no_all(
             "Package(s) to assemble",
@@ -41,6 +45,14 @@ pub fn cli() -> Command {
 }
 
 pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
+    if args.flag("semver-check") {
+        gctx.cli_unstable().fail_if_stable_opt_custom_z(
+            "--semver-check",
+            None,
+            "semver-check",
+            gctx.cli_unstable().semver_check,
+        )?;
+    }
     let ws = args.workspace(gctx)?;
     if ws.root_maybe().is_embedded() {
         return Err(anyhow::format_err!(
@@ -64,6 +76,7 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
             jobs: args.jobs()?,
             keep_going: args.keep_going(),
             cli_features: args.cli_features()?,
+            semver_check: args.flag("semver-check"),
         },
     )?;
 
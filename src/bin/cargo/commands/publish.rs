@@ -17,8 +17,16 @@ pub fn cli() -> Command {
             "allow-dirty",
             "Allow dirty working directories to be packaged",
         ))
+        .arg(flag(
+            "semver-check",
+            "Check the changes to the public API against SemVer (unstable)",
+        ))
         .arg_silent_suggestion()
-        .arg_package("Package to publish")
+        .arg_package_spec_no_all(
+            "Package(s) to publish",
+            "Publish all packages in the workspace (unstable)",
+            "Don't publish specified packages (unstable)",
+        )
         .arg_features()
         .arg_parallel()
         .arg_target_triple("Build for the target triple")
@@ -31,6 +39,29 @@ pub fn cli() -> Command {
 
 pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
     let reg_or_index = args.registry_or_index(gctx)?;
+    if args.flag("semver-check") {
+        gctx.cli_unstable().fail_if_stable_opt_custom_z(
+            "--semver-check",
+            None,
+            "semver-check",
+            gctx.cli_unstable().semver_check,
+        )?;
+    }
+    let flag = if args.flag("workspace") {
+        Some("--workspace")
+    } else if args.contains_id("exclude") {
+        Some("--exclude")
+    } else {
+        None
+    };
+    if let Some(flag) = flag {
+        gctx.cli_unstable().fail_if_stable_opt_custom_z(
+            flag,
+            None,
+            "package-workspace",
+            gctx.cli_unstable().package_workspace,
+        )?;
+    }
     let ws = args.workspace(gctx)?;
     if ws.root_maybe().is_embedded() {
         return Err(anyhow::format_err!(
@@ -56,6 +87,7 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
             keep_going: args.keep_going(),
             dry_run: args.dry_run(),
             cli_features: args.cli_features()?,
+            semver_check: args.flag("semver-check"),
         },
     )?;
     Ok(())
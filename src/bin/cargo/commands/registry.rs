@@ -0,0 +1,118 @@
+use crate::command_prelude::*;
+use cargo::ops;
+use cargo::util::IntoUrl;
+use cargo_util::paths;
+use std::path::PathBuf;
+
+pub fn cli() -> Command {
+    subcommand("registry")
+        .about("Work with the contents of registries (unstable)")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            subcommand("mirror")
+                .about(
+                    "Copy the packages of the lockfile, with their index entries, to a directory",
+                )
+                .arg(
+                    Arg::new("path")
+                        .action(ArgAction::Set)
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("Where to write the mirror"),
+                )
+                .arg(
+                    multi_opt(
+                        "package",
+                        "SPEC",
+                        "Mirror the given packages and their dependencies instead of the lockfile",
+                    )
+                    .short('p'),
+                )
+                .arg(opt("url", "The URL the mirror will be served from").value_name("URL"))
+                .arg_index("Registry index URL to mirror")
+                .arg_registry("Registry to mirror")
+                .arg_manifest_path(),
+        )
+        .subcommand(
+            subcommand("serve")
+                .about("Serve a registry directory over the sparse HTTP protocol")
+                .arg(
+                    Arg::new("path")
+                        .action(ArgAction::Set)
+                        .required(true)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .help("The registry directory to serve"),
+                )
+                .arg(
+                    opt("address", "The address to listen on")
+                        .value_name("ADDR")
+                        .default_value("127.0.0.1:8080"),
+                )
+                .arg(opt("url", "The URL clients reach the server at").value_name("URL"))
+                .arg(
+                    opt(
+                        "token-file",
+                        "Require the token in the given file to access the registry",
+                    )
+                    .value_name("PATH")
+                    .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+}
+
+pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
+    gctx.cli_unstable().fail_if_stable_command(
+        gctx,
+        "registry",
+        None,
+        "unstable-options",
+        gctx.cli_unstable().unstable_options,
+    )?;
+    match args.subcommand() {
+        Some(("mirror", args)) => {
+            let opts = ops::MirrorOptions {
+                destination: args.get_one::<PathBuf>("path").unwrap().clone(),
+                reg_or_index: args.registry_or_index(gctx)?,
+                url: args
+                    .get_one::<String>("url")
+                    .map(|url| url.into_url())
+                    .transpose()?,
+            };
+            let specs: Vec<String> = args
+                .get_many::<String>("package")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+            if specs.is_empty() {
+                let ws = args.workspace(gctx)?;
+                ops::mirror(&ws, &opts)?;
+            } else {
+                ops::mirror_packages(gctx, &specs, &opts)?;
+            }
+        }
+        Some(("serve", args)) => {
+            let token = args
+                .get_one::<PathBuf>("token-file")
+                .map(|path| paths::read(path).map(|token| token.trim().to_string()))
+                .transpose()?;
+            let opts = ops::ServeOptions {
+                root: args.get_one::<PathBuf>("path").unwrap().clone(),
+                address: args.get_one::<String>("address").unwrap().clone(),
+                url: args
+                    .get_one::<String>("url")
+                    .map(|url| url.into_url())
+                    .transpose()?,
+                token,
+            };
+            ops::serve(gctx, &opts)?;
+        }
+        Some((cmd, _)) => {
+            unreachable!("unexpected command {}", cmd)
+        }
+        None => {
+            unreachable!("unexpected command")
+        }
+    }
+    Ok(())
+}
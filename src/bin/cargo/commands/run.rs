@@ -40,6 +40,7 @@ pub fn cli() -> Command {
         .arg_manifest_path()
         .arg_ignore_rust_version()
         .arg_unit_graph()
+        .arg_explain_rebuild()
         .arg_timings()
         .after_help(color_print::cstr!(
             "Run `<cyan,bold>cargo help run</>` for more detailed information.\n"
@@ -183,6 +184,7 @@ pub fn exec_manifest_command(gctx: &mut GlobalContext, cmd: &str, args: &[OsStri
     if gctx.cli_unstable().avoid_dev_deps {
         ws.set_require_optional_deps(false);
     }
+    cargo::ops::record_script(&ws)?;
 
     let mut compile_opts =
         cargo::ops::CompileOptions::new(gctx, cargo::core::compiler::CompileMode::Build)?;
@@ -50,6 +50,7 @@ pub fn cli() -> Command {
         .arg_target_triple("Target triple which compiles will be for")
         .arg_target_dir()
         .arg_unit_graph()
+        .arg_explain_rebuild()
         .arg_timings()
         .arg_manifest_path()
         .arg_ignore_rust_version()
@@ -0,0 +1,28 @@
+use crate::command_prelude::*;
+use cargo::ops;
+
+pub fn cli() -> Command {
+    subcommand("script")
+        .about("Manage the build directories of scripts (unstable)")
+        .arg(flag("list", "List the build directories of scripts"))
+        .arg(flag(
+            "clean",
+            "Remove the build directories of scripts that were deleted",
+        ))
+        .group(
+            clap::ArgGroup::new("action")
+                .args(["list", "clean"])
+                .required(true),
+        )
+        .arg_dry_run("Display what would be deleted without deleting anything")
+        .arg_silent_suggestion()
+}
+
+pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
+    if args.flag("list") {
+        ops::script_list(gctx)?;
+    } else {
+        ops::script_clean(gctx, args.dry_run())?;
+    }
+    Ok(())
+}
@@ -77,6 +77,15 @@ pub fn cli() -> Command {
                 .short('f')
                 .default_value("{p}"),
         )
+        .arg(
+            opt(
+                "output-format",
+                "Output the whole graph in another format (unstable)",
+            )
+            .value_name("FMT")
+            .value_parser(["text", "dot", "json"])
+            .default_value("text"),
+        )
         .arg(
             // Backwards compatibility with old cargo-tree.
             flag("version", "Print version info and exit")
@@ -209,6 +218,14 @@ subtree of the package given to -p.\n\
             Charset::Ascii => gctx.shell().set_unicode(false)?,
         }
     }
+    let output_format = args.get_one::<String>("output-format").unwrap();
+    let output_format =
+        tree::OutputFormat::from_str(output_format).map_err(|e| anyhow::anyhow!("{}", e))?;
+    if output_format != tree::OutputFormat::Text {
+        gctx.cli_unstable()
+            .fail_if_stable_opt("--output-format", None)?;
+    }
+
     let opts = tree::TreeOptions {
         cli_features: args.cli_features()?,
         packages,
@@ -223,6 +240,7 @@ subtree of the package given to -p.\n\
         graph_features,
         max_display_depth: args.value_of_u32("depth")?.unwrap_or(u32::MAX),
         no_proc_macro,
+        output_format,
     };
 
     if opts.graph_features && opts.duplicates {
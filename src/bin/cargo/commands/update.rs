@@ -35,6 +35,14 @@ pub fn cli() -> Command {
                 .value_name("PRECISE")
                 .requires("package-group"),
         )
+        .arg(
+            flag(
+                "explain",
+                "Explain why [SPEC]... aren't at their latest version, without updating them (unstable)",
+            )
+            .requires("package-group")
+            .conflicts_with_all(["precise", "recursive", "workspace"]),
+        )
         .arg_silent_suggestion()
         .arg(
             flag("workspace", "Only update the workspace packages")
@@ -81,6 +89,17 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
         }
     }
 
+    if args.flag("explain") {
+        gctx.cli_unstable().fail_if_stable_opt_custom_z(
+            "--explain",
+            None,
+            "update-explain",
+            gctx.cli_unstable().update_explain,
+        )?;
+        ops::explain_update(&ws, &to_update)?;
+        return Ok(());
+    }
+
     let update_opts = UpdateOptions {
         recursive: args.flag("recursive"),
         precise: args.get_one::<String>("precise").map(String::as_str),
@@ -32,6 +32,26 @@ pub fn cli() -> Command {
             "versioned-dirs",
             "Always include version in subdir name",
         ))
+        .arg(flag(
+            "incremental",
+            "Only copy the crates that changed since the last run (unstable)",
+        ))
+        .arg(flag(
+            "per-source",
+            "Vendor git dependencies in a `git` subdirectory (unstable)",
+        ))
+        .arg(
+            flag(
+                "verify",
+                "Check the vendor directory against `Cargo.lock` (unstable)",
+            )
+            .conflicts_with_all([
+                "no-delete",
+                "incremental",
+                "per-source",
+                "versioned-dirs",
+            ]),
+        )
         .arg(unsupported("no-merge-sources"))
         .arg(unsupported("relative-path"))
         .arg(unsupported("only-git-deps"))
@@ -55,6 +75,17 @@ fn unsupported(name: &'static str) -> Arg {
 }
 
 pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
+    for flag in ["incremental", "per-source", "verify"] {
+        if args.flag(flag) {
+            gctx.cli_unstable().fail_if_stable_opt_custom_z(
+                &format!("--{flag}"),
+                None,
+                "vendor-sync",
+                gctx.cli_unstable().vendor_sync,
+            )?;
+        }
+    }
+
     // We're doing the vendoring operation ourselves, so we don't actually want
     // to respect any of the `source` configuration in Cargo itself. That's
     // intended for other consumers of Cargo, but we want to go straight to the
@@ -74,6 +105,9 @@ pub fn exec(gctx: &mut GlobalContext, args: &ArgMatches) -> CliResult {
             no_delete: args.flag("no-delete"),
             destination: &path,
             versioned_dirs: args.flag("versioned-dirs"),
+            incremental: args.flag("incremental"),
+            per_source: args.flag("per-source"),
+            verify: args.flag("verify"),
             extra: args
                 .get_many::<PathBuf>("tomls")
                 .unwrap_or_default()
@@ -30,6 +30,8 @@ fn main() {
 
     let result = if let Some(lock_addr) = cargo::ops::fix_get_proxy_lock_addr() {
         cargo::ops::fix_exec_rustc(&gctx, &lock_addr).map_err(|e| CliError::from(e))
+    } else if let Some(config) = cargo::core::compiler::sandbox_get_config() {
+        cargo::core::compiler::sandbox_exec(&config).map_err(|e| CliError::from(e))
     } else {
         let _token = cargo::util::job::setup();
         cli::main(&mut gctx)
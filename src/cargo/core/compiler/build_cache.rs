@@ -0,0 +1,417 @@
+//! A cache of compiled units shared between workspaces, see [`BuildCache`].
+//!
+//! When `-Zbuild-cache` is enabled, the outputs of units built from
+//! immutable sources (registries and git) are stored in a content-addressed
+//! directory after they are compiled, and restored from there instead of
+//! invoking `rustc` when another target directory needs the same unit.
+//!
+//! ## Cache keys
+//!
+//! A unit's key is computed right before its job runs, once all of its
+//! dependencies have finished. It combines:
+//!
+//! * The parts of the unit's fingerprint that don't depend on the target
+//!   directory, see [`fingerprint::build_cache_hash`]. This includes the
+//!   unit's [`Metadata`], `rustc` version, profile, features and `RUSTFLAGS`.
+//! * The keys of the units it depends on.
+//! * For a build script dependency, the output of the script, the contents
+//!   of its `OUT_DIR` and the values of its `rerun-if-env-changed` variables.
+//!   Build scripts themselves are always run, since what they do can depend
+//!   on anything on the machine.
+//! * The values of the environment variables the unit reads with `env!` and
+//!   `option_env!`. These are only known once the unit has been compiled, so
+//!   each unit has an index entry listing them, see [`BuildCache::restore`].
+//!
+//! A unit that depends on something that can't be cached (such as a `[patch]`
+//! to a local path) is never cached either.
+//!
+//! ## Storage
+//!
+//! Entries are stored through a [`BuildCacheBackend`]. The only backend at
+//! the moment is [`LocalBuildCache`], which keeps one directory per entry
+//! under `build-cache.path` (by default `$CARGO_HOME/build-cache`).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context as _;
+use cargo_util::paths;
+use tracing::debug;
+
+use super::build_runner::Metadata;
+use super::fingerprint;
+use super::{BuildRunner, CompileMode, Unit, Work};
+use crate::core::shell::Verbosity;
+use crate::util::context::ConfigRelativePath;
+use crate::util::errors::CargoResult;
+use crate::util::{style, StableHasher};
+use crate::{GlobalContext, CARGO_ENV};
+
+/// The file of an index entry listing the environment variables of a unit.
+const ENV_DEPS: &str = "env-deps";
+
+/// Storage for build cache entries.
+///
+/// An entry is a set of files, identified by their file name, stored under a
+/// key. Implementations should make [`store`](Self::store) atomic, so that a
+/// concurrent [`restore`](Self::restore) either finds the whole entry or
+/// nothing at all.
+pub trait BuildCacheBackend: Send + Sync {
+    /// Copies the files of the entry for `key` to `files`, matching them by
+    /// file name. Files that aren't part of the entry are left alone.
+    ///
+    /// Returns `false` if there is no entry for `key`.
+    fn restore(&self, key: &str, files: &[PathBuf]) -> CargoResult<bool>;
+
+    /// Stores the files in `files` that exist as the entry for `key`.
+    fn store(&self, key: &str, files: &[PathBuf]) -> CargoResult<()>;
+}
+
+/// A [`BuildCacheBackend`] keeping each entry in a directory named after its
+/// key.
+pub struct LocalBuildCache {
+    root: PathBuf,
+}
+
+impl LocalBuildCache {
+    pub fn new(root: PathBuf) -> LocalBuildCache {
+        LocalBuildCache { root }
+    }
+}
+
+impl BuildCacheBackend for LocalBuildCache {
+    fn restore(&self, key: &str, files: &[PathBuf]) -> CargoResult<bool> {
+        let entry = self.root.join(key);
+        if !entry.is_dir() {
+            return Ok(false);
+        }
+        for file in files {
+            let src = entry.join(file.file_name().unwrap());
+            if src.exists() {
+                // Copy rather than hard link, so that the restored files are
+                // newer than their dependencies, like freshly built ones.
+                paths::copy(&src, file)?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn store(&self, key: &str, files: &[PathBuf]) -> CargoResult<()> {
+        let entry = self.root.join(key);
+        if entry.exists() {
+            return Ok(());
+        }
+        paths::create_dir_all(&self.root)?;
+        let tmp = tempfile::Builder::new()
+            .prefix(".tmp")
+            .tempdir_in(&self.root)?;
+        for file in files.iter().filter(|f| f.exists()) {
+            paths::copy(file, tmp.path().join(file.file_name().unwrap()))?;
+        }
+        // Another process may have stored the same entry in the meantime, in
+        // which case either copy is as good as the other.
+        if let Err(e) = fs::rename(tmp.path(), &entry) {
+            if !entry.exists() {
+                return Err(e).with_context(|| {
+                    format!("failed to create build cache entry `{}`", entry.display())
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// State of the build cache for a single build.
+pub struct BuildCache {
+    backend: Box<dyn BuildCacheBackend>,
+    /// Keys of the units whose jobs have started, by their metadata.
+    keys: Mutex<HashMap<Metadata, u64>>,
+    /// The environment of the build, which units and build scripts read
+    /// variables from.
+    env: HashMap<String, String>,
+}
+
+/// What a unit's cache key depends on besides the unit itself.
+enum KeyDep {
+    /// Another unit with a key of its own.
+    Unit(Metadata),
+    /// The execution of a build script.
+    BuildScript { output: PathBuf, out_dir: PathBuf },
+    /// Something that isn't cached, which makes the unit uncacheable.
+    Uncacheable,
+}
+
+/// Everything needed to compute a unit's key while its job runs.
+struct KeyInputs {
+    metadata: Metadata,
+    name: String,
+    hash: u64,
+    deps: Vec<KeyDep>,
+    /// The dep-info file of the unit, listing the environment variables it
+    /// reads once it has been compiled.
+    dep_info: PathBuf,
+}
+
+impl BuildCache {
+    /// Creates the build cache if `-Zbuild-cache` is enabled.
+    pub fn new(gctx: &GlobalContext) -> CargoResult<Option<Arc<BuildCache>>> {
+        if !gctx.cli_unstable().build_cache {
+            return Ok(None);
+        }
+        let root = match gctx.get::<Option<ConfigRelativePath>>("build-cache.path")? {
+            Some(path) => path.resolve_path(gctx),
+            None => gctx.home().as_path_unlocked().join("build-cache"),
+        };
+        debug!("using build cache at {}", root.display());
+        Ok(Some(Arc::new(BuildCache {
+            backend: Box::new(LocalBuildCache::new(root)),
+            keys: Mutex::new(HashMap::new()),
+            env: gctx
+                .env()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        })))
+    }
+
+    /// Whether the outputs of `unit` can be stored in the cache.
+    pub fn is_cacheable(unit: &Unit) -> bool {
+        !unit.is_local()
+            && !unit.is_std
+            && matches!(
+                unit.mode,
+                CompileMode::Build | CompileMode::Check { test: false }
+            )
+    }
+
+    /// Wraps the `work` of a dirty unit so that its outputs are restored from
+    /// the cache if possible, or stored in the cache after building them.
+    pub fn dirty_work(
+        self: &Arc<Self>,
+        build_runner: &mut BuildRunner<'_, '_>,
+        unit: &Unit,
+        work: Work,
+    ) -> CargoResult<Work> {
+        let inputs = key_inputs(build_runner, unit)?;
+        let mut files: Vec<_> = build_runner
+            .outputs(unit)?
+            .iter()
+            .map(|output| output.path.clone())
+            .collect();
+        files.push(inputs.dep_info.clone());
+        let verbose = build_runner.bcx.gctx.shell().verbosity() == Verbosity::Verbose;
+        let pkg_descr = unit.pkg.to_string();
+        let cache = Arc::clone(self);
+
+        Ok(Work::new(move |state| {
+            let Some(key) = cache.record_key(&inputs)? else {
+                return work.call(state);
+            };
+            match cache.restore(&inputs, key, &files) {
+                Ok(true) => {
+                    if verbose {
+                        let status = "Restored";
+                        state.stderr(format!(
+                            "{style}{status:>12}{style:#} {pkg_descr} from the build cache",
+                            style = style::HEADER
+                        ))?;
+                    }
+                    return Ok(());
+                }
+                Ok(false) => {}
+                Err(e) => state.warning(format!(
+                    "failed to restore {pkg_descr} from the build cache: {e:#}"
+                ))?,
+            }
+            work.call(state)?;
+            if let Err(e) = cache.store(&inputs, key, &files) {
+                state.warning(format!(
+                    "failed to store {pkg_descr} in the build cache: {e:#}"
+                ))?;
+            }
+            Ok(())
+        }))
+    }
+
+    /// Wraps the `work` of a fresh unit so that its key is known to the
+    /// units depending on it.
+    pub fn fresh_work(
+        self: &Arc<Self>,
+        build_runner: &mut BuildRunner<'_, '_>,
+        unit: &Unit,
+        work: Work,
+    ) -> CargoResult<Work> {
+        let inputs = key_inputs(build_runner, unit)?;
+        let cache = Arc::clone(self);
+        Ok(Work::new(move |state| {
+            if let Some(key) = cache.record_key(&inputs)? {
+                // The dep-info of a fresh unit is up-to-date.
+                let vars = env_deps(&inputs.dep_info)?;
+                cache.record_env_key(&inputs, key, &vars);
+            }
+            work.call(state)
+        }))
+    }
+
+    /// Computes the key of a unit without its environment variables, which
+    /// are only known once it has been compiled. Returns `None` if the unit
+    /// can't be cached.
+    fn record_key(&self, inputs: &KeyInputs) -> CargoResult<Option<u64>> {
+        let mut hasher = StableHasher::new();
+        inputs.hash.hash(&mut hasher);
+        for dep in &inputs.deps {
+            match dep {
+                KeyDep::Unit(metadata) => match self.keys.lock().unwrap().get(metadata) {
+                    Some(key) => key.hash(&mut hasher),
+                    None => return Ok(None),
+                },
+                KeyDep::BuildScript { output, out_dir } => {
+                    self.hash_build_script_output(output, out_dir, &mut hasher)?
+                }
+                KeyDep::Uncacheable => return Ok(None),
+            }
+        }
+        Ok(Some(hasher.finish()))
+    }
+
+    /// Adds the values of the environment variables `vars` to `key`, and
+    /// records the result as the key of the unit, returning it in the form
+    /// used by [`BuildCacheBackend`].
+    fn record_env_key(&self, inputs: &KeyInputs, key: u64, vars: &[String]) -> String {
+        let mut hasher = StableHasher::new();
+        key.hash(&mut hasher);
+        for var in vars {
+            (var, self.env.get(var)).hash(&mut hasher);
+        }
+        let key = hasher.finish();
+        self.keys.lock().unwrap().insert(inputs.metadata, key);
+        format!("{}-{key:016x}", inputs.name)
+    }
+
+    /// The key of the index entry of a unit, listing the environment
+    /// variables the unit reads.
+    fn index_key(inputs: &KeyInputs, key: u64) -> String {
+        format!("{}-{key:016x}-{ENV_DEPS}", inputs.name)
+    }
+
+    /// Restores the outputs of a unit to `files`, returning `false` if they
+    /// aren't in the cache.
+    ///
+    /// The environment variables the unit reads are looked up in its index
+    /// entry first, so that the outputs are only restored if the variables
+    /// have the values they had when the unit was compiled.
+    fn restore(&self, inputs: &KeyInputs, key: u64, files: &[PathBuf]) -> CargoResult<bool> {
+        let tmp = tempfile::tempdir()?;
+        let index = tmp.path().join(ENV_DEPS);
+        if !self
+            .backend
+            .restore(&Self::index_key(inputs, key), &[index.clone()])?
+        {
+            return Ok(false);
+        }
+        let vars: Vec<_> = paths::read(&index)?.lines().map(str::to_string).collect();
+        let key = self.record_env_key(inputs, key, &vars);
+        self.backend.restore(&key, files)
+    }
+
+    /// Stores the outputs of a freshly compiled unit, along with its index
+    /// entry.
+    fn store(&self, inputs: &KeyInputs, key: u64, files: &[PathBuf]) -> CargoResult<()> {
+        let vars = env_deps(&inputs.dep_info)?;
+        let tmp = tempfile::tempdir()?;
+        let index = tmp.path().join(ENV_DEPS);
+        let mut contents = String::new();
+        for var in &vars {
+            contents.push_str(var);
+            contents.push('\n');
+        }
+        paths::write(&index, contents)?;
+        self.backend
+            .store(&Self::index_key(inputs, key), &[index])?;
+        let key = self.record_env_key(inputs, key, &vars);
+        self.backend.store(&key, files)
+    }
+
+    /// Hashes what a build script told Cargo, the values of the environment
+    /// variables it asked to be rerun for, and what it generated in
+    /// `OUT_DIR`.
+    ///
+    /// The output mentions `OUT_DIR` by its absolute path, which is replaced
+    /// so that the same script gets the same hash in every target directory.
+    fn hash_build_script_output(
+        &self,
+        output: &Path,
+        out_dir: &Path,
+        hasher: &mut StableHasher,
+    ) -> CargoResult<()> {
+        let contents = paths::read_bytes(output)?;
+        let contents = String::from_utf8_lossy(&contents);
+        contents
+            .replace(&*out_dir.to_string_lossy(), "$OUT_DIR")
+            .hash(hasher);
+        for line in contents.lines() {
+            let Some(var) = line
+                .strip_prefix("cargo::")
+                .or_else(|| line.strip_prefix("cargo:"))
+                .and_then(|l| l.strip_prefix("rerun-if-env-changed="))
+            else {
+                continue;
+            };
+            (var, self.env.get(var)).hash(hasher);
+        }
+        for entry in walkdir::WalkDir::new(out_dir).sort_by_file_name() {
+            let entry = entry?;
+            entry.path().strip_prefix(out_dir).unwrap().hash(hasher);
+            if entry.file_type().is_file() {
+                paths::read_bytes(entry.path())?.hash(hasher);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The environment variables a compiled unit reads, from its `dep_info`.
+///
+/// `CARGO` is left out, as it is the path of the Cargo executable rather
+/// than something the user sets.
+fn env_deps(dep_info: &Path) -> CargoResult<Vec<String>> {
+    let Some(info) = fingerprint::parse_dep_info(Path::new(""), Path::new(""), dep_info)? else {
+        anyhow::bail!("failed to read the dep-info file `{}`", dep_info.display());
+    };
+    let vars: BTreeMap<_, _> = info
+        .env
+        .into_iter()
+        .filter(|(var, _)| var != CARGO_ENV)
+        .collect();
+    Ok(vars.into_keys().collect())
+}
+
+fn key_inputs(build_runner: &mut BuildRunner<'_, '_>, unit: &Unit) -> CargoResult<KeyInputs> {
+    let hash = fingerprint::build_cache_hash(build_runner, unit)?;
+    let deps = build_runner
+        .unit_deps(unit)
+        .iter()
+        .map(|dep| {
+            if dep.unit.mode.is_run_custom_build() {
+                let files = build_runner.files();
+                KeyDep::BuildScript {
+                    output: files.build_script_run_dir(&dep.unit).join("output"),
+                    out_dir: files.build_script_out_dir(&dep.unit),
+                }
+            } else if BuildCache::is_cacheable(&dep.unit) {
+                KeyDep::Unit(build_runner.files().metadata(&dep.unit))
+            } else {
+                KeyDep::Uncacheable
+            }
+        })
+        .collect();
+    Ok(KeyInputs {
+        metadata: build_runner.files().metadata(unit),
+        name: format!("{}-{}", unit.pkg.name(), unit.pkg.version()),
+        hash,
+        deps,
+        dep_info: fingerprint::dep_info_loc(build_runner, unit),
+    })
+}
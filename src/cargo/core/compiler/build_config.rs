@@ -31,6 +31,14 @@ pub struct BuildConfig {
     pub build_plan: bool,
     /// Output the unit graph to stdout instead of actually compiling.
     pub unit_graph: bool,
+    /// Explain why each unit was rebuilt once the build is over.
+    pub explain_rebuild: bool,
+    /// Report the progress of the build with JSON messages on stdout, see
+    /// `-Zbuild-events`.
+    pub build_events: bool,
+    /// Document as JSON without `-Zunstable-options`, for the SemVer check
+    /// of `cargo package` and `cargo publish`.
+    pub rustdoc_json: bool,
     /// An optional override of the rustc process for primary units
     pub primary_unit_rustc: Option<ProcessBuilder>,
     /// A thread used by `cargo fix` to receive messages on a socket regarding
@@ -112,6 +120,9 @@ impl BuildConfig {
             force_rebuild: false,
             build_plan: false,
             unit_graph: false,
+            explain_rebuild: false,
+            rustdoc_json: false,
+            build_events: false,
             primary_unit_rustc: None,
             rustfix_diagnostic_server: Rc::new(RefCell::new(None)),
             export_dir: None,
@@ -224,11 +224,17 @@ impl<'a, 'gctx: 'a> CompilationFiles<'a, 'gctx> {
     /// taken in those cases!
     fn pkg_dir(&self, unit: &Unit) -> String {
         let name = unit.pkg.package_id().name();
+        format!("{}-{}", name, self.unit_hash(unit))
+    }
+
+    /// Returns the hash used in the names of the files of the given unit in
+    /// the target directory, like the `HASH` of [`Self::pkg_dir`].
+    pub fn unit_hash(&self, unit: &Unit) -> String {
         let meta = &self.metas[unit];
         if meta.use_extra_filename {
-            format!("{}-{}", name, meta.meta_hash)
+            meta.meta_hash.to_string()
         } else {
-            format!("{}-{}", name, self.target_short_hash(unit))
+            self.target_short_hash(unit)
         }
     }
 
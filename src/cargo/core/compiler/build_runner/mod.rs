@@ -14,10 +14,12 @@ use filetime::FileTime;
 use itertools::Itertools;
 use jobserver::Client;
 
+use super::build_cache::BuildCache;
 use super::build_plan::BuildPlan;
 use super::custom_build::{self, BuildDeps, BuildScriptOutputs, BuildScripts};
 use super::fingerprint::Fingerprint;
 use super::job_queue::JobQueue;
+use super::last_use;
 use super::layout::Layout;
 use super::lto::Lto;
 use super::unit_graph::UnitDep;
@@ -85,6 +87,10 @@ pub struct BuildRunner<'a, 'gctx> {
     /// because the target has a type error. This is in an Arc<Mutex<..>>
     /// because it is continuously updated as the job progresses.
     pub failed_scrape_units: Arc<Mutex<HashSet<Metadata>>>,
+
+    /// The cache of compiled units shared with other workspaces, if
+    /// `-Zbuild-cache` is enabled.
+    pub build_cache: Option<Arc<BuildCache>>,
 }
 
 impl<'a, 'gctx> BuildRunner<'a, 'gctx> {
@@ -123,6 +129,7 @@ impl<'a, 'gctx> BuildRunner<'a, 'gctx> {
             lto: HashMap::new(),
             metadata_for_doc_units: HashMap::new(),
             failed_scrape_units: Arc::new(Mutex::new(HashSet::new())),
+            build_cache: BuildCache::new(bcx.gctx)?,
         })
     }
 
@@ -144,7 +151,6 @@ impl<'a, 'gctx> BuildRunner<'a, 'gctx> {
         let mut queue = JobQueue::new(self.bcx);
         let mut plan = BuildPlan::new();
         let build_plan = self.bcx.build_config.build_plan;
-        self.lto = super::lto::generate(self.bcx)?;
         self.prepare_units()?;
         self.prepare()?;
         custom_build::build_map(&mut self)?;
@@ -181,6 +187,10 @@ impl<'a, 'gctx> BuildRunner<'a, 'gctx> {
         // Now that we've figured out everything that we're going to do, do it!
         queue.execute(&mut self, &mut plan)?;
 
+        if self.bcx.gctx.cli_unstable().clean_stale {
+            last_use::record(&self)?;
+        }
+
         if build_plan {
             plan.set_inputs(self.build_plan_inputs()?);
             plan.output_plan(self.bcx.gctx);
@@ -323,12 +333,20 @@ impl<'a, 'gctx> BuildRunner<'a, 'gctx> {
 
     #[tracing::instrument(skip_all)]
     pub fn prepare_units(&mut self) -> CargoResult<()> {
+        // The metadata of the units depends on how they are optimized.
+        self.lto = super::lto::generate(self.bcx)?;
         let dest = self.bcx.profiles.get_dir_name();
-        let host_layout = Layout::new(self.bcx.ws, None, &dest)?;
+        let host_layout =
+            Layout::new(self.bcx.ws, None, &dest, self.bcx.build_config.build_events)?;
         let mut targets = HashMap::new();
         for kind in self.bcx.all_kinds.iter() {
             if let CompileKind::Target(target) = *kind {
-                let layout = Layout::new(self.bcx.ws, Some(target), &dest)?;
+                let layout = Layout::new(
+                    self.bcx.ws,
+                    Some(target),
+                    &dest,
+                    self.bcx.build_config.build_events,
+                )?;
                 targets.insert(target, layout);
             }
         }
@@ -0,0 +1,817 @@
+//! Build script sandbox for Linux, based on Landlock and seccomp.
+//!
+//! The sandbox is made of two parts, both inherited by every process the
+//! build script spawns:
+//!
+//! * A Landlock ruleset, which is what actually denies writes outside of the
+//!   writable directories, reads outside of the readable ones, and TCP
+//!   connections on kernels that support it.
+//! * A seccomp filter. It kills the build script if it makes system calls
+//!   through another ABI than the native one, like `x32` or `i386` on
+//!   `x86_64`, whose system call numbers it doesn't know about. It denies
+//!   opening IP sockets, and sends a notification to the wrapper for the
+//!   system calls that access paths. The wrapper looks
+//!   at the path involved to record the accesses Landlock is going to deny,
+//!   then lets the kernel carry on with the system call. Since the decision
+//!   is still Landlock's, it doesn't matter that the build script could
+//!   change the path in its memory after it has been inspected.
+
+use std::collections::BTreeSet;
+use std::ffi::{CString, OsStr};
+use std::fs::{self, File};
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileExt;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{bail, Context as _};
+
+use super::Violation;
+use crate::CargoResult;
+
+// From `linux/landlock.h`.
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: libc::c_int = 1;
+
+const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+/// `REMOVE_DIR` up to `MAKE_SYM`, the rights to modify a directory.
+const ACCESS_FS_MODIFY_DIR: u64 = 0b1_1111_1111 << 4;
+/// ABI 2.
+const ACCESS_FS_REFER: u64 = 1 << 13;
+/// ABI 3.
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+/// ABI 5.
+const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+/// The rights that apply to files rather than directories.
+const ACCESS_FILE: u64 = ACCESS_FS_EXECUTE
+    | ACCESS_FS_WRITE_FILE
+    | ACCESS_FS_READ_FILE
+    | ACCESS_FS_TRUNCATE
+    | ACCESS_FS_IOCTL_DEV;
+const ACCESS_READ: u64 = ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+
+/// ABI 4.
+const ACCESS_NET_BIND_TCP: u64 = 1 << 0;
+const ACCESS_NET_CONNECT_TCP: u64 = 1 << 1;
+
+#[repr(C)]
+struct LandlockRulesetAttr {
+    handled_access_fs: u64,
+    handled_access_net: u64,
+}
+
+#[repr(C, packed)]
+struct LandlockPathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+// From `linux/seccomp.h`.
+const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+const SECCOMP_IOCTL_NOTIF_RECV: u64 = 0xc050_2100;
+const SECCOMP_IOCTL_NOTIF_SEND: u64 = 0xc018_2101;
+const SECCOMP_IOCTL_NOTIF_ID_VALID: u64 = 0x4008_2102;
+
+// From `linux/audit.h`, the architecture of the native system call ABI.
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// Set in the number of the system calls of the `x32` ABI, which shares its
+/// architecture with `x86_64`.
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+#[repr(C)]
+struct SeccompData {
+    nr: i32,
+    arch: u32,
+    instruction_pointer: u64,
+    args: [u64; 6],
+}
+
+#[repr(C)]
+struct SeccompNotif {
+    id: u64,
+    pid: u32,
+    flags: u32,
+    data: SeccompData,
+}
+
+#[repr(C)]
+struct SeccompNotifResp {
+    id: u64,
+    val: i64,
+    error: i32,
+    flags: u32,
+}
+
+/// How a system call accesses the paths it is given.
+#[derive(Clone, Copy)]
+enum Access {
+    /// Opens a path, with the flags in the given argument.
+    Open(usize),
+    /// Opens a path, with the flags in the `open_how` pointed to by the given
+    /// argument.
+    OpenHow(usize),
+    Write,
+    Execute,
+}
+
+/// A system call taking paths, as the arguments of the directory file
+/// descriptor they are relative to, if any, and of the path.
+struct PathSyscall {
+    nr: libc::c_long,
+    paths: &'static [(Option<usize>, usize)],
+    access: Access,
+}
+
+const PATH_SYSCALLS: &[PathSyscall] = &[
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_open,
+        paths: &[(None, 0)],
+        access: Access::Open(1),
+    },
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_creat,
+        paths: &[(None, 0)],
+        access: Access::Write,
+    },
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_mkdir,
+        paths: &[(None, 0)],
+        access: Access::Write,
+    },
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_rmdir,
+        paths: &[(None, 0)],
+        access: Access::Write,
+    },
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_unlink,
+        paths: &[(None, 0)],
+        access: Access::Write,
+    },
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_rename,
+        paths: &[(None, 0), (None, 1)],
+        access: Access::Write,
+    },
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_link,
+        paths: &[(None, 1)],
+        access: Access::Write,
+    },
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_symlink,
+        paths: &[(None, 1)],
+        access: Access::Write,
+    },
+    #[cfg(target_arch = "x86_64")]
+    PathSyscall {
+        nr: libc::SYS_mknod,
+        paths: &[(None, 0)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_openat,
+        paths: &[(Some(0), 1)],
+        access: Access::Open(2),
+    },
+    PathSyscall {
+        nr: libc::SYS_openat2,
+        paths: &[(Some(0), 1)],
+        access: Access::OpenHow(2),
+    },
+    PathSyscall {
+        nr: libc::SYS_mkdirat,
+        paths: &[(Some(0), 1)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_unlinkat,
+        paths: &[(Some(0), 1)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_renameat,
+        paths: &[(Some(0), 1), (Some(2), 3)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_renameat2,
+        paths: &[(Some(0), 1), (Some(2), 3)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_linkat,
+        paths: &[(Some(2), 3)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_symlinkat,
+        paths: &[(Some(1), 2)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_mknodat,
+        paths: &[(Some(0), 1)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_truncate,
+        paths: &[(None, 0)],
+        access: Access::Write,
+    },
+    PathSyscall {
+        nr: libc::SYS_execve,
+        paths: &[(None, 0)],
+        access: Access::Execute,
+    },
+    PathSyscall {
+        nr: libc::SYS_execveat,
+        paths: &[(Some(0), 1)],
+        access: Access::Execute,
+    },
+];
+
+/// Returns an error if the running kernel can't sandbox build scripts.
+pub fn check_supported() -> CargoResult<()> {
+    if !cfg!(any(target_arch = "x86_64", target_arch = "aarch64")) {
+        bail!("`-Zbuild-script-sandbox` is only supported on x86_64 and aarch64");
+    }
+    landlock_abi().context(
+        "`-Zbuild-script-sandbox` requires Landlock, \
+         which is not supported or not enabled by this kernel",
+    )?;
+    Ok(())
+}
+
+fn landlock_abi() -> io::Result<u32> {
+    // SAFETY: with `LANDLOCK_CREATE_RULESET_VERSION`, the attributes must be
+    // null and their size 0, and nothing is created.
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<LandlockRulesetAttr>(),
+            0,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    if abi < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(abi as u32)
+}
+
+/// Runs `cmd` in the sandbox, until it exits.
+///
+/// Returns its exit status, along with the accesses that were denied.
+pub fn run(
+    mut cmd: Command,
+    write: &[PathBuf],
+    read: &[PathBuf],
+) -> CargoResult<(ExitStatus, BTreeSet<Violation>)> {
+    // Paths are compared once resolved, like Landlock does.
+    let write: Vec<PathBuf> = write
+        .iter()
+        .filter_map(|p| fs::canonicalize(p).ok())
+        .collect();
+    let read: Vec<PathBuf> = read
+        .iter()
+        .filter_map(|p| fs::canonicalize(p).ok())
+        .collect();
+
+    let ruleset = create_ruleset(&write, &read).context("failed to create Landlock ruleset")?;
+    let filter = seccomp_filter();
+    let (sock, child_sock) = socket_pair()?;
+
+    let ruleset_fd = ruleset.as_raw_fd();
+    let child_sock_fd = child_sock.as_raw_fd();
+    // SAFETY: only async-signal-safe system calls are made between `fork`
+    // and `exec`, and nothing is allocated.
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::syscall(libc::SYS_landlock_restrict_self, ruleset_fd, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let prog = libc::sock_fprog {
+                len: filter.len() as u16,
+                filter: filter.as_ptr() as *mut libc::sock_filter,
+            };
+            let listener = libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+                &prog,
+            );
+            if listener < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            send_fd(child_sock_fd, listener as RawFd)?;
+            libc::close(listener as RawFd);
+            Ok(())
+        });
+    }
+    // The child is stopped on its `exec` until it gets an answer, so the
+    // notifications must be handled while it is being spawned.
+    let stop = Arc::new(AtomicBool::new(false));
+    let supervisor = thread::spawn({
+        let stop = Arc::clone(&stop);
+        move || -> CargoResult<BTreeSet<Violation>> {
+            let listener = recv_fd(&sock).context("failed to receive seccomp listener")?;
+            let mut supervisor = Supervisor {
+                listener,
+                write,
+                read,
+                violations: BTreeSet::new(),
+            };
+            supervisor.run(&stop)?;
+            Ok(supervisor.violations)
+        }
+    });
+    let child = cmd.spawn();
+    drop(child_sock);
+    drop(ruleset);
+    let status = child
+        .and_then(|mut child| child.wait())
+        .context("failed to run build script in sandbox");
+    stop.store(true, Ordering::Relaxed);
+    let violations = supervisor.join().unwrap();
+    let status = status?;
+    Ok((status, violations?))
+}
+
+/// Exits the process like a child with `status` did.
+pub fn exit_like(status: ExitStatus) -> ! {
+    if let Some(signal) = status.signal() {
+        // SAFETY: restoring the default disposition and raising the signal
+        // again to be killed by it.
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+    std::process::exit(status.code().unwrap_or(101))
+}
+
+fn create_ruleset(write: &[PathBuf], read: &[PathBuf]) -> io::Result<OwnedFd> {
+    let abi = landlock_abi()?;
+    let mut handled_fs = ACCESS_READ | ACCESS_FS_WRITE_FILE | ACCESS_FS_MODIFY_DIR;
+    if abi >= 2 {
+        handled_fs |= ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        handled_fs |= ACCESS_FS_TRUNCATE;
+    }
+    if abi >= 5 {
+        handled_fs |= ACCESS_FS_IOCTL_DEV;
+    }
+    let attr = LandlockRulesetAttr {
+        handled_access_fs: handled_fs,
+        handled_access_net: if abi >= 4 {
+            ACCESS_NET_BIND_TCP | ACCESS_NET_CONNECT_TCP
+        } else {
+            0
+        },
+    };
+    // SAFETY: `attr` is a valid `landlock_ruleset_attr` of the given size,
+    // which outlives the call.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr,
+            mem::size_of::<LandlockRulesetAttr>(),
+            0,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the file descriptor was just created.
+    let ruleset = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+
+    for path in read {
+        add_rule(&ruleset, path, ACCESS_READ & handled_fs)?;
+    }
+    // Devices can be used, but only `/dev/null` written to.
+    add_rule(
+        &ruleset,
+        Path::new("/dev"),
+        (ACCESS_READ | ACCESS_FS_IOCTL_DEV) & handled_fs,
+    )?;
+    add_rule(
+        &ruleset,
+        Path::new("/dev/null"),
+        (ACCESS_FS_READ_FILE | ACCESS_FS_WRITE_FILE | ACCESS_FS_TRUNCATE) & handled_fs,
+    )?;
+    for path in write {
+        add_rule(&ruleset, path, handled_fs)?;
+    }
+    Ok(ruleset)
+}
+
+/// Allows `access` beneath `path`, if it exists.
+fn add_rule(ruleset: &OwnedFd, path: &Path, access: u64) -> io::Result<()> {
+    let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+        return Ok(());
+    };
+    // SAFETY: `c_path` is a NUL terminated string that outlives the call.
+    let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Ok(());
+    }
+    // SAFETY: the file descriptor was just opened.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    let is_dir = File::from(fd.try_clone()?)
+        .metadata()
+        .map(|m| m.is_dir())
+        .unwrap_or(false);
+    let attr = LandlockPathBeneathAttr {
+        allowed_access: if is_dir { access } else { access & ACCESS_FILE },
+        parent_fd: fd.as_raw_fd(),
+    };
+    // SAFETY: `attr` is a valid `landlock_path_beneath_attr` holding an open
+    // file descriptor, and both outlive the call.
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset.as_raw_fd(),
+            LANDLOCK_RULE_PATH_BENEATH,
+            &attr,
+            0,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Builds the seccomp filter, which notifies the wrapper of the system calls
+/// in [`PATH_SYSCALLS`] and of the creation of IP sockets.
+///
+/// System calls are only recognized by their number, which means something
+/// else in another ABI, so the process is killed if it uses one.
+fn seccomp_filter() -> Vec<libc::sock_filter> {
+    let ld = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+    let jeq = (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16;
+    let ret = (libc::BPF_RET | libc::BPF_K) as u16;
+    let nr_offset = mem::offset_of!(SeccompData, nr) as u32;
+    let arch_offset = mem::offset_of!(SeccompData, arch) as u32;
+    // The lower half of the first argument.
+    let arg0_offset = mem::offset_of!(SeccompData, args) as u32;
+
+    let mut filter = vec![
+        stmt(ld, arch_offset),
+        jump(jeq, AUDIT_ARCH, 1, 0),
+        stmt(ret, libc::SECCOMP_RET_KILL_PROCESS),
+        stmt(ld, nr_offset),
+    ];
+    #[cfg(target_arch = "x86_64")]
+    {
+        // `x32` system calls, except for -1, which a tracer sets to skip one.
+        let jge = (libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K) as u16;
+        filter.extend([
+            jump(jge, X32_SYSCALL_BIT, 0, 2),
+            jump(jeq, u32::MAX, 1, 0),
+            stmt(ret, libc::SECCOMP_RET_KILL_PROCESS),
+        ]);
+    }
+    // Every check jumps to the final instruction, which notifies, past the
+    // remaining checks and the 6 instructions checking sockets.
+    for (i, syscall) in PATH_SYSCALLS.iter().enumerate() {
+        let to_notify = (PATH_SYSCALLS.len() - i - 1 + 6) as u8;
+        filter.push(jump(jeq, syscall.nr as u32, to_notify, 0));
+    }
+    filter.extend([
+        jump(jeq, libc::SYS_socket as u32, 1, 0),
+        stmt(ret, libc::SECCOMP_RET_ALLOW),
+        stmt(ld, arg0_offset),
+        jump(jeq, libc::AF_INET as u32, 2, 0),
+        jump(jeq, libc::AF_INET6 as u32, 1, 0),
+        stmt(ret, libc::SECCOMP_RET_ALLOW),
+        stmt(ret, SECCOMP_RET_USER_NOTIF),
+    ]);
+    filter
+}
+
+fn stmt(code: u16, k: u32) -> libc::sock_filter {
+    jump(code, k, 0, 0)
+}
+
+fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+fn socket_pair() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two file descriptors.
+    let ret = unsafe {
+        libc::socketpair(
+            libc::AF_UNIX,
+            libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+            0,
+            fds.as_mut_ptr(),
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the file descriptors were just created.
+    unsafe { Ok((OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1]))) }
+}
+
+/// Control message buffer for a single file descriptor, aligned for
+/// `cmsghdr`.
+#[repr(C)]
+struct FdMessage {
+    header: libc::cmsghdr,
+    fd: RawFd,
+}
+
+/// Sends `fd` over the `sock` Unix socket.
+///
+/// This is called between `fork` and `exec`, so it must not allocate.
+fn send_fd(sock: RawFd, fd: RawFd) -> io::Result<()> {
+    // SAFETY: `msghdr` and `cmsghdr` are plain C structures, for which zeroes
+    // are valid, and the buffers they point to outlive the call to `sendmsg`.
+    unsafe {
+        let mut byte = 0u8;
+        let mut iov = libc::iovec {
+            iov_base: &mut byte as *mut u8 as *mut libc::c_void,
+            iov_len: 1,
+        };
+        let mut cmsg: FdMessage = mem::zeroed();
+        cmsg.header.cmsg_len = libc::CMSG_LEN(mem::size_of::<RawFd>() as u32) as _;
+        cmsg.header.cmsg_level = libc::SOL_SOCKET;
+        cmsg.header.cmsg_type = libc::SCM_RIGHTS;
+        cmsg.fd = fd;
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = &mut cmsg as *mut FdMessage as *mut libc::c_void;
+        msg.msg_controllen = mem::size_of::<FdMessage>() as _;
+        if libc::sendmsg(sock, &msg, 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Receives a file descriptor sent with [`send_fd`].
+fn recv_fd(sock: &OwnedFd) -> io::Result<OwnedFd> {
+    // SAFETY: as in `send_fd`, and the file descriptor is owned once it has
+    // been received, after checking that the message carries one.
+    unsafe {
+        let mut byte = 0u8;
+        let mut iov = libc::iovec {
+            iov_base: &mut byte as *mut u8 as *mut libc::c_void,
+            iov_len: 1,
+        };
+        let mut cmsg: FdMessage = mem::zeroed();
+        let mut msg: libc::msghdr = mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = &mut cmsg as *mut FdMessage as *mut libc::c_void;
+        msg.msg_controllen = mem::size_of::<FdMessage>() as _;
+        if libc::recvmsg(sock.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if cmsg.header.cmsg_level != libc::SOL_SOCKET || cmsg.header.cmsg_type != libc::SCM_RIGHTS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no file descriptor received",
+            ));
+        }
+        Ok(OwnedFd::from_raw_fd(cmsg.fd))
+    }
+}
+
+/// Watches the notifications of the seccomp filter.
+struct Supervisor {
+    listener: OwnedFd,
+    write: Vec<PathBuf>,
+    read: Vec<PathBuf>,
+    violations: BTreeSet<Violation>,
+}
+
+impl Supervisor {
+    /// Handles notifications until `stop` is set, once the build script
+    /// exited.
+    ///
+    /// Processes spawned by the build script that outlive it can't make the
+    /// watched system calls anymore once the wrapper exits.
+    fn run(&mut self, stop: &AtomicBool) -> CargoResult<()> {
+        while !stop.load(Ordering::Relaxed) {
+            let mut pollfd = libc::pollfd {
+                fd: self.listener.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `pollfd` is a single valid `pollfd`.
+            let ret = unsafe { libc::poll(&mut pollfd, 1, 100) };
+            if ret < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err.into());
+            }
+            if pollfd.revents & libc::POLLIN != 0 {
+                self.handle_notification()?;
+            } else if pollfd.revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_notification(&mut self) -> io::Result<()> {
+        let fd = self.listener.as_raw_fd();
+        // SAFETY: the kernel requires the request to be zeroed.
+        let mut req: SeccompNotif = unsafe { mem::zeroed() };
+        // SAFETY: `req` is a `seccomp_notif` the kernel writes to.
+        if unsafe { libc::ioctl(fd, SECCOMP_IOCTL_NOTIF_RECV as _, &mut req) } != 0 {
+            // The process may have been killed in the meantime.
+            return Ok(());
+        }
+        let mut resp = SeccompNotifResp {
+            id: req.id,
+            val: 0,
+            error: 0,
+            flags: libc::SECCOMP_USER_NOTIF_FLAG_CONTINUE as u32,
+        };
+        if req.data.nr as libc::c_long == libc::SYS_socket {
+            self.violations.insert(Violation::Network);
+            resp.error = -libc::EACCES;
+            resp.flags = 0;
+        } else if let Some(syscall) = PATH_SYSCALLS
+            .iter()
+            .find(|syscall| syscall.nr == req.data.nr as libc::c_long)
+        {
+            self.check_paths(&req, syscall);
+        }
+        // This fails if the process was killed in the meantime.
+        // SAFETY: `resp` is a `seccomp_notif_resp` the kernel reads from.
+        unsafe { libc::ioctl(fd, SECCOMP_IOCTL_NOTIF_SEND as _, &resp) };
+        Ok(())
+    }
+
+    fn check_paths(&mut self, req: &SeccompNotif, syscall: &PathSyscall) {
+        let Ok(mem) = File::open(format!("/proc/{}/mem", req.pid)) else {
+            return;
+        };
+        let args = &req.data.args;
+        let write = match syscall.access {
+            Access::Open(arg) => Some(is_write(args[arg] as libc::c_int)),
+            Access::OpenHow(arg) => {
+                // `flags` is the first field of `open_how`.
+                let mut flags = [0; 8];
+                if mem.read_exact_at(&mut flags, args[arg]).is_err() {
+                    return;
+                }
+                Some(is_write(u64::from_ne_bytes(flags) as libc::c_int))
+            }
+            Access::Write => Some(true),
+            Access::Execute => None,
+        };
+        for &(dirfd, path) in syscall.paths {
+            let Some(path) = read_c_str(&mem, args[path]) else {
+                continue;
+            };
+            // Make sure the memory that was read belongs to the process that
+            // made the system call.
+            // SAFETY: the kernel reads the notification id from `req.id`.
+            let valid = unsafe {
+                libc::ioctl(
+                    self.listener.as_raw_fd(),
+                    SECCOMP_IOCTL_NOTIF_ID_VALID as _,
+                    &req.id,
+                )
+            };
+            if valid != 0 {
+                return;
+            }
+            let dirfd = dirfd.map(|arg| args[arg] as libc::c_int);
+            let Some(path) = resolve(req.pid, dirfd, &path) else {
+                continue;
+            };
+            if let Some(violation) = self.check(path, write) {
+                self.violations.insert(violation);
+            }
+        }
+    }
+
+    /// Returns the violation if Landlock is going to deny the access.
+    fn check(&self, path: PathBuf, write: Option<bool>) -> Option<Violation> {
+        let beneath = |dirs: &[PathBuf]| dirs.iter().any(|dir| path.starts_with(dir));
+        if beneath(&self.write) || path == Path::new("/dev/null") {
+            return None;
+        }
+        match write {
+            Some(true) => {
+                // Accesses to missing directories fail regardless.
+                if !path.exists() && !path.parent().map_or(false, |p| p.exists()) {
+                    return None;
+                }
+                Some(Violation::Write(path))
+            }
+            _ if beneath(&self.read) || path.starts_with("/dev") || !path.exists() => None,
+            Some(false) => Some(Violation::Read(path)),
+            None => Some(Violation::Execute(path)),
+        }
+    }
+}
+
+fn is_write(flags: libc::c_int) -> bool {
+    flags & libc::O_PATH == 0
+        && (flags & libc::O_ACCMODE != libc::O_RDONLY
+            || flags & (libc::O_CREAT | libc::O_TRUNC) != 0)
+}
+
+/// Reads a NUL terminated string from the memory of another process.
+fn read_c_str(mem: &File, addr: u64) -> Option<Vec<u8>> {
+    const PAGE: u64 = 4096;
+    let mut result = Vec::new();
+    let mut addr = addr;
+    while result.len() < libc::PATH_MAX as usize {
+        // Don't read across pages, the next one may not be mapped.
+        let mut buf = vec![0; (PAGE - addr % PAGE) as usize];
+        let n = mem.read_at(&mut buf, addr).ok().filter(|&n| n > 0)?;
+        if let Some(end) = buf[..n].iter().position(|&b| b == 0) {
+            result.extend_from_slice(&buf[..end]);
+            return Some(result);
+        }
+        result.extend_from_slice(&buf[..n]);
+        addr += n as u64;
+    }
+    None
+}
+
+/// Resolves a path given to a system call by process `pid`, following
+/// symbolic links like the kernel does.
+///
+/// Returns `None` for paths that don't refer to the filesystem, such as
+/// `/proc/self/fd/1` when it is a pipe.
+fn resolve(pid: u32, dirfd: Option<libc::c_int>, path: &[u8]) -> Option<PathBuf> {
+    let path = Path::new(OsStr::from_bytes(path));
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        let base = match dirfd {
+            None | Some(libc::AT_FDCWD) => format!("/proc/{pid}/cwd"),
+            Some(fd) => format!("/proc/{pid}/fd/{fd}"),
+        };
+        fs::read_link(base).ok()?.join(path)
+    };
+    // Paths in `/proc` are about the process itself, which the sandbox
+    // doesn't restrict, like the streams it inherited.
+    if path.starts_with("/proc") || path.starts_with("/dev/fd") || path.starts_with("/dev/std") {
+        return None;
+    }
+    if let Ok(path) = fs::canonicalize(&path) {
+        return Some(path);
+    }
+    // The path doesn't exist yet, resolve its parent instead.
+    let path = normalize(&path);
+    let parent = fs::canonicalize(path.parent()?).unwrap_or_else(|_| path.parent().unwrap().into());
+    Some(parent.join(path.file_name()?))
+}
+
+/// Removes `.` and `..` components without looking at the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                result.pop();
+            }
+            Component::CurDir => {}
+            c => result.push(c),
+        }
+    }
+    result
+}
@@ -0,0 +1,293 @@
+//! Sandboxing of build scripts, for `-Zbuild-script-sandbox`.
+//!
+//! When enabled, a build script isn't run directly. Cargo runs itself as a
+//! wrapper around it instead, with the [`SANDBOX_ENV`] environment variable
+//! describing what the build script is allowed to access. The wrapper
+//! confines the build script, and every process it spawns, so that it can
+//! only write to `OUT_DIR` and a temporary directory, only read from a
+//! declared set of paths, and can't open network sockets.
+//!
+//! On Linux this is done with [Landlock]. Landlock only makes the denied
+//! operations fail, and build scripts rarely report the path involved when
+//! they do. To be able to give a useful diagnostic, the wrapper also watches
+//! the filesystem and network system calls of the build script with a
+//! seccomp user notification filter, and records the ones the sandbox denies
+//! in a file that Cargo reads once the build script is done, see
+//! [`Sandbox::violations`].
+//!
+//! Other platforms aren't supported, and enabling the sandbox there is an
+//! error rather than silently running build scripts unconfined.
+//!
+//! [Landlock]: https://docs.kernel.org/userspace-api/landlock.html
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use cargo_util::{paths, ProcessBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::core::compiler::{BuildRunner, CompileKind, Unit};
+use crate::CargoResult;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+/// Environment variable set for Cargo when it runs as the wrapper of a build
+/// script, containing the JSON encoded [`SandboxConfig`].
+const SANDBOX_ENV: &str = "__CARGO_BUILD_SCRIPT_SANDBOX";
+
+/// System directories build scripts can always read from, for their shared
+/// libraries and the tools they commonly run.
+const SYSTEM_READ_PATHS: &[&str] = &[
+    "/bin",
+    "/dev",
+    "/etc",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/nix/store",
+    "/proc",
+    "/sbin",
+    "/sys",
+    "/usr",
+];
+
+/// What a sandboxed build script can access, passed from Cargo to the
+/// wrapper.
+#[derive(Debug, Serialize, Deserialize)]
+struct SandboxConfig {
+    /// Directories the build script can write to (and read from).
+    write: Vec<PathBuf>,
+    /// Files and directories the build script can read from.
+    read: Vec<PathBuf>,
+    /// File in which the wrapper records the accesses that were denied.
+    violations: PathBuf,
+}
+
+/// An access a build script attempted that its sandbox denied.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Violation {
+    Write(PathBuf),
+    Read(PathBuf),
+    Execute(PathBuf),
+    Network,
+}
+
+impl Violation {
+    /// Whether the build fails because of this, even if the build script
+    /// succeeded.
+    ///
+    /// Build scripts and the tools they run commonly probe for files and
+    /// carry on when they can't be read, so denied reads only fail the build
+    /// along with the build script.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Violation::Write(_) | Violation::Network)
+    }
+
+    fn parse(line: &str) -> Option<Violation> {
+        let (kind, path) = line.split_once('\t')?;
+        let path = PathBuf::from(path);
+        match kind {
+            "write" => Some(Violation::Write(path)),
+            "read" => Some(Violation::Read(path)),
+            "execute" => Some(Violation::Execute(path)),
+            "network" => Some(Violation::Network),
+            _ => None,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match self {
+            Violation::Write(path) => format!("write\t{}", path.display()),
+            Violation::Read(path) => format!("read\t{}", path.display()),
+            Violation::Execute(path) => format!("execute\t{}", path.display()),
+            Violation::Network => String::from("network\t"),
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Write(path) => write!(f, "write to `{}`", path.display()),
+            Violation::Read(path) => write!(f, "read from `{}`", path.display()),
+            Violation::Execute(path) => write!(f, "execute `{}`", path.display()),
+            Violation::Network => f.write_str("access the network"),
+        }
+    }
+}
+
+/// The sandbox of a single build script execution.
+pub struct Sandbox {
+    config: SandboxConfig,
+    /// The temporary directory of the build script.
+    tmp_dir: PathBuf,
+    /// The Cargo executable, which runs as the wrapper.
+    cargo_exe: PathBuf,
+}
+
+impl Sandbox {
+    /// Determines what the build script run by `unit` can access.
+    ///
+    /// Besides `OUT_DIR` and its temporary directory, a build script can read
+    /// its own package, the workspace, the target directory, the sources of
+    /// the dependencies in the Cargo home, the Rust toolchain, the [system directories](SYSTEM_READ_PATHS) and
+    /// whatever is listed in `build-scripts.sandbox.read`.
+    pub fn new(
+        build_runner: &BuildRunner<'_, '_>,
+        unit: &Unit,
+        script_out_dir: &Path,
+        script_run_dir: &Path,
+    ) -> CargoResult<Sandbox> {
+        check_supported()?;
+        let bcx = build_runner.bcx;
+        let gctx = bcx.gctx;
+        let tmp_dir = script_run_dir.join("tmp");
+
+        let mut read = vec![
+            unit.pkg.root().to_path_buf(),
+            bcx.ws.root().to_path_buf(),
+            bcx.ws.target_dir().into_path_unlocked(),
+            // Not the whole Cargo home, which holds the registry credentials.
+            gctx.home().as_path_unlocked().join("registry/src"),
+            gctx.home().as_path_unlocked().join("git/checkouts"),
+            bcx.target_data.info(CompileKind::Host).sysroot.clone(),
+        ];
+        if let Some(bin_dir) = bcx.rustc().path.parent() {
+            read.push(bin_dir.to_path_buf());
+        }
+        match gctx.get_env_os("RUSTUP_HOME") {
+            Some(rustup_home) => read.push(PathBuf::from(rustup_home)),
+            None => {
+                if let Some(home) = home::home_dir() {
+                    read.push(home.join(".rustup"));
+                }
+            }
+        }
+        read.extend(SYSTEM_READ_PATHS.iter().map(PathBuf::from));
+        if let Some(declared) = gctx
+            .build_scripts_config()?
+            .sandbox
+            .as_ref()
+            .and_then(|sandbox| sandbox.read.as_ref())
+        {
+            read.extend(declared.iter().map(|path| path.resolve_path(gctx)));
+        }
+
+        Ok(Sandbox {
+            config: SandboxConfig {
+                write: vec![script_out_dir.to_path_buf(), tmp_dir.clone()],
+                read,
+                violations: script_run_dir.join("sandbox-violations"),
+            },
+            tmp_dir,
+            cargo_exe: gctx.cargo_exe()?.to_path_buf(),
+        })
+    }
+
+    /// Returns `cmd` wrapped so that it runs in the sandbox, and prepares its
+    /// temporary directory.
+    pub fn wrap(&self, cmd: &ProcessBuilder) -> CargoResult<ProcessBuilder> {
+        if self.tmp_dir.exists() {
+            paths::remove_dir_all(&self.tmp_dir)?;
+        }
+        paths::create_dir_all(&self.tmp_dir)?;
+        if self.config.violations.exists() {
+            paths::remove_file(&self.config.violations)?;
+        }
+
+        let mut cmd = cmd.clone().wrapped(Some(&self.cargo_exe));
+        cmd.env(SANDBOX_ENV, serde_json::to_string(&self.config)?)
+            .env("TMPDIR", &self.tmp_dir)
+            .env("TMP", &self.tmp_dir)
+            .env("TEMP", &self.tmp_dir);
+        Ok(cmd)
+    }
+
+    /// The accesses the sandbox denied during the last run of the build
+    /// script.
+    pub fn violations(&self) -> CargoResult<Vec<Violation>> {
+        if !self.config.violations.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = paths::read(&self.config.violations)?;
+        Ok(contents.lines().filter_map(Violation::parse).collect())
+    }
+
+    /// Describes where the build script can write, for diagnostics.
+    pub fn describe_writable(&self) -> String {
+        let dirs: Vec<_> = self
+            .config
+            .write
+            .iter()
+            .map(|dir| format!("`{}`", dir.display()))
+            .collect();
+        dirs.join(" and ")
+    }
+}
+
+/// Returns an error if build scripts can't be sandboxed on this system.
+fn check_supported() -> CargoResult<()> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::check_supported()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        anyhow::bail!("`-Zbuild-script-sandbox` is only supported on Linux")
+    }
+}
+
+/// Returns the configuration of the sandbox if Cargo is running as the
+/// wrapper of a build script.
+///
+/// This is checked every time `cargo` is run, like
+/// [`fix_get_proxy_lock_addr`](crate::ops::fix_get_proxy_lock_addr).
+pub fn sandbox_get_config() -> Option<String> {
+    // ALLOWED: For the internal mechanism of `-Zbuild-script-sandbox` only.
+    // Shouldn't be set directly by anyone.
+    #[allow(clippy::disallowed_methods)]
+    std::env::var(SANDBOX_ENV).ok()
+}
+
+/// Entry point for `cargo` running as the wrapper of a build script.
+///
+/// The build script is given by the arguments. This does not return if the
+/// build script could be run: the process exits like the build script did.
+pub fn sandbox_exec(config: &str) -> CargoResult<()> {
+    let config: SandboxConfig =
+        serde_json::from_str(config).context("invalid build script sandbox configuration")?;
+    let mut args = std::env::args_os().skip(1);
+    let program = args
+        .next()
+        .context("no build script given to the sandbox wrapper")?;
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args).env_remove(SANDBOX_ENV);
+
+    #[cfg(target_os = "linux")]
+    {
+        let (status, violations) = linux::run(cmd, &config.write, &config.read)?;
+        record_violations(&config.violations, &violations)?;
+        linux::exit_like(status)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = cmd;
+        anyhow::bail!("`-Zbuild-script-sandbox` is only supported on Linux")
+    }
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn record_violations(path: &Path, violations: &BTreeSet<Violation>) -> CargoResult<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let mut contents = String::new();
+    for violation in violations {
+        contents.push_str(&violation.to_line());
+        contents.push('\n');
+    }
+    paths::write(path, contents)
+}
@@ -34,6 +34,7 @@
 use super::{fingerprint, BuildRunner, Job, Unit, Work};
 use crate::core::compiler::artifact;
 use crate::core::compiler::build_runner::Metadata;
+use crate::core::compiler::build_script_sandbox::{Sandbox, Violation};
 use crate::core::compiler::fingerprint::DirtyReason;
 use crate::core::compiler::job_queue::JobState;
 use crate::core::{profiles::ProfileRoot, PackageId, Target};
@@ -47,6 +48,7 @@ use cargo_util_schemas::manifest::RustVersion;
 use std::collections::hash_map::{Entry, HashMap};
 use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
+use std::process::Output;
 use std::str::{self, FromStr};
 use std::sync::{Arc, Mutex};
 
@@ -403,6 +405,16 @@ fn build_work(build_runner: &mut BuildRunner<'_, '_>, unit: &Unit) -> CargoResul
     let extra_verbose = bcx.gctx.extra_verbose();
     let (prev_output, prev_script_out_dir) = prev_build_output(build_runner, unit);
     let metadata_hash = build_runner.get_run_build_script_metadata(unit);
+    let sandbox = if build_runner.bcx.gctx.cli_unstable().build_script_sandbox {
+        Some(Sandbox::new(
+            build_runner,
+            unit,
+            &script_out_dir,
+            &script_run_dir,
+        )?)
+    } else {
+        None
+    };
 
     paths::create_dir_all(&script_dir)?;
     paths::create_dir_all(&script_out_dir)?;
@@ -482,7 +494,10 @@ fn build_work(build_runner: &mut BuildRunner<'_, '_>, unit: &Unit) -> CargoResul
         let timestamp = paths::set_invocation_time(&script_run_dir)?;
         let prefix = format!("[{} {}] ", id.name(), id.version());
         let mut warnings_in_case_of_panic = Vec::new();
-        let output = cmd
+        let sandboxed_cmd = sandbox.as_ref().map(|s| s.wrap(&cmd)).transpose()?;
+        let output = sandboxed_cmd
+            .as_ref()
+            .unwrap_or(&cmd)
             .exec_with_streaming(
                 &mut |stdout| {
                     if let Some(warning) = stdout
@@ -528,6 +543,10 @@ fn build_work(build_runner: &mut BuildRunner<'_, '_>, unit: &Unit) -> CargoResul
 
                 build_error_context
             });
+        let output = match &sandbox {
+            Some(sandbox) => check_sandbox_violations(sandbox, &pkg_descr, output, state)?,
+            None => output,
+        };
 
         if let Err(error) = output {
             insert_warnings_in_build_outputs(
@@ -620,6 +639,42 @@ fn build_work(build_runner: &mut BuildRunner<'_, '_>, unit: &Unit) -> CargoResul
     Ok(job)
 }
 
+/// Reports the accesses the sandbox of a build script denied.
+///
+/// Denied writes and network accesses fail the build, as the build script
+/// most likely didn't work as intended. Other denied accesses only do when
+/// the build script failed, and are otherwise reported as warnings.
+fn check_sandbox_violations(
+    sandbox: &Sandbox,
+    pkg_descr: &str,
+    output: CargoResult<Output>,
+    state: &JobState<'_, '_>,
+) -> CargoResult<CargoResult<Output>> {
+    let violations = sandbox.violations()?;
+    if violations.iter().any(Violation::is_fatal) || (output.is_err() && !violations.is_empty()) {
+        let mut msg =
+            format!("build script of `{pkg_descr}` was denied access outside of its sandbox:");
+        for violation in &violations {
+            msg.push_str(&format!("\n  it tried to {violation}"));
+        }
+        msg.push_str(&format!(
+            "\nnote: build scripts can only write to {}, \
+             and read from paths declared in `build-scripts.sandbox.read`",
+            sandbox.describe_writable()
+        ));
+        return Ok(match output {
+            Ok(_) => Err(anyhow::format_err!(msg)),
+            Err(e) => Err(e.context(msg)),
+        });
+    }
+    for violation in &violations {
+        state.warning(format!(
+            "build script was denied access outside of its sandbox: it tried to {violation}"
+        ))?;
+    }
+    Ok(output)
+}
+
 /// When a build script run fails, store only warnings and nuke other outputs,
 /// as they are likely broken.
 fn insert_warnings_in_build_outputs(
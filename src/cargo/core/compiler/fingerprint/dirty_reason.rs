@@ -146,81 +146,118 @@ impl DirtyReason {
         }
     }
 
-    pub fn present_to(&self, s: &mut Shell, unit: &Unit, root: &Path) -> CargoResult<()> {
+    /// The name of the dependency this unit was rebuilt because of, if any.
+    ///
+    /// This is the name the dependency is known by in the unit that depends
+    /// on it, see [`UnitDep::extern_crate_name`].
+    ///
+    /// [`UnitDep::extern_crate_name`]: crate::core::compiler::unit_graph::UnitDep::extern_crate_name
+    pub fn dependency(&self) -> Option<InternedString> {
+        match self {
+            DirtyReason::UnitDependencyInfoChanged { new_name, .. } => Some(*new_name),
+            DirtyReason::FsStatusOutdated(
+                FsStatus::StaleDependency { name, .. } | FsStatus::StaleDepFingerprint { name },
+            ) => Some(*name),
+            _ => None,
+        }
+    }
+
+    /// A short, stable identifier of the kind of reason, used in JSON
+    /// messages.
+    pub fn kind(&self) -> &'static str {
         match self {
-            DirtyReason::RustcChanged => s.dirty_because(unit, "the toolchain changed"),
-            DirtyReason::FeaturesChanged { .. } => {
-                s.dirty_because(unit, "the list of features changed")
+            DirtyReason::RustcChanged => "rustc-changed",
+            DirtyReason::FeaturesChanged { .. } => "features-changed",
+            DirtyReason::DeclaredFeaturesChanged { .. } => "declared-features-changed",
+            DirtyReason::TargetConfigurationChanged => "target-configuration-changed",
+            DirtyReason::PathToSourceChanged => "path-to-source-changed",
+            DirtyReason::ProfileConfigurationChanged => "profile-configuration-changed",
+            DirtyReason::RustflagsChanged { .. } => "rustflags-changed",
+            DirtyReason::MetadataChanged => "metadata-changed",
+            DirtyReason::ConfigSettingsChanged => "config-settings-changed",
+            DirtyReason::CompileKindChanged => "compile-kind-changed",
+            DirtyReason::LocalLengthsChanged => "local-lengths-changed",
+            DirtyReason::PrecalculatedComponentsChanged { .. } => {
+                "precalculated-components-changed"
+            }
+            DirtyReason::DepInfoOutputChanged { .. } => "dep-info-output-changed",
+            DirtyReason::RerunIfChangedOutputFileChanged { .. } => {
+                "rerun-if-changed-output-file-changed"
             }
+            DirtyReason::RerunIfChangedOutputPathsChanged { .. } => {
+                "rerun-if-changed-output-paths-changed"
+            }
+            DirtyReason::EnvVarsChanged { .. } => "env-vars-changed",
+            DirtyReason::EnvVarChanged { .. } => "env-var-changed",
+            DirtyReason::LocalFingerprintTypeChanged { .. } => "local-fingerprint-type-changed",
+            DirtyReason::NumberOfDependenciesChanged { .. } => "number-of-dependencies-changed",
+            DirtyReason::UnitDependencyNameChanged { .. } => "unit-dependency-name-changed",
+            DirtyReason::UnitDependencyInfoChanged { .. } => "unit-dependency-info-changed",
+            DirtyReason::FsStatusOutdated(status) => match status {
+                FsStatus::Stale => "stale",
+                FsStatus::StaleItem(StaleItem::MissingFile(_)) => "file-missing",
+                FsStatus::StaleItem(StaleItem::ChangedFile { .. }) => "file-changed",
+                FsStatus::StaleItem(StaleItem::ChangedEnv { .. }) => "env-changed",
+                FsStatus::StaleDependency { .. } => "stale-dependency",
+                FsStatus::StaleDepFingerprint { .. } => "stale-dependency-fingerprint",
+                FsStatus::UpToDate { .. } => unreachable!(),
+            },
+            DirtyReason::NothingObvious => "nothing-obvious",
+            DirtyReason::Forced => "forced",
+            DirtyReason::FreshBuild => "fresh-build",
+        }
+    }
+
+    /// Describes the reason in a few words, with paths relative to `root`.
+    pub fn description(&self, root: &Path) -> String {
+        match self {
+            DirtyReason::RustcChanged => "the toolchain changed".to_string(),
+            DirtyReason::FeaturesChanged { .. } => "the list of features changed".to_string(),
             DirtyReason::DeclaredFeaturesChanged { .. } => {
-                s.dirty_because(unit, "the list of declared features changed")
+                "the list of declared features changed".to_string()
             }
             DirtyReason::TargetConfigurationChanged => {
-                s.dirty_because(unit, "the target configuration changed")
-            }
-            DirtyReason::PathToSourceChanged => {
-                s.dirty_because(unit, "the path to the source changed")
+                "the target configuration changed".to_string()
             }
+            DirtyReason::PathToSourceChanged => "the path to the source changed".to_string(),
             DirtyReason::ProfileConfigurationChanged => {
-                s.dirty_because(unit, "the profile configuration changed")
-            }
-            DirtyReason::RustflagsChanged { .. } => s.dirty_because(unit, "the rustflags changed"),
-            DirtyReason::MetadataChanged => s.dirty_because(unit, "the metadata changed"),
-            DirtyReason::ConfigSettingsChanged => {
-                s.dirty_because(unit, "the config settings changed")
-            }
-            DirtyReason::CompileKindChanged => {
-                s.dirty_because(unit, "the rustc compile kind changed")
-            }
-            DirtyReason::LocalLengthsChanged => {
-                s.dirty_because(unit, "the local lengths changed")?;
-                s.note(
-                    "this could happen because of added/removed `cargo::rerun-if` instructions in the build script",
-                )?;
-
-                Ok(())
+                "the profile configuration changed".to_string()
             }
+            DirtyReason::RustflagsChanged { .. } => "the rustflags changed".to_string(),
+            DirtyReason::MetadataChanged => "the metadata changed".to_string(),
+            DirtyReason::ConfigSettingsChanged => "the config settings changed".to_string(),
+            DirtyReason::CompileKindChanged => "the rustc compile kind changed".to_string(),
+            DirtyReason::LocalLengthsChanged => "the local lengths changed".to_string(),
             DirtyReason::PrecalculatedComponentsChanged { .. } => {
-                s.dirty_because(unit, "the precalculated components changed")
+                "the precalculated components changed".to_string()
             }
             DirtyReason::DepInfoOutputChanged { .. } => {
-                s.dirty_because(unit, "the dependency info output changed")
+                "the dependency info output changed".to_string()
             }
             DirtyReason::RerunIfChangedOutputFileChanged { .. } => {
-                s.dirty_because(unit, "rerun-if-changed output file path changed")
+                "rerun-if-changed output file path changed".to_string()
             }
             DirtyReason::RerunIfChangedOutputPathsChanged { .. } => {
-                s.dirty_because(unit, "the rerun-if-changed instructions changed")
-            }
-            DirtyReason::EnvVarsChanged { .. } => {
-                s.dirty_because(unit, "the environment variables changed")
-            }
-            DirtyReason::EnvVarChanged { name, .. } => {
-                s.dirty_because(unit, format_args!("the env variable {name} changed"))
+                "the rerun-if-changed instructions changed".to_string()
             }
+            DirtyReason::EnvVarsChanged { .. } => "the environment variables changed".to_string(),
+            DirtyReason::EnvVarChanged { name, .. } => format!("the env variable {name} changed"),
             DirtyReason::LocalFingerprintTypeChanged { .. } => {
-                s.dirty_because(unit, "the local fingerprint type changed")
+                "the local fingerprint type changed".to_string()
+            }
+            DirtyReason::NumberOfDependenciesChanged { old, new } => {
+                format!("number of dependencies changed ({old} => {new})")
             }
-            DirtyReason::NumberOfDependenciesChanged { old, new } => s.dirty_because(
-                unit,
-                format_args!("number of dependencies changed ({old} => {new})",),
-            ),
-            DirtyReason::UnitDependencyNameChanged { old, new } => s.dirty_because(
-                unit,
-                format_args!("name of dependency changed ({old} => {new})"),
-            ),
-            DirtyReason::UnitDependencyInfoChanged { .. } => {
-                s.dirty_because(unit, "dependency info changed")
+            DirtyReason::UnitDependencyNameChanged { old, new } => {
+                format!("name of dependency changed ({old} => {new})")
             }
+            DirtyReason::UnitDependencyInfoChanged { .. } => "dependency info changed".to_string(),
             DirtyReason::FsStatusOutdated(status) => match status {
-                FsStatus::Stale => s.dirty_because(unit, "stale, unknown reason"),
+                FsStatus::Stale => "stale, unknown reason".to_string(),
                 FsStatus::StaleItem(item) => match item {
                     StaleItem::MissingFile(missing_file) => {
                         let file = missing_file.strip_prefix(root).unwrap_or(&missing_file);
-                        s.dirty_because(
-                            unit,
-                            format_args!("the file `{}` is missing", file.display()),
-                        )
+                        format!("the file `{}` is missing", file.display())
                     }
                     StaleItem::ChangedFile {
                         stale,
@@ -230,15 +267,11 @@ impl DirtyReason {
                     } => {
                         let file = stale.strip_prefix(root).unwrap_or(&stale);
                         let after = Self::after(*reference_mtime, *stale_mtime, "last build");
-                        s.dirty_because(
-                            unit,
-                            format_args!("the file `{}` has changed ({after})", file.display()),
-                        )
+                        format!("the file `{}` has changed ({after})", file.display())
+                    }
+                    StaleItem::ChangedEnv { var, .. } => {
+                        format!("the environment variable {var} changed")
                     }
-                    StaleItem::ChangedEnv { var, .. } => s.dirty_because(
-                        unit,
-                        format_args!("the environment variable {var} changed"),
-                    ),
                 },
                 FsStatus::StaleDependency {
                     name,
@@ -247,13 +280,10 @@ impl DirtyReason {
                     ..
                 } => {
                     let after = Self::after(*max_mtime, *dep_mtime, "last build");
-                    s.dirty_because(
-                        unit,
-                        format_args!("the dependency {name} was rebuilt ({after})"),
-                    )
+                    format!("the dependency {name} was rebuilt ({after})")
                 }
                 FsStatus::StaleDepFingerprint { name } => {
-                    s.dirty_because(unit, format_args!("the dependency {name} was rebuilt"))
+                    format!("the dependency {name} was rebuilt")
                 }
                 FsStatus::UpToDate { .. } => {
                     unreachable!()
@@ -261,10 +291,20 @@ impl DirtyReason {
             },
             DirtyReason::NothingObvious => {
                 // See comment in fingerprint compare method.
-                s.dirty_because(unit, "the fingerprint comparison turned up nothing obvious")
+                "the fingerprint comparison turned up nothing obvious".to_string()
             }
-            DirtyReason::Forced => s.dirty_because(unit, "forced"),
-            DirtyReason::FreshBuild => s.dirty_because(unit, "fresh build"),
+            DirtyReason::Forced => "forced".to_string(),
+            DirtyReason::FreshBuild => "fresh build".to_string(),
+        }
+    }
+
+    pub fn present_to(&self, s: &mut Shell, unit: &Unit, root: &Path) -> CargoResult<()> {
+        s.dirty_because(unit, self.description(root))?;
+        if let DirtyReason::LocalLengthsChanged = self {
+            s.note(
+                "this could happen because of added/removed `cargo::rerun-if` instructions in the build script",
+            )?;
         }
+        Ok(())
     }
 }
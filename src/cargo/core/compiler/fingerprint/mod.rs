@@ -1740,6 +1740,33 @@ pub fn dep_info_loc(build_runner: &mut BuildRunner<'_, '_>, unit: &Unit) -> Path
     build_runner.files().fingerprint_file_path(unit, "dep-")
 }
 
+/// Hashes everything in the fingerprint of `unit` that doesn't depend on the
+/// state of the target directory, for use as part of a [build cache] key.
+///
+/// The `deps` and `local` fields are left out: `local` tracks files in the
+/// target directory, and the build cache accounts for dependencies and for
+/// the environment variables listed in `local` itself once they are known.
+///
+/// [build cache]: super::build_cache
+pub fn build_cache_hash(build_runner: &mut BuildRunner<'_, '_>, unit: &Unit) -> CargoResult<u64> {
+    let fingerprint = calculate(build_runner, unit)?;
+    let mut hasher = StableHasher::new();
+    (
+        fingerprint.rustc,
+        &fingerprint.features,
+        &fingerprint.declared_features,
+        fingerprint.target,
+        fingerprint.profile,
+        fingerprint.path,
+        &fingerprint.rustflags,
+        fingerprint.metadata,
+        fingerprint.config,
+        fingerprint.compile_kind,
+    )
+        .hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
 /// Returns an absolute path that target directory.
 /// All paths are rewritten to be relative to this.
 fn target_root(build_runner: &BuildRunner<'_, '_>) -> PathBuf {
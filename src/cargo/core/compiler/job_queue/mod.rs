@@ -132,6 +132,7 @@ pub use self::job::Freshness::{self, Dirty, Fresh};
 pub use self::job::{Job, Work};
 pub use self::job_state::JobState;
 use super::build_runner::OutputFile;
+use super::rebuild_report::RebuildReport;
 use super::timings::Timings;
 use super::{BuildContext, BuildPlan, BuildRunner, CompileMode, Unit};
 use crate::core::compiler::descriptive_pkg_name;
@@ -181,6 +182,9 @@ struct DrainState<'gctx> {
     progress: Progress<'gctx>,
     next_id: u32,
     timings: Timings<'gctx>,
+    rebuild_report: RebuildReport,
+    /// The counts last reported with a `job-queue-concurrency` message.
+    last_concurrency: Option<machine_message::JobQueueConcurrency>,
 
     /// Tokens that are currently owned by this Cargo, and may be "associated"
     /// with a rustc process. They may also be unused, though if so will be
@@ -495,6 +499,8 @@ impl<'gctx> JobQueue<'gctx> {
             progress,
             next_id: 0,
             timings: self.timings,
+            rebuild_report: RebuildReport::new(build_runner.bcx),
+            last_concurrency: None,
             tokens: Vec::new(),
             pending_queue: Vec::new(),
             print: DiagnosticPrinter::new(
@@ -585,6 +591,20 @@ impl<'gctx> DrainState<'gctx> {
                     job.freshness(),
                 )?;
             }
+            self.rebuild_report.unit_started(&unit, job.freshness());
+            emit_build_event(
+                build_runner,
+                &machine_message::UnitStarted {
+                    package_id: unit.pkg.package_id().to_spec(),
+                    target: &unit.target,
+                    mode: unit.mode,
+                    fresh: job.freshness().is_fresh(),
+                    dirty_reason: match job.freshness() {
+                        Dirty(reason) => Some(reason.kind()),
+                        Fresh => None,
+                    },
+                },
+            )?;
             self.run(&unit, job, build_runner, scope);
         }
 
@@ -673,6 +693,17 @@ impl<'gctx> DrainState<'gctx> {
                     }
                 };
                 debug!("end ({:?}): {:?}", unit, result);
+                if let Artifact::All = artifact {
+                    emit_build_event(
+                        build_runner,
+                        &machine_message::UnitFinished {
+                            package_id: unit.pkg.package_id().to_spec(),
+                            target: &unit.target,
+                            mode: unit.mode,
+                            success: result.is_ok(),
+                        },
+                    )?;
+                }
                 match result {
                     Ok(()) => self.finish(id, &unit, artifact, build_runner)?,
                     Err(_) if build_runner.bcx.unit_can_fail_for_docscraping(&unit) => {
@@ -768,6 +799,9 @@ impl<'gctx> DrainState<'gctx> {
                     self.handle_error(&mut build_runner.bcx.gctx.shell(), &mut errors, e);
                 }
             }
+            if let Err(e) = self.report_concurrency(build_runner) {
+                self.handle_error(&mut build_runner.bcx.gctx.shell(), &mut errors, e);
+            }
 
             // If after all that we're not actually running anything then we're
             // done!
@@ -810,6 +844,9 @@ impl<'gctx> DrainState<'gctx> {
         if let Err(e) = self.timings.finished(build_runner, &errors.to_error()) {
             self.handle_error(&mut build_runner.bcx.gctx.shell(), &mut errors, e);
         }
+        if let Err(e) = self.rebuild_report.finished(build_runner) {
+            self.handle_error(&mut build_runner.bcx.gctx.shell(), &mut errors, e);
+        }
         if build_runner.bcx.build_config.emit_json() {
             let mut shell = build_runner.bcx.gctx.shell();
             let msg = machine_message::BuildFinished {
@@ -892,6 +929,21 @@ impl<'gctx> DrainState<'gctx> {
         );
     }
 
+    /// Emits a `job-queue-concurrency` message if the number of units in
+    /// each state changed.
+    fn report_concurrency(&mut self, build_runner: &BuildRunner<'_, '_>) -> CargoResult<()> {
+        let concurrency = machine_message::JobQueueConcurrency {
+            active: self.active.len(),
+            waiting: self.pending_queue.len(),
+            inactive: self.queue.len(),
+        };
+        if self.last_concurrency == Some(concurrency) {
+            return Ok(());
+        }
+        self.last_concurrency = Some(concurrency);
+        emit_build_event(build_runner, &concurrency)
+    }
+
     fn name_for_progress(&self, unit: &Unit) -> String {
         let pkg_name = unit.pkg.name();
         let target_name = unit.target.name();
@@ -1204,6 +1256,22 @@ feature resolver. Try updating to diesel 1.4.8 to fix this error.
     }
 }
 
+/// Emits `msg` on stdout if the progress of the build is reported with JSON
+/// messages, see `-Zbuild-events`.
+fn emit_build_event(
+    build_runner: &BuildRunner<'_, '_>,
+    msg: &impl machine_message::Message,
+) -> CargoResult<()> {
+    if build_runner.bcx.build_config.build_events {
+        writeln!(
+            build_runner.bcx.gctx.shell().out(),
+            "{}",
+            msg.to_json_string()
+        )?;
+    }
+    Ok(())
+}
+
 impl ErrorsDuringDrain {
     fn to_error(&self) -> Option<anyhow::Error> {
         match self.count {
@@ -0,0 +1,84 @@
+//! Tracking of when the units in a target directory were last used, for
+//! `cargo clean --max-age`.
+//!
+//! With `-Zclean-stale`, every successful build records the time at which it
+//! used each of its units, fresh or not, in the `.last-use` file of the
+//! directory of the profile. Units are identified by the hash in the names of
+//! their files, see `CompilationFiles::unit_hash`. The file is only read and
+//! written while holding the lock of that directory.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use cargo_util::paths;
+use tracing::debug;
+
+use super::layout::Layout;
+use super::BuildRunner;
+use crate::util::errors::CargoResult;
+
+/// The contents of a `.last-use` file.
+pub struct LastUse {
+    path: PathBuf,
+    /// Seconds since the Unix epoch at which each unit was last used.
+    entries: BTreeMap<String, u64>,
+}
+
+impl LastUse {
+    /// Loads the last-use file of `layout`.
+    ///
+    /// A missing or corrupt file is treated as empty, since the times are
+    /// only a hint for `cargo clean`.
+    pub fn load(layout: &Layout) -> LastUse {
+        let path = layout.last_use().to_path_buf();
+        let entries = match paths::read(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                debug!("ignoring invalid {}: {e}", path.display());
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        LastUse { path, entries }
+    }
+
+    /// Returns when the unit with the given hash was last used, if known.
+    pub fn get(&self, hash: &str) -> Option<SystemTime> {
+        let secs = *self.entries.get(hash)?;
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    pub fn insert(&mut self, hash: String, time: SystemTime) {
+        let secs = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.entries.insert(hash, secs);
+    }
+
+    pub fn remove(&mut self, hash: &str) {
+        self.entries.remove(hash);
+    }
+
+    pub fn save(&self) -> CargoResult<()> {
+        paths::write(&self.path, serde_json::to_string(&self.entries)?)
+    }
+}
+
+/// Records that every unit of the build was used now.
+pub fn record(build_runner: &BuildRunner<'_, '_>) -> CargoResult<()> {
+    let now = SystemTime::now();
+    let files = build_runner.files();
+    let mut last_uses = HashMap::new();
+    for unit in build_runner.bcx.unit_graph.keys() {
+        let last_use = match last_uses.entry(unit.kind) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(LastUse::load(files.layout(unit.kind))),
+        };
+        last_use.insert(files.unit_hash(unit), now);
+    }
+    for last_use in last_uses.values() {
+        last_use.save()?;
+    }
+    Ok(())
+}
@@ -22,6 +22,9 @@
 //!         # from using it at the same time.
 //!         .cargo-lock
 //!
+//!         # When each unit was last used, with `-Zclean-stale`.
+//!         .last-use
+//!
 //!         # Hidden directory that holds all of the fingerprint files for all
 //!         # packages
 //!         .fingerprint/
@@ -103,7 +106,7 @@
 
 use crate::core::compiler::CompileTarget;
 use crate::core::Workspace;
-use crate::util::{CargoResult, FileLock};
+use crate::util::{self, CargoResult, FileLock};
 use cargo_util::paths;
 use std::path::{Path, PathBuf};
 
@@ -132,6 +135,8 @@ pub struct Layout {
     doc: PathBuf,
     /// The directory for temporary data of integration tests and benches: `$dest/tmp`
     tmp: PathBuf,
+    /// The file recording when each unit was last used: `$dest/.last-use`
+    last_use: PathBuf,
     /// The lockfile for a build (`.cargo-lock`). Will be unlocked when this
     /// struct is `drop`ped.
     _lock: FileLock,
@@ -143,11 +148,13 @@ impl Layout {
     /// This function will block if the directory is already locked.
     ///
     /// `dest` should be the final artifact directory name. Currently either
-    /// "debug" or "release".
+    /// "debug" or "release". `build_events` reports waiting on the lock with
+    /// `-Zbuild-events`.
     pub fn new(
         ws: &Workspace<'_>,
         target: Option<CompileTarget>,
         dest: &str,
+        build_events: bool,
     ) -> CargoResult<Layout> {
         let mut root = ws.target_dir();
         if let Some(target) = target {
@@ -166,7 +173,14 @@ impl Layout {
         // For now we don't do any more finer-grained locking on the artifact
         // directory, so just lock the entire thing for the duration of this
         // compile.
-        let lock = dest.open_rw_exclusive_create(".cargo-lock", ws.gctx(), "build directory")?;
+        let lock = util::acquire_reporting_wait(
+            ws.gctx(),
+            build_events,
+            &dest.as_path_unlocked().join(".cargo-lock"),
+            "build directory",
+            || dest.try_open_rw_exclusive_create(".cargo-lock"),
+            || dest.open_rw_exclusive_create(".cargo-lock", ws.gctx(), "build directory"),
+        )?;
         let root = root.into_path_unlocked();
         let dest = dest.into_path_unlocked();
         let deps = dest.join("deps");
@@ -181,6 +195,7 @@ impl Layout {
             examples: dest.join("examples"),
             doc: root.join("doc"),
             tmp: root.join("tmp"),
+            last_use: dest.join(".last-use"),
             root,
             dest,
             _lock: lock,
@@ -234,6 +249,10 @@ impl Layout {
     pub fn artifact(&self) -> &Path {
         &self.artifact
     }
+    /// Fetch the path of the last-use file.
+    pub fn last_use(&self) -> &Path {
+        &self.last_use
+    }
     /// Create and return the tmp path.
     pub fn prepare_tmp(&self) -> CargoResult<&Path> {
         paths::create_dir_all(&self.tmp)?;
@@ -32,10 +32,12 @@
 //! [`ops::cargo_compile::compile`]: crate::ops::compile
 
 pub mod artifact;
+pub(crate) mod build_cache;
 mod build_config;
 pub(crate) mod build_context;
 mod build_plan;
 pub(crate) mod build_runner;
+pub(crate) mod build_script_sandbox;
 mod compilation;
 mod compile_kind;
 mod crate_type;
@@ -43,10 +45,12 @@ mod custom_build;
 pub(crate) mod fingerprint;
 pub mod future_incompat;
 pub(crate) mod job_queue;
+pub(crate) mod last_use;
 pub(crate) mod layout;
 mod links;
 mod lto;
 mod output_depinfo;
+mod rebuild_report;
 pub mod rustdoc;
 pub mod standard_lib;
 mod timings;
@@ -67,12 +71,14 @@ use anyhow::{bail, Context as _, Error};
 use lazycell::LazyCell;
 use tracing::{debug, trace};
 
+use self::build_cache::BuildCache;
 pub use self::build_config::{BuildConfig, CompileMode, MessageFormat, TimingOutput};
 pub use self::build_context::{
     BuildContext, FileFlavor, FileType, RustDocFingerprint, RustcTargetData, TargetInfo,
 };
 use self::build_plan::BuildPlan;
 pub use self::build_runner::{BuildRunner, Metadata};
+pub use self::build_script_sandbox::{sandbox_exec, sandbox_get_config};
 pub use self::compilation::{Compilation, Doctest, UnitOutput};
 pub use self::compile_kind::{CompileKind, CompileTarget};
 pub use self::crate_type::CrateType;
@@ -190,12 +196,19 @@ fn compile<'gctx>(
     } else {
         let force = exec.force_rebuild(unit) || force_rebuild;
         let mut job = fingerprint::prepare_target(build_runner, unit, force)?;
+        let build_cache = build_runner
+            .build_cache
+            .clone()
+            .filter(|_| BuildCache::is_cacheable(unit));
         job.before(if job.freshness().is_dirty() {
-            let work = if unit.mode.is_doc() || unit.mode.is_doc_scrape() {
+            let mut work = if unit.mode.is_doc() || unit.mode.is_doc_scrape() {
                 rustdoc(build_runner, unit)?
             } else {
                 rustc(build_runner, unit, exec)?
             };
+            if let Some(build_cache) = build_cache {
+                work = build_cache.dirty_work(build_runner, unit, work)?;
+            }
             work.then(link_targets(build_runner, unit, false)?)
         } else {
             // We always replay the output cache,
@@ -208,6 +221,10 @@ fn compile<'gctx>(
                 build_runner.bcx.build_config.message_format,
                 unit.show_warnings(bcx.gctx),
             );
+            let work = match build_cache {
+                Some(build_cache) => build_cache.fresh_work(build_runner, unit, work)?,
+                None => work,
+            };
             // Need to link targets on both the dirty and fresh.
             work.then(link_targets(build_runner, unit, true)?)
         });
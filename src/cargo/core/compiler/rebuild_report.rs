@@ -0,0 +1,157 @@
+//! Explanation of why units were rebuilt, for `--explain-rebuild`.
+//!
+//! The [`DirtyReason`] of every unit that gets rebuilt is recorded while the
+//! job queue runs. Once the build is over, each of them is printed along with
+//! the chain of dependencies that caused it: a unit that was rebuilt because
+//! a dependency was rebuilt points at that dependency, which in turn has a
+//! reason of its own, and so on until something that actually changed.
+
+use std::collections::{HashMap, HashSet};
+
+use super::fingerprint::DirtyReason;
+use super::job_queue::Freshness;
+use super::{BuildContext, BuildRunner, CompileMode, Unit};
+use crate::util::machine_message::{self, Message};
+use crate::util::CargoResult;
+
+/// The reasons units were rebuilt during a build.
+///
+/// Like [`Timings`], this is fed by `DrainState` as jobs start.
+///
+/// [`Timings`]: super::timings::Timings
+pub struct RebuildReport {
+    /// Whether `--explain-rebuild` was passed.
+    enabled: bool,
+    /// Units that were rebuilt, in the order they started.
+    rebuilt: Vec<(Unit, DirtyReason)>,
+}
+
+impl RebuildReport {
+    pub fn new(bcx: &BuildContext<'_, '_>) -> RebuildReport {
+        RebuildReport {
+            enabled: bcx.build_config.explain_rebuild,
+            rebuilt: Vec::new(),
+        }
+    }
+
+    /// Records the freshness of a unit whose job is about to start.
+    pub fn unit_started(&mut self, unit: &Unit, freshness: &Freshness) {
+        if !self.enabled {
+            return;
+        }
+        if let Freshness::Dirty(reason) = freshness {
+            self.rebuilt.push((unit.clone(), reason.clone()));
+        }
+    }
+
+    /// Prints the report, and emits a `unit-rebuilt` message per unit if JSON
+    /// messages are enabled.
+    pub fn finished(&self, build_runner: &BuildRunner<'_, '_>) -> CargoResult<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let gctx = build_runner.bcx.gctx;
+        let root = build_runner.bcx.ws.root();
+        let reasons: HashMap<&Unit, &DirtyReason> = self
+            .rebuilt
+            .iter()
+            .map(|(unit, reason)| (unit, reason))
+            .collect();
+
+        let mut shell = gctx.shell();
+        if self.rebuilt.is_empty() {
+            shell.note("no units were rebuilt")?;
+        }
+        for (unit, reason) in &self.rebuilt {
+            let dep = self.cause(build_runner, &reasons, unit, reason);
+            if build_runner.bcx.build_config.emit_json() {
+                let msg = machine_message::UnitRebuilt {
+                    package_id: unit.pkg.package_id().to_spec(),
+                    target: &unit.target,
+                    mode: unit.mode,
+                    cause: reason.kind(),
+                    message: reason.description(root),
+                    dependency: dep.map(|dep| machine_message::RebuiltDependency {
+                        package_id: dep.pkg.package_id().to_spec(),
+                        target: &dep.target,
+                        mode: dep.mode,
+                    }),
+                }
+                .to_json_string();
+                writeln!(shell.out(), "{}", msg)?;
+            }
+
+            shell.status(
+                "Rebuilt",
+                format!("{}: {}", describe(unit), reason.description(root)),
+            )?;
+            // Follow the dependencies that cascaded into this unit until
+            // something that changed by itself.
+            let mut seen = HashSet::from([unit]);
+            let mut next = dep;
+            while let Some(dep) = next {
+                if !seen.insert(dep) {
+                    break;
+                }
+                let Some(reason) = reasons.get(dep) else {
+                    break;
+                };
+                shell.note(format!(
+                    "{} was rebuilt because {}",
+                    describe(dep),
+                    reason.description(root)
+                ))?;
+                next = self.cause(build_runner, &reasons, dep, reason);
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the dependency of `unit` that `reason` refers to.
+    ///
+    /// A dependency that was also rebuilt is preferred, since several units
+    /// can share the same name, such as a build script and its execution.
+    fn cause<'a>(
+        &self,
+        build_runner: &'a BuildRunner<'_, '_>,
+        reasons: &HashMap<&Unit, &DirtyReason>,
+        unit: &Unit,
+        reason: &DirtyReason,
+    ) -> Option<&'a Unit> {
+        let name = reason.dependency()?;
+        let mut candidates = build_runner
+            .unit_deps(unit)
+            .iter()
+            .filter(|dep| dep.extern_crate_name == name)
+            .map(|dep| &dep.unit)
+            .peekable();
+        let first = *candidates.peek()?;
+        Some(
+            candidates
+                .find(|dep| reasons.contains_key(dep))
+                .unwrap_or(first),
+        )
+    }
+}
+
+/// Describes a unit as `name version`, followed by its target and mode unless
+/// it is the library being built, which is the common case.
+fn describe(unit: &Unit) -> String {
+    let mut desc = format!("{} v{}", unit.pkg.name(), unit.pkg.version());
+    if !(unit.target.is_lib() && unit.mode == CompileMode::Build) {
+        desc.push(' ');
+        desc.push_str(&unit.target.description_named());
+    }
+    match unit.mode {
+        CompileMode::Test => desc.push_str(" (test)"),
+        CompileMode::Build => {}
+        CompileMode::Check { test: true } => desc.push_str(" (check-test)"),
+        CompileMode::Check { test: false } => desc.push_str(" (check)"),
+        CompileMode::Bench => desc.push_str(" (bench)"),
+        CompileMode::Doc { .. } => desc.push_str(" (doc)"),
+        CompileMode::Doctest => desc.push_str(" (doc test)"),
+        CompileMode::Docscrape => desc.push_str(" (doc scrape)"),
+        CompileMode::RunCustomBuild => desc.push_str(" (run)"),
+    }
+    desc
+}
@@ -254,7 +254,7 @@ pub fn add_output_format(
     rustdoc: &mut ProcessBuilder,
 ) -> CargoResult<()> {
     let gctx = build_runner.bcx.gctx;
-    if !gctx.cli_unstable().unstable_options {
+    if !gctx.cli_unstable().unstable_options && !build_runner.bcx.build_config.rustdoc_json {
         tracing::debug!("`unstable-options` is ignored, required -Zunstable-options flag");
         return Ok(());
     }
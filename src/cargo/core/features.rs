@@ -754,12 +754,19 @@ unstable_cli_options!(
     avoid_dev_deps: bool = ("Avoid installing dev-dependencies if possible"),
     binary_dep_depinfo: bool = ("Track changes to dependency artifacts"),
     bindeps: bool = ("Allow Cargo packages to depend on bin, cdylib, and staticlib crates, and use the artifacts built by those crates"),
+    build_cache: bool = ("Share compiled dependencies between workspaces through a local cache directory"),
+    build_events: bool = ("Emit JSON messages about the progress of the build with `--message-format=json`"),
+    build_script_allowlist: bool = ("Only run build scripts and proc-macros of dependencies listed in `build-scripts.allow`"),
+    build_script_sandbox: bool = ("Run build scripts in a sandbox that confines their writes to OUT_DIR"),
     #[serde(deserialize_with = "deserialize_build_std")]
     build_std: Option<Vec<String>>  = ("Enable Cargo to compile the standard library itself as part of a crate graph compilation"),
     build_std_features: Option<Vec<String>>  = ("Configure features enabled for the standard library itself when building the standard library"),
     cargo_lints: bool = ("Enable the `[lints.cargo]` table"),
+    clean_stale: bool = ("Enable `cargo clean --stale` and `--max-age`, and track when units are used"),
     codegen_backend: bool = ("Enable the `codegen-backend` option in profiles in .cargo/config.toml file"),
+    config_check: bool = ("Warn about the keys of the configuration that Cargo doesn't know"),
     config_include: bool = ("Enable the `include` key in config files"),
+    config_when: bool = ("Enable the conditional `[when.<condition>]` tables in config files"),
     direct_minimal_versions: bool = ("Resolve minimal dependency versions instead of maximum (direct dependencies only)"),
     doctest_xcompile: bool = ("Compile and run doctests for non-host target using runner config"),
     dual_proc_macros: bool = ("Build proc-macros for both the host and the target"),
@@ -768,11 +775,15 @@ unstable_cli_options!(
     git: Option<GitFeatures> = ("Enable support for shallow git fetch operations"),
     gitoxide: Option<GitoxideFeatures> = ("Use gitoxide for the given git interactions, or all of them if no argument is given"),
     host_config: bool = ("Enable the `[host]` section in the .cargo/config.toml file"),
+    install_from: bool = ("Enable `cargo install --from` to install the tools listed in a file"),
+    install_prebuilt: bool = ("Reuse prebuilt binaries from `install.prebuilt-dir` in `cargo install`"),
+    lockfile_merge: bool = ("Resolve merge conflicts in Cargo.lock, and enable `cargo generate-lockfile --merge`"),
     minimal_versions: bool = ("Resolve minimal dependency versions instead of maximum"),
     msrv_policy: bool = ("Enable rust-version aware policy within cargo"),
     mtime_on_use: bool = ("Configure Cargo to update the mtime of used files"),
     next_lockfile_bump: bool,
     no_index_update: bool = ("Do not update the registry index even if the cache is outdated"),
+    package_workspace: bool = ("Handle intra-workspace dependencies when packaging and publishing"),
     panic_abort_tests: bool = ("Enable support to run tests with -Cpanic=abort"),
     profile_rustflags: bool = ("Enable the `rustflags` option in profiles in .cargo/config.toml file"),
     public_dependency: bool = ("Respect a dependency's `public` field in Cargo.toml to control public/private dependencies"),
@@ -780,11 +791,15 @@ unstable_cli_options!(
     rustdoc_map: bool = ("Allow passing external documentation mappings to rustdoc"),
     rustdoc_scrape_examples: bool = ("Allows Rustdoc to scrape code examples from reverse-dependencies"),
     script: bool = ("Enable support for single-file, `.rs` packages"),
+    semver_check: bool = ("Allow checking the API of packages against SemVer when packaging and publishing"),
     separate_nightlies: bool,
     skip_rustdoc_fingerprint: bool,
     target_applies_to_host: bool = ("Enable the `target-applies-to-host` key in the .cargo/config.toml file"),
+    test_events: bool = ("Emit test results as JSON messages with `--message-format=json`"),
     trim_paths: bool = ("Enable the `trim-paths` option in profiles"),
     unstable_options: bool = ("Allow the usage of unstable options"),
+    update_explain: bool = ("Enable `cargo update --explain` to report why packages aren't at their latest version"),
+    vendor_sync: bool = ("Enable incremental `cargo vendor`, its per-source layout and `--verify`"),
 );
 
 const STABILIZED_COMPILE_PROGRESS: &str = "The progress bar is now always \
@@ -1125,13 +1140,20 @@ impl CliUnstable {
             "avoid-dev-deps" => self.avoid_dev_deps = parse_empty(k, v)?,
             "binary-dep-depinfo" => self.binary_dep_depinfo = parse_empty(k, v)?,
             "bindeps" => self.bindeps = parse_empty(k, v)?,
+            "build-cache" => self.build_cache = parse_empty(k, v)?,
+            "build-events" => self.build_events = parse_empty(k, v)?,
+            "build-script-allowlist" => self.build_script_allowlist = parse_empty(k, v)?,
+            "build-script-sandbox" => self.build_script_sandbox = parse_empty(k, v)?,
             "build-std" => {
                 self.build_std = Some(crate::core::compiler::standard_lib::parse_unstable_flag(v))
             }
             "build-std-features" => self.build_std_features = Some(parse_features(v)),
             "cargo-lints" => self.cargo_lints = parse_empty(k, v)?,
+            "clean-stale" => self.clean_stale = parse_empty(k, v)?,
             "codegen-backend" => self.codegen_backend = parse_empty(k, v)?,
+            "config-check" => self.config_check = parse_empty(k, v)?,
             "config-include" => self.config_include = parse_empty(k, v)?,
+            "config-when" => self.config_when = parse_empty(k, v)?,
             "direct-minimal-versions" => self.direct_minimal_versions = parse_empty(k, v)?,
             "doctest-xcompile" => self.doctest_xcompile = parse_empty(k, v)?,
             "dual-proc-macros" => self.dual_proc_macros = parse_empty(k, v)?,
@@ -1149,12 +1171,16 @@ impl CliUnstable {
                 )?
             }
             "host-config" => self.host_config = parse_empty(k, v)?,
+            "install-from" => self.install_from = parse_empty(k, v)?,
+            "install-prebuilt" => self.install_prebuilt = parse_empty(k, v)?,
+            "lockfile-merge" => self.lockfile_merge = parse_empty(k, v)?,
             "next-lockfile-bump" => self.next_lockfile_bump = parse_empty(k, v)?,
             "minimal-versions" => self.minimal_versions = parse_empty(k, v)?,
             "msrv-policy" => self.msrv_policy = parse_empty(k, v)?,
             // can also be set in .cargo/config or with and ENV
             "mtime-on-use" => self.mtime_on_use = parse_empty(k, v)?,
             "no-index-update" => self.no_index_update = parse_empty(k, v)?,
+            "package-workspace" => self.package_workspace = parse_empty(k, v)?,
             "panic-abort-tests" => self.panic_abort_tests = parse_empty(k, v)?,
             "public-dependency" => self.public_dependency = parse_empty(k, v)?,
             "profile-rustflags" => self.profile_rustflags = parse_empty(k, v)?,
@@ -1165,8 +1191,12 @@ impl CliUnstable {
             "separate-nightlies" => self.separate_nightlies = parse_empty(k, v)?,
             "skip-rustdoc-fingerprint" => self.skip_rustdoc_fingerprint = parse_empty(k, v)?,
             "script" => self.script = parse_empty(k, v)?,
+            "semver-check" => self.semver_check = parse_empty(k, v)?,
             "target-applies-to-host" => self.target_applies_to_host = parse_empty(k, v)?,
+            "test-events" => self.test_events = parse_empty(k, v)?,
             "unstable-options" => self.unstable_options = parse_empty(k, v)?,
+            "update-explain" => self.update_explain = parse_empty(k, v)?,
+            "vendor-sync" => self.vendor_sync = parse_empty(k, v)?,
             _ => bail!("\
             unknown `-Z` flag specified: {k}\n\n\
             For available unstable features, see https://doc.rust-lang.org/nightly/cargo/reference/unstable.html\n\
@@ -1178,35 +1208,35 @@ impl CliUnstable {
 
     /// Generates an error if `-Z unstable-options` was not used for a new,
     /// unstable command-line flag.
-    pub fn fail_if_stable_opt(&self, flag: &str, issue: u32) -> CargoResult<()> {
+    ///
+    /// `issue` is the tracking issue of the flag, if it has one.
+    pub fn fail_if_stable_opt(&self, flag: &str, issue: impl Into<Option<u32>>) -> CargoResult<()> {
         self.fail_if_stable_opt_custom_z(flag, issue, "unstable-options", self.unstable_options)
     }
 
     pub fn fail_if_stable_opt_custom_z(
         &self,
         flag: &str,
-        issue: u32,
+        issue: impl Into<Option<u32>>,
         z_name: &str,
         enabled: bool,
     ) -> CargoResult<()> {
         if !enabled {
-            let see = format!(
-                "See https://github.com/rust-lang/cargo/issues/{issue} for more \
-                 information about the `{flag}` flag."
-            );
+            let see = issue.into().map_or_else(String::new, |issue| {
+                format!(
+                    "\nSee https://github.com/rust-lang/cargo/issues/{issue} for more \
+                     information about the `{flag}` flag."
+                )
+            });
             // NOTE: a `config` isn't available here, check the channel directly
             let channel = channel();
             if channel == "nightly" || channel == "dev" {
-                bail!(
-                    "the `{flag}` flag is unstable, pass `-Z {z_name}` to enable it\n\
-                     {see}"
-                );
+                bail!("the `{flag}` flag is unstable, pass `-Z {z_name}` to enable it{see}");
             } else {
                 bail!(
                     "the `{flag}` flag is unstable, and only available on the nightly channel \
                      of Cargo, but this is the `{channel}` channel\n\
-                     {SEE_CHANNELS}\n\
-                     {see}"
+                     {SEE_CHANNELS}{see}"
                 );
             }
         }
@@ -1215,34 +1245,34 @@ impl CliUnstable {
 
     /// Generates an error if `-Z unstable-options` was not used for a new,
     /// unstable subcommand.
+    ///
+    /// `issue` is the tracking issue of the command, if it has one.
     pub fn fail_if_stable_command(
         &self,
         gctx: &GlobalContext,
         command: &str,
-        issue: u32,
+        issue: impl Into<Option<u32>>,
         z_name: &str,
         enabled: bool,
     ) -> CargoResult<()> {
         if enabled {
             return Ok(());
         }
-        let see = format!(
-            "See https://github.com/rust-lang/cargo/issues/{} for more \
-            information about the `cargo {}` command.",
-            issue, command
-        );
+        let see = issue.into().map_or_else(String::new, |issue| {
+            format!(
+                "\nSee https://github.com/rust-lang/cargo/issues/{issue} for more \
+                 information about the `cargo {command}` command."
+            )
+        });
         if gctx.nightly_features_allowed {
             bail!(
-                "the `cargo {command}` command is unstable, pass `-Z {z_name}` \
-                 to enable it\n\
-                 {see}",
+                "the `cargo {command}` command is unstable, pass `-Z {z_name}` to enable it{see}"
             );
         } else {
             bail!(
                 "the `cargo {}` command is unstable, and only available on the \
                  nightly channel of Cargo, but this is the `{}` channel\n\
-                 {}\n\
-                 {}",
+                 {}{}",
                 command,
                 channel(),
                 SEE_CHANNELS,
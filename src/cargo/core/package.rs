@@ -28,11 +28,12 @@ use crate::sources::source::{MaybePackage, SourceMap};
 use crate::util::cache_lock::{CacheLock, CacheLockMode};
 use crate::util::errors::{CargoResult, HttpNotSuccessful};
 use crate::util::interning::InternedString;
+use crate::util::machine_message;
 use crate::util::network::http::http_handle_and_timeout;
 use crate::util::network::http::HttpTimeout;
 use crate::util::network::retry::{Retry, RetryResult};
 use crate::util::network::sleep::SleepTracker;
-use crate::util::{self, internal, GlobalContext, Progress, ProgressStyle};
+use crate::util::{self, internal, GlobalContext, Progress, ProgressStyle, Throttle};
 
 /// Information about a package that is available somewhere in the file system.
 ///
@@ -311,6 +312,8 @@ pub struct Downloads<'a, 'gctx> {
     next: usize,
     /// Progress bar.
     progress: RefCell<Option<Progress<'gctx>>>,
+    /// Limits the `download-progress` messages of `-Zbuild-events`.
+    event_throttle: RefCell<Throttle>,
     /// Number of downloads that have successfully finished.
     downloads_finished: usize,
     /// Total bytes for all successfully downloaded packages.
@@ -435,6 +438,7 @@ impl<'gctx> PackageSet<'gctx> {
                 ProgressStyle::Ratio,
                 self.gctx,
             ))),
+            event_throttle: RefCell::new(Throttle::new()),
             downloads_finished: 0,
             downloaded_bytes: 0,
             largest: (0, InternedString::new("")),
@@ -460,7 +464,7 @@ impl<'gctx> PackageSet<'gctx> {
         let mut pkgs = Vec::new();
         let _lock = self
             .gctx
-            .acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
+            .acquire_package_cache_lock_reporting_wait(CacheLockMode::DownloadExclusive)?;
         let mut downloads = self.enable_download()?;
         for id in ids {
             pkgs.extend(downloads.start(id)?);
@@ -761,6 +765,10 @@ impl<'a, 'gctx> Downloads<'a, 'gctx> {
         {
             self.set.gctx.shell().status("Downloading", "crates ...")?;
         }
+        self.emit_build_event(&machine_message::DownloadStarted {
+            package_id: id.to_spec(),
+            url: &url,
+        })?;
 
         let dl = Download {
             token,
@@ -871,6 +879,11 @@ impl<'a, 'gctx> Downloads<'a, 'gctx> {
         // progress bar is enabled this provides a good log of what's happening.
         self.progress.borrow_mut().as_mut().unwrap().clear();
         self.set.gctx.shell().status("Downloaded", &dl.descriptor)?;
+        self.emit_build_event(&machine_message::DownloadFinished {
+            package_id: dl.id.to_spec(),
+            bytes: dl.total.get(),
+            duration: dl.start.elapsed().as_secs_f64(),
+        })?;
 
         self.downloads_finished += 1;
         self.downloaded_bytes += dl.total.get();
@@ -1051,6 +1064,15 @@ impl<'a, 'gctx> Downloads<'a, 'gctx> {
     }
 
     fn tick(&self, why: WhyTick<'_>) -> CargoResult<()> {
+        if let WhyTick::DownloadUpdate = why {
+            if self.event_throttle.borrow_mut().allowed() {
+                self.emit_build_event(&machine_message::DownloadProgress {
+                    pending: self.remaining(),
+                    remaining_bytes: self.remaining_bytes(),
+                })?;
+            }
+        }
+
         let mut progress = self.progress.borrow_mut();
         let progress = progress.as_mut().unwrap();
 
@@ -1070,17 +1092,12 @@ impl<'a, 'gctx> Downloads<'a, 'gctx> {
                 msg.push_str(&format!(", extracting {} ...", krate));
             }
             _ => {
-                let mut dur = Duration::new(0, 0);
-                let mut remaining = 0;
-                for (dl, _) in self.pending.values() {
-                    dur += dl.start.elapsed();
-                    // If the total/current look weird just throw out the data
-                    // point, sounds like curl has more to learn before we have
-                    // the true information.
-                    if dl.total.get() >= dl.current.get() {
-                        remaining += dl.total.get() - dl.current.get();
-                    }
-                }
+                let dur: Duration = self
+                    .pending
+                    .values()
+                    .map(|(dl, _)| dl.start.elapsed())
+                    .sum();
+                let remaining = self.remaining_bytes();
                 if remaining > 0 && dur > Duration::from_millis(500) {
                     msg.push_str(&format!(", remaining bytes: {}", ByteSize(remaining)));
                 }
@@ -1088,6 +1105,27 @@ impl<'a, 'gctx> Downloads<'a, 'gctx> {
         }
         progress.print_now(&msg)
     }
+
+    /// Emits `msg` on stdout if the downloads are reported with JSON messages.
+    fn emit_build_event(&self, msg: &impl machine_message::Message) -> CargoResult<()> {
+        let mut shell = self.set.gctx.shell();
+        if shell.build_events() {
+            writeln!(shell.out(), "{}", msg.to_json_string())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of bytes left to download.
+    fn remaining_bytes(&self) -> u64 {
+        self.pending
+            .values()
+            // If the total/current look weird just throw out the data
+            // point, sounds like curl has more to learn before we have
+            // the true information.
+            .filter(|(dl, _)| dl.total.get() >= dl.current.get())
+            .map(|(dl, _)| dl.total.get() - dl.current.get())
+            .sum()
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -196,6 +196,13 @@ pub struct LockedPatchDependency {
 impl<'gctx> PackageRegistry<'gctx> {
     pub fn new(gctx: &'gctx GlobalContext) -> CargoResult<PackageRegistry<'gctx>> {
         let source_config = SourceConfigMap::new(gctx)?;
+        PackageRegistry::new_with_source_config(gctx, source_config)
+    }
+
+    pub fn new_with_source_config(
+        gctx: &'gctx GlobalContext,
+        source_config: SourceConfigMap<'gctx>,
+    ) -> CargoResult<PackageRegistry<'gctx>> {
         Ok(PackageRegistry {
             gctx,
             sources: SourceMap::new(),
@@ -730,8 +737,7 @@ impl<'gctx> Registry for PackageRegistry<'gctx> {
                             return;
                         }
                     }
-                    let summary = summary.into_summary();
-                    f(IndexSummary::Candidate(lock(locked, all_patches, summary)))
+                    f(summary.map_summary(|summary| lock(locked, all_patches, summary)))
                 };
                 return source.query(dep, kind, callback);
             }
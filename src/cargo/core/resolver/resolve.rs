@@ -1,6 +1,7 @@
 use cargo_util_schemas::core::PartialVersion;
 use cargo_util_schemas::manifest::RustVersion;
 
+use super::context::ActivationsKey;
 use super::encode::Metadata;
 use crate::core::dependency::DepKind;
 use crate::core::{Dependency, PackageId, PackageIdSpec, PackageIdSpecQuery, Summary, Target};
@@ -213,6 +214,97 @@ impl Resolve {
         }
     }
 
+    /// The union of the packages locked by two resolves, like the two sides
+    /// of a merge conflict in `Cargo.lock`.
+    ///
+    /// Where the two resolves lock different semver-compatible versions of a
+    /// package from the same source, only one is kept, as a resolve can't
+    /// activate both, and the dependencies on the other one are moved to it.
+    /// That is the one changed from `base`, the resolve both come from, if
+    /// only one of them changed it, and the newer one otherwise.
+    pub fn union(&self, other: &Resolve, base: Option<&Resolve>) -> Resolve {
+        fn newest(resolve: &Resolve) -> HashMap<ActivationsKey, PackageId> {
+            let mut newest = HashMap::new();
+            for id in resolve.iter() {
+                newest
+                    .entry(id.as_activations_key())
+                    .and_modify(|newest: &mut PackageId| *newest = (*newest).max(id))
+                    .or_insert(id);
+            }
+            newest
+        }
+
+        let ours = newest(self);
+        let theirs = newest(other);
+        let base = base.map(newest).unwrap_or_default();
+        let mut chosen = ours.clone();
+        for (key, &their_id) in &theirs {
+            let id = match ours.get(key) {
+                Some(&our_id) if our_id != their_id => match base.get(key) {
+                    Some(&base_id) if base_id == our_id => their_id,
+                    Some(&base_id) if base_id == their_id => our_id,
+                    _ if their_id.version() > our_id.version() => their_id,
+                    _ => our_id,
+                },
+                _ => their_id,
+            };
+            chosen.insert(key.clone(), id);
+        }
+        let kept = |id: PackageId| chosen[&id.as_activations_key()];
+
+        let mut graph: Graph<PackageId, HashSet<Dependency>> = Graph::new();
+        let mut replacements = HashMap::new();
+        let mut features = HashMap::new();
+        let mut checksums = HashMap::new();
+        let mut metadata = Metadata::new();
+        let mut unused_patches = Vec::new();
+        let mut summaries = HashMap::new();
+        for resolve in [self, other] {
+            for id in resolve.iter() {
+                graph.add(kept(id));
+                for (dep, deps) in resolve.deps(id) {
+                    graph.link(kept(id), kept(dep)).extend(deps.iter().cloned());
+                }
+            }
+            for (&id, &replacement) in &resolve.replacements {
+                replacements.entry(kept(id)).or_insert(kept(replacement));
+            }
+            for (&id, id_features) in &resolve.features {
+                features
+                    .entry(kept(id))
+                    .or_insert_with(|| id_features.clone());
+            }
+            for (&id, checksum) in &resolve.checksums {
+                if kept(id) == id {
+                    checksums.entry(id).or_insert_with(|| checksum.clone());
+                }
+            }
+            for (key, value) in &resolve.metadata {
+                metadata.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            for &id in &resolve.unused_patches {
+                if !unused_patches.contains(&id) {
+                    unused_patches.push(id);
+                }
+            }
+            for (&id, summary) in &resolve.summaries {
+                if kept(id) == id {
+                    summaries.entry(id).or_insert_with(|| summary.clone());
+                }
+            }
+        }
+        Resolve::new(
+            graph,
+            replacements,
+            features,
+            checksums,
+            metadata,
+            unused_patches,
+            self.version.max(other.version),
+            summaries,
+        )
+    }
+
     pub fn merge_from(&mut self, previous: &Resolve) -> CargoResult<()> {
         // Given a previous instance of resolve, it should be forbidden to ever
         // have a checksums which *differ*. If the same package ID has differing
@@ -22,6 +22,7 @@ pub struct VersionPreferences {
     prefer_patch_deps: HashMap<InternedString, HashSet<Dependency>>,
     version_ordering: VersionOrdering,
     max_rust_version: Option<PartialVersion>,
+    package_rust_versions: HashMap<InternedString, PartialVersion>,
 }
 
 #[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
@@ -53,13 +54,24 @@ impl VersionPreferences {
         self.max_rust_version = ver;
     }
 
+    /// Overrides [`VersionPreferences::max_rust_version`] for the packages
+    /// with the given names.
+    pub fn package_rust_versions(&mut self, vers: HashMap<InternedString, PartialVersion>) {
+        self.package_rust_versions = vers;
+    }
+
+    pub fn get_package_rust_versions(&self) -> &HashMap<InternedString, PartialVersion> {
+        &self.package_rust_versions
+    }
+
     /// Sort (and filter) the given vector of summaries in-place
     ///
     /// Note: all summaries presumed to be for the same package.
     ///
     /// Sort order:
     /// 1. Preferred packages
-    /// 2. [`VersionPreferences::max_rust_version`]
+    /// 2. [`VersionPreferences::package_rust_versions`], falling back to
+    ///    [`VersionPreferences::max_rust_version`]
     /// 3. `first_version`, falling back to [`VersionPreferences::version_ordering`] when `None`
     ///
     /// Filtering:
@@ -85,7 +97,11 @@ impl VersionPreferences {
                 return previous_cmp;
             }
 
-            if let Some(max_rust_version) = &self.max_rust_version {
+            let max_rust_version = self
+                .package_rust_versions
+                .get(&a.name())
+                .or(self.max_rust_version.as_ref());
+            if let Some(max_rust_version) = max_rust_version {
                 let a_is_compat = a
                     .rust_version()
                     .map(|a| a.is_compatible_with(max_rust_version))
@@ -267,6 +283,38 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_package_rust_versions() {
+        let mut vp = VersionPreferences::default();
+        vp.max_rust_version(Some("1.50".parse().unwrap()));
+        vp.package_rust_versions(HashMap::from([(
+            InternedString::new("foo"),
+            "1.60".parse().unwrap(),
+        )]));
+
+        let mut summaries = vec![
+            summ("foo", "1.2.4", Some("1.70")),
+            summ("foo", "1.2.3", Some("1.60")),
+            summ("foo", "1.2.2", Some("1.50")),
+        ];
+        vp.sort_summaries(&mut summaries, None);
+        assert_eq!(
+            describe(&summaries),
+            "foo/1.2.3, foo/1.2.2, foo/1.2.4".to_string()
+        );
+
+        let mut summaries = vec![
+            summ("bar", "1.2.4", Some("1.70")),
+            summ("bar", "1.2.3", Some("1.60")),
+            summ("bar", "1.2.2", Some("1.50")),
+        ];
+        vp.sort_summaries(&mut summaries, None);
+        assert_eq!(
+            describe(&summaries),
+            "bar/1.2.2, bar/1.2.4, bar/1.2.3".to_string()
+        );
+    }
+
     #[test]
     fn test_empty_summaries() {
         let vp = VersionPreferences::default();
@@ -22,6 +22,9 @@ pub struct Shell {
     /// printing. Used when a progress bar is currently displayed.
     needs_clear: bool,
     hostname: Option<String>,
+    /// Whether the progress of the build is reported with the JSON messages
+    /// of `-Zbuild-events` on stdout.
+    build_events: bool,
 }
 
 impl fmt::Debug for Shell {
@@ -60,6 +63,7 @@ impl Shell {
             verbosity: Verbosity::Verbose,
             needs_clear: false,
             hostname: None,
+            build_events: false,
         }
     }
 
@@ -70,6 +74,7 @@ impl Shell {
             verbosity: Verbosity::Verbose,
             needs_clear: false,
             hostname: None,
+            build_events: false,
         }
     }
 
@@ -230,6 +235,18 @@ impl Shell {
         self.verbosity
     }
 
+    /// Updates whether the progress of the build is reported with the JSON
+    /// messages of `-Zbuild-events`.
+    pub fn set_build_events(&mut self, build_events: bool) {
+        self.build_events = build_events;
+    }
+
+    /// Gets whether the progress of the build is reported with the JSON
+    /// messages of `-Zbuild-events`.
+    pub fn build_events(&self) -> bool {
+        self.build_events
+    }
+
     /// Updates the color choice (always, never, or auto) from a string..
     pub fn set_color_choice(&mut self, color: Option<&str>) -> CargoResult<()> {
         if let ShellOut::Stream {
@@ -20,7 +20,7 @@ use crate::core::{
 };
 use crate::core::{EitherManifest, Package, SourceId, VirtualManifest};
 use crate::ops;
-use crate::sources::{PathSource, CRATES_IO_INDEX, CRATES_IO_REGISTRY};
+use crate::sources::{PathSource, SourceConfigMap, CRATES_IO_INDEX, CRATES_IO_REGISTRY};
 use crate::util::edit_distance;
 use crate::util::errors::{CargoResult, ManifestError};
 use crate::util::interning::InternedString;
@@ -103,12 +103,29 @@ pub struct Workspace<'gctx> {
     // file. This is set for `cargo install` without `--locked`.
     ignore_lock: bool,
 
+    // If `true`, the lock file of a script is kept next to it, as
+    // `<script>.lock`, even if it doesn't exist yet. This is set for
+    // `cargo generate-lockfile --lock`.
+    script_lock: bool,
+
+    // The hash of the dependencies and directory of a script, when its target
+    // directory is shared with the scripts next to it with the same
+    // dependencies. This is set with the `script.share-target-dir` config.
+    script_dependencies_hash: Option<String>,
+
     /// The resolver behavior specified with the `resolver` field.
     resolve_behavior: ResolveBehavior,
     resolve_honors_rust_version: bool,
+    /// If `true`, each member's `rust-version` applies to its own
+    /// dependencies rather than the lowest one applying to all of them.
+    resolve_rust_version_per_member: bool,
 
     /// Workspace-level custom metadata
     custom_metadata: Option<toml::Value>,
+
+    /// Local registries overlaid on top of registries, by the id of the
+    /// registry. See [`Workspace::add_local_overlay`].
+    local_overlays: HashMap<SourceId, PathBuf>,
 }
 
 // Separate structure for tracking loaded packages (to avoid loading anything
@@ -214,6 +231,7 @@ impl<'gctx> Workspace<'gctx> {
         ws.find_members()?;
         ws.set_resolve_behavior()?;
         ws.validate()?;
+        ws.script_dependencies_hash = ws.script_dependencies_hash()?;
         Ok(ws)
     }
 
@@ -234,9 +252,13 @@ impl<'gctx> Workspace<'gctx> {
             require_optional_deps: true,
             loaded_packages: RefCell::new(HashMap::new()),
             ignore_lock: false,
+            script_lock: false,
+            script_dependencies_hash: None,
             resolve_behavior: ResolveBehavior::V1,
             resolve_honors_rust_version: false,
+            resolve_rust_version_per_member: false,
             custom_metadata: None,
+            local_overlays: HashMap::new(),
         }
     }
 
@@ -319,8 +341,13 @@ impl<'gctx> Workspace<'gctx> {
                 something_like_precedence: Some(precedence),
             }) => {
                 if self.gctx().cli_unstable().msrv_policy {
-                    self.resolve_honors_rust_version =
-                        precedence == CargoResolverPrecedence::SomethingLikeRustVersion;
+                    self.resolve_honors_rust_version = matches!(
+                        precedence,
+                        CargoResolverPrecedence::SomethingLikeRustVersion
+                            | CargoResolverPrecedence::SomethingLikePerMemberRustVersion
+                    );
+                    self.resolve_rust_version_per_member =
+                        precedence == CargoResolverPrecedence::SomethingLikePerMemberRustVersion;
                 } else {
                     self.gctx()
                         .shell()
@@ -436,18 +463,89 @@ impl<'gctx> Workspace<'gctx> {
 
     fn default_target_dir(&self) -> Filesystem {
         if self.root_maybe().is_embedded() {
-            let hash = crate::util::hex::short_hash(&self.root_manifest().to_string_lossy());
-            let mut rel_path = PathBuf::new();
-            rel_path.push("target");
-            rel_path.push(&hash[0..2]);
-            rel_path.push(&hash[2..]);
-
-            self.gctx().home().join(rel_path)
+            match &self.script_dependencies_hash {
+                Some(hash) => self.gctx().home().join("target").join("shared").join(hash),
+                None => self.script_dir(),
+            }
         } else {
             Filesystem::new(self.root().join("target"))
         }
     }
 
+    /// The directory of a script in `$CARGO_HOME/target`, which is its
+    /// default target directory unless it is shared.
+    pub fn script_dir(&self) -> Filesystem {
+        let hash = crate::util::hex::short_hash(&self.root_manifest().to_string_lossy());
+        let mut rel_path = PathBuf::new();
+        rel_path.push("target");
+        rel_path.push(&hash[0..2]);
+        rel_path.push(&hash[2..]);
+
+        self.gctx().home().join(rel_path)
+    }
+
+    /// Whether the target directory of a script is shared with the scripts
+    /// next to it with the same dependencies, instead of being `script_dir`.
+    pub fn shares_script_target_dir(&self) -> bool {
+        self.target_dir.is_none() && self.script_dependencies_hash.is_some()
+    }
+
+    /// Hashes the dependencies of a script, if it is configured to share its
+    /// target directory with the scripts next to it with the same
+    /// dependencies.
+    ///
+    /// The directory of the script is hashed too: the binaries of scripts
+    /// with the same name in different directories would overwrite each
+    /// other.
+    fn script_dependencies_hash(&self) -> CargoResult<Option<String>> {
+        #[derive(serde::Serialize)]
+        #[serde(rename_all = "kebab-case")]
+        struct Dependencies<'a> {
+            directory: &'a Path,
+            edition: Option<&'a manifest::InheritableString>,
+            dependencies:
+                &'a Option<BTreeMap<manifest::PackageName, manifest::InheritableDependency>>,
+            dev_dependencies:
+                &'a Option<BTreeMap<manifest::PackageName, manifest::InheritableDependency>>,
+            build_dependencies:
+                &'a Option<BTreeMap<manifest::PackageName, manifest::InheritableDependency>>,
+            features: &'a Option<BTreeMap<manifest::FeatureName, Vec<String>>>,
+            target: &'a Option<BTreeMap<String, manifest::TomlPlatform>>,
+            patch: &'a Option<
+                BTreeMap<String, BTreeMap<manifest::PackageName, manifest::TomlDependency>>,
+            >,
+            replace: &'a Option<BTreeMap<String, manifest::TomlDependency>>,
+            profile: &'a Option<manifest::TomlProfiles>,
+        }
+
+        let MaybePackage::Package(pkg) = self.root_maybe() else {
+            return Ok(None);
+        };
+        if !pkg.manifest().is_embedded()
+            || !self
+                .gctx
+                .get::<Option<bool>>("script.share-target-dir")?
+                .unwrap_or(false)
+        {
+            return Ok(None);
+        }
+        let toml = pkg.manifest().resolved_toml();
+        let dependencies = Dependencies {
+            directory: self.root(),
+            edition: toml.package().and_then(|p| p.edition.as_ref()),
+            dependencies: &toml.dependencies,
+            dev_dependencies: &toml.dev_dependencies,
+            build_dependencies: &toml.build_dependencies,
+            features: &toml.features,
+            target: &toml.target,
+            patch: &toml.patch,
+            replace: &toml.replace,
+            profile: &toml.profile,
+        };
+        let dependencies = toml::to_string(&dependencies)?;
+        Ok(Some(crate::util::hex::short_hash(&dependencies)))
+    }
+
     /// Returns the root `[replace]` section of this workspace.
     ///
     /// This may be from a virtual crate or an actual crate.
@@ -643,6 +741,15 @@ impl<'gctx> Workspace<'gctx> {
         self
     }
 
+    pub fn script_lock(&self) -> bool {
+        self.script_lock
+    }
+
+    pub fn set_script_lock(&mut self, script_lock: bool) -> &mut Workspace<'gctx> {
+        self.script_lock = script_lock;
+        self
+    }
+
     /// Get the lowest-common denominator `package.rust-version` within the workspace, if specified
     /// anywhere
     pub fn rust_version(&self) -> Option<&RustVersion> {
@@ -659,10 +766,37 @@ impl<'gctx> Workspace<'gctx> {
         self.resolve_honors_rust_version
     }
 
+    /// Whether the resolver should prefer versions compatible with the
+    /// `rust-version` of each member that depends on them, rather than with
+    /// [`Workspace::rust_version`].
+    pub fn resolve_rust_version_per_member(&self) -> bool {
+        self.resolve_honors_rust_version && self.resolve_rust_version_per_member
+    }
+
     pub fn custom_metadata(&self) -> Option<&toml::Value> {
         self.custom_metadata.as_ref()
     }
 
+    /// Makes the packages of the local registry at `registry_path` available
+    /// as if they were in the registry `id`, shadowing the versions that
+    /// are also in `id`.
+    ///
+    /// This is used to verify packages that depend on other packages being
+    /// packaged along with them, which aren't published yet.
+    pub fn add_local_overlay(&mut self, id: SourceId, registry_path: PathBuf) {
+        self.local_overlays.insert(id, registry_path);
+    }
+
+    /// Creates a [`PackageRegistry`] for resolving the dependencies of this
+    /// workspace, with the overlays added by [`Workspace::add_local_overlay`].
+    pub fn package_registry(&self) -> CargoResult<PackageRegistry<'gctx>> {
+        let mut source_config = SourceConfigMap::new(self.gctx)?;
+        for (&id, path) in &self.local_overlays {
+            source_config.add_overlay(id, SourceId::for_local_registry(path)?);
+        }
+        PackageRegistry::new_with_source_config(self.gctx, source_config)
+    }
+
     pub fn load_workspace_config(&mut self) -> CargoResult<Option<WorkspaceRootConfig>> {
         // If we didn't find a root, it must mean there is no [workspace] section, and thus no
         // metadata.
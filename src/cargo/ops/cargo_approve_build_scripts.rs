@@ -0,0 +1,241 @@
+//! Implementation of `cargo approve-build-scripts`, and of the check of
+//! `build-scripts.allow` done before compiling with `-Zbuild-script-allowlist`.
+//!
+//! Build scripts and proc-macros run arbitrary code on the machine building a
+//! package. With `-Zbuild-script-allowlist`, Cargo refuses to build anything
+//! if a package that doesn't come from the local filesystem would run a build
+//! script or be loaded as a proc-macro without being listed in the
+//! configuration:
+//!
+//! ```toml
+//! [build-scripts]
+//! allow = ["cc", "serde_derive@1.0.200"]
+//! ```
+//!
+//! `cargo approve-build-scripts` adds the packages of the lock file that need
+//! to be approved to the `.cargo/config.toml` of the workspace, as one fully
+//! qualified package ID spec each, so that a new build script, a new version
+//! of one, or one coming from another source shows up in the diffs under
+//! review.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use cargo_util::paths;
+
+use crate::core::compiler::unit_graph::UnitGraph;
+use crate::core::{Package, PackageId, PackageIdSpec, PackageIdSpecQuery, Workspace};
+use crate::ops;
+use crate::util::CargoResult;
+use crate::util::GlobalContext;
+
+pub struct ApproveBuildScriptsOptions {
+    /// Package ID specs of the packages to approve. All the packages that
+    /// need to be approved are if empty.
+    pub specs: Vec<String>,
+    pub dry_run: bool,
+}
+
+/// What a package runs while building its dependents.
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct RunsCode {
+    build_script: bool,
+    proc_macro: bool,
+}
+
+impl RunsCode {
+    fn of(pkg: &Package) -> RunsCode {
+        RunsCode {
+            build_script: pkg.targets().iter().any(|t| t.is_custom_build()),
+            proc_macro: pkg.targets().iter().any(|t| t.proc_macro()),
+        }
+    }
+
+    fn any(&self) -> bool {
+        self.build_script || self.proc_macro
+    }
+}
+
+impl fmt::Display for RunsCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.build_script, self.proc_macro) {
+            (true, true) => f.write_str("build script and proc-macro"),
+            (true, false) => f.write_str("build script"),
+            _ => f.write_str("proc-macro"),
+        }
+    }
+}
+
+/// Parses `build-scripts.allow`.
+fn allowlist(gctx: &GlobalContext) -> CargoResult<Vec<PackageIdSpec>> {
+    gctx.build_scripts_config()?
+        .allow
+        .iter()
+        .flatten()
+        .map(|spec| {
+            PackageIdSpec::parse(spec).map_err(|e| {
+                anyhow::format_err!("invalid package ID spec in `build-scripts.allow`: {e}")
+            })
+        })
+        .collect()
+}
+
+/// Packages from the local filesystem are reviewed along with the workspace,
+/// and don't need to be approved.
+fn needs_approval(id: PackageId, allowlist: &[PackageIdSpec]) -> bool {
+    !id.source_id().is_path() && !allowlist.iter().any(|spec| spec.matches(id))
+}
+
+/// Returns an error listing the packages in `unit_graph` that would run a
+/// build script or a proc-macro without being allowed to.
+pub(crate) fn check_allowlist(gctx: &GlobalContext, unit_graph: &UnitGraph) -> CargoResult<()> {
+    let allowlist = allowlist(gctx)?;
+    let mut denied: BTreeMap<PackageId, RunsCode> = BTreeMap::new();
+    for unit in unit_graph.keys() {
+        let id = unit.pkg.package_id();
+        if !needs_approval(id, &allowlist) {
+            continue;
+        }
+        if unit.mode.is_run_custom_build() {
+            denied.entry(id).or_default().build_script = true;
+        } else if unit.target.proc_macro() {
+            denied.entry(id).or_default().proc_macro = true;
+        }
+    }
+    if denied.is_empty() {
+        return Ok(());
+    }
+
+    let plural = if denied.len() == 1 { "" } else { "s" };
+    let mut message = format!(
+        "the following package{plural} would run code at build time \
+         without being allowed by `build-scripts.allow`:\n"
+    );
+    for (id, runs) in &denied {
+        message.push_str(&format!("  {id} ({runs})\n"));
+    }
+    message.push_str(
+        "Review them and run `cargo approve-build-scripts -Zbuild-script-allowlist` \
+         to allow them",
+    );
+    Err(anyhow::Error::msg(message))
+}
+
+pub fn approve_build_scripts(
+    ws: &Workspace<'_>,
+    opts: &ApproveBuildScriptsOptions,
+) -> CargoResult<()> {
+    let gctx = ws.gctx();
+    let allowlist = allowlist(gctx)?;
+    let specs = opts
+        .specs
+        .iter()
+        .map(|spec| PackageIdSpec::parse(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (pkg_set, resolve) = ops::resolve_ws(ws)?;
+    let ids: Vec<_> = resolve
+        .iter()
+        .filter(|&id| needs_approval(id, &allowlist))
+        .collect();
+    let mut pending: Vec<_> = pkg_set
+        .get_many(ids)?
+        .into_iter()
+        .map(|pkg| (pkg.package_id(), RunsCode::of(pkg)))
+        .filter(|(_, runs)| runs.any())
+        .collect();
+    pending.sort();
+
+    if !specs.is_empty() {
+        if let Some(spec) = specs
+            .iter()
+            .find(|spec| !pending.iter().any(|(id, _)| spec.matches(*id)))
+        {
+            anyhow::bail!(
+                "package ID specification `{spec}` did not match any package \
+                 that needs to be approved"
+            );
+        }
+        pending.retain(|(id, _)| specs.iter().any(|spec| spec.matches(*id)));
+    }
+    if pending.is_empty() {
+        return gctx
+            .shell()
+            .note("all build scripts and proc-macros are already allowed");
+    }
+
+    for (id, runs) in &pending {
+        gctx.shell().status("Approving", format!("{id} ({runs})"))?;
+    }
+    if opts.dry_run {
+        return gctx.shell().warn("not recording approvals due to dry run");
+    }
+    let path = record_approvals(ws, pending.iter().map(|(id, _)| *id))?;
+    gctx.shell().status(
+        "Recorded",
+        format!(
+            "{} approval{} in `{}`",
+            pending.len(),
+            if pending.len() == 1 { "" } else { "s" },
+            path.display()
+        ),
+    )
+}
+
+/// Adds `ids` to `build-scripts.allow` in the configuration file of the
+/// workspace, returning its path.
+fn record_approvals(
+    ws: &Workspace<'_>,
+    ids: impl Iterator<Item = PackageId>,
+) -> CargoResult<PathBuf> {
+    let dir = ws.root().join(".cargo");
+    let legacy = dir.join("config");
+    let path = if legacy.exists() && !dir.join("config.toml").exists() {
+        legacy
+    } else {
+        dir.join("config.toml")
+    };
+    let contents = if path.exists() {
+        paths::read(&path)?
+    } else {
+        String::new()
+    };
+    let mut doc: toml_edit::DocumentMut = contents
+        .parse()
+        .with_context(|| format!("could not parse `{}`", path.display()))?;
+
+    let table = doc
+        .entry("build-scripts")
+        .or_insert(toml_edit::table())
+        .as_table_mut()
+        .with_context(|| format!("`build-scripts` in `{}` is not a table", path.display()))?;
+    // `[build-scripts.sandbox]` alone leaves it implicit, which would hide
+    // the keys added to it.
+    table.set_implicit(false);
+    let allow = table
+        .entry("allow")
+        .or_insert(toml_edit::value(toml_edit::Array::new()))
+        .as_array_mut()
+        .with_context(|| {
+            format!(
+                "`build-scripts.allow` in `{}` is not an array",
+                path.display()
+            )
+        })?;
+    // One entry per line, for readable diffs. The source is part of the
+    // entry, so that a package of the same name and version from another
+    // registry or a git repository isn't approved along with it.
+    for id in ids {
+        let mut value = toml_edit::Value::from(id.to_spec().to_string());
+        value.decor_mut().set_prefix("\n    ");
+        allow.push_formatted(value);
+    }
+    allow.set_trailing_comma(true);
+    allow.set_trailing("\n");
+
+    paths::create_dir_all(&dir)?;
+    paths::write(&path, doc.to_string())?;
+    Ok(path)
+}
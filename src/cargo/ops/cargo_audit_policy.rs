@@ -0,0 +1,441 @@
+//! Implementation of `cargo audit-policy`.
+//!
+//! The dependency graph is the one `cargo tree` builds, so that only the
+//! dependencies that are actually enabled by the selected features and
+//! targets are checked. Every package reachable from the selected workspace
+//! members is checked against the `[policy]` table of the configuration:
+//!
+//! ```toml
+//! [policy]
+//! allowed-licenses = ["MIT", "Apache-2.0"]
+//! denied-packages = ["openssl", "time@0.1"]
+//! allowed-sources = ["crates-io", "https://github.com/rust-lang/"]
+//! ```
+//!
+//! Workspace members themselves are not checked.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use anyhow::bail;
+use serde::Deserialize;
+
+use crate::core::dependency::DepKind;
+use crate::core::resolver::features::CliFeatures;
+use crate::core::{Package, PackageId, PackageIdSpec, PackageIdSpecQuery, SourceId, Workspace};
+use crate::ops::tree::graph::Graph;
+use crate::ops::tree::{self, EdgeKind, Node, TreeOptions};
+use crate::ops::{self, Packages};
+use crate::util::machine_message::{self, Message};
+use crate::util::CargoResult;
+use crate::util::GlobalContext;
+
+pub struct AuditPolicyOptions {
+    pub cli_features: CliFeatures,
+    pub packages: Packages,
+    /// The platform to filter for.
+    pub target: tree::Target,
+    /// The dependency kinds to follow.
+    pub edge_kinds: HashSet<EdgeKind>,
+    /// Whether violations are emitted as JSON messages instead of being
+    /// displayed.
+    pub json: bool,
+}
+
+/// The `[policy]` table.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct PolicyConfig {
+    /// SPDX license identifiers that are accepted. A package is accepted if
+    /// its license expression can be satisfied with these.
+    allowed_licenses: Option<Vec<String>>,
+    /// Package ID specs of packages that must not be used.
+    denied_packages: Option<Vec<String>>,
+    /// Registries (by name) and URL prefixes of registries and git
+    /// repositories that packages may come from.
+    allowed_sources: Option<Vec<String>>,
+}
+
+/// A package that doesn't comply with the policy.
+struct Violation {
+    package_id: PackageId,
+    /// The policy key that was violated.
+    rule: &'static str,
+    message: String,
+    /// The packages from a root to this package.
+    path: Vec<PackageId>,
+}
+
+/// Checks the dependencies of the workspace against the `[policy]` table.
+pub fn audit_policy(ws: &Workspace<'_>, opts: &AuditPolicyOptions) -> CargoResult<()> {
+    let gctx = ws.gctx();
+    let Some(policy) = gctx.get::<Option<PolicyConfig>>("policy")? else {
+        bail!(
+            "no `[policy]` table was found in the configuration\n\
+             See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#audit-policy \
+             for how to define one."
+        );
+    };
+    if ops::load_pkg_lockfile(ws)?.is_none() {
+        bail!(
+            "`cargo audit-policy` requires a `Cargo.lock` file, \
+             run `cargo generate-lockfile` to create one"
+        );
+    }
+    let denied = policy
+        .denied_packages
+        .iter()
+        .flatten()
+        .map(|spec| {
+            PackageIdSpec::parse(spec)
+                .map(|parsed| (spec.as_str(), parsed))
+                .map_err(|e| {
+                    anyhow::format_err!("invalid package ID spec in `policy.denied-packages`: {e}")
+                })
+        })
+        .collect::<CargoResult<Vec<_>>>()?;
+
+    let tree_opts = TreeOptions {
+        cli_features: opts.cli_features.clone(),
+        packages: opts.packages.clone(),
+        target: opts.target.clone(),
+        edge_kinds: opts.edge_kinds.clone(),
+        invert: Vec::new(),
+        pkgs_to_prune: Vec::new(),
+        prefix: tree::Prefix::None,
+        no_dedupe: false,
+        duplicates: false,
+        format: String::from("{p}"),
+        graph_features: false,
+        max_display_depth: u32::MAX,
+        no_proc_macro: false,
+        output_format: tree::OutputFormat::Text,
+    };
+    let members: HashSet<PackageId> = ws.members().map(|pkg| pkg.package_id()).collect();
+
+    let (checked, violations) = tree::with_graph(ws, &tree_opts, |resolve, specs, graph| {
+        let roots = graph.indexes_from_ids(&resolve.specs_to_ids(&specs)?);
+        let mut checked = 0;
+        let mut violations = Vec::new();
+        for (package_id, path) in reachable_packages(&graph, &roots, &opts.edge_kinds) {
+            if members.contains(&package_id) {
+                continue;
+            }
+            checked += 1;
+            let pkg = graph.package_for_id(package_id);
+            let mut violation = |rule, message| {
+                violations.push(Violation {
+                    package_id,
+                    rule,
+                    message,
+                    path: path.clone(),
+                })
+            };
+            for (spec, parsed) in &denied {
+                if parsed.matches(package_id) {
+                    violation(
+                        "denied-packages",
+                        format!("`{package_id}` is denied by `policy.denied-packages` (`{spec}`)"),
+                    );
+                }
+            }
+            if let Some(allowed) = &policy.allowed_sources {
+                let source_id = package_id.source_id();
+                if !source_allowed(gctx, source_id, allowed) {
+                    violation(
+                        "allowed-sources",
+                        format!(
+                            "`{package_id}` comes from `{}`, which is not in `policy.allowed-sources`",
+                            source_id.url()
+                        ),
+                    );
+                }
+            }
+            if let Some(allowed) = &policy.allowed_licenses {
+                if let Some(message) = check_license(pkg, allowed) {
+                    violation("allowed-licenses", message);
+                }
+            }
+        }
+        Ok((checked, violations))
+    })?;
+
+    let mut shell = gctx.shell();
+    for violation in &violations {
+        if opts.json {
+            let msg = machine_message::PolicyViolation {
+                package_id: violation.package_id.to_spec(),
+                rule: violation.rule,
+                message: &violation.message,
+                path: violation.path.iter().map(|id| id.to_spec()).collect(),
+            }
+            .to_json_string();
+            writeln!(shell.out(), "{}", msg)?;
+        } else {
+            shell.error(&violation.message)?;
+            let path: Vec<_> = violation.path.iter().map(|id| id.to_string()).collect();
+            shell.note(format!("required by {}", path.join(" -> ")))?;
+        }
+    }
+    match violations.len() {
+        0 => shell.status(
+            "Audited",
+            format!(
+                "{checked} {} against the policy",
+                if checked == 1 { "package" } else { "packages" }
+            ),
+        ),
+        1 => bail!("1 package does not comply with the policy"),
+        n => bail!("{n} packages do not comply with the policy"),
+    }
+}
+
+const EDGE_KINDS: [EdgeKind; 3] = [
+    EdgeKind::Dep(DepKind::Normal),
+    EdgeKind::Dep(DepKind::Build),
+    EdgeKind::Dep(DepKind::Development),
+];
+
+/// Walks the graph breadth-first from `roots`, returning each package along
+/// with the shortest path that reaches it, starting from a root.
+fn reachable_packages(
+    graph: &Graph<'_>,
+    roots: &[usize],
+    edge_kinds: &HashSet<EdgeKind>,
+) -> Vec<(PackageId, Vec<PackageId>)> {
+    let package_id = |index| match graph.node(index) {
+        Node::Package { package_id, .. } => *package_id,
+        Node::Feature { .. } => unreachable!("feature nodes are not built"),
+    };
+    let mut parents: HashMap<usize, Option<usize>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    for &root in roots {
+        parents.entry(root).or_insert_with(|| {
+            queue.push_back(root);
+            None
+        });
+    }
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    while let Some(index) = queue.pop_front() {
+        let id = package_id(index);
+        // The same package may be built for the host and the target.
+        if seen.insert(id) {
+            let mut path = vec![id];
+            let mut parent = parents[&index];
+            while let Some(p) = parent {
+                path.push(package_id(p));
+                parent = parents[&p];
+            }
+            path.reverse();
+            result.push((id, path));
+        }
+        // In a fixed order, so that the paths are stable.
+        for kind in EDGE_KINDS.iter().filter(|kind| edge_kinds.contains(kind)) {
+            for dep in graph.connected_nodes(index, kind) {
+                parents.entry(dep).or_insert_with(|| {
+                    queue.push_back(dep);
+                    Some(index)
+                });
+            }
+        }
+    }
+    result
+}
+
+/// Whether a package from `source_id` may be used.
+///
+/// Path dependencies are always allowed. Other sources match an entry that
+/// is either the name of their registry, or a prefix of their URL.
+fn source_allowed(gctx: &GlobalContext, source_id: SourceId, allowed: &[String]) -> bool {
+    if source_id.is_path() {
+        return true;
+    }
+    let url = source_id.url().as_str();
+    allowed.iter().any(|entry| {
+        if entry == "crates-io" {
+            source_id.is_crates_io()
+        } else if source_id.alt_registry_key() == Some(entry.as_str()) {
+            true
+        } else if entry.contains("://") {
+            url.starts_with(entry.as_str())
+        } else {
+            // Also check the index URL of a registry from the configuration,
+            // since the key isn't recorded in the lock file.
+            SourceId::alt_registry(gctx, entry)
+                .map(|registry| registry.url() == source_id.url())
+                .unwrap_or(false)
+        }
+    })
+}
+
+/// Returns a message if the license of `pkg` isn't allowed.
+fn check_license(pkg: &Package, allowed: &[String]) -> Option<String> {
+    let metadata = pkg.manifest().metadata();
+    let Some(license) = &metadata.license else {
+        return Some(match &metadata.license_file {
+            Some(file) => format!(
+                "`{}` has no `license` expression, only a `license-file` (`{file}`), \
+                 which can't be checked against `policy.allowed-licenses`",
+                pkg.package_id()
+            ),
+            None => format!("`{}` has no license", pkg.package_id()),
+        });
+    };
+    match LicenseExpr::parse(license) {
+        Ok(expr) if expr.satisfied_by(allowed) => None,
+        Ok(_) => Some(format!(
+            "`{}` is licensed under `{license}`, which is not allowed by `policy.allowed-licenses`",
+            pkg.package_id()
+        )),
+        Err(e) => Some(format!(
+            "`{}` has an invalid license expression `{license}`: {e}",
+            pkg.package_id()
+        )),
+    }
+}
+
+/// An SPDX license expression.
+///
+/// The `/` separator that older packages use is read as `OR`.
+#[derive(Debug, PartialEq)]
+enum LicenseExpr {
+    /// A license identifier, with its exception if any, such as
+    /// `Apache-2.0 WITH LLVM-exception`.
+    License(String),
+    And(Box<LicenseExpr>, Box<LicenseExpr>),
+    Or(Box<LicenseExpr>, Box<LicenseExpr>),
+}
+
+impl LicenseExpr {
+    fn parse(s: &str) -> Result<LicenseExpr, LicenseError> {
+        let spaced = s
+            .replace('(', " ( ")
+            .replace(')', " ) ")
+            .replace('/', " OR ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        let mut pos = 0;
+        let expr = Self::parse_or(&tokens, &mut pos)?;
+        match tokens.get(pos) {
+            None => Ok(expr),
+            Some(token) => Err(LicenseError::Unexpected(token.to_string())),
+        }
+    }
+
+    fn parse_or(tokens: &[&str], pos: &mut usize) -> Result<LicenseExpr, LicenseError> {
+        let mut expr = Self::parse_and(tokens, pos)?;
+        while tokens.get(*pos) == Some(&"OR") {
+            *pos += 1;
+            expr = LicenseExpr::Or(Box::new(expr), Box::new(Self::parse_and(tokens, pos)?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(tokens: &[&str], pos: &mut usize) -> Result<LicenseExpr, LicenseError> {
+        let mut expr = Self::parse_license(tokens, pos)?;
+        while tokens.get(*pos) == Some(&"AND") {
+            *pos += 1;
+            expr = LicenseExpr::And(Box::new(expr), Box::new(Self::parse_license(tokens, pos)?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_license(tokens: &[&str], pos: &mut usize) -> Result<LicenseExpr, LicenseError> {
+        let Some(&token) = tokens.get(*pos) else {
+            return Err(LicenseError::UnexpectedEnd);
+        };
+        *pos += 1;
+        match token {
+            "(" => {
+                let expr = Self::parse_or(tokens, pos)?;
+                if tokens.get(*pos) != Some(&")") {
+                    return Err(LicenseError::UnclosedParen);
+                }
+                *pos += 1;
+                Ok(expr)
+            }
+            ")" | "AND" | "OR" | "WITH" => Err(LicenseError::Unexpected(token.to_string())),
+            license => {
+                if tokens.get(*pos) != Some(&"WITH") {
+                    return Ok(LicenseExpr::License(license.to_string()));
+                }
+                match tokens.get(*pos + 1) {
+                    Some(&exception) if !matches!(exception, "(" | ")" | "AND" | "OR") => {
+                        *pos += 2;
+                        Ok(LicenseExpr::License(format!("{license} WITH {exception}")))
+                    }
+                    Some(token) => Err(LicenseError::Unexpected(token.to_string())),
+                    None => Err(LicenseError::UnexpectedEnd),
+                }
+            }
+        }
+    }
+
+    /// Whether the expression holds when only the `allowed` licenses can be
+    /// chosen. Identifiers are compared case-insensitively.
+    fn satisfied_by(&self, allowed: &[String]) -> bool {
+        match self {
+            LicenseExpr::License(license) => allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(license)),
+            LicenseExpr::And(a, b) => a.satisfied_by(allowed) && b.satisfied_by(allowed),
+            LicenseExpr::Or(a, b) => a.satisfied_by(allowed) || b.satisfied_by(allowed),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum LicenseError {
+    Unexpected(String),
+    UnexpectedEnd,
+    UnclosedParen,
+}
+
+impl fmt::Display for LicenseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LicenseError::Unexpected(token) => write!(f, "unexpected `{token}`"),
+            LicenseError::UnexpectedEnd => f.write_str("unexpected end of expression"),
+            LicenseError::UnclosedParen => f.write_str("unclosed parenthesis"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LicenseExpr;
+
+    fn allowed(licenses: &[&str]) -> Vec<String> {
+        licenses.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn license_expressions() {
+        let mit = allowed(&["MIT"]);
+        for (expr, expected) in [
+            ("MIT", true),
+            ("mit", true),
+            ("MIT OR Apache-2.0", true),
+            ("MIT/Apache-2.0", true),
+            ("MIT AND Apache-2.0", false),
+            ("(MIT OR Apache-2.0) AND BSD-3-Clause", false),
+            ("Apache-2.0 WITH LLVM-exception", false),
+            ("GPL-3.0-only OR (MIT AND MIT)", true),
+        ] {
+            let parsed = LicenseExpr::parse(expr).unwrap();
+            assert_eq!(parsed.satisfied_by(&mit), expected, "{expr}");
+        }
+
+        let llvm = allowed(&["Apache-2.0 WITH LLVM-exception"]);
+        assert!(LicenseExpr::parse("Apache-2.0 WITH LLVM-exception")
+            .unwrap()
+            .satisfied_by(&llvm));
+        assert!(!LicenseExpr::parse("Apache-2.0")
+            .unwrap()
+            .satisfied_by(&llvm));
+
+        for invalid in ["", "MIT OR", "(MIT", "MIT)", "MIT WITH", "AND MIT"] {
+            assert!(LicenseExpr::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+}
@@ -1,17 +1,22 @@
-use crate::core::compiler::{CompileKind, CompileMode, Layout, RustcTargetData};
+use crate::core::compiler::last_use::LastUse;
+use crate::core::compiler::{
+    BuildRunner, CompileKind, CompileMode, Layout, RustcTargetData, UnitInterner,
+};
 use crate::core::profiles::Profiles;
+use crate::core::resolver::CliFeatures;
 use crate::core::{PackageIdSpec, PackageIdSpecQuery, TargetKind, Workspace};
-use crate::ops;
+use crate::ops::{self, CompileFilter, CompileOptions, Packages};
 use crate::util::edit_distance;
 use crate::util::errors::CargoResult;
 use crate::util::interning::InternedString;
 use crate::util::{human_readable_bytes, GlobalContext, Progress, ProgressStyle};
-use anyhow::bail;
+use anyhow::{bail, Context as _};
 use cargo_util::paths;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{Duration, SystemTime};
 
 pub struct CleanOptions<'gctx> {
     pub gctx: &'gctx GlobalContext,
@@ -27,6 +32,14 @@ pub struct CleanOptions<'gctx> {
     pub doc: bool,
     /// If set, doesn't delete anything.
     pub dry_run: bool,
+    /// Whether to only clean the artifacts that aren't used by the current
+    /// units of the workspace.
+    pub stale: bool,
+    /// The features the workspace is built with, to tell which artifacts
+    /// are stale.
+    pub cli_features: CliFeatures,
+    /// If set, only clean the artifacts that weren't used for this long.
+    pub max_age: Option<Duration>,
 }
 
 pub struct CleanContext<'gctx> {
@@ -58,6 +71,8 @@ pub fn clean(ws: &Workspace<'_>, opts: &CleanOptions<'_>) -> CargoResult<()> {
         // If the doc option is set, we just want to delete the doc directory.
         target_dir = target_dir.join("doc");
         clean_ctx.remove_paths(&[target_dir.into_path_unlocked()])?;
+    } else if opts.stale || opts.max_age.is_some() {
+        clean_stale(&mut clean_ctx, ws, opts)?;
     } else {
         let profiles = Profiles::new(&ws, opts.requested_profile)?;
 
@@ -97,15 +112,17 @@ fn clean_specs(
     let target_data = RustcTargetData::new(ws, &requested_kinds)?;
     let (pkg_set, resolve) = ops::resolve_ws(ws)?;
     let prof_dir_name = profiles.get_dir_name();
-    let host_layout = Layout::new(ws, None, &prof_dir_name)?;
+    let host_layout = Layout::new(ws, None, &prof_dir_name, false)?;
     // Convert requested kinds to a Vec of layouts.
     let target_layouts: Vec<(CompileKind, Layout)> = requested_kinds
         .into_iter()
         .filter_map(|kind| match kind {
-            CompileKind::Target(target) => match Layout::new(ws, Some(target), &prof_dir_name) {
-                Ok(layout) => Some(Ok((kind, layout))),
-                Err(e) => Some(Err(e)),
-            },
+            CompileKind::Target(target) => {
+                match Layout::new(ws, Some(target), &prof_dir_name, false) {
+                    Ok(layout) => Some(Ok((kind, layout))),
+                    Err(e) => Some(Err(e)),
+                }
+            }
             CompileKind::Host => None,
         })
         .collect::<CargoResult<_>>()?;
@@ -275,6 +292,208 @@ fn clean_specs(
     Ok(())
 }
 
+/// Cleans the artifacts of the selected profile that are stale, and/or that
+/// weren't used for a while.
+///
+/// Only the files and directories with a unit hash in their names, and the
+/// incremental directories, are considered. The artifacts uplifted to the
+/// directory of the profile are overwritten by every build and left alone.
+fn clean_stale(
+    clean_ctx: &mut CleanContext<'_>,
+    ws: &Workspace<'_>,
+    opts: &CleanOptions<'_>,
+) -> CargoResult<()> {
+    if opts.stale && ops::load_pkg_lockfile(ws)?.is_none() {
+        bail!(
+            "`cargo clean --stale` needs a lockfile to tell which artifacts are stale, \
+             run `cargo generate-lockfile` to create one"
+        );
+    }
+
+    let cutoff = opts.max_age.map(|age| {
+        SystemTime::now()
+            .checked_sub(age)
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    });
+    // Whether an artifact was used since the cutoff, with its last use
+    // defaulting to its modification time for the builds that weren't
+    // tracked.
+    let recently_used = |last_use: Option<SystemTime>, path: &Path| match cutoff {
+        Some(cutoff) => last_use
+            .or_else(|| fs::symlink_metadata(path).and_then(|m| m.modified()).ok())
+            .map_or(false, |time| time >= cutoff),
+        None => false,
+    };
+
+    let profiles = Profiles::new(ws, opts.requested_profile)?;
+    let dir_name = profiles.get_dir_name();
+    // Computed before the build directories are locked below, as preparing
+    // the units locks them too.
+    let current = if opts.stale {
+        Some(current_units(ws, opts, &dir_name)?)
+    } else {
+        None
+    };
+    let mut layouts = vec![Layout::new(ws, None, &dir_name, false)?];
+    for kind in CompileKind::from_requested_targets(clean_ctx.gctx, &opts.targets)? {
+        if let CompileKind::Target(target) = kind {
+            layouts.push(Layout::new(ws, Some(target), &dir_name, false)?);
+        }
+    }
+
+    let mut to_remove = Vec::new();
+    let mut last_uses = Vec::new();
+    for layout in &layouts {
+        let mut last_use = LastUse::load(layout);
+        let mut removed_hashes = Vec::new();
+        for dir in [
+            layout.fingerprint(),
+            layout.build(),
+            layout.deps(),
+            layout.artifact(),
+            layout.examples(),
+        ] {
+            for (path, name) in dir_entries(dir)? {
+                let Some(hash) = unit_hash_of(&name) else {
+                    continue;
+                };
+                if current.as_ref().is_some_and(|c| c.hashes.contains(hash))
+                    || recently_used(last_use.get(hash), &path)
+                {
+                    continue;
+                }
+                removed_hashes.push(hash.to_string());
+                to_remove.push(path);
+            }
+        }
+        for (path, name) in dir_entries(layout.incremental())? {
+            let Some((crate_name, _)) = name.rsplit_once('-') else {
+                continue;
+            };
+            if current
+                .as_ref()
+                .is_some_and(|c| c.crate_names.contains(crate_name))
+                || recently_used(None, &path)
+            {
+                continue;
+            }
+            to_remove.push(path);
+        }
+        for hash in &removed_hashes {
+            last_use.remove(hash);
+        }
+        last_uses.push(last_use);
+    }
+
+    clean_ctx.remove_paths(&to_remove)?;
+    if !clean_ctx.dry_run {
+        for last_use in &last_uses {
+            last_use.save()?;
+        }
+    }
+    Ok(())
+}
+
+/// The units that the builds of the workspace use.
+struct CurrentUnits {
+    /// The hashes in the names of their files, see
+    /// [`CompilationFiles::unit_hash`].
+    hashes: HashSet<String>,
+    /// The names of their crates, which name their incremental directories.
+    crate_names: HashSet<String>,
+}
+
+/// Finds the units that `cargo build`, `check`, `test`, `bench` and `doc`
+/// would build in the `dir_name` directory, with the features, targets and
+/// profile given to `cargo clean`.
+///
+/// The unit graph of each command is built like [`ops::compile`] does, so
+/// the artifacts of a dependency built with features that are no longer
+/// enabled, or of a package that is gone from the lockfile, aren't current.
+fn current_units(
+    ws: &Workspace<'_>,
+    opts: &CleanOptions<'_>,
+    dir_name: &str,
+) -> CargoResult<CurrentUnits> {
+    let commands = [
+        (CompileMode::Build, false, "dev"),
+        (CompileMode::Build, true, "dev"),
+        (CompileMode::Check { test: false }, false, "dev"),
+        (CompileMode::Check { test: false }, true, "dev"),
+        (CompileMode::Test, false, "test"),
+        (CompileMode::Bench, false, "bench"),
+        (
+            CompileMode::Doc {
+                deps: true,
+                json: false,
+            },
+            false,
+            "dev",
+        ),
+    ];
+    let mut current = CurrentUnits {
+        hashes: HashSet::new(),
+        crate_names: HashSet::new(),
+    };
+    for (mode, all_targets, default_profile) in commands {
+        let requested_profile = if opts.profile_specified {
+            opts.requested_profile
+        } else {
+            InternedString::new(default_profile)
+        };
+        // Other profiles build in other directories.
+        if Profiles::new(ws, requested_profile)?.get_dir_name() != dir_name {
+            continue;
+        }
+        let mut compile_opts = CompileOptions::new(opts.gctx, mode)?;
+        compile_opts.build_config.requested_kinds =
+            CompileKind::from_requested_targets(opts.gctx, &opts.targets)?;
+        compile_opts.build_config.requested_profile = requested_profile;
+        compile_opts.cli_features = opts.cli_features.clone();
+        compile_opts.spec = Packages::All;
+        if all_targets {
+            compile_opts.filter = CompileFilter::new_all_targets();
+        }
+
+        let interner = UnitInterner::new();
+        let bcx = ops::create_bcx(ws, &compile_opts, &interner)?;
+        let mut build_runner = BuildRunner::new(&bcx)?;
+        build_runner.prepare_units()?;
+        let files = build_runner.files();
+        for unit in bcx.unit_graph.keys() {
+            current.hashes.insert(files.unit_hash(unit));
+            current.crate_names.insert(unit.target.crate_name());
+        }
+    }
+    Ok(current)
+}
+
+/// Returns the entries of `dir` with their names, if it exists.
+fn dir_entries(dir: &Path) -> CargoResult<Vec<(PathBuf, String)>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    let read_dir =
+        fs::read_dir(dir).with_context(|| format!("failed to read `{}`", dir.display()))?;
+    for entry in read_dir {
+        let entry = entry?;
+        if let Some(name) = entry.file_name().to_str() {
+            entries.push((entry.path(), name.to_string()));
+        }
+    }
+    Ok(entries)
+}
+
+/// Returns the unit hash in the name of an artifact, like
+/// `libfoo-0123456789abcdef.rlib`, `foo-0123456789abcdef.d` or the
+/// `foo-0123456789abcdef` directory.
+fn unit_hash_of(name: &str) -> Option<&str> {
+    let stem = name.split('.').next()?;
+    let (_, hash) = stem.rsplit_once('-')?;
+    (hash.len() == 16 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hash)
+}
+
 fn escape_glob_path(pattern: &Path) -> CargoResult<String> {
     let pattern = pattern
         .to_str()
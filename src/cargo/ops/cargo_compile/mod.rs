@@ -525,6 +525,10 @@ where `<compatible-ver>` is the latest version supporting rustc {rustc_version}"
         }
     }
 
+    if gctx.cli_unstable().build_script_allowlist {
+        ops::cargo_approve_build_scripts::check_allowlist(gctx, &unit_graph)?;
+    }
+
     let bcx = BuildContext::new(
         ws,
         pkg_set,
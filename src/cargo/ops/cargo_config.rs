@@ -4,9 +4,11 @@ use crate::util::context::{ConfigKey, ConfigValue as CV, Definition, GlobalConte
 use crate::util::errors::CargoResult;
 use crate::{drop_eprintln, drop_println};
 use anyhow::{bail, format_err, Error};
+use serde::Serialize;
 use serde_json::json;
 use std::borrow::Cow;
 use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 pub enum ConfigFormat {
@@ -51,9 +53,10 @@ pub struct GetOptions<'a> {
 }
 
 pub fn get(gctx: &GlobalContext, opts: &GetOptions<'_>) -> CargoResult<()> {
-    if opts.show_origin && !matches!(opts.format, ConfigFormat::Toml) {
+    if opts.show_origin && matches!(opts.format, ConfigFormat::JsonValue) {
         bail!(
-            "the `{}` format does not support --show-origin, try the `toml` format instead",
+            "the `{}` format does not support --show-origin, \
+             try the `toml` or `json` format instead",
             opts.format
         );
     }
@@ -65,10 +68,20 @@ pub fn get(gctx: &GlobalContext, opts: &GetOptions<'_>) -> CargoResult<()> {
         let cv = gctx
             .get_cv_with_env(&key)?
             .ok_or_else(|| format_err!("config value `{}` is not set", key))?;
-        match opts.format {
-            ConfigFormat::Toml => print_toml(gctx, opts, &key, &cv),
-            ConfigFormat::Json => print_json(gctx, &key, &cv, true),
-            ConfigFormat::JsonValue => print_json(gctx, &key, &cv, false),
+        if opts.show_origin {
+            let mut origins = Vec::new();
+            collect_origins(gctx, &layers(gctx)?, &key, &cv, &mut origins)?;
+            match opts.format {
+                ConfigFormat::Toml => print_toml_origins(gctx, &origins),
+                ConfigFormat::Json => print_json_origins(gctx, &origins)?,
+                ConfigFormat::JsonValue => unreachable!(),
+            }
+        } else {
+            match opts.format {
+                ConfigFormat::Toml => print_toml(gctx, opts, &key, &cv),
+                ConfigFormat::Json => print_json(gctx, &key, &cv, true),
+                ConfigFormat::JsonValue => print_json(gctx, &key, &cv, false),
+            }
         }
         if let Some(env) = maybe_env(gctx, &key, &cv) {
             match opts.format {
@@ -88,6 +101,23 @@ pub fn get(gctx: &GlobalContext, opts: &GetOptions<'_>) -> CargoResult<()> {
     Ok(())
 }
 
+/// Checks the configuration for keys that Cargo doesn't know, for
+/// `cargo config check`.
+pub fn check(gctx: &GlobalContext) -> CargoResult<()> {
+    let unknown = gctx.unknown_config_keys()?;
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    for key in &unknown {
+        gctx.shell().error(key)?;
+    }
+    bail!(
+        "the configuration has {} unknown key{}",
+        unknown.len(),
+        if unknown.len() == 1 { "" } else { "s" }
+    )
+}
+
 /// Checks for environment variables that might be used.
 fn maybe_env<'gctx>(
     gctx: &'gctx GlobalContext,
@@ -161,6 +191,238 @@ fn print_toml(gctx: &GlobalContext, opts: &GetOptions<'_>, key: &ConfigKey, cv:
     }
 }
 
+/// Where a value of the configuration comes from, along with the values of
+/// the same key it overrides.
+struct Origin {
+    key: ConfigKey,
+    /// The value in use, from the layer of the highest priority.
+    cv: CV,
+    /// The values of the other layers, in priority order.
+    overridden: Vec<CV>,
+}
+
+/// Loads every layer of the configuration but the environment, in priority
+/// order: the `--config` arguments, then the config files.
+fn layers(gctx: &GlobalContext) -> CargoResult<Vec<CV>> {
+    let mut layers = vec![gctx.cli_args_as_table()?];
+    layers.extend(gctx.load_values_unmerged()?);
+    Ok(layers)
+}
+
+/// Collects the origins of the values of `key`, down to every value within
+/// the tables.
+///
+/// Each value is looked up again with the environment, since tables don't
+/// include the environment variables overriding their values.
+fn collect_origins(
+    gctx: &GlobalContext,
+    layers: &[CV],
+    key: &ConfigKey,
+    cv: &CV,
+    origins: &mut Vec<Origin>,
+) -> CargoResult<()> {
+    if let CV::Table(table, _def) = cv {
+        let mut key_vals: Vec<_> = table.iter().collect();
+        key_vals.sort_by(|a, b| a.0.cmp(b.0));
+        for (table_key, val) in key_vals {
+            let mut subkey = key.clone();
+            subkey.push(table_key);
+            collect_origins(gctx, layers, &subkey, val, origins)?;
+        }
+        return Ok(());
+    }
+    let cv = gctx.get_cv_with_env(key)?.unwrap_or_else(|| cv.clone());
+    let mut overridden = Vec::new();
+    // Lists are merged, so no layer overrides another.
+    if !matches!(cv, CV::List(..)) {
+        for layer in layers {
+            if let Some(val) = lookup(layer, key) {
+                if !same_definition(val.definition(), cv.definition()) {
+                    overridden.push(val.clone());
+                }
+            }
+        }
+        if let Some(env) = gctx.env().find(|(k, _)| *k == key.as_env_key()) {
+            let def = Definition::Environment(env.0.to_string());
+            if !same_definition(&def, cv.definition()) {
+                let env = match env.1 {
+                    "true" => CV::Boolean(true, def),
+                    "false" => CV::Boolean(false, def),
+                    env => match env.parse() {
+                        Ok(i) => CV::Integer(i, def),
+                        Err(_) => CV::String(env.to_string(), def),
+                    },
+                };
+                // The environment comes after the `--config` arguments.
+                let at = overridden
+                    .iter()
+                    .take_while(|v| matches!(v.definition(), Definition::Cli(_)))
+                    .count();
+                overridden.insert(at, env);
+            }
+        }
+    }
+    origins.push(Origin {
+        key: key.clone(),
+        cv,
+        overridden,
+    });
+    Ok(())
+}
+
+/// Finds the value of `key` in a layer of the configuration.
+fn lookup<'a>(mut cv: &'a CV, key: &ConfigKey) -> Option<&'a CV> {
+    for part in key.parts() {
+        match cv {
+            CV::Table(map, _def) => cv = map.get(part)?,
+            _ => return None,
+        }
+    }
+    Some(cv)
+}
+
+/// Whether two definitions are the same place, unlike `==` which only
+/// compares their kind.
+fn same_definition(a: &Definition, b: &Definition) -> bool {
+    match (a, b) {
+        (Definition::Path(a), Definition::Path(b)) => a == b,
+        (Definition::Environment(a), Definition::Environment(b)) => a == b,
+        (Definition::Cli(a), Definition::Cli(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn print_toml_origins(gctx: &GlobalContext, origins: &[Origin]) {
+    let strikethrough = anstyle::Style::new().effects(anstyle::Effects::STRIKETHROUGH);
+    for origin in origins {
+        let key = &origin.key;
+        match &origin.cv {
+            CV::List(vals, _def) => {
+                drop_println!(gctx, "{} = [", key);
+                for (val, def) in vals {
+                    drop_println!(gctx, "    {}, # {}", toml_edit::Value::from(val), def);
+                }
+                drop_println!(gctx, "]");
+            }
+            cv => drop_println!(gctx, "{} = {} # {}", key, toml_value(cv), cv.definition()),
+        }
+        for cv in &origin.overridden {
+            drop_println!(
+                gctx,
+                "# {strikethrough}{} = {}{strikethrough:#} # {} (overridden)",
+                key,
+                toml_value(cv),
+                cv.definition()
+            );
+        }
+    }
+}
+
+fn toml_value(cv: &CV) -> toml_edit::Value {
+    match cv {
+        CV::Boolean(val, _def) => (*val).into(),
+        CV::Integer(val, _def) => (*val).into(),
+        CV::String(val, _def) => val.into(),
+        CV::List(vals, _def) => vals
+            .iter()
+            .map(|x| &x.0)
+            .collect::<toml_edit::Array>()
+            .into(),
+        CV::Table(..) => unreachable!("tables are printed by key"),
+    }
+}
+
+/// The `--format=json --show-origin` output.
+#[derive(Serialize)]
+struct SerializedOrigins {
+    /// The version of the format, bumped on breaking changes.
+    version: u32,
+    values: Vec<SerializedOrigin>,
+}
+
+#[derive(Serialize)]
+struct SerializedOrigin {
+    key: String,
+    value: serde_json::Value,
+    /// Where the value is defined, for values other than arrays.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    definition: Option<SerializedDefinition>,
+    /// The elements of arrays, with where each one is defined.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    elements: Option<Vec<SerializedValue>>,
+    overridden: Vec<SerializedValue>,
+}
+
+#[derive(Serialize)]
+struct SerializedValue {
+    value: serde_json::Value,
+    definition: SerializedDefinition,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum SerializedDefinition {
+    File { path: PathBuf },
+    Environment { name: String },
+    Cli { path: Option<PathBuf> },
+}
+
+impl From<&Definition> for SerializedDefinition {
+    fn from(def: &Definition) -> SerializedDefinition {
+        match def {
+            Definition::Path(path) => SerializedDefinition::File { path: path.clone() },
+            Definition::Environment(name) => {
+                SerializedDefinition::Environment { name: name.clone() }
+            }
+            Definition::Cli(path) => SerializedDefinition::Cli { path: path.clone() },
+        }
+    }
+}
+
+fn print_json_origins(gctx: &GlobalContext, origins: &[Origin]) -> CargoResult<()> {
+    let json_value = |cv: &CV| match cv {
+        CV::Boolean(val, _def) => json!(val),
+        CV::Integer(val, _def) => json!(val),
+        CV::String(val, _def) => json!(val),
+        CV::List(vals, _def) => json!(vals.iter().map(|x| &x.0).collect::<Vec<_>>()),
+        CV::Table(..) => unreachable!("tables are printed by key"),
+    };
+    let values = origins
+        .iter()
+        .map(|origin| {
+            let (definition, elements) = match &origin.cv {
+                CV::List(vals, _def) => {
+                    let elements = vals
+                        .iter()
+                        .map(|(val, def)| SerializedValue {
+                            value: json!(val),
+                            definition: def.into(),
+                        })
+                        .collect();
+                    (None, Some(elements))
+                }
+                cv => (Some(cv.definition().into()), None),
+            };
+            SerializedOrigin {
+                key: origin.key.to_string(),
+                value: json_value(&origin.cv),
+                definition,
+                elements,
+                overridden: origin
+                    .overridden
+                    .iter()
+                    .map(|cv| SerializedValue {
+                        value: json_value(cv),
+                        definition: cv.definition().into(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    gctx.shell()
+        .print_json(&SerializedOrigins { version: 1, values })
+}
+
 fn print_toml_env(gctx: &GlobalContext, env: &[(&str, &str)]) {
     drop_println!(
         gctx,
@@ -2,16 +2,21 @@ use crate::core::registry::PackageRegistry;
 use crate::core::resolver::features::{CliFeatures, HasDevUnits};
 use crate::core::shell::Verbosity;
 use crate::core::Registry as _;
-use crate::core::{PackageId, PackageIdSpec, PackageIdSpecQuery};
+use crate::core::{Dependency, PackageId, PackageIdSpec, PackageIdSpecQuery};
 use crate::core::{Resolve, SourceId, Workspace};
 use crate::ops;
 use crate::sources::source::QueryKind;
+use crate::sources::IndexSummary;
 use crate::util::cache_lock::CacheLockMode;
 use crate::util::context::GlobalContext;
 use crate::util::style;
 use crate::util::CargoResult;
+use crate::util::{OptVersionReq, VersionExt};
+use anyhow::Context as _;
+use cargo_util::paths;
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
 use tracing::debug;
 
 pub struct UpdateOptions<'a> {
@@ -41,6 +46,48 @@ pub fn generate_lockfile(ws: &Workspace<'_>) -> CargoResult<()> {
     Ok(())
 }
 
+/// Merges the lock files `ours` and `theirs`, which both come from `base`,
+/// into `ours`, as a git merge driver does.
+///
+/// The packages locked by both are kept where they are compatible with each
+/// other, and the others are resolved again.
+pub fn merge_lockfiles(
+    ws: &Workspace<'_>,
+    base: &Path,
+    ours: &Path,
+    theirs: &Path,
+) -> CargoResult<()> {
+    let parse = |path: &Path| -> CargoResult<Option<Resolve>> {
+        let contents = paths::read(path)?;
+        if contents.trim().is_empty() {
+            return Ok(None);
+        }
+        let resolve = super::lockfile::parse_lockfile(ws, &contents)
+            .with_context(|| format!("failed to parse lock file at: {}", path.display()))?;
+        Ok(Some(resolve))
+    };
+    let base = parse(base)?;
+    let previous = match (parse(ours)?, parse(theirs)?) {
+        (Some(ours), Some(theirs)) => Some(ours.union(&theirs, base.as_ref())),
+        (Some(resolve), None) | (None, Some(resolve)) => Some(resolve),
+        (None, None) => None,
+    };
+
+    let mut registry = PackageRegistry::new(ws.gctx())?;
+    let resolve = ops::resolve_with_previous(
+        &mut registry,
+        ws,
+        &CliFeatures::new_all(true),
+        HasDevUnits::Yes,
+        previous.as_ref(),
+        None,
+        &[],
+        true,
+    )?;
+    paths::write(ours, ops::resolve_to_string(ws, &resolve)?)?;
+    Ok(())
+}
+
 pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoResult<()> {
     if opts.recursive && opts.precise.is_some() {
         anyhow::bail!("cannot specify both recursive and precise simultaneously")
@@ -177,6 +224,7 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
         opts.precise.is_some(),
         &mut registry,
     )?;
+    print_rust_version_report(ws, &resolve, &mut registry)?;
     if opts.dry_run {
         opts.gctx
             .shell()
@@ -187,6 +235,260 @@ pub fn update_lockfile(ws: &Workspace<'_>, opts: &UpdateOptions<'_>) -> CargoRes
     Ok(())
 }
 
+/// Explains why the packages matching `specs` aren't at their latest
+/// version, without updating the lockfile.
+///
+/// For the latest version of each package, this reports whether it was
+/// yanked, the requirements on it, the features it lacks and the
+/// `rust-version` preventing its selection, with the path from a workspace
+/// member to each requirement. Pre-releases are only considered if a
+/// requirement on the package allows them.
+/// The latest version matching all of them is then tried with a resolve
+/// as if it was selected with `--precise`, whose error is reported if it
+/// fails.
+pub fn explain_update(ws: &Workspace<'_>, specs: &[String]) -> CargoResult<()> {
+    let gctx = ws.gctx();
+    let _lock = gctx.acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
+
+    // Resolve again while keeping the locked versions, as the lockfile alone
+    // doesn't record the requirements between packages.
+    let previous_resolve = ops::load_pkg_lockfile(ws)?;
+    let mut registry = PackageRegistry::new(gctx)?;
+    let resolve = ops::resolve_with_previous(
+        &mut registry,
+        ws,
+        &CliFeatures::new_all(true),
+        HasDevUnits::Yes,
+        previous_resolve.as_ref(),
+        None,
+        &[],
+        true,
+    )?;
+
+    for spec in specs {
+        let id = resolve.query(spec)?;
+        if !id.source_id().is_registry() {
+            gctx.shell().status("Explaining", id)?;
+            gctx.shell().note(format!(
+                "`{}` isn't from a registry, so it has no other versions to select",
+                id.name()
+            ))?;
+            continue;
+        }
+
+        let query = Dependency::parse(id.name(), None, id.source_id().without_precise())?;
+        let mut candidates = Vec::new();
+        for kind in [QueryKind::Exact, QueryKind::RejectedVersions] {
+            let summaries = loop {
+                match registry.query_vec(&query, kind) {
+                    std::task::Poll::Ready(res) => break res?,
+                    std::task::Poll::Pending => registry.block_until_ready()?,
+                }
+            };
+            candidates.extend(summaries);
+        }
+        let requirements = requirements_on(&resolve, id);
+        let mut candidates: Vec<_> = candidates
+            .into_iter()
+            .filter(|s| {
+                let version = s.as_summary().version();
+                version > id.version()
+                    && (!version.is_prerelease()
+                        || requirements
+                            .iter()
+                            .flat_map(|(_, deps)| deps.iter())
+                            .any(|dep| requirement_matches(dep, version)))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.as_summary().version().cmp(a.as_summary().version()));
+
+        let Some(latest) = candidates.first() else {
+            gctx.shell().status("Explaining", id)?;
+            gctx.shell()
+                .note(format!("`{id}` is the latest version of `{}`", id.name()))?;
+            continue;
+        };
+        let yanked = if latest.is_yanked() { " (yanked)" } else { "" };
+        gctx.shell().status(
+            "Explaining",
+            format!(
+                "{id}, the latest version is v{}{yanked}",
+                latest.as_summary().version()
+            ),
+        )?;
+
+        let blockers = update_blockers(ws, &resolve, &requirements, latest)?;
+        if !blockers.is_empty() {
+            let mut msg = format!("v{} isn't selected because:", latest.as_summary().version());
+            for blocker in &blockers {
+                msg.push_str("\n  - ");
+                msg.push_str(blocker);
+            }
+            gctx.shell().note(msg)?;
+        }
+
+        let mut allowed = None;
+        for candidate in &candidates {
+            if update_blockers(ws, &resolve, &requirements, candidate)?.is_empty() {
+                allowed = Some(candidate.as_summary().version().clone());
+                break;
+            }
+        }
+        let Some(allowed) = allowed else {
+            gctx.shell().note(format!(
+                "`{id}` is the latest version allowed by the requirements on it"
+            ))?;
+            continue;
+        };
+
+        // Resolve again with only `allowed` available, to find out whether
+        // the other requirements of the dependency graph allow it.
+        let source_id = id.source_id().with_precise_registry_version(
+            id.name(),
+            id.version().clone(),
+            &allowed.to_string(),
+        )?;
+        let mut trial_registry = PackageRegistry::new(gctx)?;
+        trial_registry.add_sources([source_id])?;
+        let keep = |p: &PackageId| *p != id;
+        let trial = ops::resolve_with_previous(
+            &mut trial_registry,
+            ws,
+            &CliFeatures::new_all(true),
+            HasDevUnits::Yes,
+            Some(&resolve),
+            Some(&keep),
+            &[],
+            true,
+        );
+        match trial {
+            Ok(_) => gctx.shell().note(format!(
+                "v{allowed} is allowed by the requirements on it, \
+                 run `cargo update {name}` to select it",
+                name = id.name()
+            ))?,
+            Err(e) => {
+                let mut msg = format!("v{allowed} is allowed by the requirements on it, but:");
+                for cause in e.chain() {
+                    for line in cause.to_string().lines() {
+                        msg.push('\n');
+                        if !line.is_empty() {
+                            msg.push_str("  ");
+                            msg.push_str(line);
+                        }
+                    }
+                }
+                gctx.shell().note(msg)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The packages depending on `id` in `resolve`, with their dependencies on
+/// it, sorted.
+fn requirements_on(resolve: &Resolve, id: PackageId) -> Vec<(PackageId, &HashSet<Dependency>)> {
+    let mut parents: Vec<_> = resolve
+        .iter()
+        .filter_map(|parent| {
+            resolve
+                .deps(parent)
+                .find(|(dep, _)| *dep == id)
+                .map(|(_, deps)| (parent, deps))
+        })
+        .collect();
+    parents.sort_by_key(|(parent, _)| *parent);
+    parents
+}
+
+/// Whether the original requirement of `dep` matches `version`.
+///
+/// The requirements are locked to the current version, so the original ones
+/// are checked instead.
+fn requirement_matches(dep: &Dependency, version: &semver::Version) -> bool {
+    match dep.version_req() {
+        OptVersionReq::Any => true,
+        OptVersionReq::Req(req)
+        | OptVersionReq::Locked(_, req)
+        | OptVersionReq::Precise(_, req) => req.matches(version),
+    }
+}
+
+/// Why `candidate` can't replace the package with the `requirements` in
+/// `resolve`: whether it was yanked, the requirements it doesn't match, the
+/// features it lacks, and the `rust-version` of a dependent it is newer than.
+fn update_blockers(
+    ws: &Workspace<'_>,
+    resolve: &Resolve,
+    requirements: &[(PackageId, &HashSet<Dependency>)],
+    candidate: &IndexSummary,
+) -> CargoResult<Vec<String>> {
+    let mut blockers = Vec::new();
+    if candidate.is_yanked() {
+        blockers.push("it was yanked by its author".to_owned());
+    }
+    let candidate = candidate.as_summary();
+    for &(parent, deps) in requirements {
+        let path = resolve
+            .path_to_top(&parent)
+            .into_iter()
+            .rev()
+            .map(|(id, _)| id.name().to_string())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        for dep in deps {
+            if !requirement_matches(dep, candidate.version()) {
+                blockers.push(format!(
+                    "`{parent}` requires `{} {}`, through {path}",
+                    dep.package_name(),
+                    dep.version_req()
+                ));
+            }
+            for feature in dep.features() {
+                if !feature.contains('/') && !candidate.features().contains_key(feature) {
+                    blockers.push(format!(
+                        "`{parent}` enables its feature `{feature}`, which it doesn't have, \
+                         through {path}"
+                    ));
+                }
+            }
+        }
+    }
+
+    if ws.resolve_honors_rust_version() {
+        if let Some(required) = candidate.rust_version() {
+            for &(parent, _) in requirements {
+                let blocker = match resolve.summary(parent).rust_version() {
+                    Some(rust_version)
+                        if !required.is_compatible_with(&rust_version.clone().into_partial()) =>
+                    {
+                        format!(
+                            "it requires Rust {required}, which is newer than the \
+                             rust-version of `{}` ({rust_version})",
+                            parent.name()
+                        )
+                    }
+                    Some(_) => continue,
+                    None => {
+                        let rustc = ws.gctx().load_global_rustc(Some(ws))?;
+                        let rust_version = rustc.version.clone().into();
+                        if required.is_compatible_with(&rust_version) {
+                            continue;
+                        }
+                        format!(
+                            "it requires Rust {required}, which is newer than Rust {rust_version}"
+                        )
+                    }
+                };
+                if !blockers.contains(&blocker) {
+                    blockers.push(blocker);
+                }
+            }
+        }
+    }
+    Ok(blockers)
+}
+
 /// Prints lockfile change statuses.
 ///
 /// This would acquire the package-cache lock, as it may update the index to
@@ -201,10 +503,11 @@ pub fn print_lockfile_changes(
         .gctx()
         .acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
     if let Some(previous_resolve) = previous_resolve {
-        print_lockfile_sync(ws, previous_resolve, resolve, registry)
+        print_lockfile_sync(ws, previous_resolve, resolve, registry)?;
     } else {
-        print_lockfile_generation(ws, resolve, registry)
+        print_lockfile_generation(ws, resolve, registry)?;
     }
+    print_rust_version_report(ws, resolve, registry)
 }
 
 fn print_lockfile_generation(
@@ -511,7 +814,9 @@ fn status_locking(ws: &Workspace<'_>, num_pkgs: usize) -> CargoResult<()> {
             write!(&mut cfg, " latest")?;
         }
 
-        if ws.resolve_honors_rust_version() {
+        if ws.resolve_rust_version_per_member() {
+            write!(&mut cfg, " per-member Rust")?;
+        } else if ws.resolve_honors_rust_version() {
             let rust_version = if let Some(ver) = ws.rust_version() {
                 ver.clone().into_partial()
             } else {
@@ -530,6 +835,114 @@ fn status_locking(ws: &Workspace<'_>, num_pkgs: usize) -> CargoResult<()> {
     Ok(())
 }
 
+/// Warns about the packages that aren't compatible with the `rust-version` of
+/// a member depending on them, when each member's `rust-version` is honored
+/// separately.
+///
+/// Each one comes with the path through which the member depends on it, and
+/// whether any version matching the requirement on it would have been
+/// compatible.
+fn print_rust_version_report(
+    ws: &Workspace<'_>,
+    resolve: &Resolve,
+    registry: &mut PackageRegistry<'_>,
+) -> CargoResult<()> {
+    if !ws.resolve_rust_version_per_member() {
+        return Ok(());
+    }
+
+    for (member, member_rust_version) in ops::member_rust_versions(ws)? {
+        // Breadth-first, so that the shortest path to each package is kept.
+        let mut parents = HashMap::new();
+        let mut queue = VecDeque::from([member.package_id()]);
+        let mut visited = vec![member.package_id()];
+        while let Some(id) = queue.pop_front() {
+            let mut deps: Vec<_> = resolve.deps(id).map(|(dep, _)| dep).collect();
+            deps.sort();
+            for dep in deps {
+                if !parents.contains_key(&dep) && dep != member.package_id() {
+                    parents.insert(dep, id);
+                    visited.push(dep);
+                    queue.push_back(dep);
+                }
+            }
+        }
+
+        for id in visited {
+            let Some(rust_version) = resolve.summary(id).rust_version() else {
+                continue;
+            };
+            if rust_version.is_compatible_with(&member_rust_version) {
+                continue;
+            }
+            ws.gctx().shell().warn(format!(
+                "{id} requires Rust {rust_version}, which is newer than the \
+                 rust-version of `{}` ({member_rust_version})",
+                member.name()
+            ))?;
+
+            let Some(&parent) = parents.get(&id) else {
+                continue;
+            };
+            let mut path = vec![id.name()];
+            let mut current = id;
+            while let Some(&parent) = parents.get(&current) {
+                path.push(parent.name());
+                current = parent;
+            }
+            path.reverse();
+            ws.gctx().shell().note(format!(
+                "`{}` depends on it through {}",
+                member.name(),
+                path.join(" -> ")
+            ))?;
+
+            if !id.source_id().is_registry() {
+                continue;
+            }
+            let reqs = resolve
+                .deps(parent)
+                .filter(|(dep, _)| *dep == id)
+                .flat_map(|(_, deps)| deps.iter());
+            for req in reqs {
+                let candidates = loop {
+                    match registry.query_vec(req, QueryKind::Exact) {
+                        std::task::Poll::Ready(res) => {
+                            break res?;
+                        }
+                        std::task::Poll::Pending => registry.block_until_ready()?,
+                    }
+                };
+                let compatible = candidates
+                    .iter()
+                    .map(|s| s.as_summary())
+                    .filter(|s| {
+                        s.rust_version()
+                            .map(|v| v.is_compatible_with(&member_rust_version))
+                            .unwrap_or(true)
+                    })
+                    .map(|s| s.version())
+                    .max();
+                let msg = match compatible {
+                    Some(version) => format!(
+                        "v{version} of `{}` supports Rust {member_rust_version}, but \
+                         v{} is required by another dependent or was kept from the lockfile",
+                        id.name(),
+                        id.version()
+                    ),
+                    None => format!(
+                        "no version of `{}` matching `{}` supports Rust {member_rust_version}",
+                        id.name(),
+                        req.version_req()
+                    ),
+                };
+                ws.gctx().shell().note(msg)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn is_latest(candidate: &semver::Version, current: &semver::Version) -> bool {
     current < candidate
                 // Only match pre-release if major.minor.patch are the same
@@ -680,12 +1093,8 @@ impl PackageDiff {
         .next()
         // Limit to registry as that is the only source with meaningful alternative versions
         .filter(|s| s.source_id().is_registry())?;
-        let query = crate::core::dependency::Dependency::parse(
-            package_id.name(),
-            None,
-            package_id.source_id(),
-        )
-        .expect("already a valid dependency");
+        let query = Dependency::parse(package_id.name(), None, package_id.source_id())
+            .expect("already a valid dependency");
         Some(query)
     }
 }
@@ -4,20 +4,25 @@ use std::sync::Arc;
 use std::{env, fs};
 
 use crate::core::compiler::{CompileKind, DefaultExecutor, Executor, UnitOutput};
-use crate::core::{Dependency, Edition, Package, PackageId, SourceId, Target, Workspace};
+use crate::core::resolver::CliFeatures;
+use crate::core::{
+    Dependency, Edition, GitReference, Package, PackageId, SourceId, Target, Workspace,
+};
 use crate::ops::{common_for_install_and_uninstall::*, FilterRule};
 use crate::ops::{CompileFilter, Packages};
 use crate::sources::source::Source;
 use crate::sources::{GitSource, PathSource, SourceConfigMap};
+use crate::util::context::ConfigRelativePath;
 use crate::util::errors::CargoResult;
-use crate::util::{Filesystem, GlobalContext, Rustc};
+use crate::util::{self, Filesystem, GlobalContext, IntoUrl, Rustc, VersionExt};
 use crate::{drop_println, ops};
 
 use anyhow::{bail, Context as _};
-use cargo_util::paths;
+use cargo_util::{paths, Sha256};
 use cargo_util_schemas::core::PartialVersion;
 use itertools::Itertools;
 use semver::VersionReq;
+use serde::{Deserialize, Serialize};
 use tempfile::Builder as TempFileBuilder;
 
 struct Transaction {
@@ -320,34 +325,56 @@ impl<'gctx> InstallablePackage<'gctx> {
 
         self.check_yanked_install()?;
 
-        let exec: Arc<dyn Executor> = Arc::new(DefaultExecutor);
-        let compile = ops::compile_ws(&self.ws, &self.opts, &exec).with_context(|| {
-            if let Some(td) = td_opt.take() {
-                // preserve the temporary directory, so the user can inspect it
-                drop(td.into_path());
-            }
+        let prebuilt_config = PrebuiltConfig::load(self.gctx)?;
+        let prebuilt = match &prebuilt_config {
+            Some(config) => self.find_prebuilt(&config.dir)?,
+            None => None,
+        };
+        let compile;
+        let mut binaries: Vec<(&str, &Path)> = if let Some(prebuilt) = &prebuilt {
+            prebuilt
+                .iter()
+                .map(|(name, path)| (name.as_str(), path.as_path()))
+                .collect()
+        } else {
+            let exec: Arc<dyn Executor> = Arc::new(DefaultExecutor);
+            compile = ops::compile_ws(&self.ws, &self.opts, &exec).with_context(|| {
+                if let Some(td) = td_opt.take() {
+                    // preserve the temporary directory, so the user can inspect it
+                    drop(td.into_path());
+                }
 
-            format!(
-                "failed to compile `{}`, intermediate artifacts can be \
-                 found at `{}`.\nTo reuse those artifacts with a future \
-                 compilation, set the environment variable \
-                 `CARGO_TARGET_DIR` to that path.",
-                self.pkg,
-                self.ws.target_dir().display()
-            )
-        })?;
-        let mut binaries: Vec<(&str, &Path)> = compile
-            .binaries
-            .iter()
-            .map(|UnitOutput { path, .. }| {
-                let name = path.file_name().unwrap();
-                if let Some(s) = name.to_str() {
-                    Ok((s, path.as_ref()))
-                } else {
-                    bail!("Binary `{:?}` name can't be serialized into string", name)
+                format!(
+                    "failed to compile `{}`, intermediate artifacts can be \
+                     found at `{}`.\nTo reuse those artifacts with a future \
+                     compilation, set the environment variable \
+                     `CARGO_TARGET_DIR` to that path.",
+                    self.pkg,
+                    self.ws.target_dir().display()
+                )
+            })?;
+            let binaries: Vec<(&str, &Path)> = compile
+                .binaries
+                .iter()
+                .map(|UnitOutput { path, .. }| {
+                    let name = path.file_name().unwrap();
+                    if let Some(s) = name.to_str() {
+                        Ok((s, path.as_ref()))
+                    } else {
+                        bail!("Binary `{:?}` name can't be serialized into string", name)
+                    }
+                })
+                .collect::<CargoResult<_>>()?;
+            if let Some(config) = prebuilt_config.as_ref().filter(|c| c.store) {
+                if let Err(e) = self.store_prebuilt(&config.dir, &binaries) {
+                    self.gctx.shell().warn(format!(
+                        "failed to store the prebuilt binaries of `{}`: {e:#}",
+                        self.pkg
+                    ))?;
                 }
-            })
-            .collect::<CargoResult<_>>()?;
+            }
+            binaries
+        };
         if binaries.is_empty() {
             // Cargo already warns the user if they use a target specifier that matches nothing,
             // but we want to error if the user asked for a _particular_ binary to be installed,
@@ -422,7 +449,7 @@ impl<'gctx> InstallablePackage<'gctx> {
         for &(bin, src) in binaries.iter() {
             let dst = staging_dir.path().join(bin);
             // Try to move if `target_dir` is transient.
-            if !self.source_id.is_path() && fs::rename(src, &dst).is_ok() {
+            if !self.source_id.is_path() && prebuilt.is_none() && fs::rename(src, &dst).is_ok() {
                 continue;
             }
             paths::copy(src, &dst)?;
@@ -554,6 +581,112 @@ impl<'gctx> InstallablePackage<'gctx> {
         }
     }
 
+    /// The directory in the prebuilt directory `dir` with the binaries of
+    /// this package, and the metadata they must have been built with.
+    fn prebuilt_entry(&self, dir: &Path) -> (PathBuf, PrebuiltMetadata) {
+        let metadata = PrebuiltMetadata {
+            package: self.pkg.package_id(),
+            target: self.target.clone(),
+            features: feature_set(&self.opts.cli_features.features),
+            all_features: self.opts.cli_features.all_features,
+            no_default_features: !self.opts.cli_features.uses_default_features,
+            profile: self.opts.build_config.requested_profile.to_string(),
+            rustc: util::short_hash(&self.rustc.verbose_version),
+            files: BTreeMap::new(),
+        };
+        let exes = exe_names(&self.pkg, &self.opts.filter);
+        let hash = util::short_hash(&(&metadata, &exes));
+        let name = format!(
+            "{}-{}-{}-{hash}",
+            self.pkg.name(),
+            self.pkg.version(),
+            self.target
+        );
+        (dir.join(name), metadata)
+    }
+
+    /// Looks for the binaries of this package in the prebuilt directory
+    /// `dir`, returning their names and paths if they are all there and
+    /// match their checksums.
+    fn find_prebuilt(&self, dir: &Path) -> CargoResult<Option<Vec<(String, PathBuf)>>> {
+        // Packages from a path may change without changing version.
+        if self.source_id.is_path() {
+            return Ok(None);
+        }
+        let (entry, expected) = self.prebuilt_entry(dir);
+        let metadata_path = entry.join(PREBUILT_METADATA);
+        if !metadata_path.exists() {
+            tracing::debug!("no prebuilt binaries at `{}`", entry.display());
+            return Ok(None);
+        }
+        let metadata = paths::read(&metadata_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| Ok(serde_json::from_str::<PrebuiltMetadata>(&s)?));
+        let mismatch = match metadata {
+            Ok(metadata) if metadata.same_build(&expected) => {
+                let mut binaries = Vec::new();
+                let mut mismatch = None;
+                for exe in exe_names(&self.pkg, &self.opts.filter) {
+                    let path = entry.join(&exe);
+                    let checksum = Sha256::new().update_path(&path).map(|s| s.finish_hex());
+                    match (checksum, metadata.files.get(&exe)) {
+                        (Ok(actual), Some(expected)) if actual == *expected => {
+                            binaries.push((exe, path));
+                        }
+                        _ => {
+                            mismatch = Some(format!("`{exe}` doesn't match its checksum"));
+                            break;
+                        }
+                    }
+                }
+                match mismatch {
+                    None => {
+                        self.gctx.shell().status(
+                            "Reusing",
+                            format!("prebuilt binaries from `{}`", entry.display()),
+                        )?;
+                        return Ok(Some(binaries));
+                    }
+                    Some(mismatch) => mismatch,
+                }
+            }
+            Ok(_) => format!("`{}` describes another build", metadata_path.display()),
+            Err(e) => format!("`{}` is invalid: {e}", metadata_path.display()),
+        };
+        self.gctx.shell().warn(format!(
+            "ignoring the prebuilt binaries of `{}` in `{}`: {mismatch}, \
+             building from source instead",
+            self.pkg,
+            entry.display()
+        ))?;
+        Ok(None)
+    }
+
+    /// Copies the binaries built for this package to the prebuilt directory
+    /// `dir`, for later installs to reuse.
+    fn store_prebuilt(&self, dir: &Path, binaries: &[(&str, &Path)]) -> CargoResult<()> {
+        if self.source_id.is_path() {
+            return Ok(());
+        }
+        let (entry, mut metadata) = self.prebuilt_entry(dir);
+        if entry.exists() {
+            paths::remove_dir_all(&entry)?;
+        }
+        paths::create_dir_all(&entry)?;
+        for &(name, path) in binaries {
+            paths::copy(path, entry.join(name))?;
+            let checksum = Sha256::new().update_path(path)?.finish_hex();
+            metadata.files.insert(name.to_string(), checksum);
+        }
+        let json = serde_json::to_string_pretty(&metadata)?;
+        paths::write(entry.join(PREBUILT_METADATA), json)?;
+        self.gctx.shell().status(
+            "Storing",
+            format!("prebuilt binaries in `{}`", entry.display()),
+        )?;
+        Ok(())
+    }
+
     fn check_yanked_install(&self) -> CargoResult<()> {
         if self.ws.ignore_lock() || !self.ws.root().join("Cargo.lock").exists() {
             return Ok(());
@@ -739,18 +872,7 @@ pub fn install(
     };
 
     if installed_anything {
-        // Print a warning that if this directory isn't in PATH that they won't be
-        // able to run these commands.
-        let path = gctx.get_env_os("PATH").unwrap_or_default();
-        let dst_in_path = env::split_paths(&path).any(|path| path == dst);
-
-        if !dst_in_path {
-            gctx.shell().warn(&format!(
-                "be sure to add `{}` to your PATH to be \
-             able to run the installed binaries",
-                dst.display()
-            ))?;
-        }
+        warn_if_not_in_path(gctx, &dst)?;
     }
 
     if scheduled_error {
@@ -760,6 +882,22 @@ pub fn install(
     Ok(())
 }
 
+/// Print a warning that if this directory isn't in PATH that they won't be
+/// able to run these commands.
+fn warn_if_not_in_path(gctx: &GlobalContext, dst: &Path) -> CargoResult<()> {
+    let path = gctx.get_env_os("PATH").unwrap_or_default();
+    let dst_in_path = env::split_paths(&path).any(|path| path == dst);
+
+    if !dst_in_path {
+        gctx.shell().warn(&format!(
+            "be sure to add `{}` to your PATH to be \
+         able to run the installed binaries",
+            dst.display()
+        ))?;
+    }
+    Ok(())
+}
+
 fn is_installed(
     pkg: &Package,
     gctx: &GlobalContext,
@@ -900,3 +1038,400 @@ fn remove_orphaned_bins(
     }
     Ok(())
 }
+
+/// The file describing the binaries in an entry of the prebuilt directory.
+const PREBUILT_METADATA: &str = "prebuilt.json";
+
+/// The `install.prebuilt-dir` and `install.prebuilt-store` config, a local
+/// directory of binaries built before, to reuse instead of building.
+struct PrebuiltConfig {
+    dir: PathBuf,
+    /// Whether to store the binaries built from source in `dir`.
+    store: bool,
+}
+
+impl PrebuiltConfig {
+    fn load(gctx: &GlobalContext) -> CargoResult<Option<PrebuiltConfig>> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "kebab-case")]
+        struct InstallConfig {
+            prebuilt_dir: Option<ConfigRelativePath>,
+            prebuilt_store: Option<bool>,
+        }
+
+        if !gctx.cli_unstable().install_prebuilt {
+            return Ok(None);
+        }
+        let Some(config) = gctx.get::<Option<InstallConfig>>("install")? else {
+            return Ok(None);
+        };
+        let Some(dir) = config.prebuilt_dir else {
+            return Ok(None);
+        };
+        let dir = if dir.raw_value().starts_with("file://") {
+            dir.raw_value()
+                .into_url()?
+                .to_file_path()
+                .map_err(|()| anyhow::format_err!("invalid file URL `{}`", dir.raw_value()))?
+        } else {
+            dir.resolve_path(gctx)
+        };
+        Ok(Some(PrebuiltConfig {
+            dir,
+            store: config.prebuilt_store.unwrap_or(false),
+        }))
+    }
+}
+
+/// What a set of prebuilt binaries was built from, and their checksums.
+#[derive(Hash, Deserialize, Serialize)]
+struct PrebuiltMetadata {
+    package: PackageId,
+    target: String,
+    features: BTreeSet<String>,
+    all_features: bool,
+    no_default_features: bool,
+    profile: String,
+    /// The hash of the output of `rustc -vV`, since another compiler builds
+    /// other binaries.
+    #[serde(default)]
+    rustc: String,
+    /// The SHA256 checksum of each binary.
+    files: BTreeMap<String, String>,
+}
+
+impl PrebuiltMetadata {
+    fn same_build(&self, other: &PrebuiltMetadata) -> bool {
+        self.package == other.package
+            && self.target == other.target
+            && self.features == other.features
+            && self.all_features == other.all_features
+            && self.no_default_features == other.no_default_features
+            && self.profile == other.profile
+            && self.rustc == other.rustc
+    }
+}
+
+/// The file listing the tools to install with `cargo install --from`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToolsFile {
+    #[serde(default)]
+    tools: BTreeMap<String, ToolSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ToolSpec {
+    Version(String),
+    Detailed(DetailedToolSpec),
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct DetailedToolSpec {
+    version: Option<String>,
+    registry: Option<String>,
+    index: Option<String>,
+    git: Option<String>,
+    branch: Option<String>,
+    tag: Option<String>,
+    rev: Option<String>,
+    path: Option<PathBuf>,
+    #[serde(default)]
+    features: Vec<String>,
+    #[serde(default)]
+    all_features: bool,
+    default_features: Option<bool>,
+    bins: Option<Vec<String>>,
+}
+
+/// The lockfile recording the exact version of each tool installed by
+/// `cargo install --from`, next to the tools file.
+#[derive(Default, PartialEq, Deserialize, Serialize)]
+struct ToolsLock {
+    #[serde(default, rename = "tool")]
+    tools: Vec<LockedTool>,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+struct LockedTool {
+    name: String,
+    version: semver::Version,
+    source: String,
+}
+
+const TOOLS_LOCK_HEADER: &str = "# This file is automatically @generated by Cargo.\n\
+                                 # It is not intended for manual editing.\n";
+
+impl DetailedToolSpec {
+    fn source_id(&self, gctx: &GlobalContext, base: &Path) -> CargoResult<SourceId> {
+        let sources = [
+            self.registry.is_some(),
+            self.index.is_some(),
+            self.git.is_some(),
+            self.path.is_some(),
+        ];
+        if sources.iter().filter(|s| **s).count() > 1 {
+            bail!("only one of `registry`, `index`, `git` or `path` can be specified");
+        }
+        if self.git.is_none() && (self.branch.is_some() || self.tag.is_some() || self.rev.is_some())
+        {
+            bail!("`branch`, `tag` and `rev` can only be specified with `git`");
+        }
+        if let Some(git) = &self.git {
+            let reference = match (&self.branch, &self.tag, &self.rev) {
+                (Some(branch), None, None) => GitReference::Branch(branch.clone()),
+                (None, Some(tag), None) => GitReference::Tag(tag.clone()),
+                (None, None, Some(rev)) => GitReference::Rev(rev.clone()),
+                (None, None, None) => GitReference::DefaultBranch,
+                _ => bail!("only one of `branch`, `tag` or `rev` can be specified"),
+            };
+            SourceId::for_git(&git.into_url()?, reference)
+        } else if let Some(path) = &self.path {
+            SourceId::for_path(&paths::normalize_path(&base.join(path)))
+        } else if let Some(registry) = &self.registry {
+            SourceId::alt_registry(gctx, registry)
+        } else if let Some(index) = &self.index {
+            SourceId::for_registry(&index.into_url()?)
+        } else {
+            SourceId::crates_io(gctx)
+        }
+    }
+
+    fn compile_options(&self, opts: &ops::CompileOptions) -> CargoResult<ops::CompileOptions> {
+        let mut opts = opts.clone();
+        opts.cli_features = CliFeatures::from_command_line(
+            &self.features,
+            self.all_features,
+            self.default_features.unwrap_or(true),
+        )?;
+        if let Some(bins) = &self.bins {
+            opts.filter = CompileFilter::new(
+                ops::LibRule::False,
+                FilterRule::Just(bins.clone()),
+                FilterRule::none(),
+                FilterRule::none(),
+                FilterRule::none(),
+            );
+        }
+        Ok(opts)
+    }
+}
+
+impl LockedTool {
+    /// Whether this locked version can be used for a tool with the given
+    /// source and version requirement.
+    fn satisfies(&self, name: &str, source_id: SourceId, req: Option<&VersionReq>) -> bool {
+        let Ok(locked_source) = SourceId::from_url(&self.source) else {
+            return false;
+        };
+        self.name == name
+            && locked_source.without_precise() == source_id.without_precise()
+            && req.map_or(true, |req| req.matches(&self.version))
+    }
+}
+
+/// Installs the tools listed in the file at `path`, at the versions recorded
+/// in the lockfile next to it when they still match, and uninstalls the tools
+/// that were installed from the file but were removed from it.
+pub fn install_from(
+    gctx: &GlobalContext,
+    root: Option<&str>,
+    path: &Path,
+    opts: &ops::CompileOptions,
+    force: bool,
+) -> CargoResult<()> {
+    let path = gctx.cwd().join(path);
+    let contents = paths::read(&path)?;
+    let file: ToolsFile = toml::from_str(&contents)
+        .with_context(|| format!("failed to parse `{}`", path.display()))?;
+    let lock_path = path.with_extension("lock");
+    let lock: ToolsLock = if lock_path.exists() {
+        toml::from_str(&paths::read(&lock_path)?)
+            .with_context(|| format!("failed to parse `{}`", lock_path.display()))?
+    } else {
+        ToolsLock::default()
+    };
+    let base = path.parent().unwrap();
+
+    let root = resolve_root(root, gctx)?;
+    let dst = root.join("bin").into_path_unlocked();
+    let map = SourceConfigMap::new(gctx)?;
+    let current_rust_version = if opts.honor_rust_version.unwrap_or(true) {
+        let rustc = gctx.load_global_rustc(None)?;
+        Some(rustc.version.clone().into())
+    } else {
+        None
+    };
+
+    // Work out the source and version of each tool first, so that nothing
+    // is installed if the lockfile can't be used as is with `--locked`.
+    let mut tools = Vec::new();
+    for (name, spec) in file.tools {
+        let spec = match spec {
+            ToolSpec::Version(version) => DetailedToolSpec {
+                version: Some(version),
+                ..Default::default()
+            },
+            ToolSpec::Detailed(spec) => spec,
+        };
+        let source_id = spec
+            .source_id(gctx, base)
+            .with_context(|| format!("invalid specification of tool `{name}`"))?;
+        let req = spec
+            .version
+            .as_deref()
+            .map(|v| v.parse::<VersionReq>())
+            .transpose()
+            .with_context(|| format!("invalid version requirement of tool `{name}`"))?;
+        let locked = if source_id.is_path() {
+            None
+        } else {
+            lock.tools
+                .iter()
+                .find(|t| t.satisfies(&name, source_id, req.as_ref()))
+        };
+        if locked.is_none() && !source_id.is_path() && gctx.locked() {
+            bail!(
+                "the lock file {} needs to be updated for tool `{name}` \
+                 but --locked was passed to prevent this",
+                lock_path.display()
+            );
+        }
+        tools.push((name, spec, source_id, req, locked.cloned()));
+    }
+
+    let mut succeeded = Vec::new();
+    let mut failed = Vec::new();
+    let mut updated_sources = HashSet::new();
+    for (name, spec, source_id, req, locked) in &tools {
+        let result = (|| -> CargoResult<bool> {
+            let (source_id, req) = match locked {
+                Some(locked) => (
+                    SourceId::from_url(&locked.source)?,
+                    Some(locked.version.to_exact_req()),
+                ),
+                None => (*source_id, req.clone()),
+            };
+            let opts = spec.compile_options(opts)?;
+            let installable = InstallablePackage::new(
+                gctx,
+                root.clone(),
+                map.clone(),
+                (!source_id.is_path()).then_some(name.as_str()),
+                source_id,
+                false,
+                req.as_ref(),
+                &opts,
+                force,
+                false,
+                updated_sources.insert(source_id.without_precise()),
+                current_rust_version.as_ref(),
+            )?;
+            match installable {
+                Some(installable) => {
+                    if installable.pkg.name().as_str() != name {
+                        bail!(
+                            "package at `{}` is named `{}`, not `{name}`",
+                            installable.pkg.root().display(),
+                            installable.pkg.name(),
+                        );
+                    }
+                    installable.install_one()
+                }
+                None => Ok(false),
+            }
+        })();
+        match result {
+            Ok(true) => succeeded.push(name.as_str()),
+            Ok(false) => {}
+            Err(e) => {
+                crate::display_error(
+                    &e.context(format!("failed to install tool `{name}`")),
+                    &mut gctx.shell(),
+                );
+                failed.push(name.as_str());
+            }
+        }
+    }
+
+    // Record what is installed now, keeping the previous versions of the
+    // tools that failed to install.
+    let mut new_lock = ToolsLock::default();
+    let tracker = InstallTracker::load(gctx, &root)?;
+    for (name, _, source_id, _, locked) in &tools {
+        if source_id.is_path() {
+            continue;
+        }
+        let installed = tracker
+            .all_installed_bins()
+            .map(|(id, _)| *id)
+            .filter(|id| {
+                id.name().as_str() == name
+                    && id.source_id().without_precise() == source_id.without_precise()
+            })
+            .max_by_key(|id| id.version().clone());
+        let entry = match (failed.contains(&name.as_str()), installed) {
+            (false, Some(id)) => Some(LockedTool {
+                name: name.clone(),
+                version: id.version().clone(),
+                source: id.source_id().as_encoded_url().to_string(),
+            }),
+            _ => locked.clone(),
+        };
+        new_lock.tools.extend(entry);
+    }
+    drop(tracker);
+
+    // Uninstall the tools installed from this file before, but no longer
+    // listed in it.
+    let removed: Vec<_> = lock
+        .tools
+        .iter()
+        .filter(|t| !tools.iter().any(|(name, ..)| *name == t.name))
+        .collect();
+    for tool in removed {
+        let spec = format!("{}@{}", tool.name, tool.version);
+        let tracker = InstallTracker::load(gctx, &root)?;
+        let is_installed = tracker
+            .all_installed_bins()
+            .any(|(id, _)| id.name().as_str() == tool.name && *id.version() == tool.version);
+        drop(tracker);
+        if is_installed {
+            ops::cargo_uninstall::uninstall_one(&root, &spec, &[], gctx)?;
+        }
+    }
+
+    if new_lock != lock || !lock_path.exists() {
+        if gctx.locked() {
+            bail!(
+                "the lock file {} needs to be updated but --locked was passed to prevent this",
+                lock_path.display()
+            );
+        }
+        let contents = toml::to_string_pretty(&new_lock)?;
+        paths::write(&lock_path, format!("{TOOLS_LOCK_HEADER}{contents}"))?;
+    }
+
+    let mut summary = vec![];
+    if !succeeded.is_empty() {
+        summary.push(format!("Successfully installed {}!", succeeded.join(", ")));
+    }
+    if !failed.is_empty() {
+        summary.push(format!(
+            "Failed to install {} (see error(s) above).",
+            failed.join(", ")
+        ));
+    }
+    if !summary.is_empty() {
+        gctx.shell().status("Summary", summary.join(" "))?;
+    }
+    if !succeeded.is_empty() {
+        warn_if_not_in_path(gctx, &dst)?;
+    }
+    if !failed.is_empty() {
+        bail!("some tools failed to install");
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::SeekFrom;
@@ -7,20 +7,27 @@ use std::sync::Arc;
 use std::task::Poll;
 
 use crate::core::compiler::{BuildConfig, CompileMode, DefaultExecutor, Executor};
+use crate::core::dependency::DepKind;
 use crate::core::manifest::Target;
 use crate::core::resolver::CliFeatures;
-use crate::core::{registry::PackageRegistry, resolver::HasDevUnits};
-use crate::core::{Feature, PackageIdSpecQuery, Shell, Verbosity, Workspace};
+use crate::core::resolver::HasDevUnits;
+use crate::core::{Dependency, Feature, PackageIdSpecQuery, Shell, Verbosity, Workspace};
 use crate::core::{Package, PackageId, PackageSet, Resolve, SourceId};
+use crate::sources::registry;
 use crate::sources::PathSource;
 use crate::util::cache_lock::CacheLockMode;
 use crate::util::context::JobsConfig;
 use crate::util::errors::CargoResult;
+use crate::util::graph::Graph;
 use crate::util::toml::prepare_for_publish;
-use crate::util::{self, human_readable_bytes, restricted_names, FileLock, GlobalContext};
+use crate::util::{
+    self, human_readable_bytes, restricted_names, FileLock, Filesystem, GlobalContext,
+};
 use crate::{drop_println, ops};
 use anyhow::Context as _;
 use cargo_util::paths;
+use cargo_util::registry::make_dep_path;
+use cargo_util::Sha256;
 use flate2::read::GzDecoder;
 use flate2::{Compression, GzBuilder};
 use serde::Serialize;
@@ -39,6 +46,9 @@ pub struct PackageOpts<'gctx> {
     pub to_package: ops::Packages,
     pub targets: Vec<String>,
     pub cli_features: CliFeatures,
+    /// Whether to check the changes to the public API of the packages
+    /// against SemVer, see the `semver_check` module.
+    pub semver_check: bool,
 }
 
 const ORIGINAL_MANIFEST_FILE: &str = "Cargo.toml.orig";
@@ -82,10 +92,15 @@ struct GitVcsInfo {
     sha1: String,
 }
 
+/// Packages `pkg`, verifying it unless `opts.verify` is unset.
+///
+/// If `local_reg` is set, the new package is added to it, and the packages
+/// already in it are used as if they were published while verifying.
 pub fn package_one(
     ws: &Workspace<'_>,
     pkg: &Package,
     opts: &PackageOpts<'_>,
+    mut local_reg: Option<&mut TmpRegistry>,
 ) -> CargoResult<Option<FileLock>> {
     let gctx = ws.gctx();
     let mut src = PathSource::new(pkg.root(), pkg.package_id().source_id(), gctx);
@@ -143,11 +158,25 @@ pub fn package_one(
     gctx.shell()
         .status("Packaging", pkg.package_id().to_string())?;
     dst.file().set_len(0)?;
-    let uncompressed_size = tar(ws, pkg, ar_files, dst.file(), &filename)
-        .with_context(|| "failed to prepare local package for uploading")?;
+    let (uncompressed_size, publish_pkg) = tar(
+        ws,
+        pkg,
+        local_reg.as_deref(),
+        ar_files,
+        dst.file(),
+        &filename,
+    )
+    .with_context(|| "failed to prepare local package for uploading")?;
+    if let Some(local_reg) = local_reg.as_deref_mut() {
+        local_reg.add_package(&publish_pkg, &dst)?;
+    }
     if opts.verify {
         dst.seek(SeekFrom::Start(0))?;
-        run_verify(ws, pkg, &dst, opts).with_context(|| "failed to verify package tarball")?
+        run_verify(ws, pkg, &dst, opts, local_reg.as_deref())
+            .with_context(|| "failed to verify package tarball")?
+    }
+    if opts.semver_check {
+        super::semver_check::check(ws, pkg, opts)?;
     }
 
     dst.seek(SeekFrom::Start(0))?;
@@ -184,10 +213,20 @@ pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option
             spec.query(member_ids)?;
         }
     }
-    let pkgs = ws.members_with_features(specs, &opts.cli_features)?;
+    let mut pkgs = ws.members_with_features(specs, &opts.cli_features)?;
 
     let mut dsts = Vec::with_capacity(pkgs.len());
 
+    // Packages depending on other selected packages are verified against
+    // the new `.crate` files of those, since they may not be published yet.
+    let mut local_reg =
+        if ws.gctx().cli_unstable().package_workspace && pkgs.len() > 1 && !opts.list {
+            pkgs = sort_by_local_deps(pkgs);
+            Some(TmpRegistry::new(ws)?)
+        } else {
+            None
+        };
+
     if ws.root().join("Cargo.lock").exists() {
         // Make sure the Cargo.lock is up-to-date and valid.
         let _ = ops::resolve_ws(ws)?;
@@ -210,7 +249,9 @@ pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option
                 to_package: ops::Packages::Default,
                 targets: opts.targets.clone(),
                 cli_features: cli_features,
+                semver_check: opts.semver_check,
             },
+            local_reg.as_mut(),
         )?;
 
         if !opts.list {
@@ -226,6 +267,178 @@ pub fn package(ws: &Workspace<'_>, opts: &PackageOpts<'_>) -> CargoResult<Option
     }
 }
 
+/// Sorts `pkgs` so that each package comes after the packages of `pkgs` it
+/// depends on. Dev-dependencies are ignored, since they aren't needed to
+/// verify or publish a package, and often form cycles between members.
+pub(crate) fn sort_by_local_deps<'a, T>(pkgs: Vec<(&'a Package, T)>) -> Vec<(&'a Package, T)> {
+    let mut graph: Graph<PackageId, ()> = Graph::new();
+    let mut by_id: HashMap<_, _> = pkgs
+        .into_iter()
+        .map(|(pkg, extra)| (pkg.package_id(), (pkg, extra)))
+        .collect();
+    for (&id, (pkg, _)) in &by_id {
+        graph.add(id);
+        for dep in pkg.dependencies() {
+            if dep.kind() == DepKind::Development {
+                continue;
+            }
+            if let Some(&dep_id) = by_id.keys().find(|dep_id| dep.matches_id(**dep_id)) {
+                graph.link(id, dep_id);
+            }
+        }
+    }
+    // Dependencies come first.
+    graph
+        .sort()
+        .into_iter()
+        .map(|id| by_id.remove(&id).unwrap())
+        .collect()
+}
+
+/// A local registry holding the packages created by a single invocation of
+/// `cargo package` or `cargo publish --dry-run`, so that the packages
+/// depending on them can be verified before they are published.
+///
+/// It lives in `target/package/tmp-registry`, and is overlaid on top of the
+/// registries the packages depend on with [`OverlaySource`].
+///
+/// [`OverlaySource`]: crate::sources::OverlaySource
+pub struct TmpRegistry {
+    root: Filesystem,
+    packages: Vec<PackageId>,
+    /// The registries that the packages added so far depend on other
+    /// packages of this registry through.
+    upstreams: HashSet<SourceId>,
+    _lock: FileLock,
+}
+
+impl TmpRegistry {
+    /// Creates an empty registry, removing the packages of earlier runs.
+    pub fn new(ws: &Workspace<'_>) -> CargoResult<TmpRegistry> {
+        let gctx = ws.gctx();
+        let root = ws.target_dir().join("package").join("tmp-registry");
+        let lock = root.open_rw_exclusive_create(".cargo-lock", gctx, "temporary registry")?;
+        let path = root.as_path_unlocked();
+        for entry in fs::read_dir(path)
+            .with_context(|| format!("failed to read directory `{}`", path.display()))?
+        {
+            let entry = entry?;
+            if entry.file_name() == "index" {
+                paths::remove_dir_all(entry.path())?;
+            } else if entry.path().extension().is_some_and(|ext| ext == "crate") {
+                paths::remove_file(entry.path())?;
+            }
+        }
+        paths::create_dir_all(path.join("index"))?;
+
+        // Registry sources keep the packages they unpack by the path of the
+        // registry, which is the same for every run.
+        let _cache_lock = gctx.acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
+        let unpacked = gctx.registry_source_path().join(registry::short_name(
+            SourceId::for_local_registry(path)?,
+            false,
+        ));
+        let unpacked = unpacked.as_path_unlocked();
+        if unpacked.exists() {
+            paths::remove_dir_all(unpacked)?;
+        }
+
+        Ok(TmpRegistry {
+            root,
+            packages: Vec::new(),
+            upstreams: HashSet::new(),
+            _lock: lock,
+        })
+    }
+
+    /// Adds `publish_pkg`, whose `.crate` file is `tarball`, to the index.
+    fn add_package(&mut self, publish_pkg: &Package, tarball: &FileLock) -> CargoResult<()> {
+        let root = self.root.as_path_unlocked().to_path_buf();
+        let id = publish_pkg.package_id();
+        debug!("adding {id} to the temporary registry");
+        let crate_path = root.join(id.tarball_name());
+        paths::copy(tarball.path(), &crate_path)?;
+        let cksum = Sha256::new().update_path(&crate_path)?.finish_hex();
+
+        let deps: Vec<_> = publish_pkg
+            .dependencies()
+            .iter()
+            .filter(|dep| dep.source_id().is_registry())
+            .map(|dep| {
+                if self.provides(dep) {
+                    self.upstreams.insert(dep.source_id());
+                }
+                serde_json::json!({
+                    "name": dep.name_in_toml(),
+                    "req": dep.version_req().to_string(),
+                    "features": dep.features(),
+                    "optional": dep.is_optional(),
+                    "default_features": dep.uses_default_features(),
+                    "target": dep.platform().map(|p| p.to_string()),
+                    "kind": match dep.kind() {
+                        DepKind::Normal => "normal",
+                        DepKind::Build => "build",
+                        DepKind::Development => "dev",
+                    },
+                    "registry": dep.source_id().url().to_string(),
+                    "package": (dep.name_in_toml() != dep.package_name())
+                        .then(|| dep.package_name()),
+                })
+            })
+            .collect();
+        let features: BTreeMap<_, Vec<_>> = publish_pkg
+            .summary()
+            .features()
+            .iter()
+            .map(|(name, values)| (name, values.iter().map(|v| v.to_string()).collect()))
+            .collect();
+        let line = serde_json::json!({
+            "name": id.name(),
+            "vers": id.version().to_string(),
+            "deps": deps,
+            "features": features,
+            "cksum": cksum,
+            "yanked": false,
+            "links": publish_pkg.manifest().links(),
+            "rust_version": publish_pkg.rust_version(),
+        });
+
+        let index_path = root
+            .join("index")
+            .join(make_dep_path(&id.name().to_lowercase(), false));
+        paths::create_dir_all(index_path.parent().unwrap())?;
+        paths::append(&index_path, format!("{line}\n").as_bytes())?;
+        self.packages.push(id);
+        Ok(())
+    }
+
+    /// Whether `dep` may be resolved to a package of this registry.
+    fn provides(&self, dep: &Dependency) -> bool {
+        self.packages
+            .iter()
+            .any(|&id| id.name() == dep.package_name() && dep.version_req().matches(id.version()))
+    }
+
+    /// Overlays this registry on top of the registries through which `ws`,
+    /// or the packages in this registry, depend on packages of this
+    /// registry.
+    fn add_overlays(&self, ws: &mut Workspace<'_>) {
+        let mut upstreams = self.upstreams.clone();
+        for pkg in ws.members() {
+            for dep in pkg.dependencies() {
+                if dep.source_id().is_registry() && self.provides(dep) {
+                    upstreams.insert(dep.source_id());
+                }
+            }
+        }
+        let path = self.root.as_path_unlocked();
+        for upstream in upstreams {
+            debug!("overlaying {} on top of {upstream}", path.display());
+            ws.add_local_overlay(upstream, path.to_path_buf());
+        }
+    }
+}
+
 /// Builds list of files to archive.
 fn build_ar_list(
     ws: &Workspace<'_>,
@@ -453,12 +666,19 @@ fn error_custom_build_file_not_in_package(
 }
 
 /// Construct `Cargo.lock` for the package to be published.
-fn build_lock(ws: &Workspace<'_>, publish_pkg: &Package) -> CargoResult<String> {
+fn build_lock(
+    ws: &Workspace<'_>,
+    publish_pkg: &Package,
+    local_reg: Option<&TmpRegistry>,
+) -> CargoResult<String> {
     let gctx = ws.gctx();
     let orig_resolve = ops::load_pkg_lockfile(ws)?;
 
-    let tmp_ws = Workspace::ephemeral(publish_pkg.clone(), ws.gctx(), None, true)?;
-    let mut tmp_reg = PackageRegistry::new(ws.gctx())?;
+    let mut tmp_ws = Workspace::ephemeral(publish_pkg.clone(), ws.gctx(), None, true)?;
+    if let Some(local_reg) = local_reg {
+        local_reg.add_overlays(&mut tmp_ws);
+    }
+    let mut tmp_reg = tmp_ws.package_registry()?;
     let mut new_resolve = ops::resolve_with_previous(
         &mut tmp_reg,
         &tmp_ws,
@@ -669,14 +889,16 @@ fn check_repo_state(
 
 /// Compresses and packages a list of [`ArchiveFile`]s and writes into the given file.
 ///
-/// Returns the uncompressed size of the contents of the new archive file.
+/// Returns the uncompressed size of the contents of the new archive file, and
+/// the package as described by its generated manifest.
 fn tar(
     ws: &Workspace<'_>,
     pkg: &Package,
+    local_reg: Option<&TmpRegistry>,
     ar_files: Vec<ArchiveFile>,
     dst: &File,
     filename: &str,
-) -> CargoResult<u64> {
+) -> CargoResult<(u64, Package)> {
     // Prepare the encoder and its header.
     let filename = Path::new(filename);
     let encoder = GzBuilder::new()
@@ -725,7 +947,7 @@ fn tar(
             FileContents::Generated(generated_kind) => {
                 let contents = match generated_kind {
                     GeneratedFile::Manifest => publish_pkg.manifest().to_resolved_contents()?,
-                    GeneratedFile::Lockfile => build_lock(ws, &publish_pkg)?,
+                    GeneratedFile::Lockfile => build_lock(ws, &publish_pkg, local_reg)?,
                     GeneratedFile::VcsInfo(ref s) => serde_json::to_string_pretty(s)?,
                 };
                 header.set_entry_type(EntryType::file());
@@ -743,7 +965,7 @@ fn tar(
 
     let encoder = ar.into_inner()?;
     encoder.finish()?;
-    Ok(uncompressed_size)
+    Ok((uncompressed_size, publish_pkg))
 }
 
 /// Generate warnings when packaging Cargo.lock, and the resolve have changed.
@@ -882,6 +1104,7 @@ fn run_verify(
     pkg: &Package,
     tar: &FileLock,
     opts: &PackageOpts<'_>,
+    local_reg: Option<&TmpRegistry>,
 ) -> CargoResult<()> {
     let gctx = ws.gctx();
 
@@ -906,7 +1129,10 @@ fn run_verify(
     let mut src = PathSource::new(&dst, id, ws.gctx());
     let new_pkg = src.root_package()?;
     let pkg_fingerprint = hash_all(&dst)?;
-    let ws = Workspace::ephemeral(new_pkg, gctx, None, true)?;
+    let mut ws = Workspace::ephemeral(new_pkg, gctx, None, true)?;
+    if let Some(local_reg) = local_reg {
+        local_reg.add_overlays(&mut ws);
+    }
 
     let rustc_args = if pkg
         .manifest()
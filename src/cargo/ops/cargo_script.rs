@@ -0,0 +1,154 @@
+//! Management of the build directories of scripts, with `cargo script`.
+//!
+//! Scripts are built in `$CARGO_HOME/target`, in a directory of their own,
+//! or one shared by the scripts next to it with the same dependencies. Each
+//! directory records the scripts built in it, so that the directories of
+//! scripts that were deleted can be found and removed.
+
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use cargo_util::paths;
+
+use crate::core::Workspace;
+use crate::drop_println;
+use crate::ops::CleanContext;
+use crate::util::{human_readable_bytes, CargoResult, FileLock, Filesystem, GlobalContext};
+
+/// The file listing the scripts built in a build directory.
+const SCRIPTS_FILE: &str = ".cargo-scripts";
+
+/// A build directory of scripts.
+struct ScriptDir {
+    path: PathBuf,
+    /// The scripts built in the directory.
+    scripts: Vec<PathBuf>,
+}
+
+/// Records the script of `ws` in the build directories it uses.
+pub fn record_script(ws: &Workspace<'_>) -> CargoResult<()> {
+    let mut dirs = vec![ws.script_dir()];
+    if ws.shares_script_target_dir() {
+        dirs.push(ws.target_dir());
+    }
+    let script = ws.root_manifest();
+    for dir in dirs {
+        let lock = lock_scripts(&dir, ws.gctx())?;
+        let mut scripts = read_locked_scripts(&lock)?;
+        if !scripts.iter().any(|s| s == script) {
+            scripts.push(script.to_owned());
+            write_locked_scripts(&lock, &scripts)?;
+        }
+    }
+    Ok(())
+}
+
+/// Locks the list of scripts of a build directory, for a read-modify-write.
+fn lock_scripts(dir: &Filesystem, gctx: &GlobalContext) -> CargoResult<FileLock> {
+    dir.open_rw_exclusive_create(SCRIPTS_FILE, gctx, "script list")
+}
+
+fn read_scripts(file: &Path) -> CargoResult<Vec<PathBuf>> {
+    if !file.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_scripts(&paths::read(file)?))
+}
+
+fn read_locked_scripts(lock: &FileLock) -> CargoResult<Vec<PathBuf>> {
+    let mut contents = String::new();
+    lock.file().read_to_string(&mut contents)?;
+    Ok(parse_scripts(&contents))
+}
+
+fn parse_scripts(contents: &str) -> Vec<PathBuf> {
+    contents.lines().map(PathBuf::from).collect()
+}
+
+fn write_locked_scripts(lock: &FileLock, scripts: &[PathBuf]) -> CargoResult<()> {
+    let mut contents = String::new();
+    for script in scripts {
+        contents.push_str(&script.to_string_lossy());
+        contents.push('\n');
+    }
+    let mut file = lock.file();
+    file.seek(SeekFrom::Start(0))?;
+    file.set_len(0)?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+/// Finds the build directories of scripts, in `$CARGO_HOME/target/*/*`.
+fn script_dirs(gctx: &GlobalContext) -> CargoResult<Vec<ScriptDir>> {
+    let root = gctx.home().join("target").into_path_unlocked();
+    let mut dirs = Vec::new();
+    if !root.is_dir() {
+        return Ok(dirs);
+    }
+    for parent in fs::read_dir(&root)? {
+        let parent = parent?.path();
+        if !parent.is_dir() {
+            continue;
+        }
+        for path in fs::read_dir(&parent)? {
+            let path = path?.path();
+            let file = path.join(SCRIPTS_FILE);
+            if file.is_file() {
+                let scripts = read_scripts(&file)?;
+                dirs.push(ScriptDir { path, scripts });
+            }
+        }
+    }
+    dirs.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(dirs)
+}
+
+/// Lists the build directories of scripts, with their size and the scripts
+/// built in them.
+pub fn script_list(gctx: &GlobalContext) -> CargoResult<()> {
+    for dir in script_dirs(gctx)? {
+        let (size, unit) = human_readable_bytes(cargo_util::du(&dir.path, &[]).unwrap_or(0));
+        drop_println!(gctx, "{} ({size:.1}{unit})", dir.path.display());
+        for script in &dir.scripts {
+            if script.exists() {
+                drop_println!(gctx, "    {}", script.display());
+            } else {
+                drop_println!(gctx, "    {} (deleted)", script.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Removes the build directories of scripts that were all deleted.
+pub fn script_clean(gctx: &GlobalContext, dry_run: bool) -> CargoResult<()> {
+    let mut clean_ctx = CleanContext::new(gctx);
+    clean_ctx.dry_run = dry_run;
+    for dir in script_dirs(gctx)? {
+        // Re-read the list under the lock, a script may have been built in
+        // the directory since it was found.
+        let lock = lock_scripts(&Filesystem::new(dir.path.clone()), gctx)?;
+        let scripts = read_locked_scripts(&lock)?;
+        let remaining: Vec<_> = scripts.iter().filter(|s| s.exists()).cloned().collect();
+        if remaining.is_empty() {
+            // Everything is removed under the lock, the list last, so that a
+            // script recorded in the directory meanwhile keeps its outputs.
+            for entry in fs::read_dir(&dir.path)? {
+                let path = entry?.path();
+                if path != lock.path() {
+                    clean_ctx.rm_rf(&path)?;
+                }
+            }
+            clean_ctx.rm_rf(lock.path())?;
+            drop(lock);
+            if !dry_run {
+                // Left alone if a script was recorded in it since.
+                let _ = fs::remove_dir(&dir.path);
+            }
+        } else if remaining.len() < scripts.len() && !dry_run {
+            write_locked_scripts(&lock, &remaining)?;
+        }
+    }
+    clean_ctx.display_summary()
+}
@@ -0,0 +1,312 @@
+//! Structured test events for `-Ztest-events`.
+//!
+//! libtest only offers `--format=json` on the nightly channel, so instead of
+//! asking the harness for JSON, Cargo reads the default ("pretty") format
+//! line by line and translates it into [`machine_message`] events on stdout.
+//! The human-readable output is forwarded to stderr unchanged, keeping stdout
+//! a pure stream of JSON messages.
+//!
+//! The pretty format only reports a test once it has finished running, so
+//! `test-started` and `test-finished` are emitted back to back. A failed test
+//! is reported once its captured output has been read, which libtest prints
+//! at the end of the suite.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::core::compiler::Unit;
+use crate::util::errors::CargoResult;
+use crate::util::machine_message::{self, BenchResult, Message, TestOutcome, TestSummary};
+use crate::GlobalContext;
+
+use super::TestKind;
+
+/// Tracks the state of a single test executable (or doctest run) while its
+/// output is being streamed.
+pub(super) struct TestEvents<'a> {
+    gctx: &'a GlobalContext,
+    unit: &'a Unit,
+    kind: TestKind,
+    start: Instant,
+    /// Tests that failed, in the order they were reported, waiting for their
+    /// captured output.
+    failures: Vec<String>,
+    /// Captured output of failed tests, keyed by test name.
+    captured: HashMap<String, String>,
+    /// The test whose `---- name stdout ----` section is currently being read.
+    capturing: Option<String>,
+    summary: Option<TestSummary>,
+}
+
+impl<'a> TestEvents<'a> {
+    /// Emits `test-suite-started` and starts tracking a new suite.
+    pub fn start(gctx: &'a GlobalContext, unit: &'a Unit, kind: TestKind) -> CargoResult<Self> {
+        let events = TestEvents {
+            gctx,
+            unit,
+            kind,
+            start: Instant::now(),
+            failures: Vec::new(),
+            captured: HashMap::new(),
+            capturing: None,
+            summary: None,
+        };
+        events.emit(machine_message::TestSuiteStarted {
+            package_id: unit.pkg.package_id().to_spec(),
+            target: &unit.target,
+            kind: kind.as_str(),
+        })?;
+        Ok(events)
+    }
+
+    /// Handles one line of the harness's stdout.
+    pub fn on_stdout_line(&mut self, line: &str) -> CargoResult<()> {
+        writeln!(self.gctx.shell().err(), "{line}")?;
+
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" stdout ----"))
+        {
+            self.captured.entry(name.to_string()).or_default();
+            self.capturing = Some(name.to_string());
+        } else if line == "failures:" {
+            self.capturing = None;
+        } else if let Some(summary) = parse_summary(line) {
+            self.capturing = None;
+            self.summary = Some(summary);
+            self.flush_failures()?;
+        } else if let Some(name) = &self.capturing {
+            let output = self.captured.get_mut(name).unwrap();
+            output.push_str(line);
+            output.push('\n');
+        } else if let Some((name, result)) = parse_test_line(line) {
+            self.emit(machine_message::TestStarted {
+                package_id: self.unit.pkg.package_id().to_spec(),
+                target: &self.unit.target,
+                name,
+            })?;
+            if result.outcome == TestOutcome::Failed {
+                self.failures.push(name.to_string());
+            } else {
+                self.emit_finished(name, &result, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits any outstanding failures and `test-suite-finished`.
+    pub fn finish(mut self, success: bool) -> CargoResult<()> {
+        self.flush_failures()?;
+        self.emit(machine_message::TestSuiteFinished {
+            package_id: self.unit.pkg.package_id().to_spec(),
+            target: &self.unit.target,
+            kind: self.kind.as_str(),
+            success,
+            duration: self.start.elapsed().as_secs_f64(),
+            summary: self.summary,
+        })
+    }
+
+    fn flush_failures(&mut self) -> CargoResult<()> {
+        let result = TestLine {
+            outcome: TestOutcome::Failed,
+            exec_time: None,
+            message: None,
+            bench: None,
+        };
+        for name in std::mem::take(&mut self.failures) {
+            let stdout = self.captured.remove(&name);
+            let stdout = stdout.as_deref().map(|s| s.trim_end_matches('\n'));
+            self.emit_finished(&name, &result, stdout)?;
+        }
+        Ok(())
+    }
+
+    fn emit_finished(
+        &self,
+        name: &str,
+        result: &TestLine<'_>,
+        stdout: Option<&str>,
+    ) -> CargoResult<()> {
+        self.emit(machine_message::TestFinished {
+            package_id: self.unit.pkg.package_id().to_spec(),
+            target: &self.unit.target,
+            name,
+            outcome: result.outcome,
+            exec_time: result.exec_time,
+            message: result.message,
+            stdout,
+            bench: result.bench,
+        })
+    }
+
+    fn emit(&self, msg: impl Message) -> CargoResult<()> {
+        writeln!(self.gctx.shell().out(), "{}", msg.to_json_string())?;
+        Ok(())
+    }
+}
+
+/// The result part of a `test <name> ... <result>` line.
+#[derive(Debug, PartialEq)]
+struct TestLine<'a> {
+    outcome: TestOutcome,
+    exec_time: Option<f64>,
+    message: Option<&'a str>,
+    bench: Option<BenchResult>,
+}
+
+/// Parses a line such as `test foo ... ok`, `test foo ... ignored, reason`
+/// or `test foo ... bench:   1,234 ns/iter (+/- 56)`.
+fn parse_test_line(line: &str) -> Option<(&str, TestLine<'_>)> {
+    let (name, mut result) = line.strip_prefix("test ")?.split_once(" ... ")?;
+
+    // `--report-time` appends the execution time, e.g. `ok <0.001s>`.
+    let mut exec_time = None;
+    if let Some((rest, time)) = result.rsplit_once(" <") {
+        if let Some(time) = time.strip_suffix("s>").and_then(|t| t.parse().ok()) {
+            exec_time = Some(time);
+            result = rest;
+        }
+    }
+
+    let mut line = TestLine {
+        outcome: TestOutcome::Ok,
+        exec_time,
+        message: None,
+        bench: None,
+    };
+    match result {
+        "ok" => {}
+        "FAILED" => line.outcome = TestOutcome::Failed,
+        "ignored" => line.outcome = TestOutcome::Ignored,
+        _ => {
+            if let Some(reason) = result.strip_prefix("ignored, ") {
+                line.outcome = TestOutcome::Ignored;
+                line.message = Some(reason);
+            } else if let Some(bench) = result.strip_prefix("bench:") {
+                line.outcome = TestOutcome::Bench;
+                line.bench = Some(parse_bench(bench)?);
+            } else {
+                return None;
+            }
+        }
+    }
+    Some((name, line))
+}
+
+/// Parses `   1,234 ns/iter (+/- 56)`.
+fn parse_bench(s: &str) -> Option<BenchResult> {
+    let parse = |n: &str| n.trim().replace(',', "").parse().ok();
+    let (median, rest) = s.split_once(" ns/iter")?;
+    let deviation = rest.trim().strip_prefix("(+/- ")?.strip_suffix(')')?;
+    Some(BenchResult {
+        median: parse(median)?,
+        deviation: parse(deviation)?,
+    })
+}
+
+/// Parses `test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0
+/// filtered out; finished in 0.00s`.
+fn parse_summary(line: &str) -> Option<TestSummary> {
+    let (_, counts) = line.strip_prefix("test result: ")?.split_once(". ")?;
+    let mut summary = TestSummary::default();
+    for part in counts.split("; ") {
+        let Some((n, what)) = part.split_once(' ') else {
+            continue;
+        };
+        let Ok(n) = n.parse() else {
+            continue;
+        };
+        match what {
+            "passed" => summary.passed = n,
+            "failed" => summary.failed = n,
+            "ignored" => summary.ignored = n,
+            "measured" => summary.measured = n,
+            "filtered out" => summary.filtered_out = n,
+            _ => {}
+        }
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let ok = |outcome| TestLine {
+            outcome,
+            exec_time: None,
+            message: None,
+            bench: None,
+        };
+        assert_eq!(
+            parse_test_line("test foo::bar ... ok"),
+            Some(("foo::bar", ok(TestOutcome::Ok)))
+        );
+        assert_eq!(
+            parse_test_line("test foo ... FAILED"),
+            Some(("foo", ok(TestOutcome::Failed)))
+        );
+        assert_eq!(
+            parse_test_line("test src/lib.rs - foo (line 3) ... ignored"),
+            Some(("src/lib.rs - foo (line 3)", ok(TestOutcome::Ignored)))
+        );
+        assert_eq!(
+            parse_test_line("test foo ... ignored, not yet"),
+            Some((
+                "foo",
+                TestLine {
+                    message: Some("not yet"),
+                    ..ok(TestOutcome::Ignored)
+                }
+            ))
+        );
+        assert_eq!(
+            parse_test_line("test foo ... ok <0.250s>"),
+            Some((
+                "foo",
+                TestLine {
+                    exec_time: Some(0.25),
+                    ..ok(TestOutcome::Ok)
+                }
+            ))
+        );
+        assert_eq!(
+            parse_test_line("test foo ... bench:       1,234 ns/iter (+/- 56)"),
+            Some((
+                "foo",
+                TestLine {
+                    bench: Some(BenchResult {
+                        median: 1234,
+                        deviation: 56
+                    }),
+                    ..ok(TestOutcome::Bench)
+                }
+            ))
+        );
+        assert_eq!(
+            parse_test_line("test foo has been running for over 60 seconds"),
+            None
+        );
+        assert_eq!(parse_test_line("test result: ok. 1 passed"), None);
+    }
+
+    #[test]
+    fn summary_line() {
+        assert_eq!(
+            parse_summary(
+                "test result: FAILED. 2 passed; 1 failed; 3 ignored; 0 measured; 4 filtered out; finished in 0.01s"
+            ),
+            Some(TestSummary {
+                passed: 2,
+                failed: 1,
+                ignored: 3,
+                measured: 0,
+                filtered_out: 4,
+            })
+        );
+        assert_eq!(parse_summary("running 1 test"), None);
+    }
+}
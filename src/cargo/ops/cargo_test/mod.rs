@@ -11,6 +11,8 @@ use std::ffi::OsString;
 use std::fmt::Write;
 use std::path::{Path, PathBuf};
 
+mod events;
+
 pub struct TestOptions {
     pub compile_opts: ops::CompileOptions,
     pub no_run: bool,
@@ -28,6 +30,16 @@ enum TestKind {
     Doctest,
 }
 
+impl TestKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TestKind::Test => "test",
+            TestKind::Bench => "bench",
+            TestKind::Doctest => "doctest",
+        }
+    }
+}
+
 /// A unit that failed to run.
 struct UnitTestError {
     unit: Unit,
@@ -64,6 +76,7 @@ impl UnitTestError {
 /// On error, the returned [`CliError`] will have the appropriate process exit
 /// code that Cargo should use.
 pub fn run_tests(ws: &Workspace<'_>, options: &TestOptions, test_args: &[&str]) -> CliResult {
+    check_test_events(ws.gctx(), options, test_args)?;
     let compilation = compile_tests(ws, options)?;
 
     if options.no_run {
@@ -84,6 +97,7 @@ pub fn run_tests(ws: &Workspace<'_>, options: &TestOptions, test_args: &[&str])
 /// On error, the returned [`CliError`] will have the appropriate process exit
 /// code that Cargo should use.
 pub fn run_benches(ws: &Workspace<'_>, options: &TestOptions, args: &[&str]) -> CliResult {
+    check_test_events(ws.gctx(), options, args)?;
     let compilation = compile_tests(ws, options)?;
 
     if options.no_run {
@@ -136,6 +150,7 @@ fn run_unit_tests(
             test_args,
             compilation,
             "unittests",
+            emit_test_events(gctx, options),
         )?;
 
         if gctx.extra_verbose() {
@@ -147,7 +162,7 @@ fn run_unit_tests(
         gctx.shell()
             .verbose(|shell| shell.status("Running", &cmd))?;
 
-        if let Err(e) = cmd.exec() {
+        if let Err(e) = exec_test(gctx, options, &cmd, unit, test_kind) {
             let code = fail_fast_code(&e);
             let unit_err = UnitTestError {
                 unit: unit.clone(),
@@ -264,7 +279,7 @@ fn run_doc_tests(
             p.arg("--test-args").arg(arg);
         }
 
-        if gctx.shell().verbosity() == Verbosity::Quiet {
+        if gctx.shell().verbosity() == Verbosity::Quiet && !emit_test_events(gctx, options) {
             p.arg("--test-args").arg("--quiet");
         }
 
@@ -283,7 +298,7 @@ fn run_doc_tests(
         gctx.shell()
             .verbose(|shell| shell.status("Running", p.to_string()))?;
 
-        if let Err(e) = p.exec() {
+        if let Err(e) = exec_test(gctx, options, &p, unit, TestKind::Doctest) {
             let code = fail_fast_code(&e);
             let unit_err = UnitTestError {
                 unit: unit.clone(),
@@ -299,6 +314,64 @@ fn run_doc_tests(
     Ok(errors)
 }
 
+/// Returns whether test results should be reported as JSON messages, see
+/// [`events`].
+fn emit_test_events(gctx: &GlobalContext, options: &TestOptions) -> bool {
+    gctx.cli_unstable().test_events && options.compile_opts.build_config.emit_json()
+}
+
+/// Warns when `-Ztest-events` has no effect, and rejects harness arguments
+/// that change the output format [`events`] reads.
+fn check_test_events(
+    gctx: &GlobalContext,
+    options: &TestOptions,
+    args: &[&str],
+) -> CargoResult<()> {
+    if !gctx.cli_unstable().test_events {
+        return Ok(());
+    }
+    if !options.compile_opts.build_config.emit_json() {
+        return gctx
+            .shell()
+            .warn("`-Ztest-events` has no effect without `--message-format=json`");
+    }
+    let format_arg = args
+        .iter()
+        .find(|arg| matches!(**arg, "--format" | "-q" | "--quiet") || arg.starts_with("--format="));
+    if let Some(arg) = format_arg {
+        anyhow::bail!(
+            "the test harness argument `{arg}` cannot be used with `-Ztest-events`\n\
+             Test events are read from the harness's default output format."
+        );
+    }
+    Ok(())
+}
+
+/// Runs a test executable (or rustdoc for doctests), translating its output
+/// into test events when [`emit_test_events`] is enabled.
+fn exec_test(
+    gctx: &GlobalContext,
+    options: &TestOptions,
+    cmd: &ProcessBuilder,
+    unit: &Unit,
+    kind: TestKind,
+) -> CargoResult<()> {
+    if !emit_test_events(gctx, options) {
+        return cmd.exec();
+    }
+    let mut events = events::TestEvents::start(gctx, unit, kind)?;
+    let result = cmd.exec_with_streaming(
+        &mut |line| events.on_stdout_line(line),
+        &mut |line| {
+            crate::drop_eprintln!(gctx, "{line}");
+            Ok(())
+        },
+        false,
+    );
+    events.finish(result.is_ok())?;
+    result.map(drop)
+}
+
 /// Displays human-readable descriptions of the test executables.
 ///
 /// This is used when `cargo test --no-run` is used.
@@ -325,6 +398,7 @@ fn display_no_run_information(
             test_args,
             compilation,
             exec_type,
+            false,
         )?;
         gctx.shell()
             .concise(|shell| shell.status("Executable", &exe_display))?;
@@ -340,6 +414,9 @@ fn display_no_run_information(
 /// Returns a tuple `(exe_display, process)` where `exe_display` is a string
 /// to display that describes the executable path in a human-readable form.
 /// `process` is the `ProcessBuilder` to use for executing the test.
+///
+/// `emit_test_events` keeps the harness on its default output format, which
+/// is what [`events`] knows how to read.
 fn cmd_builds(
     gctx: &GlobalContext,
     cwd: &Path,
@@ -349,6 +426,7 @@ fn cmd_builds(
     test_args: &[&str],
     compilation: &Compilation<'_>,
     exec_type: &str,
+    emit_test_events: bool,
 ) -> CargoResult<(String, ProcessBuilder)> {
     let test_path = unit.target.src_path().path().unwrap();
     let short_test_path = test_path
@@ -372,7 +450,7 @@ fn cmd_builds(
 
     let mut cmd = compilation.target_process(path, unit.kind, &unit.pkg, *script_meta)?;
     cmd.args(test_args);
-    if unit.target.harness() && gctx.shell().verbosity() == Verbosity::Quiet {
+    if unit.target.harness() && gctx.shell().verbosity() == Verbosity::Quiet && !emit_test_events {
         cmd.arg("--quiet");
     }
 
@@ -764,7 +764,7 @@ where
 }
 
 /// Helper to convert features to a BTreeSet.
-fn feature_set(features: &Rc<BTreeSet<FeatureValue>>) -> BTreeSet<String> {
+pub fn feature_set(features: &Rc<BTreeSet<FeatureValue>>) -> BTreeSet<String> {
     features.iter().map(|s| s.to_string()).collect()
 }
 
@@ -1,4 +1,5 @@
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
 
 use crate::core::{resolver, Resolve, ResolveVersion, Workspace};
 use crate::util::errors::CargoResult;
@@ -8,28 +9,127 @@ use anyhow::Context as _;
 
 #[tracing::instrument(skip_all)]
 pub fn load_pkg_lockfile(ws: &Workspace<'_>) -> CargoResult<Option<Resolve>> {
-    let lock_root = lock_root(ws);
-    if !lock_root.as_path_unlocked().join("Cargo.lock").exists() {
+    let (lock_root, lock_name) = lock_file(ws);
+    if !lock_root.as_path_unlocked().join(&lock_name).exists() {
         return Ok(None);
     }
 
-    let mut f = lock_root.open_ro_shared("Cargo.lock", ws.gctx(), "Cargo.lock file")?;
+    let mut f = lock_root.open_ro_shared(&lock_name, ws.gctx(), "Cargo.lock file")?;
 
     let mut s = String::new();
     f.read_to_string(&mut s)
         .with_context(|| format!("failed to read file: {}", f.path().display()))?;
 
-    let resolve = (|| -> CargoResult<Option<Resolve>> {
-        let v: resolver::EncodableResolve = toml::from_str(&s)?;
-        Ok(Some(v.into_resolve(&s, ws)?))
-    })()
-    .with_context(|| format!("failed to parse lock file at: {}", f.path().display()))?;
-    Ok(resolve)
+    if ws.gctx().cli_unstable().lockfile_merge {
+        if let Some(sides) = split_merge_conflict(&s) {
+            let resolve = (|| -> CargoResult<Resolve> {
+                let sides = sides?;
+                let ours = parse_lockfile(ws, &sides.ours)?;
+                let theirs = parse_lockfile(ws, &sides.theirs)?;
+                let base = sides
+                    .base
+                    .map(|base| parse_lockfile(ws, &base))
+                    .transpose()?;
+                Ok(ours.union(&theirs, base.as_ref()))
+            })()
+            .with_context(|| {
+                format!(
+                    "failed to resolve the merge conflict in lock file at: {}",
+                    f.path().display()
+                )
+            })?;
+            ws.gctx().shell().status(
+                "Resolving",
+                format!("merge conflict in `{}`", f.path().display()),
+            )?;
+            return Ok(Some(resolve));
+        }
+    }
+
+    let resolve = parse_lockfile(ws, &s)
+        .with_context(|| format!("failed to parse lock file at: {}", f.path().display()))?;
+    Ok(Some(resolve))
+}
+
+/// Parses the contents of a lock file of `ws`.
+pub(crate) fn parse_lockfile(ws: &Workspace<'_>, s: &str) -> CargoResult<Resolve> {
+    let v: resolver::EncodableResolve = toml::from_str(s)?;
+    v.into_resolve(s, ws)
+}
+
+/// The sides of a merge conflict in a lock file.
+struct MergeConflict {
+    ours: String,
+    theirs: String,
+    /// The side both come from, when the conflict is in the `diff3` style.
+    base: Option<String>,
+}
+
+/// Splits a lock file with git merge conflict markers into its sides, or
+/// returns `None` if it has no conflict.
+fn split_merge_conflict(s: &str) -> Option<CargoResult<MergeConflict>> {
+    enum Section {
+        Both,
+        Ours,
+        Base,
+        Theirs,
+    }
+
+    if !s.lines().any(|line| line.starts_with("<<<<<<<")) {
+        return None;
+    }
+    let mut conflict = MergeConflict {
+        ours: String::new(),
+        theirs: String::new(),
+        base: None,
+    };
+    let mut base = String::new();
+    let mut section = Section::Both;
+    for (i, line) in s.lines().enumerate() {
+        section = match section {
+            Section::Both if line.starts_with("<<<<<<<") => Section::Ours,
+            Section::Ours if line.starts_with("|||||||") => {
+                conflict.base = Some(String::new());
+                Section::Base
+            }
+            Section::Ours | Section::Base if line.starts_with("=======") => Section::Theirs,
+            Section::Theirs if line.starts_with(">>>>>>>") => Section::Both,
+            section => {
+                if ["<<<<<<<", "|||||||", "=======", ">>>>>>>"]
+                    .iter()
+                    .any(|marker| line.starts_with(marker))
+                {
+                    return Some(Err(anyhow::format_err!(
+                        "unexpected merge conflict marker on line {}",
+                        i + 1
+                    )));
+                }
+                let sides: &mut [&mut String] = match section {
+                    Section::Both => &mut [&mut conflict.ours, &mut conflict.theirs, &mut base],
+                    Section::Ours => &mut [&mut conflict.ours],
+                    Section::Base => &mut [&mut base],
+                    Section::Theirs => &mut [&mut conflict.theirs],
+                };
+                for side in sides {
+                    side.push_str(line);
+                    side.push('\n');
+                }
+                section
+            }
+        };
+    }
+    if !matches!(section, Section::Both) {
+        return Some(Err(anyhow::format_err!("unterminated merge conflict")));
+    }
+    if conflict.base.is_some() {
+        conflict.base = Some(base);
+    }
+    Some(Ok(conflict))
 }
 
 /// Generate a toml String of Cargo.lock from a Resolve.
 pub fn resolve_to_string(ws: &Workspace<'_>, resolve: &Resolve) -> CargoResult<String> {
-    let (_orig, out, _lock_root) = resolve_to_string_orig(ws, resolve);
+    let (_orig, out, _lock_path) = resolve_to_string_orig(ws, resolve);
     Ok(out)
 }
 
@@ -38,7 +138,7 @@ pub fn resolve_to_string(ws: &Workspace<'_>, resolve: &Resolve) -> CargoResult<S
 /// Returns `true` if the lockfile changed
 #[tracing::instrument(skip_all)]
 pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoResult<bool> {
-    let (orig, mut out, lock_root) = resolve_to_string_orig(ws, resolve);
+    let (orig, mut out, (lock_root, lock_name)) = resolve_to_string_orig(ws, resolve);
 
     // If the lock file contents haven't changed so don't rewrite it. This is
     // helpful on read-only filesystems.
@@ -58,7 +158,7 @@ pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoRes
             "the lock file {} needs to be updated but {} was passed to prevent this\n\
              If you want to try to generate the lock file without accessing the network, \
              remove the {} flag and use --offline instead.",
-            lock_root.as_path_unlocked().join("Cargo.lock").display(),
+            lock_root.as_path_unlocked().join(&lock_name).display(),
             flag,
             flag
         );
@@ -84,7 +184,7 @@ pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoRes
 
     // Ok, if that didn't work just write it out
     lock_root
-        .open_rw_exclusive_create("Cargo.lock", ws.gctx(), "Cargo.lock file")
+        .open_rw_exclusive_create(&lock_name, ws.gctx(), "Cargo.lock file")
         .and_then(|mut f| {
             f.file().set_len(0)?;
             f.write_all(out.as_bytes())?;
@@ -93,7 +193,7 @@ pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoRes
         .with_context(|| {
             format!(
                 "failed to write {}",
-                lock_root.as_path_unlocked().join("Cargo.lock").display()
+                lock_root.as_path_unlocked().join(&lock_name).display()
             )
         })?;
     Ok(true)
@@ -102,17 +202,17 @@ pub fn write_pkg_lockfile(ws: &Workspace<'_>, resolve: &mut Resolve) -> CargoRes
 fn resolve_to_string_orig(
     ws: &Workspace<'_>,
     resolve: &Resolve,
-) -> (Option<String>, String, Filesystem) {
+) -> (Option<String>, String, (Filesystem, String)) {
     // Load the original lock file if it exists.
-    let lock_root = lock_root(ws);
-    let orig = lock_root.open_ro_shared("Cargo.lock", ws.gctx(), "Cargo.lock file");
+    let (lock_root, lock_name) = lock_file(ws);
+    let orig = lock_root.open_ro_shared(&lock_name, ws.gctx(), "Cargo.lock file");
     let orig = orig.and_then(|mut f| {
         let mut s = String::new();
         f.read_to_string(&mut s)?;
         Ok(s)
     });
     let out = serialize_resolve(resolve, orig.as_deref().ok());
-    (orig.ok(), out, lock_root)
+    (orig.ok(), out, (lock_root, lock_name))
 }
 
 #[tracing::instrument(skip_all)]
@@ -246,10 +346,38 @@ fn emit_package(dep: &toml::Table, out: &mut String) {
     }
 }
 
-fn lock_root(ws: &Workspace<'_>) -> Filesystem {
+/// The lock file of `ws`, as the directory it is in and its name.
+///
+/// The lock file of a script is kept in its target directory, unless it was
+/// asked to be kept next to the script, as `<script>.lock`, or the target
+/// directory is shared with other scripts.
+fn lock_file(ws: &Workspace<'_>) -> (Filesystem, String) {
     if ws.root_maybe().is_embedded() {
-        ws.target_dir()
+        let script_lock = script_lockfile_path(ws.root_manifest());
+        if ws.script_lock() || script_lock.exists() {
+            let name = script_lock
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            let dir = script_lock.parent().unwrap().to_owned();
+            return (Filesystem::new(dir), name);
+        }
+        let dir = if ws.shares_script_target_dir() {
+            ws.script_dir()
+        } else {
+            ws.target_dir()
+        };
+        (dir, "Cargo.lock".to_owned())
     } else {
-        Filesystem::new(ws.root().to_owned())
+        (
+            Filesystem::new(ws.root().to_owned()),
+            "Cargo.lock".to_owned(),
+        )
     }
 }
+
+/// The lock file kept next to the script at `manifest_path`.
+pub fn script_lockfile_path(manifest_path: &Path) -> PathBuf {
+    manifest_path.with_extension("lock")
+}
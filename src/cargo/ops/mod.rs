@@ -1,5 +1,7 @@
 use crate::sources::CRATES_IO_DOMAIN;
 
+pub use self::cargo_approve_build_scripts::{approve_build_scripts, ApproveBuildScriptsOptions};
+pub use self::cargo_audit_policy::{audit_policy, AuditPolicyOptions};
 pub use self::cargo_clean::{clean, CleanContext, CleanOptions};
 pub use self::cargo_compile::{
     compile, compile_with_exec, compile_ws, create_bcx, print, resolve_all_features, CompileOptions,
@@ -7,38 +9,48 @@ pub use self::cargo_compile::{
 pub use self::cargo_compile::{CompileFilter, FilterRule, LibRule, Packages};
 pub use self::cargo_doc::{doc, DocOptions, OutputFormat};
 pub use self::cargo_fetch::{fetch, FetchOptions};
+pub use self::cargo_generate_lockfile::explain_update;
 pub use self::cargo_generate_lockfile::generate_lockfile;
+pub use self::cargo_generate_lockfile::merge_lockfiles;
 pub use self::cargo_generate_lockfile::print_lockfile_changes;
 pub use self::cargo_generate_lockfile::update_lockfile;
 pub use self::cargo_generate_lockfile::UpdateOptions;
-pub use self::cargo_install::{install, install_list};
+pub use self::cargo_install::{install, install_from, install_list};
 pub use self::cargo_new::{init, new, NewOptions, NewProjectKind, VersionControl};
 pub use self::cargo_output_metadata::{output_metadata, ExportInfo, OutputMetadataOptions};
-pub use self::cargo_package::{check_yanked, package, package_one, PackageOpts};
+pub use self::cargo_package::{check_yanked, package, package_one, PackageOpts, TmpRegistry};
 pub use self::cargo_pkgid::pkgid;
 pub use self::cargo_read_manifest::{read_package, read_packages};
 pub use self::cargo_run::run;
+pub use self::cargo_script::{record_script, script_clean, script_list};
 pub use self::cargo_test::{run_benches, run_tests, TestOptions};
 pub use self::cargo_uninstall::uninstall;
 pub use self::fix::{fix, fix_exec_rustc, fix_get_proxy_lock_addr, FixOptions};
 pub use self::lockfile::{load_pkg_lockfile, resolve_to_string, write_pkg_lockfile};
+pub use self::registry::mirror;
+pub use self::registry::mirror_packages;
 pub use self::registry::modify_owners;
 pub use self::registry::publish;
 pub use self::registry::registry_login;
 pub use self::registry::registry_logout;
 pub use self::registry::search;
+pub use self::registry::serve;
 pub use self::registry::yank;
+pub use self::registry::MirrorOptions;
 pub use self::registry::OwnersOptions;
 pub use self::registry::PublishOpts;
 pub use self::registry::RegistryCredentialConfig;
 pub use self::registry::RegistryOrIndex;
+pub use self::registry::ServeOptions;
 pub use self::resolve::{
-    add_overrides, get_resolved_packages, resolve_with_previous, resolve_ws, resolve_ws_with_opts,
-    WorkspaceResolve,
+    add_overrides, get_resolved_packages, member_rust_versions, package_rust_versions,
+    resolve_with_previous, resolve_ws, resolve_ws_with_opts, WorkspaceResolve,
 };
 pub use self::vendor::{vendor, VendorOptions};
 
 pub mod cargo_add;
+pub(crate) mod cargo_approve_build_scripts;
+mod cargo_audit_policy;
 mod cargo_clean;
 pub(crate) mod cargo_compile;
 pub mod cargo_config;
@@ -53,6 +65,7 @@ mod cargo_pkgid;
 mod cargo_read_manifest;
 pub mod cargo_remove;
 mod cargo_run;
+pub(crate) mod cargo_script;
 mod cargo_test;
 mod cargo_uninstall;
 mod common_for_install_and_uninstall;
@@ -60,6 +73,7 @@ mod fix;
 pub(crate) mod lockfile;
 pub(crate) mod registry;
 pub(crate) mod resolve;
+mod semver_check;
 pub mod tree;
 mod vendor;
 
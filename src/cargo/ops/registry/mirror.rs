@@ -0,0 +1,344 @@
+//! Copies packages of a registry to a directory, with `cargo registry mirror`.
+//!
+//! The directory is a sparse registry of its own: it has a `config.json`,
+//! the index files of the packages at the same paths as in the registry, and
+//! the `.crate` files in `crates/<name>/<name>-<version>.crate`. It can be
+//! used through `sparse+file://` URLs, or served by any static web server,
+//! and replace the registry with source replacement.
+//!
+//! The index entries are copied verbatim from the registry, keeping only the
+//! mirrored versions. Running it again in the same directory adds versions
+//! to the ones already there, so that a mirror can be shared by several
+//! projects.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::task::Poll;
+
+use anyhow::{bail, Context as _};
+use cargo_util::paths;
+use cargo_util::registry::make_dep_path;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{get_source_id, RegistryOrIndex};
+use crate::core::{PackageId, SourceId, Workspace};
+use crate::ops;
+use crate::sources::source::Source;
+use crate::sources::{RegistrySource, CRATES_IO_REGISTRY};
+use crate::util::cache_lock::CacheLockMode;
+use crate::util::errors::CargoResult;
+use crate::util::GlobalContext;
+
+/// The directory of the `.crate` files in a mirror.
+pub(super) const CRATES_DIR: &str = "crates";
+
+pub struct MirrorOptions {
+    /// The directory to write the mirror to.
+    pub destination: PathBuf,
+    /// The registry to mirror, crates.io by default.
+    pub reg_or_index: Option<RegistryOrIndex>,
+    /// The URL the mirror will be served from, which is written in its
+    /// `config.json`. Defaults to the `file://` URL of the destination.
+    pub url: Option<Url>,
+}
+
+/// Mirrors the packages of the lockfile of `ws` that come from the registry.
+pub fn mirror(ws: &Workspace<'_>, opts: &MirrorOptions) -> CargoResult<()> {
+    let gctx = ws.gctx();
+    let source_ids = get_source_id(gctx, opts.reg_or_index.as_ref())?;
+    let registry = source_ids.original;
+    if !source_ids.replacement.is_remote_registry() {
+        bail!(
+            "`{}` is not a remote registry, only remote registries can be mirrored",
+            source_ids.replacement
+        );
+    }
+    if source_ids.replacement.is_sparse() && gctx.offline() {
+        bail!("mirroring a sparse registry requires network access, remove the `--offline` flag");
+    }
+
+    let (pkg_set, resolve) = ops::resolve_ws(ws)?;
+    let mut ids = Vec::new();
+    for id in resolve.iter() {
+        let source_id = id.source_id();
+        let is_registry = if registry.is_crates_io() {
+            source_id.is_crates_io()
+        } else {
+            source_id == registry
+        };
+        if is_registry {
+            ids.push(id);
+        } else if !source_id.is_path() {
+            gctx.shell().warn(format!(
+                "skipping `{}`, it doesn't come from {}",
+                id,
+                registry.display_registry_name()
+            ))?;
+        }
+    }
+    if ids.is_empty() {
+        gctx.shell().note(format!(
+            "there is no package from {} to mirror",
+            registry.display_registry_name()
+        ))?;
+        return Ok(());
+    }
+    ids.sort();
+    pkg_set.get_many(ids.iter().copied())?;
+
+    let _lock = gctx.acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
+    let mut source = RegistrySource::remote(source_ids.replacement, &HashSet::new(), gctx)?;
+    source.set_quiet(true);
+
+    let names: BTreeSet<&str> = ids.iter().map(|id| id.name().as_str()).collect();
+    let files = load_index_files(&mut source, &names)?;
+
+    let dst = &opts.destination;
+    paths::create_dir_all(dst)?;
+    let dst = dst
+        .canonicalize()
+        .with_context(|| format!("failed to canonicalize `{}`", dst.display()))?;
+    for name in &names {
+        let Some(file) = &files[name] else {
+            bail!(
+                "package `{}` is not in the index of {}",
+                name,
+                registry.display_registry_name()
+            );
+        };
+        let versions: Vec<_> = ids.iter().filter(|id| id.name() == *name).collect();
+        write_index_file(&dst, name, file, &versions)
+            .with_context(|| format!("failed to mirror the index entries of `{}`", name))?;
+    }
+
+    for id in &ids {
+        let checksum = resolve.checksums().get(id).cloned().flatten();
+        let Some(mut file) = source.downloaded_crate(*id, checksum.as_deref().unwrap_or(""))?
+        else {
+            bail!("the `.crate` file of `{}` was not downloaded", id);
+        };
+        let path = crate_path(&dst, *id);
+        if path.exists() {
+            continue;
+        }
+        gctx.shell().status("Mirroring", id)?;
+        paths::create_dir_all(path.parent().unwrap())?;
+        let mut out = File::create(&path)
+            .with_context(|| format!("failed to create `{}`", path.display()))?;
+        io::copy(&mut file, &mut out)
+            .with_context(|| format!("failed to copy the `.crate` file of `{}`", id))?;
+    }
+
+    let url = match &opts.url {
+        Some(url) => url.clone(),
+        None => Url::from_directory_path(&dst)
+            .map_err(|()| anyhow::format_err!("invalid path `{}`", dst.display()))?,
+    };
+    let base = url.as_str().trim_end_matches('/');
+    let config = serde_json::json!({
+        "dl": format!("{base}/{CRATES_DIR}/{{crate}}/{{crate}}-{{version}}.crate"),
+        "api": null,
+    });
+    paths::write(dst.join("config.json"), format!("{config}\n"))?;
+
+    gctx.shell().status(
+        "Mirrored",
+        format!(
+            "{} package{} to {}",
+            ids.len(),
+            if ids.len() == 1 { "" } else { "s" },
+            dst.display()
+        ),
+    )?;
+    crate::drop_eprint!(
+        gctx,
+        "To use the mirror, add this to your .cargo/config.toml:\n\n"
+    );
+    let config = mirror_config(registry, &format!("sparse+{base}/"));
+    crate::drop_print!(gctx, "{}", toml::to_string_pretty(&config).unwrap());
+    Ok(())
+}
+
+/// Mirrors the packages of `specs`, like `foo` or `foo@1.2.3`, with their
+/// dependencies.
+///
+/// They are resolved as the dependencies of a temporary package, so that
+/// the versions mirrored are the ones a package depending on them gets.
+pub fn mirror_packages(
+    gctx: &GlobalContext,
+    specs: &[String],
+    opts: &MirrorOptions,
+) -> CargoResult<()> {
+    let mut deps = toml::Table::new();
+    for (i, spec) in specs.iter().enumerate() {
+        let (name, req) = match spec.split_once('@') {
+            None => (spec.as_str(), "*".to_string()),
+            Some((name, version)) if semver::Version::parse(version).is_ok() => {
+                (name, format!("={version}"))
+            }
+            Some((name, req)) => {
+                semver::VersionReq::parse(req).with_context(|| {
+                    format!("invalid version requirement in package spec `{spec}`")
+                })?;
+                (name, req.to_string())
+            }
+        };
+        let mut dep = toml::Table::new();
+        dep.insert("package".to_string(), name.into());
+        dep.insert("version".to_string(), req.into());
+        match &opts.reg_or_index {
+            Some(RegistryOrIndex::Registry(registry)) => {
+                dep.insert("registry".to_string(), registry.as_str().into());
+            }
+            Some(RegistryOrIndex::Index(url)) => {
+                dep.insert("registry-index".to_string(), url.as_str().into());
+            }
+            None => {}
+        }
+        deps.insert(format!("mirrored-{i}"), dep.into());
+    }
+    let mut package = toml::Table::new();
+    package.insert("name".to_string(), "cargo-registry-mirror".into());
+    package.insert("version".to_string(), "0.0.0".into());
+    package.insert("edition".to_string(), "2021".into());
+    package.insert("publish".to_string(), false.into());
+    let mut manifest = toml::Table::new();
+    manifest.insert("package".to_string(), package.into());
+    manifest.insert("workspace".to_string(), toml::Table::new().into());
+    manifest.insert("dependencies".to_string(), deps.into());
+
+    let tmp = tempfile::Builder::new()
+        .prefix("cargo-registry-mirror")
+        .tempdir()?;
+    let manifest_path = tmp.path().join("Cargo.toml");
+    paths::write(&manifest_path, toml::to_string(&manifest)?)?;
+    paths::create_dir_all(tmp.path().join("src"))?;
+    paths::write(tmp.path().join("src/lib.rs"), "")?;
+    let ws = Workspace::new(&manifest_path, gctx)?;
+    mirror(&ws, opts)
+}
+
+/// Loads the index files of `names` from `source`, `None` for the packages
+/// it doesn't have.
+fn load_index_files<'a>(
+    source: &mut RegistrySource<'_>,
+    names: &BTreeSet<&'a str>,
+) -> CargoResult<BTreeMap<&'a str, Option<Vec<u8>>>> {
+    let mut files = BTreeMap::new();
+    while files.len() < names.len() {
+        for name in names {
+            if files.contains_key(name) {
+                continue;
+            }
+            if let Poll::Ready(file) = source.load_index_file(name)? {
+                files.insert(*name, file);
+            }
+        }
+        if files.len() < names.len() {
+            source.block_until_ready()?;
+        }
+    }
+    Ok(files)
+}
+
+/// The fields of an index entry needed to mirror it.
+#[derive(Deserialize)]
+struct IndexEntry {
+    vers: semver::Version,
+}
+
+/// Writes the index entries of `versions` from `file`, the index file of
+/// `name` in the registry, to the mirror in `dst`, along with the entries
+/// already there for other versions.
+fn write_index_file(
+    dst: &Path,
+    name: &str,
+    file: &[u8],
+    versions: &[&PackageId],
+) -> CargoResult<()> {
+    let path = dst.join(make_dep_path(&name.to_lowercase(), false));
+    let mut entries = BTreeMap::new();
+    if path.exists() {
+        for line in paths::read(&path)?.lines() {
+            let entry: IndexEntry = serde_json::from_str(line)
+                .with_context(|| format!("failed to parse `{}`", path.display()))?;
+            entries.insert(entry.vers, line.to_string());
+        }
+    }
+    let mut missing: BTreeSet<_> = versions.iter().map(|id| id.version().clone()).collect();
+    for line in String::from_utf8_lossy(file).lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: IndexEntry = serde_json::from_str(line)?;
+        if missing.remove(&entry.vers) {
+            entries.insert(entry.vers, line.to_string());
+        }
+    }
+    if let Some(version) = missing.first() {
+        bail!("version {} is not in the index", version);
+    }
+    let mut contents = String::new();
+    for line in entries.values() {
+        contents.push_str(line);
+        contents.push('\n');
+    }
+    paths::create_dir_all(path.parent().unwrap())?;
+    paths::write(&path, contents)
+}
+
+/// Where the `.crate` file of `id` goes in the mirror in `dst`, matching the
+/// `dl` template of its `config.json`.
+fn crate_path(dst: &Path, id: PackageId) -> PathBuf {
+    dst.join(CRATES_DIR).join(id.name().as_str()).join(format!(
+        "{}-{}.crate",
+        id.name(),
+        id.version()
+    ))
+}
+
+#[derive(Serialize)]
+struct MirrorConfig {
+    source: BTreeMap<String, MirrorSource>,
+}
+
+#[derive(Serialize)]
+struct MirrorSource {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry: Option<String>,
+    #[serde(rename = "replace-with", skip_serializing_if = "Option::is_none")]
+    replace_with: Option<String>,
+}
+
+/// The source replacement config replacing `registry` with the mirror at
+/// `index`.
+fn mirror_config(registry: SourceId, index: &str) -> MirrorConfig {
+    let mirror_name = "mirrored-registry";
+    let mut source = BTreeMap::new();
+    let (name, original) = if registry.is_crates_io() {
+        (CRATES_IO_REGISTRY.to_string(), None)
+    } else {
+        (
+            registry.as_url().to_string(),
+            Some(registry.url().to_string()),
+        )
+    };
+    source.insert(
+        name,
+        MirrorSource {
+            registry: original,
+            replace_with: Some(mirror_name.to_string()),
+        },
+    );
+    source.insert(
+        mirror_name.to_string(),
+        MirrorSource {
+            registry: Some(index.to_string()),
+            replace_with: None,
+        },
+    );
+    MirrorConfig { source }
+}
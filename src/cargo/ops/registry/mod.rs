@@ -4,9 +4,11 @@
 
 mod login;
 mod logout;
+mod mirror;
 mod owner;
 mod publish;
 mod search;
+mod serve;
 mod yank;
 
 use std::collections::HashSet;
@@ -29,11 +31,16 @@ use crate::util::network::http::http_handle;
 
 pub use self::login::registry_login;
 pub use self::logout::registry_logout;
+pub use self::mirror::mirror;
+pub use self::mirror::mirror_packages;
+pub use self::mirror::MirrorOptions;
 pub use self::owner::modify_owners;
 pub use self::owner::OwnersOptions;
 pub use self::publish::publish;
 pub use self::publish::PublishOpts;
 pub use self::search::search;
+pub use self::serve::serve;
+pub use self::serve::ServeOptions;
 pub use self::yank::yank;
 
 /// Represents either `--registry` or `--index` argument, which is mutually exclusive.
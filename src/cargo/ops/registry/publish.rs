@@ -29,7 +29,9 @@ use crate::core::Workspace;
 use crate::ops;
 use crate::ops::PackageOpts;
 use crate::ops::Packages;
+use crate::ops::TmpRegistry;
 use crate::sources::source::QueryKind;
+use crate::sources::source::Source;
 use crate::sources::SourceConfigMap;
 use crate::sources::CRATES_IO_REGISTRY;
 use crate::util::auth;
@@ -56,19 +58,24 @@ pub struct PublishOpts<'gctx> {
     pub targets: Vec<String>,
     pub dry_run: bool,
     pub cli_features: CliFeatures,
+    pub semver_check: bool,
 }
 
 pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
+    let multi_package_mode = ws.gctx().cli_unstable().package_workspace;
     let specs = opts.to_publish.to_package_id_specs(ws)?;
-    if specs.len() > 1 {
-        bail!("the `-p` argument must be specified to select a single package to publish")
+    if !multi_package_mode {
+        if specs.len() > 1 {
+            bail!("the `-p` argument must be specified to select a single package to publish")
+        }
+        if Packages::Default == opts.to_publish && ws.is_virtual() {
+            bail!("the `-p` argument must be specified in the root of a virtual workspace")
+        }
     }
-    if Packages::Default == opts.to_publish && ws.is_virtual() {
-        bail!("the `-p` argument must be specified in the root of a virtual workspace")
+    // Check that each spec matches exactly one member.
+    for spec in &specs {
+        spec.query(ws.members().map(|p| p.package_id()))?;
     }
-    let member_ids = ws.members().map(|p| p.package_id());
-    // Check that the spec matches exactly one member.
-    specs[0].query(member_ids)?;
     let mut pkgs = ws.members_with_features(&specs, &opts.cli_features)?;
     // In `members_with_features_old`, it will add "current" package (determined by the cwd)
     // So we need filter
@@ -76,11 +83,106 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
         .into_iter()
         .filter(|(m, _)| specs.iter().any(|spec| spec.matches(m.package_id())))
         .collect();
-    // Double check. It is safe theoretically, unless logic has updated.
-    assert_eq!(pkgs.len(), 1);
 
+    if pkgs.len() != 1 {
+        // Double check. It is safe theoretically, unless logic has updated.
+        assert!(multi_package_mode);
+        return publish_many(ws, opts, pkgs);
+    }
     let (pkg, cli_features) = pkgs.pop().unwrap();
+    publish_one(ws, opts, pkg, cli_features, false, None)?;
+    Ok(())
+}
+
+/// What [`publish_one`] did with a package.
+#[derive(PartialEq, Eq)]
+enum PublishOutcome {
+    /// The version was already in the index of the registry.
+    AlreadyPublished,
+    /// The package was packaged, but not uploaded because of `--dry-run`.
+    DryRun,
+    /// The package was uploaded, and showed up in the index of the registry
+    /// if `available` is set.
+    Uploaded { available: bool },
+}
+
+/// Publishes the selected members of the workspace in dependency order,
+/// waiting for each of them to be available in the registry before the
+/// members that depend on it are verified.
+///
+/// The versions already in the index are skipped, so running this again
+/// after a failure resumes the release where it stopped. With `--dry-run`,
+/// the members are verified against the packages created for the members
+/// they depend on instead, see [`TmpRegistry`].
+fn publish_many(
+    ws: &Workspace<'_>,
+    opts: &PublishOpts<'_>,
+    mut pkgs: Vec<(&Package, CliFeatures)>,
+) -> CargoResult<()> {
+    let gctx = opts.gctx;
+    // Members that can't be published are only an error when selected by
+    // name.
+    if !matches!(opts.to_publish, Packages::Packages(_)) {
+        pkgs.retain(|(pkg, _)| !pkg.publish().as_ref().is_some_and(|regs| regs.is_empty()));
+    }
+    if pkgs.is_empty() {
+        bail!("no packages selected to publish");
+    }
+    let pkgs = ops::cargo_package::sort_by_local_deps(pkgs);
+    let mut local_reg = if opts.dry_run {
+        Some(TmpRegistry::new(ws)?)
+    } else {
+        None
+    };
+
+    let count = pkgs.len();
+    for (i, (pkg, cli_features)) in pkgs.into_iter().enumerate() {
+        let outcome = match publish_one(ws, opts, pkg, cli_features, true, local_reg.as_mut()) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                if 0 < i && !opts.dry_run {
+                    gctx.shell().note(format!(
+                        "the {i} package{} before `{} v{}` {} published, \
+                         run the command again to resume from it",
+                        if i == 1 { "" } else { "s" },
+                        pkg.name(),
+                        pkg.version(),
+                        if i == 1 { "is" } else { "are" },
+                    ))?;
+                }
+                return Err(e);
+            }
+        };
+        if let PublishOutcome::Uploaded { available: false } = outcome {
+            let remaining = count - i - 1;
+            if 0 < remaining {
+                bail!(
+                    "`{} v{}` is not available in the registry yet, \
+                     so the packages that depend on it can't be verified\n\
+                     Run the command again to resume publishing the {remaining} \
+                     remaining package{} once it is.",
+                    pkg.name(),
+                    pkg.version(),
+                    if remaining == 1 { "" } else { "s" },
+                );
+            }
+        }
+    }
+    Ok(())
+}
 
+/// Publishes a single package.
+///
+/// If `skip_published` is set, nothing is done when the version of the
+/// package is already in the index of the registry.
+fn publish_one(
+    ws: &Workspace<'_>,
+    opts: &PublishOpts<'_>,
+    pkg: &Package,
+    cli_features: CliFeatures,
+    skip_published: bool,
+    local_reg: Option<&mut TmpRegistry>,
+) -> CargoResult<PublishOutcome> {
     let mut publish_registry = match opts.reg_or_index.as_ref() {
         Some(RegistryOrIndex::Registry(registry)) => Some(registry.clone()),
         _ => None,
@@ -139,6 +241,24 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
     )?;
     verify_dependencies(pkg, &registry, reg_ids.original)?;
 
+    if skip_published {
+        let mut source =
+            SourceConfigMap::empty(opts.gctx)?.load(reg_ids.original, &HashSet::new())?;
+        source.set_quiet(true);
+        if is_published(opts.gctx, &mut *source, pkg)? {
+            opts.gctx.shell().status(
+                "Skipping",
+                format!(
+                    "{} v{}, already published at {}",
+                    pkg.name(),
+                    pkg.version(),
+                    source.source_id()
+                ),
+            )?;
+            return Ok(PublishOutcome::AlreadyPublished);
+        }
+    }
+
     // Prepare a tarball, with a non-suppressible warning if metadata
     // is missing since this is being put online.
     let tarball = ops::package_one(
@@ -155,7 +275,9 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
             jobs: opts.jobs.clone(),
             keep_going: opts.keep_going,
             cli_features,
+            semver_check: opts.semver_check,
         },
+        local_reg,
     )?
     .unwrap();
 
@@ -189,7 +311,11 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
         reg_ids.original,
         opts.dry_run,
     )?;
-    if !opts.dry_run {
+    if opts.dry_run {
+        return Ok(PublishOutcome::DryRun);
+    }
+    let mut available = false;
+    {
         const DEFAULT_TIMEOUT: u64 = 60;
         let timeout = if opts.gctx.cli_unstable().publish_timeout {
             let timeout: Option<u64> = opts.gctx.get("publish.timeout")?;
@@ -199,11 +325,11 @@ pub fn publish(ws: &Workspace<'_>, opts: &PublishOpts<'_>) -> CargoResult<()> {
         };
         if 0 < timeout {
             let timeout = Duration::from_secs(timeout);
-            wait_for_publish(opts.gctx, reg_ids.original, pkg, timeout)?;
+            available = wait_for_publish(opts.gctx, reg_ids.original, pkg, timeout)?;
         }
     }
 
-    Ok(())
+    Ok(PublishOutcome::Uploaded { available })
 }
 
 fn wait_for_publish(
@@ -211,15 +337,13 @@ fn wait_for_publish(
     registry_src: SourceId,
     pkg: &Package,
     timeout: Duration,
-) -> CargoResult<()> {
-    let version_req = format!("={}", pkg.version());
+) -> CargoResult<bool> {
     let mut source = SourceConfigMap::empty(gctx)?.load(registry_src, &HashSet::new())?;
     // Disable the source's built-in progress bars. Repeatedly showing a bunch
     // of independent progress bars can be a little confusing. There is an
     // overall progress bar managed here.
     source.set_quiet(true);
     let source_description = source.source_id().to_string();
-    let query = Dependency::parse(pkg.name(), Some(&version_req), registry_src)?;
 
     let now = std::time::Instant::now();
     let sleep_time = Duration::from_secs(1);
@@ -237,27 +361,8 @@ fn wait_for_publish(
     let mut progress = Progress::with_style("Waiting", ProgressStyle::Ratio, gctx);
     progress.tick_now(0, max, "")?;
     let is_available = loop {
-        {
-            let _lock = gctx.acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
-            // Force re-fetching the source
-            //
-            // As pulling from a git source is expensive, we track when we've done it within the
-            // process to only do it once, but we are one of the rare cases that needs to do it
-            // multiple times
-            gctx.updated_sources().remove(&source.replaced_source_id());
-            source.invalidate_cache();
-            let summaries = loop {
-                // Exact to avoid returning all for path/git
-                match source.query_vec(&query, QueryKind::Exact) {
-                    std::task::Poll::Ready(res) => {
-                        break res?;
-                    }
-                    std::task::Poll::Pending => source.block_until_ready()?,
-                }
-            };
-            if !summaries.is_empty() {
-                break true;
-            }
+        if is_published(gctx, &mut *source, pkg)? {
+            break true;
         }
 
         let elapsed = now.elapsed();
@@ -282,7 +387,32 @@ fn wait_for_publish(
         )?;
     }
 
-    Ok(())
+    Ok(is_available)
+}
+
+/// Checks whether the version of `pkg` is in the index of the registry of
+/// `source`, fetching the index again.
+fn is_published(gctx: &GlobalContext, source: &mut dyn Source, pkg: &Package) -> CargoResult<bool> {
+    let version_req = format!("={}", pkg.version());
+    let query = Dependency::parse(pkg.name(), Some(&version_req), source.source_id())?;
+    let _lock = gctx.acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
+    // Force re-fetching the source
+    //
+    // As pulling from a git source is expensive, we track when we've done it within the
+    // process to only do it once, but we are one of the rare cases that needs to do it
+    // multiple times
+    gctx.updated_sources().remove(&source.replaced_source_id());
+    source.invalidate_cache();
+    let summaries = loop {
+        // Exact to avoid returning all for path/git
+        match source.query_vec(&query, QueryKind::Exact) {
+            std::task::Poll::Ready(res) => {
+                break res?;
+            }
+            std::task::Poll::Pending => source.block_until_ready()?,
+        }
+    };
+    Ok(!summaries.is_empty())
 }
 
 fn verify_dependencies(
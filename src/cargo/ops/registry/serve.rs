@@ -0,0 +1,604 @@
+//! Serves a registry directory over HTTP, with `cargo registry serve`.
+//!
+//! The directory has the layout written by `cargo registry mirror`: index
+//! files at the paths of the sparse protocol, and `.crate` files in
+//! `crates/<name>/<name>-<version>.crate`. The server speaks just enough
+//! HTTP/1.1 for Cargo and static-file clients: `GET` and `HEAD` requests,
+//! persistent connections, and the `ETag`/`If-None-Match` and
+//! `Last-Modified`/`If-Modified-Since` pairs [`HttpRegistry`] uses to check
+//! whether its cached index files are up-to-date.
+//!
+//! Connections are served by a fixed number of threads, and the head of each
+//! request has to be received within a deadline. A connection waiting for
+//! its next request gives its thread up as soon as other connections wait
+//! for one, and the connections beyond what the threads and their queue can
+//! take are answered with `503 Service Unavailable` right away, so that idle
+//! clients can't keep others from being served. Files are streamed from the
+//! directory, with an `ETag` made of their length and modification time.
+//!
+//! The `config.json` of the directory is served with its `dl` URL pointing at
+//! the server, so that a mirror written for `file://` URLs can be served
+//! as is. With a token, every request needs to be authorized with it, and
+//! `config.json` sets `auth-required`, which makes Cargo send the token.
+//!
+//! [`HttpRegistry`]: crate::sources::registry::http_remote::HttpRegistry
+
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context as _};
+use cargo_util::{paths, Sha256};
+use time::format_description::{self, FormatItem};
+use time::{OffsetDateTime, PrimitiveDateTime};
+use tracing::debug;
+use url::Url;
+
+use super::mirror::CRATES_DIR;
+use crate::util::errors::CargoResult;
+use crate::util::GlobalContext;
+
+/// The longest request line or header accepted.
+const MAX_LINE: u64 = 8 * 1024;
+
+/// The most headers accepted in a request.
+const MAX_HEADERS: usize = 100;
+
+/// How long an idle connection is kept open, while no other connection waits
+/// to be served.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How often an idle connection checks whether other connections wait to be
+/// served, in which case it is closed.
+const IDLE_POLL: Duration = Duration::from_millis(100);
+
+/// How long writing the response to a connection turned away may take.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long a client has to send the head of a request, once it started.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many connections are served at once.
+const WORKERS: usize = 32;
+
+/// How many accepted connections can wait for a worker, beyond which they
+/// are turned away.
+const QUEUE: usize = 256;
+
+/// The format of dates in HTTP headers, like `Sun, 06 Nov 1994 08:49:37 GMT`.
+const HTTP_DATE: &str =
+    "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT";
+
+pub struct ServeOptions {
+    /// The registry directory to serve.
+    pub root: PathBuf,
+    /// The address to listen on, like `127.0.0.1:8080`.
+    pub address: String,
+    /// The URL clients reach the server at, when it isn't the address it
+    /// listens on, like behind a proxy.
+    pub url: Option<Url>,
+    /// The token requests have to be authorized with.
+    pub token: Option<String>,
+}
+
+/// What the connections share.
+struct Server {
+    root: PathBuf,
+    /// The contents of `config.json`.
+    config: Vec<u8>,
+    /// The `ETag` of `config.json`.
+    config_etag: String,
+    token: Option<String>,
+    http_date: Vec<FormatItem<'static>>,
+    /// How many accepted connections wait for a worker.
+    queued: AtomicUsize,
+}
+
+/// Serves the registry directory of `opts` until the process is stopped.
+pub fn serve(gctx: &GlobalContext, opts: &ServeOptions) -> CargoResult<()> {
+    let root = &opts.root;
+    if !root.is_dir() {
+        bail!("`{}` is not a directory", root.display());
+    }
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("failed to canonicalize `{}`", root.display()))?;
+    if opts.token.as_deref().is_some_and(str::is_empty) {
+        bail!("the token of the server cannot be empty");
+    }
+    let listener = TcpListener::bind(opts.address.as_str())
+        .with_context(|| format!("failed to listen on `{}`", opts.address))?;
+    let url = match &opts.url {
+        Some(url) => url.clone(),
+        None => format!("http://{}/", listener.local_addr()?).parse()?,
+    };
+    let base = url.as_str().trim_end_matches('/');
+
+    let config_path = root.join("config.json");
+    let mut config = if config_path.exists() {
+        serde_json::from_str(&paths::read(&config_path)?)
+            .with_context(|| format!("failed to parse `{}`", config_path.display()))?
+    } else {
+        serde_json::Map::new()
+    };
+    config.insert(
+        "dl".to_string(),
+        format!("{base}/{CRATES_DIR}/{{crate}}/{{crate}}-{{version}}.crate").into(),
+    );
+    config.insert("api".to_string(), serde_json::Value::Null);
+    if opts.token.is_some() {
+        config.insert("auth-required".to_string(), true.into());
+    }
+
+    let config = serde_json::to_vec(&config)?;
+    let server = Arc::new(Server {
+        root,
+        config_etag: format!("\"{}\"", Sha256::new().update(&config).finish_hex()),
+        config,
+        token: opts.token.clone(),
+        http_date: format_description::parse(HTTP_DATE)?,
+        queued: AtomicUsize::new(0),
+    });
+    gctx.shell().status(
+        "Serving",
+        format!("`{}` at sparse+{base}/", server.root.display()),
+    )?;
+
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(QUEUE);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..WORKERS {
+        let server = Arc::clone(&server);
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || loop {
+            let Ok(stream) = receiver.lock().unwrap().recv() else {
+                return;
+            };
+            server.queued.fetch_sub(1, Ordering::SeqCst);
+            if let Err(e) = server.handle_connection(stream) {
+                debug!("connection failed: {e}");
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                debug!("failed to accept a connection: {e}");
+                continue;
+            }
+        };
+        server.queued.fetch_add(1, Ordering::SeqCst);
+        match sender.try_send(stream) {
+            Ok(()) => {}
+            Err(mpsc::TrySendError::Full(stream)) => {
+                server.queued.fetch_sub(1, Ordering::SeqCst);
+                if let Err(e) = turn_away(stream) {
+                    debug!("failed to turn a connection away: {e}");
+                }
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => bail!("all the workers stopped"),
+        }
+    }
+    Ok(())
+}
+
+/// Answers a connection with `503 Service Unavailable` when all the workers
+/// are busy and the queue is full, rather than waiting for room.
+fn turn_away(mut stream: TcpStream) -> io::Result<()> {
+    stream.set_write_timeout(Some(BUSY_TIMEOUT))?;
+    let mut response = Response::status(503, "service unavailable");
+    response.headers.push(("Retry-After", "1".to_string()));
+    write_response(&mut stream, response, false, false)?;
+    // Closing with an unread request would reset the connection, and the
+    // client could lose the response.
+    stream.shutdown(std::net::Shutdown::Write)?;
+    stream.set_nonblocking(true)?;
+    let _ = io::copy(&mut stream.take(MAX_LINE * 4), &mut io::sink());
+    Ok(())
+}
+
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    if_none_match: Option<String>,
+    if_modified_since: Option<String>,
+    keep_alive: bool,
+}
+
+struct Response {
+    code: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Body,
+}
+
+enum Body {
+    Data(Vec<u8>),
+    /// A file streamed to the client, with its length.
+    File(fs::File, u64),
+}
+
+impl Body {
+    fn len(&self) -> u64 {
+        match self {
+            Body::Data(data) => data.len() as u64,
+            Body::File(_, len) => *len,
+        }
+    }
+}
+
+impl Response {
+    fn status(code: u16, message: &str) -> Response {
+        Response {
+            code,
+            headers: vec![("Content-Type", "text/plain".to_string())],
+            body: Body::Data(format!("{message}\n").into_bytes()),
+        }
+    }
+}
+
+/// A connection whose reads fail once the deadline of the current request
+/// passed, or between requests after [`IDLE_TIMEOUT`] or as soon as other
+/// connections are queued.
+struct Connection<'a> {
+    stream: TcpStream,
+    deadline: Option<Instant>,
+    queued: &'a AtomicUsize,
+}
+
+impl Read for Connection<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(deadline) = self.deadline else {
+            return self.read_idle(buf);
+        };
+        let timeout = deadline
+            .checked_duration_since(Instant::now())
+            .filter(|timeout| !timeout.is_zero())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "request timed out"))?;
+        self.stream.set_read_timeout(Some(timeout))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Connection<'_> {
+    /// Waits for the next request, checking every [`IDLE_POLL`] whether the
+    /// worker is needed by a queued connection.
+    fn read_idle(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = Instant::now();
+        self.stream.set_read_timeout(Some(IDLE_POLL))?;
+        loop {
+            match self.stream.read(buf) {
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    if self.queued.load(Ordering::SeqCst) > 0 || start.elapsed() >= IDLE_TIMEOUT {
+                        return Err(e);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Server {
+    fn handle_connection(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(Connection {
+            stream: stream.try_clone()?,
+            deadline: None,
+            queued: &self.queued,
+        });
+        stream.set_write_timeout(Some(IDLE_TIMEOUT))?;
+        let mut writer = stream;
+        loop {
+            let request = match read_request(&mut reader) {
+                Ok(Some(request)) => request,
+                Ok(None) => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    let response = Response::status(400, "bad request");
+                    return write_response(&mut writer, response, false, false);
+                }
+                Err(e) => return Err(e),
+            };
+            let response = self.respond(&request);
+            debug!("{} {} {}", request.method, request.path, response.code);
+            let head = request.method == "HEAD";
+            write_response(&mut writer, response, head, request.keep_alive)?;
+            if !request.keep_alive {
+                return Ok(());
+            }
+        }
+    }
+
+    fn respond(&self, request: &Request) -> Response {
+        if request.method != "GET" && request.method != "HEAD" {
+            let mut response = Response::status(405, "method not allowed");
+            response.headers.push(("Allow", "GET, HEAD".to_string()));
+            return response;
+        }
+        if let Some(token) = &self.token {
+            let authorized = request
+                .authorization
+                .as_deref()
+                .is_some_and(|given| token_matches(token, given));
+            if !authorized {
+                let mut response = Response::status(401, "missing or invalid token");
+                response
+                    .headers
+                    .push(("WWW-Authenticate", "Cargo".to_string()));
+                return response;
+            }
+        }
+
+        let path = request.path.split(['?', '#']).next().unwrap();
+        if path == "/config.json" {
+            let body = Body::Data(self.config.clone());
+            let mut response = self.conditional(request, self.config_etag.clone(), None, body);
+            if response.code == 200 {
+                response
+                    .headers
+                    .push(("Content-Type", "application/json".to_string()));
+            }
+            return response;
+        }
+        let Some(file) = self.file_path(path) else {
+            return Response::status(404, "not found");
+        };
+        let (data, metadata) = match fs::File::open(&file).and_then(|data| {
+            let metadata = data.metadata()?;
+            Ok((data, metadata))
+        }) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Response::status(404, "not found");
+            }
+            Err(e) => {
+                debug!("failed to read `{}`: {e}", file.display());
+                return Response::status(500, "internal server error");
+            }
+        };
+        let modified = metadata.modified().ok();
+        let etag = file_etag(metadata.len(), modified);
+        let body = Body::File(data, metadata.len());
+        let mut response = self.conditional(request, etag, modified, body);
+        if response.code == 200 {
+            let content_type = if path.ends_with(".crate") {
+                "application/octet-stream"
+            } else {
+                "text/plain"
+            };
+            response
+                .headers
+                .push(("Content-Type", content_type.to_string()));
+        }
+        response
+    }
+
+    /// The file of the directory at the URL path `path`, if it can be served.
+    ///
+    /// Hidden files aren't served, which also rules out `..` components.
+    fn file_path(&self, path: &str) -> Option<PathBuf> {
+        let mut file = self.root.clone();
+        for segment in path.strip_prefix('/')?.split('/') {
+            if segment.is_empty() || segment.starts_with('.') || segment.contains(['\\', ':', '%'])
+            {
+                return None;
+            }
+            file.push(segment);
+        }
+        file.is_file().then_some(file)
+    }
+
+    /// Responds with `body`, or with `304 Not Modified` when the client
+    /// already has it, according to its `If-None-Match` header, or its
+    /// `If-Modified-Since` header when it doesn't send the former.
+    fn conditional(
+        &self,
+        request: &Request,
+        etag: String,
+        modified: Option<SystemTime>,
+        body: Body,
+    ) -> Response {
+        // HTTP dates only have a precision of seconds.
+        let modified = modified
+            .map(OffsetDateTime::from)
+            .and_then(|date| date.replace_nanosecond(0).ok());
+        let not_modified = if let Some(if_none_match) = &request.if_none_match {
+            if_none_match.split(',').any(|tag| {
+                let tag = tag.trim();
+                tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag
+            })
+        } else if let (Some(since), Some(modified)) = (&request.if_modified_since, modified) {
+            PrimitiveDateTime::parse(since, &self.http_date)
+                .is_ok_and(|since| modified <= since.assume_utc())
+        } else {
+            false
+        };
+
+        let mut headers = vec![("ETag", etag)];
+        if let Some(modified) = modified.and_then(|date| date.format(&self.http_date).ok()) {
+            headers.push(("Last-Modified", modified));
+        }
+        if not_modified {
+            Response {
+                code: 304,
+                headers,
+                body: Body::Data(Vec::new()),
+            }
+        } else {
+            Response {
+                code: 200,
+                headers,
+                body,
+            }
+        }
+    }
+}
+
+/// The `ETag` of a file, which changes with its length or modification time.
+fn file_etag(len: u64, modified: Option<SystemTime>) -> String {
+    let modified = modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    format!("\"{len:x}-{:x}\"", modified.as_nanos())
+}
+
+/// Compares the tokens in a time independent of where they differ.
+fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Reads the next request of a connection, `None` when the client closed it
+/// or stayed idle.
+///
+/// The head of the request has to be received within [`REQUEST_TIMEOUT`]
+/// of its first byte.
+fn read_request(reader: &mut BufReader<Connection<'_>>) -> io::Result<Option<Request>> {
+    reader.get_mut().deadline = None;
+    match reader.fill_buf() {
+        Ok([]) => return Ok(None),
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    }
+    reader.get_mut().deadline = Some(Instant::now() + REQUEST_TIMEOUT);
+
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let Some(line) = read_line(reader)? else {
+        return Ok(None);
+    };
+    let mut parts = line.split_ascii_whitespace();
+    let (Some(method), Some(path), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid("invalid request line"));
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        authorization: None,
+        if_none_match: None,
+        if_modified_since: None,
+        keep_alive: version == "HTTP/1.1",
+    };
+
+    let mut content_length = 0;
+    for i in 0.. {
+        let Some(line) = read_line(reader)? else {
+            return Err(invalid("unexpected end of request"));
+        };
+        if line.is_empty() {
+            break;
+        }
+        if i == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(invalid("invalid header"));
+        };
+        let value = value.trim().to_string();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "authorization" => request.authorization = Some(value),
+            "if-none-match" => request.if_none_match = Some(value),
+            "if-modified-since" => request.if_modified_since = Some(value),
+            "connection" => match value.to_ascii_lowercase().as_str() {
+                "close" => request.keep_alive = false,
+                "keep-alive" => request.keep_alive = true,
+                _ => {}
+            },
+            "content-length" => {
+                content_length = value
+                    .parse()
+                    .map_err(|_| invalid("invalid content length"))?;
+            }
+            "transfer-encoding" => return Err(invalid("request bodies are not supported")),
+            _ => {}
+        }
+    }
+    // Requests are answered without looking at their body.
+    if content_length > 0 {
+        if content_length > MAX_LINE {
+            return Err(invalid("request body too large"));
+        }
+        io::copy(&mut reader.take(content_length), &mut io::sink())?;
+    }
+    Ok(Some(request))
+}
+
+/// Reads a line of the request, without its line ending.
+fn read_line(reader: &mut BufReader<Connection<'_>>) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    let read = match reader.take(MAX_LINE).read_line(&mut line) {
+        Ok(read) => read,
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+    if read == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+fn write_response(
+    writer: &mut TcpStream,
+    response: Response,
+    head: bool,
+    keep_alive: bool,
+) -> io::Result<()> {
+    let reason = match response.code {
+        200 => "OK",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let mut out = format!("HTTP/1.1 {} {reason}\r\n", response.code);
+    for (name, value) in &response.headers {
+        out.push_str(&format!("{name}: {value}\r\n"));
+    }
+    if response.code != 304 {
+        out.push_str(&format!("Content-Length: {}\r\n", response.body.len()));
+    }
+    if !keep_alive {
+        out.push_str("Connection: close\r\n");
+    }
+    out.push_str("\r\n");
+    writer.write_all(out.as_bytes())?;
+    if !head {
+        match response.body {
+            Body::Data(data) => writer.write_all(&data)?,
+            Body::File(file, len) => {
+                // The file may have been truncated since its length was read.
+                if io::copy(&mut file.take(len), writer)? < len {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "file shrank while being served",
+                    ));
+                }
+            }
+        }
+    }
+    writer.flush()
+}
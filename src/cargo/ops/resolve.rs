@@ -66,6 +66,7 @@ use crate::core::resolver::{
 use crate::core::summary::Summary;
 use crate::core::Dependency;
 use crate::core::GitReference;
+use crate::core::Package;
 use crate::core::PackageId;
 use crate::core::PackageIdSpec;
 use crate::core::PackageIdSpecQuery;
@@ -76,8 +77,10 @@ use crate::ops;
 use crate::sources::PathSource;
 use crate::util::cache_lock::CacheLockMode;
 use crate::util::errors::CargoResult;
+use crate::util::interning::InternedString;
 use crate::util::CanonicalUrl;
 use anyhow::Context as _;
+use cargo_util_schemas::core::PartialVersion;
 use std::collections::{HashMap, HashSet};
 use tracing::{debug, trace};
 
@@ -116,7 +119,7 @@ version. This may also occur with an optional dependency that is not enabled.";
 /// This is a simple interface used by commands like `clean`, `fetch`, and
 /// `package`, which don't specify any options or features.
 pub fn resolve_ws<'a>(ws: &Workspace<'a>) -> CargoResult<(PackageSet<'a>, Resolve)> {
-    let mut registry = PackageRegistry::new(ws.gctx())?;
+    let mut registry = ws.package_registry()?;
     let resolve = resolve_with_registry(ws, &mut registry)?;
     let packages = get_resolved_packages(&resolve, registry)?;
     Ok((packages, resolve))
@@ -141,7 +144,7 @@ pub fn resolve_ws_with_opts<'gctx>(
     has_dev_units: HasDevUnits,
     force_all_targets: ForceAllTargets,
 ) -> CargoResult<WorkspaceResolve<'gctx>> {
-    let mut registry = PackageRegistry::new(ws.gctx())?;
+    let mut registry = ws.package_registry()?;
     let (resolve, resolved_with_overrides) = if ws.ignore_lock() {
         let add_patches = true;
         let resolve = None;
@@ -328,7 +331,7 @@ pub fn resolve_with_previous<'gctx>(
     // involve a lot of frobbing of the global caches.
     let _lock = ws
         .gctx()
-        .acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
+        .acquire_package_cache_lock_reporting_wait(CacheLockMode::DownloadExclusive)?;
 
     // Some packages are already loaded when setting up a workspace. This
     // makes it so anything that was already loaded will not be loaded again.
@@ -416,6 +419,34 @@ pub fn resolve_with_previous<'gctx>(
         Some(ws.gctx()),
     )?;
 
+    if ws.resolve_rust_version_per_member() {
+        // Which members a package is a dependency of is only known once it
+        // is resolved, and depends on the versions picked for the packages
+        // in between. Start from the lowest `rust-version` for everything,
+        // and re-resolve with what each package ended up being needed for
+        // until that settles.
+        let members = member_rust_versions(ws)?;
+        for _ in 0..MAX_RUST_VERSION_ITERATIONS {
+            let package_rust_versions = package_rust_versions(&resolved, &members);
+            if &package_rust_versions == version_prefs.get_package_rust_versions() {
+                break;
+            }
+            debug!(
+                "re-resolving with rust-versions {:?}",
+                package_rust_versions
+            );
+            version_prefs.package_rust_versions(package_rust_versions);
+            resolved = resolver::resolve(
+                &summaries,
+                &replace,
+                registry,
+                &version_prefs,
+                ResolveVersion::with_rust_version(ws.rust_version()),
+                Some(ws.gctx()),
+            )?;
+        }
+    }
+
     let patches = registry.patches().values().flat_map(|v| v.iter());
     resolved.register_used_patches(patches);
 
@@ -432,6 +463,66 @@ pub fn resolve_with_previous<'gctx>(
     Ok(resolved)
 }
 
+/// How many times [`resolve_with_previous`] resolves again to apply the
+/// `rust-version` of each member to its own dependencies.
+const MAX_RUST_VERSION_ITERATIONS: usize = 4;
+
+/// Returns the `rust-version` of each workspace member, using the version of
+/// `rustc` for members that don't specify one.
+pub fn member_rust_versions<'a>(
+    ws: &'a Workspace<'_>,
+) -> CargoResult<Vec<(&'a Package, PartialVersion)>> {
+    let mut rustc_version = None;
+    let mut members = Vec::new();
+    for member in ws.members() {
+        let rust_version = match member.rust_version() {
+            Some(ver) => ver.clone().into_partial(),
+            None => match &rustc_version {
+                Some(ver) => PartialVersion::clone(ver),
+                None => {
+                    let rustc = ws.gctx().load_global_rustc(Some(ws))?;
+                    let ver: PartialVersion = rustc.version.clone().into();
+                    rustc_version.insert(ver).clone()
+                }
+            },
+        };
+        members.push((member, rust_version));
+    }
+    Ok(members)
+}
+
+/// Returns the `rust-version` each package in `resolve` should be compatible
+/// with, which is the lowest one among the members depending on it, directly
+/// or not.
+///
+/// Packages are identified by name, since that is all the resolver has at
+/// hand when sorting candidates.
+pub fn package_rust_versions(
+    resolve: &Resolve,
+    members: &[(&Package, PartialVersion)],
+) -> HashMap<InternedString, PartialVersion> {
+    let mut rust_versions: HashMap<InternedString, PartialVersion> = HashMap::new();
+    for (member, rust_version) in members {
+        let mut visited = HashSet::new();
+        let mut queue = vec![member.package_id()];
+        while let Some(id) = queue.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            rust_versions
+                .entry(id.name())
+                .and_modify(|ver| {
+                    if rust_version < ver {
+                        *ver = rust_version.clone()
+                    }
+                })
+                .or_insert_with(|| rust_version.clone());
+            queue.extend(resolve.deps(id).map(|(dep, _)| dep));
+        }
+    }
+    rust_versions
+}
+
 /// Read the `paths` configuration variable to discover all path overrides that
 /// have been configured.
 #[tracing::instrument(skip_all)]
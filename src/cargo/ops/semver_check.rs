@@ -0,0 +1,1411 @@
+//! Checking the changes to the public API of a package against SemVer, for
+//! `cargo package --semver-check` and `cargo publish --semver-check`.
+//!
+//! The public API of the library of the package, and of the latest version
+//! of it published before, are extracted from the JSON output of rustdoc,
+//! which is only available on the nightly channel. Each public item is
+//! identified by its path, and the changes between the two versions are
+//! classified following the [SemVer compatibility] chapter of the Cargo
+//! book, which each change refers to by the anchor of its section.
+//!
+//! Only the declarations are compared, syntactically: a signature written
+//! differently but meaning the same is reported as a change, and changes to
+//! the layout of types or to the behavior of the code are never reported.
+//!
+//! [SemVer compatibility]: https://doc.rust-lang.org/cargo/reference/semver.html
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::task::Poll;
+
+use anyhow::Context as _;
+use cargo_util::paths;
+use semver::Version;
+use serde_json::Value;
+
+use crate::core::compiler::{BuildConfig, CompileMode};
+use crate::core::resolver::CliFeatures;
+use crate::core::{Dependency, FeatureValue, Package, SourceId, Workspace};
+use crate::ops::{self, PackageOpts};
+use crate::sources::source::{QueryKind, Source};
+use crate::sources::{SourceConfigMap, CRATES_IO_REGISTRY};
+use crate::util::cache_lock::CacheLockMode;
+use crate::util::{CargoResult, Filesystem, GlobalContext};
+
+const SEMVER_REFERENCE: &str = "https://doc.rust-lang.org/cargo/reference/semver.html";
+
+/// The versions of the JSON output of rustdoc that can be compared, from the
+/// one where struct fields gained `has_stripped_fields`.
+const FORMAT_VERSIONS: RangeInclusive<u64> = 33..=57;
+
+/// How much a version changed from another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// The changes allowed in `new` by Cargo's flavor of SemVer, where the
+    /// first non-zero component is the major one.
+    fn between(old: &Version, new: &Version) -> Bump {
+        if old.major != new.major {
+            Bump::Major
+        } else if old.major != 0 {
+            if old.minor != new.minor {
+                Bump::Minor
+            } else {
+                Bump::Patch
+            }
+        } else if old.minor != new.minor || old.minor == 0 {
+            Bump::Major
+        } else {
+            Bump::Minor
+        }
+    }
+
+    /// The smallest version after `old` allowing these changes.
+    fn next_version(self, old: &Version) -> Version {
+        match (self, old.major, old.minor) {
+            (Bump::Major, 0, 0) => Version::new(0, 0, old.patch + 1),
+            (Bump::Major, 0, minor) => Version::new(0, minor + 1, 0),
+            (Bump::Major, major, _) => Version::new(major + 1, 0, 0),
+            (Bump::Minor, 0, minor) => Version::new(0, minor, old.patch + 1),
+            (Bump::Minor, major, minor) => Version::new(major, minor + 1, 0),
+            (Bump::Patch, major, minor) => Version::new(major, minor, old.patch + 1),
+        }
+    }
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bump::Patch => f.write_str("patch"),
+            Bump::Minor => f.write_str("minor"),
+            Bump::Major => f.write_str("major"),
+        }
+    }
+}
+
+/// A change to the public API.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Change {
+    /// Either [`Bump::Minor`] or [`Bump::Major`].
+    bump: Bump,
+    /// The anchor of the section of the SemVer reference describing the
+    /// change, if there is one.
+    rule: Option<&'static str>,
+    description: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rule {
+            Some(rule) => write!(f, "{} ({rule}): {}", self.bump, self.description),
+            None => write!(f, "{}: {}", self.bump, self.description),
+        }
+    }
+}
+
+/// Checks the changes to the public API of `pkg` since the latest version
+/// published before it, returning an error if its version doesn't allow
+/// them.
+pub(crate) fn check(ws: &Workspace<'_>, pkg: &Package, opts: &PackageOpts<'_>) -> CargoResult<()> {
+    let gctx = ws.gctx();
+    if !pkg.targets().iter().any(|t| t.is_lib()) {
+        return gctx.shell().note(format!(
+            "skipping the SemVer check of `{}`, which has no library",
+            pkg.name()
+        ));
+    }
+    let registry = baseline_registry(gctx, pkg)?;
+    let Some(baseline) = download_baseline(gctx, pkg, registry)? else {
+        let reason = if gctx.offline() {
+            "no earlier version of it is in the local registry cache"
+        } else {
+            "no earlier version of it is published"
+        };
+        return gctx.shell().note(format!(
+            "skipping the SemVer check of `{}`, since {reason}",
+            pkg.name()
+        ));
+    };
+
+    gctx.shell().status(
+        "Comparing",
+        format!("{} to v{}", pkg.package_id(), baseline.version()),
+    )?;
+    let dir = ws.target_dir().join("package").join("semver-check");
+    let old = public_api(gctx, &baseline, dir.join("baseline"), opts)?;
+    let new = public_api(gctx, pkg, dir.join("current"), opts)?;
+    let changes = compare(&old, &new);
+
+    let (old_version, new_version) = (baseline.version(), pkg.version());
+    let allowed = Bump::between(old_version, new_version);
+    let required = changes.iter().map(|c| c.bump).max().unwrap_or(Bump::Patch);
+    let mut list = String::new();
+    for change in &changes {
+        list.push_str(&format!("\n  {change}"));
+    }
+    if required <= allowed {
+        if changes.is_empty() {
+            return gctx.shell().status(
+                "Compatible",
+                format!("no changes to the public API since v{old_version}"),
+            );
+        }
+        return gctx.shell().note(format!(
+            "the public API of `{}` changed since v{old_version}:{list}",
+            pkg.name()
+        ));
+    }
+    anyhow::bail!(
+        "`{} v{new_version}` only allows {allowed} changes after v{old_version}, \
+         but its public API has changes that need a {required} release:{list}\n\
+         help: set the version to {} or later, and see {SEMVER_REFERENCE} \
+         for more information about each kind of change",
+        pkg.name(),
+        required.next_version(old_version),
+    )
+}
+
+/// The registry to compare `pkg` to, which is the one it's published to by
+/// default.
+fn baseline_registry(gctx: &GlobalContext, pkg: &Package) -> CargoResult<SourceId> {
+    match pkg.publish().as_deref() {
+        Some([registry]) if registry != CRATES_IO_REGISTRY => {
+            SourceId::alt_registry(gctx, registry)
+        }
+        _ => SourceId::crates_io_maybe_sparse_http(gctx),
+    }
+}
+
+/// Downloads the latest version of `pkg` published to `registry` before the
+/// version of `pkg`, if any, ignoring yanked versions.
+///
+/// When offline, only the versions already downloaded are considered, as
+/// registry sources only return those then.
+fn download_baseline(
+    gctx: &GlobalContext,
+    pkg: &Package,
+    registry: SourceId,
+) -> CargoResult<Option<Package>> {
+    let _lock = gctx.acquire_package_cache_lock(CacheLockMode::DownloadExclusive)?;
+    let mut source = SourceConfigMap::new(gctx)?.load(registry, &HashSet::new())?;
+    let dep = Dependency::parse(pkg.name(), Some(&format!("<{}", pkg.version())), registry)?;
+    let summaries = loop {
+        match source.query_vec(&dep, QueryKind::Exact)? {
+            Poll::Ready(summaries) => break summaries,
+            Poll::Pending => source.block_until_ready()?,
+        }
+    };
+    let Some(id) = summaries
+        .iter()
+        .filter(|s| !s.is_yanked())
+        .map(|s| s.package_id())
+        .filter(|id| id.version().pre.is_empty())
+        .max()
+    else {
+        return Ok(None);
+    };
+    Box::new(source).download_now(id, gctx).map(Some)
+}
+
+/// Extracts the public API of the library of `pkg`, documenting it as JSON
+/// in `target_dir`.
+fn public_api(
+    gctx: &GlobalContext,
+    pkg: &Package,
+    target_dir: Filesystem,
+    opts: &PackageOpts<'_>,
+) -> CargoResult<Api> {
+    let id = pkg.package_id();
+    let crate_name = pkg
+        .targets()
+        .iter()
+        .find(|t| t.is_lib())
+        .map(|t| t.crate_name())
+        .unwrap();
+    let ws = Workspace::ephemeral(pkg.clone(), gctx, Some(target_dir), false)?;
+    let mut build_config = BuildConfig::new(
+        gctx,
+        opts.jobs.clone(),
+        opts.keep_going,
+        &opts.targets,
+        CompileMode::Doc {
+            deps: false,
+            json: true,
+        },
+    )?;
+    build_config.rustdoc_json = true;
+    let compilation = ops::compile(
+        &ws,
+        &ops::CompileOptions {
+            build_config,
+            cli_features: declared_features(pkg, &opts.cli_features),
+            spec: ops::Packages::Packages(Vec::new()),
+            filter: ops::CompileFilter::lib_only(),
+            target_rustdoc_args: None,
+            target_rustc_args: None,
+            target_rustc_crate_types: None,
+            rustdoc_document_private_items: false,
+            honor_rust_version: None,
+        },
+    )
+    .with_context(|| format!("failed to document the public API of `{id}` as JSON"))?;
+
+    let path = compilation
+        .root_output
+        .values()
+        .next()
+        .unwrap()
+        .with_file_name("doc")
+        .join(format!("{crate_name}.json"));
+    let doc: Value = serde_json::from_str(&paths::read(&path)?)
+        .with_context(|| format!("failed to parse `{}`", path.display()))?;
+    let format_version = doc["format_version"].as_u64().unwrap_or_default();
+    if !FORMAT_VERSIONS.contains(&format_version) {
+        anyhow::bail!(
+            "the JSON output of rustdoc for `{id}` is in format version {format_version}, \
+             but only versions {} to {} are supported\n\
+             help: use a nightly toolchain whose rustdoc outputs a supported version",
+            FORMAT_VERSIONS.start(),
+            FORMAT_VERSIONS.end(),
+        );
+    }
+    Ok(Api::from_rustdoc(&doc))
+}
+
+/// The features of `cli_features` that `pkg` declares, as an earlier version
+/// of it may not have all the features selected for the current one.
+fn declared_features(pkg: &Package, cli_features: &CliFeatures) -> CliFeatures {
+    let declared = pkg.summary().features();
+    let features = cli_features
+        .features
+        .iter()
+        .filter(|feature| match feature {
+            FeatureValue::Feature(name) => declared.contains_key(name),
+            FeatureValue::Dep { dep_name } | FeatureValue::DepFeature { dep_name, .. } => pkg
+                .dependencies()
+                .iter()
+                .any(|d| d.name_in_toml() == *dep_name),
+        })
+        .cloned()
+        .collect();
+    CliFeatures {
+        features: Rc::new(features),
+        all_features: cli_features.all_features,
+        uses_default_features: cli_features.uses_default_features,
+    }
+}
+
+/// The public items of a library, by path.
+#[derive(Default)]
+struct Api {
+    items: BTreeMap<String, Item>,
+}
+
+/// What is compared of a public item.
+struct Item {
+    /// The kind of item, as named in the JSON output of rustdoc, like
+    /// `struct` or `function`.
+    kind: String,
+    non_exhaustive: bool,
+    /// The generic parameters and where clauses, see [`Generics`].
+    generics: Generics,
+    details: Details,
+    /// The traits implemented by a type, including the auto traits.
+    traits: BTreeSet<String>,
+}
+
+enum Details {
+    /// Modules, macros, and other items only compared by presence.
+    None,
+    Function {
+        sig: Value,
+        is_unsafe: bool,
+        /// Whether the function is `const`, `async`, and its ABI.
+        qualifiers: Value,
+    },
+    /// The type of a constant or a static, or the aliased type.
+    Type(Value),
+    /// The fields of a struct or a union.
+    Fields(Fields),
+    Enum {
+        variants: BTreeMap<String, Variant>,
+    },
+    Trait {
+        is_unsafe: bool,
+        is_dyn_compatible: bool,
+        supertraits: Value,
+        items: BTreeMap<String, TraitItem>,
+    },
+}
+
+#[derive(PartialEq)]
+struct Fields {
+    /// `plain`, `tuple` or `unit`.
+    shape: &'static str,
+    /// The types of the public fields, by name, or by index in tuples.
+    public: BTreeMap<String, Value>,
+    has_private: bool,
+}
+
+struct Variant {
+    non_exhaustive: bool,
+    fields: Fields,
+}
+
+struct TraitItem {
+    kind: String,
+    /// Everything about the item but its default.
+    signature: Value,
+    has_default: bool,
+}
+
+/// The generic parameters of an item, and the bounds on them.
+#[derive(Default)]
+struct Generics {
+    /// The kind (`lifetime`, `type` or `const`) of each parameter, and
+    /// whether it has a default.
+    params: Vec<(String, String, bool)>,
+    /// Each bound, from the parameters or the where clauses, as a string.
+    bounds: BTreeSet<String>,
+}
+
+impl Api {
+    fn from_rustdoc(doc: &Value) -> Api {
+        let mut walker = Walker {
+            doc,
+            api: Api::default(),
+            modules: Vec::new(),
+        };
+        let root = &doc["root"];
+        if let Some(root) = walker.item(root) {
+            let name = root["name"].as_str().unwrap_or_default().to_string();
+            walker
+                .api
+                .items
+                .insert(name.clone(), Item::new("module", Details::None));
+            walker.walk_module(root, &name);
+        }
+        walker.api
+    }
+}
+
+/// Collects the public items reachable from the root of a crate.
+struct Walker<'a> {
+    doc: &'a Value,
+    api: Api,
+    /// The modules being walked, to stop at cycles of glob imports.
+    modules: Vec<&'a Value>,
+}
+
+impl<'a> Walker<'a> {
+    fn item(&self, id: &Value) -> Option<&'a Value> {
+        let id = match id {
+            Value::String(id) => id.clone(),
+            Value::Number(id) => id.to_string(),
+            _ => return None,
+        };
+        self.doc["index"].get(&id)
+    }
+
+    fn walk_module(&mut self, module: &'a Value, path: &str) {
+        if self.modules.iter().any(|m| std::ptr::eq(*m, module)) {
+            return;
+        }
+        self.modules.push(module);
+        let items = module["inner"]["module"]["items"].as_array();
+        for id in items.into_iter().flatten() {
+            let Some(item) = self.item(id) else { continue };
+            if item["visibility"] != "public" {
+                continue;
+            }
+            if let Some(import) = item["inner"].get("use") {
+                let target = self.item(&import["id"]);
+                let name = import["name"].as_str().unwrap_or_default();
+                match target {
+                    Some(target) if import["is_glob"] == true => {
+                        if target["inner"].get("module").is_some() {
+                            self.walk_module(target, path);
+                        }
+                    }
+                    Some(target) => self.add(target, &format!("{path}::{name}")),
+                    // Re-exports of items of other crates.
+                    None => {
+                        let source = self.canonical_path(&import["id"]).unwrap_or_else(|| {
+                            import["source"].as_str().unwrap_or_default().to_string()
+                        });
+                        self.api.items.insert(
+                            format!("{path}::{name}"),
+                            Item::new("use", Details::Type(Value::String(source))),
+                        );
+                    }
+                }
+            } else if let Some(name) = item["name"].as_str() {
+                self.add(item, &format!("{path}::{name}"));
+            }
+        }
+        self.modules.pop();
+    }
+
+    fn add(&mut self, item: &'a Value, path: &str) {
+        let Some((kind, inner)) = item["inner"].as_object().and_then(|o| o.iter().next()) else {
+            return;
+        };
+        let non_exhaustive = is_non_exhaustive(item);
+        let generics = self.generics(&inner["generics"]);
+        let details = match kind.as_str() {
+            "module" => {
+                self.api
+                    .items
+                    .insert(path.to_string(), Item::new(kind, Details::None));
+                self.walk_module(item, path);
+                return;
+            }
+            "function" => self.function(inner),
+            "constant" => Details::Type(self.normalize(&inner["type"])),
+            "static" => Details::Type(serde_json::json!([
+                self.normalize(&inner["type"]),
+                inner["is_mutable"]
+            ])),
+            "type_alias" => Details::Type(self.normalize(&inner["type"])),
+            "struct" => Details::Fields(self.struct_fields(&inner["kind"])),
+            "union" => Details::Fields(Fields {
+                shape: "plain",
+                public: self.public_fields(&inner["fields"]),
+                has_private: inner["has_stripped_fields"] == true,
+            }),
+            "enum" => Details::Enum {
+                variants: self.variants(inner),
+            },
+            "trait" => Details::Trait {
+                is_unsafe: inner["is_unsafe"] == true,
+                is_dyn_compatible: inner["is_dyn_compatible"] == true
+                    || inner["is_object_safe"] == true,
+                supertraits: self.normalize(&inner["bounds"]),
+                items: self.trait_items(inner),
+            },
+            "macro" | "proc_macro" | "trait_alias" => Details::None,
+            _ => return,
+        };
+        let traits = self.impls(inner, path);
+        self.api.items.insert(
+            path.to_string(),
+            Item {
+                kind: kind.clone(),
+                non_exhaustive,
+                generics,
+                details,
+                traits,
+            },
+        );
+    }
+
+    fn function(&self, inner: &Value) -> Details {
+        let header = &inner["header"];
+        Details::Function {
+            sig: self.normalize(&inner["sig"]),
+            is_unsafe: header["is_unsafe"] == true,
+            qualifiers: serde_json::json!([header["is_const"], header["is_async"], header["abi"]]),
+        }
+    }
+
+    fn struct_fields(&self, kind: &Value) -> Fields {
+        if let Some(plain) = kind.get("plain") {
+            Fields {
+                shape: "plain",
+                public: self.public_fields(&plain["fields"]),
+                has_private: plain["has_stripped_fields"] == true,
+            }
+        } else if let Some(fields) = kind.get("tuple").and_then(|f| f.as_array()) {
+            let mut public = BTreeMap::new();
+            let mut has_private = false;
+            for (i, field) in fields.iter().enumerate() {
+                match self.item(field) {
+                    Some(field) if field["visibility"] == "public" => {
+                        public.insert(
+                            i.to_string(),
+                            self.normalize(&field["inner"]["struct_field"]),
+                        );
+                    }
+                    _ => has_private = true,
+                }
+            }
+            Fields {
+                shape: "tuple",
+                public,
+                has_private,
+            }
+        } else {
+            Fields {
+                shape: "unit",
+                public: BTreeMap::new(),
+                has_private: false,
+            }
+        }
+    }
+
+    fn public_fields(&self, ids: &Value) -> BTreeMap<String, Value> {
+        ids.as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.item(id))
+            .filter(|field| field["visibility"] == "public")
+            .map(|field| {
+                (
+                    field["name"].as_str().unwrap_or_default().to_string(),
+                    self.normalize(&field["inner"]["struct_field"]),
+                )
+            })
+            .collect()
+    }
+
+    fn variants(&self, inner: &Value) -> BTreeMap<String, Variant> {
+        let ids = inner["variants"].as_array();
+        ids.into_iter()
+            .flatten()
+            .filter_map(|id| self.item(id))
+            .map(|variant| {
+                let kind = &variant["inner"]["variant"]["kind"];
+                let fields = match kind.get("struct") {
+                    Some(fields) => Fields {
+                        shape: "plain",
+                        // Fields of variants are always public.
+                        public: self.variant_fields(&fields["fields"]),
+                        has_private: fields["has_stripped_fields"] == true,
+                    },
+                    None => match kind.get("tuple") {
+                        Some(fields) => Fields {
+                            shape: "tuple",
+                            public: self.variant_fields(fields),
+                            has_private: false,
+                        },
+                        None => Fields {
+                            shape: "unit",
+                            public: BTreeMap::new(),
+                            has_private: false,
+                        },
+                    },
+                };
+                (
+                    variant["name"].as_str().unwrap_or_default().to_string(),
+                    Variant {
+                        non_exhaustive: is_non_exhaustive(variant),
+                        fields,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn variant_fields(&self, ids: &Value) -> BTreeMap<String, Value> {
+        ids.as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(i, id)| Some((i, self.item(id)?)))
+            .map(|(i, field)| {
+                let name = match field["name"].as_str() {
+                    Some(name) => name.to_string(),
+                    None => i.to_string(),
+                };
+                (name, self.normalize(&field["inner"]["struct_field"]))
+            })
+            .collect()
+    }
+
+    fn trait_items(&self, inner: &Value) -> BTreeMap<String, TraitItem> {
+        let ids = inner["items"].as_array();
+        ids.into_iter()
+            .flatten()
+            .filter_map(|id| self.item(id))
+            .filter_map(|item| {
+                let (kind, inner) = item["inner"].as_object()?.iter().next()?;
+                let (signature, has_default) = match kind.as_str() {
+                    "function" => (
+                        serde_json::json!([
+                            self.normalize(&inner["sig"]),
+                            self.normalize(&inner["generics"]),
+                            inner["header"],
+                        ]),
+                        inner["has_body"] == true,
+                    ),
+                    "assoc_const" => (
+                        self.normalize(&inner["type"]),
+                        !inner["value"].is_null() || !inner["default"].is_null(),
+                    ),
+                    "assoc_type" => (
+                        serde_json::json!([
+                            self.normalize(&inner["generics"]),
+                            self.normalize(&inner["bounds"]),
+                        ]),
+                        !inner["type"].is_null() || !inner["default"].is_null(),
+                    ),
+                    _ => return None,
+                };
+                Some((
+                    item["name"].as_str()?.to_string(),
+                    TraitItem {
+                        kind: kind.clone(),
+                        signature,
+                        has_default,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Adds the inherent methods and associated constants of a type as
+    /// items of their own, and returns the traits the type implements.
+    fn impls(&mut self, inner: &Value, path: &str) -> BTreeSet<String> {
+        const AUTO_TRAITS: &[&str] = &["Send", "Sync", "Unpin", "UnwindSafe", "RefUnwindSafe"];
+
+        let mut traits = BTreeSet::new();
+        let ids = inner["impls"].as_array();
+        let impls: Vec<_> = ids
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.item(id))
+            .collect();
+        for imp in impls {
+            let imp = &imp["inner"]["impl"];
+            if !imp["blanket_impl"].is_null() {
+                continue;
+            }
+            if imp["trait"].is_null() {
+                let items = imp["items"].as_array();
+                let items: Vec<_> = items
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|id| self.item(id))
+                    .collect();
+                for item in items {
+                    if item["visibility"] != "public" {
+                        continue;
+                    }
+                    let (Some(name), Some(inner)) =
+                        (item["name"].as_str(), item["inner"].as_object())
+                    else {
+                        continue;
+                    };
+                    let details = if let Some(function) = inner.get("function") {
+                        self.function(function)
+                    } else if let Some(constant) = inner.get("assoc_const") {
+                        Details::Type(self.normalize(&constant["type"]))
+                    } else {
+                        continue;
+                    };
+                    let mut method = Item::new("method", details);
+                    method.generics = self.generics(&inner.values().next().unwrap()["generics"]);
+                    self.api.items.insert(format!("{path}::{name}"), method);
+                }
+                continue;
+            }
+            let name = imp["trait"]["path"].as_str().unwrap_or_default();
+            if imp["is_synthetic"] == true && !AUTO_TRAITS.contains(&name) {
+                continue;
+            }
+            let mut trait_ = self.normalize(&imp["trait"]);
+            if let Some(path) = trait_.get("path").and_then(|p| p.as_str()) {
+                let path = path.to_string();
+                if trait_["args"].is_null() {
+                    traits.insert(path);
+                    continue;
+                }
+                trait_["path"] = Value::String(path);
+            }
+            traits.insert(trait_.to_string());
+        }
+        traits
+    }
+
+    fn generics(&self, generics: &Value) -> Generics {
+        let mut result = Generics::default();
+        for param in generics["params"].as_array().into_iter().flatten() {
+            let name = param["name"].as_str().unwrap_or_default().to_string();
+            let Some((kind, details)) = param["kind"].as_object().and_then(|o| o.iter().next())
+            else {
+                continue;
+            };
+            let has_default = !details["default"].is_null();
+            for bound in details["bounds"].as_array().into_iter().flatten() {
+                result
+                    .bounds
+                    .insert(format!("{name}: {}", self.normalize(bound)));
+            }
+            for bound in details["outlives"].as_array().into_iter().flatten() {
+                result.bounds.insert(format!("{name}: {bound}"));
+            }
+            result.params.push((name, kind.clone(), has_default));
+        }
+        for predicate in generics["where_predicates"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if let Some(bound) = predicate.get("bound_predicate") {
+                let ty = self.normalize(&bound["type"]);
+                let ty = match ty.get("generic").and_then(|g| g.as_str()) {
+                    Some(param) => param.to_string(),
+                    None => ty.to_string(),
+                };
+                for b in bound["bounds"].as_array().into_iter().flatten() {
+                    result.bounds.insert(format!("{ty}: {}", self.normalize(b)));
+                }
+            } else {
+                result.bounds.insert(self.normalize(predicate).to_string());
+            }
+        }
+        result
+    }
+
+    /// Returns the full path of the item with the given id, from any crate.
+    fn canonical_path(&self, id: &Value) -> Option<String> {
+        let id = match id {
+            Value::String(id) => id.clone(),
+            Value::Number(id) => id.to_string(),
+            _ => return None,
+        };
+        let path = self.doc["paths"].get(&id)?["path"].as_array()?;
+        let path: Vec<_> = path.iter().filter_map(|p| p.as_str()).collect();
+        Some(path.join("::"))
+    }
+
+    /// Removes the ids from a type or a signature, which differ between two
+    /// versions of a crate, replacing the paths to items by their full path.
+    fn normalize(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(object) => {
+                let mut result = serde_json::Map::new();
+                for (key, value) in object {
+                    match key.as_str() {
+                        "id" => {}
+                        "path" if object.contains_key("id") => {
+                            let path = self
+                                .canonical_path(&object["id"])
+                                .unwrap_or_else(|| value.as_str().unwrap_or_default().to_string());
+                            result.insert(key.clone(), Value::String(path));
+                        }
+                        _ => {
+                            result.insert(key.clone(), self.normalize(value));
+                        }
+                    }
+                }
+                Value::Object(result)
+            }
+            Value::Array(values) => {
+                Value::Array(values.iter().map(|v| self.normalize(v)).collect())
+            }
+            value => value.clone(),
+        }
+    }
+}
+
+fn is_non_exhaustive(item: &Value) -> bool {
+    item["attrs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|attr| attr.to_string().contains("non_exhaustive"))
+}
+
+impl Item {
+    fn new(kind: &str, details: Details) -> Item {
+        Item {
+            kind: kind.to_string(),
+            non_exhaustive: false,
+            generics: Generics::default(),
+            details,
+            traits: BTreeSet::new(),
+        }
+    }
+}
+
+/// Lists the changes from the API `old` to the API `new`.
+fn compare(old: &Api, new: &Api) -> Vec<Change> {
+    let mut changes = Changes::default();
+    let is_new_in = |api: &Api, path: &str| {
+        // Only the outermost item added or removed is reported.
+        let mut parent = path;
+        while let Some((prefix, _)) = parent.rsplit_once("::") {
+            if !api.items.contains_key(prefix) {
+                return false;
+            }
+            parent = prefix;
+        }
+        true
+    };
+    for (path, old_item) in &old.items {
+        match new.items.get(path) {
+            Some(new_item) => compare_item(&mut changes, path, old_item, new_item),
+            None if is_new_in(new, path) => changes.major(
+                Some("item-remove"),
+                format!("{} `{path}` was removed", describe(old_item)),
+            ),
+            None => {}
+        }
+    }
+    for (path, new_item) in &new.items {
+        if !old.items.contains_key(path) && is_new_in(old, path) {
+            let rule = if new_item.kind == "method" {
+                "impl-item-new"
+            } else {
+                "item-new"
+            };
+            changes.minor(
+                Some(rule),
+                format!("{} `{path}` was added", describe(new_item)),
+            );
+        }
+    }
+    let mut changes = changes.0;
+    changes.sort_by(|a, b| b.bump.cmp(&a.bump));
+    changes
+}
+
+#[derive(Default)]
+struct Changes(Vec<Change>);
+
+impl Changes {
+    fn major(&mut self, rule: Option<&'static str>, description: String) {
+        self.0.push(Change {
+            bump: Bump::Major,
+            rule,
+            description,
+        });
+    }
+
+    fn minor(&mut self, rule: Option<&'static str>, description: String) {
+        self.0.push(Change {
+            bump: Bump::Minor,
+            rule,
+            description,
+        });
+    }
+}
+
+fn describe(item: &Item) -> &str {
+    match item.kind.as_str() {
+        "type_alias" => "type alias",
+        "proc_macro" => "procedural macro",
+        "trait_alias" => "trait alias",
+        "use" => "re-export",
+        "assoc_const" => "associated constant",
+        kind => kind,
+    }
+}
+
+fn compare_item(changes: &mut Changes, path: &str, old: &Item, new: &Item) {
+    if old.kind != new.kind {
+        changes.major(
+            Some("item-remove"),
+            format!(
+                "`{path}` changed from a {} to a {}",
+                describe(old),
+                describe(new)
+            ),
+        );
+        return;
+    }
+    compare_generics(changes, path, &old.generics, &new.generics, &old.kind);
+    for removed in old.traits.difference(&new.traits) {
+        changes.major(None, format!("`{path}` no longer implements `{removed}`"));
+    }
+    match (&old.details, &new.details) {
+        (
+            Details::Function {
+                sig: old_sig,
+                is_unsafe: old_unsafe,
+                qualifiers: old_qualifiers,
+            },
+            Details::Function {
+                sig: new_sig,
+                is_unsafe: new_unsafe,
+                qualifiers: new_qualifiers,
+            },
+        ) => {
+            let arity = |sig: &Value| sig["inputs"].as_array().map_or(0, |i| i.len());
+            if arity(old_sig) != arity(new_sig) {
+                changes.major(
+                    Some("fn-change-arity"),
+                    format!("the number of parameters of `{path}` changed"),
+                );
+            } else if old_sig != new_sig {
+                changes.major(None, format!("the signature of `{path}` changed"));
+            }
+            if *old_unsafe && !*new_unsafe {
+                changes.minor(
+                    Some("fn-unsafe-safe"),
+                    format!("`{path}` is no longer `unsafe`"),
+                );
+            } else if !*old_unsafe && *new_unsafe {
+                changes.major(None, format!("`{path}` became `unsafe`"));
+            }
+            if old_qualifiers != new_qualifiers {
+                changes.major(
+                    None,
+                    format!("the `const`, `async` or `extern` qualifiers of `{path}` changed"),
+                );
+            }
+        }
+        (Details::Type(old_ty), Details::Type(new_ty)) => {
+            if old_ty != new_ty {
+                changes.major(None, format!("the type of `{path}` changed"));
+            }
+        }
+        (Details::Fields(old_fields), Details::Fields(new_fields)) => {
+            compare_fields(
+                changes,
+                path,
+                old_fields,
+                new_fields,
+                old.non_exhaustive,
+                false,
+            );
+            compare_non_exhaustive(changes, path, old, new, !old_fields.has_private);
+        }
+        (
+            Details::Enum {
+                variants: old_variants,
+            },
+            Details::Enum {
+                variants: new_variants,
+            },
+        ) => {
+            compare_non_exhaustive(changes, path, old, new, true);
+            compare_variants(changes, path, old, old_variants, new_variants);
+        }
+        (
+            Details::Trait {
+                is_unsafe: old_unsafe,
+                is_dyn_compatible: old_dyn,
+                supertraits: old_supertraits,
+                items: old_items,
+            },
+            Details::Trait {
+                is_unsafe: new_unsafe,
+                is_dyn_compatible: new_dyn,
+                supertraits: new_supertraits,
+                items: new_items,
+            },
+        ) => {
+            if !*old_unsafe && *new_unsafe {
+                changes.major(None, format!("`{path}` became an `unsafe` trait"));
+            }
+            if *old_dyn && !*new_dyn {
+                changes.major(
+                    Some("trait-object-safety"),
+                    format!("`{path}` is no longer dyn compatible"),
+                );
+            }
+            if old_supertraits != new_supertraits {
+                changes.major(None, format!("the supertraits of `{path}` changed"));
+            }
+            compare_trait_items(changes, path, old_items, new_items);
+        }
+        _ => {}
+    }
+}
+
+fn compare_non_exhaustive(
+    changes: &mut Changes,
+    path: &str,
+    old: &Item,
+    new: &Item,
+    constructible: bool,
+) {
+    if !old.non_exhaustive && new.non_exhaustive && constructible {
+        changes.major(
+            Some("attr-adding-non-exhaustive"),
+            format!("`{path}` became `#[non_exhaustive]`"),
+        );
+    }
+}
+
+/// Compares the fields of a struct, a union or an enum variant.
+///
+/// `in_variant` is set for variants, which can't have private fields.
+fn compare_fields(
+    changes: &mut Changes,
+    path: &str,
+    old: &Fields,
+    new: &Fields,
+    non_exhaustive: bool,
+    in_variant: bool,
+) {
+    for (name, old_ty) in &old.public {
+        match new.public.get(name) {
+            Some(new_ty) if old_ty != new_ty => {
+                changes.major(None, format!("the type of `{path}::{name}` changed"));
+            }
+            Some(_) => {}
+            None => changes.major(
+                Some("item-remove"),
+                format!("field `{path}::{name}` was removed"),
+            ),
+        }
+    }
+    let added: Vec<_> = new
+        .public
+        .keys()
+        .filter(|name| !old.public.contains_key(*name))
+        .collect();
+
+    if old.shape != new.shape {
+        // Tuple and normal structs with only private fields are built and
+        // matched in the same ways.
+        if old.public.is_empty() && new.public.is_empty() && old.has_private && new.has_private {
+            changes.minor(
+                Some("struct-tuple-normal-with-private"),
+                format!(
+                    "`{path}` changed from a {} to a {} struct",
+                    old.shape, new.shape
+                ),
+            );
+        } else {
+            changes.major(
+                None,
+                format!(
+                    "`{path}` changed from a {} to a {} shape",
+                    old.shape, new.shape
+                ),
+            );
+        }
+        return;
+    }
+
+    // Fields can only be added freely to types that can't be built or
+    // matched exhaustively outside of the crate.
+    let sealed = old.has_private || non_exhaustive;
+    if in_variant {
+        if !added.is_empty() && !non_exhaustive {
+            changes.major(
+                Some("enum-fields-new"),
+                format!("fields were added to `{path}`"),
+            );
+        }
+        return;
+    }
+    if !old.has_private && new.has_private && !non_exhaustive {
+        changes.major(
+            Some("struct-add-private-field-when-public"),
+            format!("a private field was added to `{path}`, whose fields were all public"),
+        );
+    }
+    for name in added {
+        if sealed {
+            changes.minor(
+                Some("struct-private-fields-with-private"),
+                format!("field `{path}::{name}` was added"),
+            );
+        } else {
+            changes.major(
+                Some("struct-add-public-field-when-no-private"),
+                format!("field `{path}::{name}` was added to a struct with no private fields"),
+            );
+        }
+    }
+}
+
+fn compare_variants(
+    changes: &mut Changes,
+    path: &str,
+    old: &Item,
+    old_variants: &BTreeMap<String, Variant>,
+    new_variants: &BTreeMap<String, Variant>,
+) {
+    for (name, old_variant) in old_variants {
+        let variant_path = format!("{path}::{name}");
+        let Some(new_variant) = new_variants.get(name) else {
+            changes.major(
+                Some("item-remove"),
+                format!("variant `{variant_path}` was removed"),
+            );
+            continue;
+        };
+        if !old_variant.non_exhaustive && new_variant.non_exhaustive {
+            changes.major(
+                Some("attr-adding-non-exhaustive"),
+                format!("variant `{variant_path}` became `#[non_exhaustive]`"),
+            );
+        }
+        compare_fields(
+            changes,
+            &variant_path,
+            &old_variant.fields,
+            &new_variant.fields,
+            old_variant.non_exhaustive,
+            true,
+        );
+    }
+    for name in new_variants.keys() {
+        if old_variants.contains_key(name) {
+            continue;
+        }
+        if old.non_exhaustive {
+            changes.minor(
+                Some("item-new"),
+                format!("variant `{path}::{name}` was added"),
+            );
+        } else {
+            changes.major(
+                Some("enum-variant-new"),
+                format!("variant `{path}::{name}` was added to an exhaustive enum"),
+            );
+        }
+    }
+}
+
+fn compare_trait_items(
+    changes: &mut Changes,
+    path: &str,
+    old_items: &BTreeMap<String, TraitItem>,
+    new_items: &BTreeMap<String, TraitItem>,
+) {
+    for (name, old_item) in old_items {
+        match new_items.get(name) {
+            None => changes.major(
+                Some("item-remove"),
+                format!("trait item `{path}::{name}` was removed"),
+            ),
+            Some(new_item)
+                if old_item.kind != new_item.kind || old_item.signature != new_item.signature =>
+            {
+                changes.major(
+                    Some("trait-item-signature"),
+                    format!("the signature of trait item `{path}::{name}` changed"),
+                )
+            }
+            Some(new_item) if old_item.has_default && !new_item.has_default => changes.major(
+                None,
+                format!("the default of trait item `{path}::{name}` was removed"),
+            ),
+            Some(_) => {}
+        }
+    }
+    for (name, new_item) in new_items {
+        if old_items.contains_key(name) {
+            continue;
+        }
+        if new_item.has_default {
+            changes.minor(
+                Some("trait-new-default-item"),
+                format!("trait item `{path}::{name}` was added with a default"),
+            );
+        } else {
+            changes.major(
+                Some("trait-new-item-no-default"),
+                format!("trait item `{path}::{name}` was added without a default"),
+            );
+        }
+    }
+}
+
+fn compare_generics(changes: &mut Changes, path: &str, old: &Generics, new: &Generics, kind: &str) {
+    let names = |g: &Generics| -> Vec<(String, String)> {
+        g.params
+            .iter()
+            .map(|(name, kind, _)| (name.clone(), kind.clone()))
+            .collect()
+    };
+    let (old_params, new_params) = (names(old), names(new));
+    if old_params != new_params {
+        let appended = new_params.starts_with(&old_params);
+        let extra = &new.params[old_params.len().min(new.params.len())..];
+        let is_fn = kind == "function" || kind == "method";
+        if appended && extra.iter().all(|(_, _, has_default)| *has_default) {
+            let rule = if kind == "trait" {
+                "trait-new-parameter-default"
+            } else {
+                "generic-new-default"
+            };
+            changes.minor(
+                Some(rule),
+                format!("defaulted generic parameters were added to `{path}`"),
+            );
+        } else if appended && is_fn {
+            changes.minor(
+                Some("fn-generic-new"),
+                format!("generic parameters were added to `{path}`"),
+            );
+        } else if appended && kind == "trait" {
+            changes.major(
+                Some("trait-new-parameter-no-default"),
+                format!("generic parameters without defaults were added to `{path}`"),
+            );
+        } else {
+            changes.major(None, format!("the generic parameters of `{path}` changed"));
+            return;
+        }
+    }
+
+    let tightened = new.bounds.difference(&old.bounds).next().is_some();
+    let loosened = old.bounds.difference(&new.bounds).next().is_some();
+    match (tightened, loosened) {
+        (true, false) => changes.major(
+            Some("generic-bounds-tighten"),
+            format!("bounds were added to the generic parameters of `{path}`"),
+        ),
+        (false, true) => changes.minor(
+            Some("generic-bounds-loosen"),
+            format!("bounds were removed from the generic parameters of `{path}`"),
+        ),
+        (true, true) => changes.major(
+            None,
+            format!("the bounds on the generic parameters of `{path}` changed"),
+        ),
+        (false, false) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn v(version: &str) -> Version {
+        version.parse().unwrap()
+    }
+
+    #[test]
+    fn bump_between() {
+        assert_eq!(Bump::between(&v("1.2.3"), &v("1.2.4")), Bump::Patch);
+        assert_eq!(Bump::between(&v("1.2.3"), &v("1.3.0")), Bump::Minor);
+        assert_eq!(Bump::between(&v("1.2.3"), &v("2.0.0")), Bump::Major);
+        assert_eq!(Bump::between(&v("0.1.0"), &v("0.1.1")), Bump::Minor);
+        assert_eq!(Bump::between(&v("0.1.0"), &v("0.2.0")), Bump::Major);
+        assert_eq!(Bump::between(&v("0.0.1"), &v("0.0.2")), Bump::Major);
+    }
+
+    #[test]
+    fn bump_next_version() {
+        assert_eq!(Bump::Patch.next_version(&v("1.2.3")), v("1.2.4"));
+        assert_eq!(Bump::Minor.next_version(&v("1.2.3")), v("1.3.0"));
+        assert_eq!(Bump::Major.next_version(&v("1.2.3")), v("2.0.0"));
+        assert_eq!(Bump::Minor.next_version(&v("0.1.3")), v("0.1.4"));
+        assert_eq!(Bump::Major.next_version(&v("0.1.3")), v("0.2.0"));
+        assert_eq!(Bump::Major.next_version(&v("0.0.3")), v("0.0.4"));
+        for old in ["1.2.3", "0.1.3", "0.0.3"] {
+            for bump in [Bump::Patch, Bump::Minor, Bump::Major] {
+                let old = v(old);
+                assert!(Bump::between(&old, &bump.next_version(&old)) >= bump);
+            }
+        }
+    }
+
+    fn api(items: Vec<(&str, Item)>) -> Api {
+        let mut items: BTreeMap<_, _> = items
+            .into_iter()
+            .map(|(path, item)| (path.to_string(), item))
+            .collect();
+        items.insert("foo".to_string(), Item::new("module", Details::None));
+        Api { items }
+    }
+
+    fn function(inputs: Value) -> Item {
+        Item::new(
+            "function",
+            Details::Function {
+                sig: json!({ "inputs": inputs }),
+                is_unsafe: false,
+                qualifiers: json!([false, false, "Rust"]),
+            },
+        )
+    }
+
+    fn strukt(public: &[&str], has_private: bool) -> Item {
+        Item::new(
+            "struct",
+            Details::Fields(Fields {
+                shape: "plain",
+                public: public
+                    .iter()
+                    .map(|name| (name.to_string(), json!({ "primitive": "u8" })))
+                    .collect(),
+                has_private,
+            }),
+        )
+    }
+
+    fn changes(old: Api, new: Api) -> Vec<String> {
+        compare(&old, &new).iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn compare_added_items() {
+        let old = api(vec![]);
+        let new = api(vec![
+            ("foo::f", function(json!([]))),
+            ("foo::m", Item::new("module", Details::None)),
+            ("foo::m::g", function(json!([]))),
+        ]);
+        assert_eq!(
+            changes(old, new),
+            [
+                "minor (item-new): function `foo::f` was added",
+                "minor (item-new): module `foo::m` was added",
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_removed_items() {
+        let old = api(vec![
+            ("foo::f", function(json!([]))),
+            ("foo::m", Item::new("module", Details::None)),
+            ("foo::m::g", function(json!([]))),
+        ]);
+        let new = api(vec![("foo::m", Item::new("module", Details::None))]);
+        assert_eq!(
+            changes(old, new),
+            [
+                "major (item-remove): function `foo::f` was removed",
+                "major (item-remove): function `foo::m::g` was removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_changed_items() {
+        let old = api(vec![
+            ("foo::f", function(json!([]))),
+            ("foo::g", function(json!([["x", { "primitive": "u8" }]]))),
+            ("foo::S", strukt(&["a"], false)),
+            ("foo::P", strukt(&["a"], true)),
+            ("foo::T", Item::new("trait", Details::None)),
+        ]);
+        let new = api(vec![
+            ("foo::f", function(json!([["x", { "primitive": "u8" }]]))),
+            ("foo::g", function(json!([["x", { "primitive": "u16" }]]))),
+            ("foo::S", strukt(&["a", "b"], false)),
+            ("foo::P", strukt(&["a", "b"], true)),
+            ("foo::T", Item::new("struct", Details::None)),
+        ]);
+        assert_eq!(
+            changes(old, new),
+            [
+                "major (struct-add-public-field-when-no-private): field `foo::S::b` was added to a struct with no private fields",
+                "major (item-remove): `foo::T` changed from a trait to a struct",
+                "major (fn-change-arity): the number of parameters of `foo::f` changed",
+                "major: the signature of `foo::g` changed",
+                "minor (struct-private-fields-with-private): field `foo::P::b` was added",
+            ]
+        );
+    }
+
+    #[test]
+    fn compare_unchanged_items() {
+        let items = || {
+            vec![
+                ("foo::f", function(json!([["x", { "primitive": "u8" }]]))),
+                ("foo::S", strukt(&["a"], true)),
+            ]
+        };
+        assert!(compare(&api(items()), &api(items())).is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+//! Output of the dependency graph as a whole, for `--output-format=dot` and
+//! `--output-format=json`.
+//!
+//! Unlike the text tree, these don't repeat or deduplicate anything: every
+//! node reachable from the roots is listed once, along with all the edges
+//! between them.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+
+use serde::Serialize;
+
+use super::format::Pattern;
+use super::{EdgeKind, Graph, Node};
+use crate::core::compiler::CompileKind;
+use crate::core::dependency::DepKind;
+use crate::core::{PackageIdSpec, PackageIdSpecQuery};
+use crate::util::interning::InternedString;
+
+/// The edge kinds, in the order they are followed.
+const EDGE_KINDS: [EdgeKind; 4] = [
+    EdgeKind::Dep(DepKind::Normal),
+    EdgeKind::Dep(DepKind::Build),
+    EdgeKind::Dep(DepKind::Development),
+    EdgeKind::Feature,
+];
+
+/// The part of a [`Graph`] that would be displayed for the given roots.
+pub struct Subgraph {
+    /// Indexes in the original graph, in the order they were first reached.
+    nodes: Vec<usize>,
+    /// Edges between positions in `nodes`.
+    edges: Vec<(usize, usize, EdgeKind)>,
+    /// Positions of the roots in `nodes`.
+    roots: Vec<usize>,
+}
+
+impl Subgraph {
+    /// Collects the nodes reachable from `roots`, skipping pruned packages and
+    /// anything deeper than `max_depth`, like the text tree does.
+    pub fn new(
+        graph: &Graph<'_>,
+        roots: &[usize],
+        pkgs_to_prune: &[PackageIdSpec],
+        max_depth: u32,
+    ) -> Subgraph {
+        let mut positions = HashMap::new();
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut queue = VecDeque::new();
+        for &root in roots {
+            if !positions.contains_key(&root) {
+                positions.insert(root, nodes.len());
+                nodes.push(root);
+                queue.push_back((root, 0));
+            }
+        }
+        let roots = roots.iter().map(|root| positions[root]).collect();
+
+        while let Some((index, depth)) = queue.pop_front() {
+            if depth >= max_depth {
+                continue;
+            }
+            for kind in &EDGE_KINDS {
+                for dep in graph.connected_nodes(index, kind) {
+                    if let Node::Package { package_id, .. } = graph.node(dep) {
+                        if pkgs_to_prune.iter().any(|spec| spec.matches(*package_id)) {
+                            continue;
+                        }
+                    }
+                    let position = *positions.entry(dep).or_insert_with(|| {
+                        nodes.push(dep);
+                        queue.push_back((dep, depth + 1));
+                        nodes.len() - 1
+                    });
+                    edges.push((positions[&index], position, *kind));
+                }
+            }
+        }
+
+        Subgraph {
+            nodes,
+            edges,
+            roots,
+        }
+    }
+
+    /// Renders the subgraph in the Graphviz DOT language, labelling the
+    /// nodes with `format`.
+    pub fn to_dot(&self, graph: &Graph<'_>, format: &Pattern) -> String {
+        let mut out = String::from("digraph {\n");
+        for (position, &index) in self.nodes.iter().enumerate() {
+            let label = format.display(graph, index).to_string();
+            let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+            let shape = match graph.node(index) {
+                Node::Package { .. } => "",
+                Node::Feature { .. } => ", shape=box",
+            };
+            writeln!(out, "    {position} [label=\"{label}\"{shape}];").unwrap();
+        }
+        for (from, to, kind) in &self.edges {
+            let attrs = match kind {
+                EdgeKind::Dep(DepKind::Normal) => "",
+                EdgeKind::Dep(DepKind::Build) => " [label=\"build\"]",
+                EdgeKind::Dep(DepKind::Development) => " [label=\"dev\"]",
+                EdgeKind::Feature => " [style=dashed]",
+            };
+            writeln!(out, "    {from} -> {to}{attrs};").unwrap();
+        }
+        out.push('}');
+        out
+    }
+
+    /// Renders the subgraph as a JSON object.
+    pub fn to_json(&self, graph: &Graph<'_>, inverted: bool) -> String {
+        let positions: HashMap<usize, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(position, &index)| (index, position))
+            .collect();
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|&index| match graph.node(index) {
+                Node::Package {
+                    package_id,
+                    features,
+                    kind,
+                } => SerializedNode::Package {
+                    package_id: package_id.to_spec(),
+                    name: package_id.name(),
+                    version: package_id.version().to_string(),
+                    features,
+                    target: kind,
+                },
+                Node::Feature { node_index, name } => SerializedNode::Feature {
+                    package: positions.get(node_index).copied(),
+                    name: *name,
+                },
+            })
+            .collect();
+        let edges = self
+            .edges
+            .iter()
+            .map(|&(from, to, kind)| SerializedEdge {
+                from,
+                to,
+                kind: match kind {
+                    EdgeKind::Dep(DepKind::Normal) => "normal",
+                    EdgeKind::Dep(DepKind::Build) => "build",
+                    EdgeKind::Dep(DepKind::Development) => "dev",
+                    EdgeKind::Feature => "feature",
+                },
+            })
+            .collect();
+        let output = SerializedGraph {
+            version: VERSION,
+            inverted,
+            roots: &self.roots,
+            nodes,
+            edges,
+        };
+        serde_json::to_string(&output).unwrap()
+    }
+}
+
+/// Version of the JSON output, increased on backwards incompatible changes.
+const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct SerializedGraph<'a> {
+    version: u32,
+    /// Whether edges point from dependencies to their dependents.
+    inverted: bool,
+    roots: &'a [usize],
+    nodes: Vec<SerializedNode<'a>>,
+    edges: Vec<SerializedEdge>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum SerializedNode<'a> {
+    Package {
+        package_id: PackageIdSpec,
+        name: InternedString,
+        version: String,
+        features: &'a [InternedString],
+        /// The platform the package is built for, `null` for the host.
+        target: &'a CompileKind,
+    },
+    Feature {
+        /// The node of the package the feature belongs to, if it is part of
+        /// the output.
+        package: Option<usize>,
+        name: InternedString,
+    },
+}
+
+#[derive(Serialize)]
+struct SerializedEdge {
+    from: usize,
+    to: usize,
+    kind: &'static str,
+}
@@ -3,7 +3,7 @@
 use self::format::Pattern;
 use crate::core::compiler::{CompileKind, RustcTargetData};
 use crate::core::dependency::DepKind;
-use crate::core::resolver::{features::CliFeatures, ForceAllTargets, HasDevUnits};
+use crate::core::resolver::{features::CliFeatures, ForceAllTargets, HasDevUnits, Resolve};
 use crate::core::{Package, PackageId, PackageIdSpec, PackageIdSpecQuery, Workspace};
 use crate::ops::{self, Packages};
 use crate::util::{CargoResult, GlobalContext};
@@ -13,8 +13,9 @@ use graph::Graph;
 use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
+mod export;
 mod format;
-mod graph;
+pub(crate) mod graph;
 
 pub use {graph::EdgeKind, graph::Node};
 
@@ -47,9 +48,11 @@ pub struct TreeOptions {
     pub max_display_depth: u32,
     /// Excludes proc-macro dependencies.
     pub no_proc_macro: bool,
+    /// How the graph is output.
+    pub output_format: OutputFormat,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub enum Target {
     Host,
     Specific(Vec<String>),
@@ -86,6 +89,29 @@ impl FromStr for Prefix {
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// An indented tree, see [`TreeOptions::prefix`].
+    Text,
+    /// A Graphviz DOT graph.
+    Dot,
+    /// A JSON object with the nodes and edges of the graph.
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<OutputFormat, &'static str> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "dot" => Ok(OutputFormat::Dot),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("invalid output format"),
+        }
+    }
+}
+
 struct Symbols {
     down: &'static str,
     tee: &'static str,
@@ -109,6 +135,65 @@ static ASCII_SYMBOLS: Symbols = Symbols {
 
 /// Entry point for the `cargo tree` command.
 pub fn build_and_print(ws: &Workspace<'_>, opts: &TreeOptions) -> CargoResult<()> {
+    with_graph(ws, opts, |resolve, specs, mut graph| {
+        let root_specs = if opts.invert.is_empty() {
+            specs
+        } else {
+            opts.invert
+                .iter()
+                .map(|p| PackageIdSpec::parse(p))
+                .collect::<Result<Vec<PackageIdSpec>, _>>()?
+        };
+        let root_ids = resolve.specs_to_ids(&root_specs)?;
+        let root_indexes = graph.indexes_from_ids(&root_ids);
+
+        let root_indexes = if opts.duplicates {
+            // `-d -p foo` will only show duplicates within foo's subtree
+            graph = graph.from_reachable(root_indexes.as_slice());
+            graph.find_duplicates()
+        } else {
+            root_indexes
+        };
+
+        if !opts.invert.is_empty() || opts.duplicates {
+            graph.invert();
+        }
+
+        // Packages to prune.
+        let pkgs_to_prune = opts
+            .pkgs_to_prune
+            .iter()
+            .map(|p| PackageIdSpec::parse(p).map_err(Into::into))
+            .map(|r| {
+                // Provide an error message if pkgid is not within the resolved
+                // dependencies graph.
+                r.and_then(|spec| spec.query(resolve.iter()).and(Ok(spec)))
+            })
+            .collect::<CargoResult<Vec<PackageIdSpec>>>()?;
+
+        if root_indexes.len() == 0 {
+            ws.gctx().shell().warn(
+                "nothing to print.\n\n\
+            To find dependencies that require specific target platforms, \
+            try to use option `--target all` first, and then narrow your search scope accordingly.",
+            )?;
+        } else if opts.output_format == OutputFormat::Text {
+            print(ws.gctx(), opts, root_indexes, &pkgs_to_prune, &graph)?;
+        } else {
+            export(ws.gctx(), opts, root_indexes, &pkgs_to_prune, &graph)?;
+        }
+        Ok(())
+    })
+}
+
+/// Resolves the workspace and builds the dependency graph described by `opts`,
+/// then calls `f` with the resolve, the specs of the selected packages and the
+/// graph.
+pub(crate) fn with_graph<T>(
+    ws: &Workspace<'_>,
+    opts: &TreeOptions,
+    f: impl FnOnce(&Resolve, Vec<PackageIdSpec>, Graph<'_>) -> CargoResult<T>,
+) -> CargoResult<T> {
     let requested_targets = match &opts.target {
         Target::All | Target::Host => Vec::new(),
         Target::Specific(t) => t.clone(),
@@ -147,7 +232,7 @@ pub fn build_and_print(ws: &Workspace<'_>, opts: &TreeOptions) -> CargoResult<()
         .map(|pkg| (pkg.package_id(), pkg))
         .collect();
 
-    let mut graph = graph::build(
+    let graph = graph::build(
         ws,
         &ws_resolve.targeted_resolve,
         &ws_resolve.resolved_features,
@@ -159,50 +244,32 @@ pub fn build_and_print(ws: &Workspace<'_>, opts: &TreeOptions) -> CargoResult<()
         opts,
     )?;
 
-    let root_specs = if opts.invert.is_empty() {
-        specs
-    } else {
-        opts.invert
-            .iter()
-            .map(|p| PackageIdSpec::parse(p))
-            .collect::<Result<Vec<PackageIdSpec>, _>>()?
-    };
-    let root_ids = ws_resolve.targeted_resolve.specs_to_ids(&root_specs)?;
-    let root_indexes = graph.indexes_from_ids(&root_ids);
+    f(&ws_resolve.targeted_resolve, specs, graph)
+}
 
-    let root_indexes = if opts.duplicates {
-        // `-d -p foo` will only show duplicates within foo's subtree
-        graph = graph.from_reachable(root_indexes.as_slice());
-        graph.find_duplicates()
-    } else {
-        root_indexes
+/// Prints the graph reachable from the given roots in one piece, see
+/// [`OutputFormat`].
+fn export(
+    gctx: &GlobalContext,
+    opts: &TreeOptions,
+    roots: Vec<usize>,
+    pkgs_to_prune: &[PackageIdSpec],
+    graph: &Graph<'_>,
+) -> CargoResult<()> {
+    let subgraph = export::Subgraph::new(graph, &roots, pkgs_to_prune, opts.max_display_depth);
+    let output = match opts.output_format {
+        OutputFormat::Dot => {
+            let format = Pattern::new(&opts.format)
+                .with_context(|| format!("tree format `{}` not valid", opts.format))?;
+            subgraph.to_dot(graph, &format)
+        }
+        OutputFormat::Json => {
+            let inverted = !opts.invert.is_empty() || opts.duplicates;
+            subgraph.to_json(graph, inverted)
+        }
+        OutputFormat::Text => unreachable!(),
     };
-
-    if !opts.invert.is_empty() || opts.duplicates {
-        graph.invert();
-    }
-
-    // Packages to prune.
-    let pkgs_to_prune = opts
-        .pkgs_to_prune
-        .iter()
-        .map(|p| PackageIdSpec::parse(p).map_err(Into::into))
-        .map(|r| {
-            // Provide an error message if pkgid is not within the resolved
-            // dependencies graph.
-            r.and_then(|spec| spec.query(ws_resolve.targeted_resolve.iter()).and(Ok(spec)))
-        })
-        .collect::<CargoResult<Vec<PackageIdSpec>>>()?;
-
-    if root_indexes.len() == 0 {
-        ws.gctx().shell().warn(
-            "nothing to print.\n\n\
-        To find dependencies that require specific target platforms, \
-        try to use option `--target all` first, and then narrow your search scope accordingly.",
-        )?;
-    } else {
-        print(ws.gctx(), opts, root_indexes, &pkgs_to_prune, &graph)?;
-    }
+    drop_println!(gctx, "{}", output);
     Ok(())
 }
 
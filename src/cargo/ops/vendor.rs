@@ -1,13 +1,14 @@
 use crate::core::shell::Verbosity;
-use crate::core::{GitReference, Package, Workspace};
+use crate::core::{GitReference, Package, PackageId, Workspace};
 use crate::ops;
 use crate::sources::path::PathSource;
+use crate::sources::source::MaybePackage;
 use crate::sources::CRATES_IO_REGISTRY;
 use crate::util::cache_lock::CacheLockMode;
 use crate::util::{try_canonicalize, CargoResult, GlobalContext};
 use anyhow::{bail, Context as _};
 use cargo_util::{paths, Sha256};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ffi::OsStr;
@@ -18,6 +19,15 @@ use std::path::{Path, PathBuf};
 pub struct VendorOptions<'a> {
     pub no_delete: bool,
     pub versioned_dirs: bool,
+    /// Only copies the crates that changed since the last run, recorded in
+    /// the vendor manifest, leaving the others untouched.
+    pub incremental: bool,
+    /// Vendors the crates from git repositories in a `git` subdirectory,
+    /// as a directory source of their own.
+    pub per_source: bool,
+    /// Checks the vendor directory against the lockfiles instead of
+    /// vendoring, without accessing the network.
+    pub verify: bool,
     pub destination: &'a Path,
     pub extra: Vec<PathBuf>,
 }
@@ -31,6 +41,9 @@ pub fn vendor(ws: &Workspace<'_>, opts: &VendorOptions<'_>) -> CargoResult<()> {
         extra_workspaces.push(ws);
     }
     let workspaces = extra_workspaces.iter().chain(Some(ws)).collect::<Vec<_>>();
+    if opts.verify {
+        return verify(gctx, &workspaces, opts);
+    }
     let _lock = gctx.acquire_package_cache_lock(CacheLockMode::MutateExclusive)?;
     let vendor_config = sync(gctx, &workspaces, opts).with_context(|| "failed to sync")?;
 
@@ -75,6 +88,66 @@ enum VendorSource {
     },
 }
 
+/// The name of the file recording what was vendored, see [`VendorManifest`].
+const MANIFEST_FILE: &str = ".vendor-manifest.json";
+
+/// The subdirectory of the crates from git repositories, with
+/// [`VendorOptions::per_source`]. Other crates named like it always get a
+/// version suffix.
+const GIT_DIR: &str = "git";
+
+/// The machine-readable record of the crates in a vendor directory, which
+/// incremental runs compare to the lockfiles to find what changed.
+#[derive(Default, Serialize, Deserialize)]
+struct VendorManifest {
+    version: u32,
+    packages: Vec<VendoredPackage>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VendoredPackage {
+    name: String,
+    version: String,
+    /// The source of the crate, as written in `Cargo.lock`.
+    source: String,
+    /// The checksum of the crate from `Cargo.lock`, if any.
+    checksum: Option<String>,
+    license: Option<String>,
+    license_file: Option<String>,
+    /// The directory of the crate, relative to the vendor directory and
+    /// using `/` as separator.
+    path: String,
+}
+
+impl VendoredPackage {
+    fn is(&self, id: PackageId, checksum: Option<&String>) -> bool {
+        self.name == id.name().as_str()
+            && self.version == id.version().to_string()
+            && self.source == id.source_id().as_encoded_url().to_string()
+            && self.checksum.as_ref() == checksum
+    }
+}
+
+fn read_manifest(dir: &Path) -> CargoResult<VendorManifest> {
+    let path = dir.join(MANIFEST_FILE);
+    if !path.exists() {
+        return Ok(VendorManifest::default());
+    }
+    let contents = paths::read(&path)?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse vendor manifest `{}`", path.display()))
+}
+
+/// Where a crate goes in the vendor directory.
+struct Placement {
+    /// The directory, relative to the vendor directory.
+    path: String,
+    dir_has_version_suffix: bool,
+    /// The directory of the crate in the vendor directory if it was vendored
+    /// before and didn't change, which may differ from `path`.
+    fresh: Option<String>,
+}
+
 fn sync(
     gctx: &GlobalContext,
     workspaces: &[&Workspace<'_>],
@@ -83,44 +156,47 @@ fn sync(
     let canonical_destination = try_canonicalize(opts.destination);
     let canonical_destination = canonical_destination.as_deref().unwrap_or(opts.destination);
     let dest_dir_already_exists = canonical_destination.exists();
+    let git_dir = canonical_destination.join(GIT_DIR);
 
     paths::create_dir_all(&canonical_destination)?;
+    let previous = if opts.incremental {
+        read_manifest(canonical_destination)?
+    } else {
+        VendorManifest::default()
+    };
     let mut to_remove = HashSet::new();
     if !opts.no_delete {
-        for entry in canonical_destination.read_dir()? {
-            let entry = entry?;
-            if !entry
-                .file_name()
-                .to_str()
-                .map_or(false, |s| s.starts_with('.'))
-            {
-                to_remove.insert(entry.path());
+        let mut dirs = vec![canonical_destination.to_path_buf()];
+        if opts.per_source && git_dir.is_dir() {
+            dirs.push(git_dir.clone());
+        }
+        for dir in dirs {
+            for entry in dir.read_dir()? {
+                let entry = entry?;
+                if !entry
+                    .file_name()
+                    .to_str()
+                    .map_or(false, |s| s.starts_with('.'))
+                {
+                    to_remove.insert(entry.path());
+                }
             }
         }
     }
 
-    // First up attempt to work around rust-lang/cargo#5956. Apparently build
-    // artifacts sprout up in Cargo's global cache for whatever reason, although
-    // it's unsure what tool is causing these issues at this time. For now we
-    // apply a heavy-hammer approach which is to delete Cargo's unpacked version
-    // of each crate to start off with. After we do this we'll re-resolve and
-    // redownload again, which should trigger Cargo to re-extract all the
-    // crates.
-    //
-    // Note that errors are largely ignored here as this is a best-effort
-    // attempt. If anything fails here we basically just move on to the next
-    // crate to work with.
-    for ws in workspaces {
-        let (packages, resolve) =
-            ops::resolve_ws(ws).with_context(|| "failed to load pkg lockfile")?;
-
-        packages
-            .get_many(resolve.iter())
-            .with_context(|| "failed to download packages")?;
+    let resolved = workspaces
+        .iter()
+        .map(|ws| ops::resolve_ws(ws).with_context(|| "failed to load pkg lockfile"))
+        .collect::<CargoResult<Vec<_>>>()?;
 
+    // Plan where each crate goes before downloading anything, so that the
+    // crates that didn't change can be skipped in incremental mode.
+    let mut checksums = HashMap::new();
+    let mut ids = BTreeSet::new();
+    for (_, resolve) in &resolved {
         for pkg in resolve.iter() {
-            // Don't delete actual source code!
             if pkg.source_id().is_path() {
+                // Don't delete actual source code!
                 if let Ok(path) = pkg.source_id().url().to_file_path() {
                     if let Ok(path) = try_canonicalize(path) {
                         to_remove.remove(&path);
@@ -128,49 +204,18 @@ fn sync(
                 }
                 continue;
             }
-            if pkg.source_id().is_git() {
-                continue;
-            }
-            if let Ok(pkg) = packages.get_one(pkg) {
-                drop(fs::remove_dir_all(pkg.root()));
-            }
-        }
-    }
-
-    let mut checksums = HashMap::new();
-    let mut ids = BTreeMap::new();
-
-    // Next up let's actually download all crates and start storing internal
-    // tables about them.
-    for ws in workspaces {
-        let (packages, resolve) =
-            ops::resolve_ws(ws).with_context(|| "failed to load pkg lockfile")?;
-
-        packages
-            .get_many(resolve.iter())
-            .with_context(|| "failed to download packages")?;
-
-        for pkg in resolve.iter() {
-            // No need to vendor path crates since they're already in the
-            // repository
-            if pkg.source_id().is_path() {
-                continue;
-            }
-            ids.insert(
-                pkg,
-                packages
-                    .get_one(pkg)
-                    .with_context(|| "failed to fetch package")?
-                    .clone(),
-            );
-
-            checksums.insert(pkg, resolve.checksums().get(&pkg).cloned());
+            ids.insert(pkg);
+            checksums.insert(pkg, resolve.checksums().get(&pkg).cloned().flatten());
         }
     }
+    let in_git_dir = |id: PackageId| opts.per_source && id.source_id().is_git();
 
     let mut versions = HashMap::new();
-    for id in ids.keys() {
-        let map = versions.entry(id.name()).or_insert_with(BTreeMap::default);
+    for id in ids.iter() {
+        // Crates in different directory sources may have the same version.
+        let map = versions
+            .entry((in_git_dir(*id), id.name()))
+            .or_insert_with(BTreeMap::default);
         if let Some(prev) = map.get(&id.version()) {
             bail!(
                 "found duplicate version of package `{} v{}` \
@@ -187,13 +232,17 @@ fn sync(
         map.insert(id.version(), id.source_id());
     }
 
-    let mut sources = BTreeSet::new();
-    let mut tmp_buf = [0; 64 * 1024];
-    for (id, pkg) in ids.iter() {
-        // Next up, copy it to the vendor directory
-        let src = pkg.root();
-        let max_version = *versions[&id.name()].iter().rev().next().unwrap().0;
-        let dir_has_version_suffix = opts.versioned_dirs || id.version() != max_version;
+    let mut placements = BTreeMap::new();
+    for id in ids.iter().copied() {
+        let max_version = *versions[&(in_git_dir(id), id.name())]
+            .iter()
+            .rev()
+            .next()
+            .unwrap()
+            .0;
+        let dir_has_version_suffix = opts.versioned_dirs
+            || id.version() != max_version
+            || (opts.per_source && !in_git_dir(id) && id.name() == GIT_DIR);
         let dst_name = if dir_has_version_suffix {
             // Eg vendor/futures-0.1.13
             format!("{}-{}", id.name(), id.version())
@@ -201,12 +250,155 @@ fn sync(
             // Eg vendor/futures
             id.name().to_string()
         };
+        let path = if in_git_dir(id) {
+            format!("{GIT_DIR}/{dst_name}")
+        } else {
+            dst_name
+        };
+        let checksum = checksums[&id].as_ref();
+        let has_checksum_file = |path: &str| {
+            canonical_destination
+                .join(path)
+                .join(".cargo-checksum.json")
+                .exists()
+        };
+        let fresh = if !opts.incremental {
+            None
+        } else if let Some(prev) = previous
+            .packages
+            .iter()
+            .find(|p| p.is(id, checksum) && has_checksum_file(&p.path))
+        {
+            Some(prev.path.clone())
+        } else if let Some(checksum) = checksum {
+            // Crates vendored before the manifest existed can still be
+            // recognized from their checksum.
+            let cksum = canonical_destination
+                .join(&path)
+                .join(".cargo-checksum.json");
+            paths::read(&cksum)
+                .ok()
+                .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                .filter(|json| json["package"] == checksum.as_str())
+                .map(|_| path.clone())
+        } else {
+            None
+        };
+        placements.insert(
+            id,
+            Placement {
+                path,
+                dir_has_version_suffix,
+                fresh,
+            },
+        );
+    }
+
+    let needs_copy = |id: &PackageId| placements.get(id).map_or(false, |p| p.fresh.is_none());
+    let mut pkgs = BTreeMap::new();
+
+    // Next up let's actually download all crates and start storing internal
+    // tables about them.
+    for (packages, resolve) in &resolved {
+        let to_copy: Vec<_> = resolve.iter().filter(needs_copy).collect();
+        packages
+            .get_many(to_copy.iter().copied())
+            .with_context(|| "failed to download packages")?;
+
+        for id in to_copy {
+            let pkg = packages
+                .get_one(id)
+                .with_context(|| "failed to fetch package")?;
+            // Work around rust-lang/cargo#5956. Apparently build artifacts
+            // sprout up in Cargo's global cache for whatever reason, although
+            // it's unsure what tool is causing these issues at this time. For
+            // now we apply a heavy-hammer approach which is to delete Cargo's
+            // unpacked version of each crate, and unpack the downloaded
+            // `.crate` file again.
+            if !id.source_id().is_git() {
+                drop(fs::remove_dir_all(pkg.root()));
+                let mut sources = packages.sources_mut();
+                let source = sources.get_mut(id.source_id()).unwrap();
+                if let MaybePackage::Download { .. } = source.download(id)? {
+                    bail!("failed to unpack `{id}` again");
+                }
+            }
+            pkgs.insert(id, pkg.clone());
+        }
+    }
+
+    // Move the crates that didn't change but whose directory did, through
+    // temporary names in case two of them swap directories.
+    let moves: Vec<_> = placements
+        .values()
+        .filter_map(|p| Some((p.fresh.as_deref()?, p.path.as_str())))
+        .filter(|(from, to)| from != to)
+        .collect();
+    for (i, (from, _)) in moves.iter().enumerate() {
+        let tmp = canonical_destination.join(format!(".vendor-move-{i}"));
+        paths::remove_dir_all(&tmp).ok();
+        fs::rename(canonical_destination.join(from), &tmp)
+            .with_context(|| format!("failed to move vendored crate `{from}`"))?;
+    }
+    for (i, (_, to)) in moves.iter().enumerate() {
+        let tmp = canonical_destination.join(format!(".vendor-move-{i}"));
+        let dst = canonical_destination.join(to);
+        paths::create_dir_all(dst.parent().unwrap())?;
+        if dst.exists() {
+            paths::remove_dir_all(&dst)?;
+        }
+        fs::rename(&tmp, &dst)
+            .with_context(|| format!("failed to move vendored crate to `{to}`"))?;
+    }
 
+    let mut sources = BTreeSet::new();
+    let mut manifest = VendorManifest {
+        version: 1,
+        packages: Vec::new(),
+    };
+    let mut tmp_buf = [0; 64 * 1024];
+    for (id, placement) in placements.iter() {
         sources.insert(id.source_id());
-        let dst = canonical_destination.join(&dst_name);
+        let dst = canonical_destination.join(&placement.path);
         to_remove.remove(&dst);
+        if let Some(parent) = dst.parent() {
+            to_remove.remove(parent);
+        }
+
+        let (license, license_file) = match (pkgs.get(id), &placement.fresh) {
+            (Some(pkg), _) => {
+                let metadata = pkg.manifest().metadata();
+                (metadata.license.clone(), metadata.license_file.clone())
+            }
+            (None, Some(_)) => {
+                let package = read_vendored_package(&dst)?;
+                let field = |key: &str| {
+                    package
+                        .get(key)
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                };
+                (field("license"), field("license-file"))
+            }
+            (None, None) => unreachable!("`{id}` was not downloaded"),
+        };
+        manifest.packages.push(VendoredPackage {
+            name: id.name().to_string(),
+            version: id.version().to_string(),
+            source: id.source_id().as_encoded_url().to_string(),
+            checksum: checksums[id].clone(),
+            license,
+            license_file,
+            path: placement.path.clone(),
+        });
+
+        let Some(pkg) = pkgs.get(id) else {
+            continue;
+        };
+        // Next up, copy it to the vendor directory
+        let src = pkg.root();
         let cksum = dst.join(".cargo-checksum.json");
-        if dir_has_version_suffix && cksum.exists() {
+        if placement.dir_has_version_suffix && cksum.exists() && !opts.incremental {
             // Always re-copy directory without version suffix in case the version changed
             continue;
         }
@@ -232,18 +424,30 @@ fn sync(
         paths::write(&cksum, json.to_string())?;
     }
 
+    let mut to_remove: Vec<_> = to_remove.into_iter().collect();
+    to_remove.sort();
     for path in to_remove {
+        if opts.incremental && path.join(".cargo-checksum.json").exists() {
+            gctx.shell().status("Removing", path.display())?;
+        }
         if path.is_dir() {
             paths::remove_dir_all(&path)?;
-        } else {
+        } else if path.exists() {
             paths::remove_file(&path)?;
         }
     }
 
+    let manifest_path = canonical_destination.join(MANIFEST_FILE);
+    if opts.incremental || manifest_path.exists() {
+        let json = serde_json::to_string_pretty(&manifest)?;
+        paths::write(&manifest_path, format!("{json}\n"))?;
+    }
+
     // add our vendored source
     let mut config = BTreeMap::new();
 
     let merged_source_name = "vendored-sources";
+    let git_source_name = "vendored-git-sources";
 
     // replace original sources with vendor
     for source_id in sources {
@@ -278,12 +482,17 @@ fn sync(
                     GitReference::DefaultBranch => {}
                 }
             }
+            let replace_with = if opts.per_source {
+                git_source_name
+            } else {
+                merged_source_name
+            };
             VendorSource::Git {
                 git: source_id.url().to_string(),
                 branch,
                 tag,
                 rev,
-                replace_with: merged_source_name.to_string(),
+                replace_with: replace_with.to_string(),
             }
         } else {
             panic!("Invalid source ID: {}", source_id)
@@ -292,15 +501,32 @@ fn sync(
     }
 
     if !config.is_empty() {
-        config.insert(
-            merged_source_name.to_string(),
-            VendorSource::Directory {
-                // Windows-flavour paths are valid here on Windows but Unix.
-                // This backslash normalization is for making output paths more
-                // cross-platform compatible.
-                directory: opts.destination.to_string_lossy().replace("\\", "/"),
-            },
-        );
+        // Windows-flavour paths are valid here on Windows but Unix.
+        // This backslash normalization is for making output paths more
+        // cross-platform compatible.
+        let directory = opts.destination.to_string_lossy().replace("\\", "/");
+        let uses = |name: &str| {
+            config.values().any(|source| match source {
+                VendorSource::Registry { replace_with, .. }
+                | VendorSource::Git { replace_with, .. } => replace_with == name,
+                VendorSource::Directory { .. } => false,
+            })
+        };
+        let (uses_git_source, uses_merged_source) =
+            (uses(git_source_name), uses(merged_source_name));
+        if uses_git_source {
+            let directory = format!("{}/{GIT_DIR}", directory.trim_end_matches('/'));
+            config.insert(
+                git_source_name.to_string(),
+                VendorSource::Directory { directory },
+            );
+        }
+        if uses_merged_source {
+            config.insert(
+                merged_source_name.to_string(),
+                VendorSource::Directory { directory },
+            );
+        }
     } else if !dest_dir_already_exists {
         // Nothing to vendor. Remove the destination dir we've just created.
         paths::remove_dir(canonical_destination)?;
@@ -309,6 +535,157 @@ fn sync(
     Ok(VendorConfig { source: config })
 }
 
+/// Checks that the vendor directory has the crates of the lockfiles, with
+/// the checksums they record, and that their files weren't modified. This
+/// only reads the lockfiles, so it works offline.
+fn verify(
+    gctx: &GlobalContext,
+    workspaces: &[&Workspace<'_>],
+    opts: &VendorOptions<'_>,
+) -> CargoResult<()> {
+    #[derive(Deserialize)]
+    struct ChecksumFile {
+        package: Option<String>,
+        files: BTreeMap<String, String>,
+    }
+
+    let destination = opts.destination;
+    if !destination.is_dir() {
+        bail!(
+            "vendor directory `{}` does not exist",
+            destination.display()
+        );
+    }
+
+    // The vendored crates, by name and version, from both the vendor
+    // directory and its `git` subdirectory.
+    let mut vendored = BTreeMap::new();
+    for dir in [destination.to_path_buf(), destination.join(GIT_DIR)] {
+        if !dir.is_dir() {
+            continue;
+        }
+        for entry in dir.read_dir()? {
+            let path = entry?.path();
+            let manifest = path.join("Cargo.toml");
+            if !manifest.exists() {
+                continue;
+            }
+            let package = read_vendored_package(&path)?;
+            let field = |key: &str| {
+                package
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            vendored
+                .entry((field("name"), field("version")))
+                .or_insert_with(Vec::new)
+                .push(path);
+        }
+    }
+
+    let mut problems = Vec::new();
+    let mut locked = BTreeSet::new();
+    for ws in workspaces {
+        let Some(resolve) = ops::load_pkg_lockfile(ws)? else {
+            bail!(
+                "no `Cargo.lock` found for `{}`, \
+                 run `cargo generate-lockfile` to create one",
+                ws.root_manifest().display()
+            );
+        };
+        for id in resolve.iter() {
+            if id.source_id().is_path() || !locked.insert(id) {
+                continue;
+            }
+            let key = (id.name().to_string(), id.version().to_string());
+            let Some(dirs) = vendored.get(&key) else {
+                problems.push(format!("`{id}` is not vendored"));
+                continue;
+            };
+            let lock_checksum = resolve.checksums().get(&id).cloned().flatten();
+            let mut found = false;
+            let mut errors = Vec::new();
+            for dir in dirs {
+                let cksum_path = dir.join(".cargo-checksum.json");
+                let cksum: ChecksumFile = match paths::read(&cksum_path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|s| Ok(serde_json::from_str(&s)?))
+                {
+                    Ok(cksum) => cksum,
+                    Err(_) => {
+                        errors.push(format!("`{}` is missing or invalid", cksum_path.display()));
+                        continue;
+                    }
+                };
+                if lock_checksum.is_some() && cksum.package != lock_checksum {
+                    // May be the same version from another source.
+                    continue;
+                }
+                found = true;
+                for (file, expected) in &cksum.files {
+                    let path = dir.join(file);
+                    let actual = Sha256::new().update_path(&path).map(|s| s.finish_hex());
+                    match actual {
+                        Ok(actual) if actual == *expected => {}
+                        Ok(_) => errors.push(format!("`{}` was modified", path.display())),
+                        Err(_) => errors.push(format!("`{}` is missing", path.display())),
+                    }
+                }
+                break;
+            }
+            if !found && errors.is_empty() {
+                problems.push(format!(
+                    "the checksum of the vendored `{id}` doesn't match `Cargo.lock`"
+                ));
+            }
+            problems.extend(errors);
+        }
+    }
+
+    for (name, version) in vendored.keys() {
+        if !locked
+            .iter()
+            .any(|id| id.name().as_str() == name && id.version().to_string() == *version)
+        {
+            gctx.shell().warn(format!(
+                "`{name} v{version}` is vendored but not in `Cargo.lock`"
+            ))?;
+        }
+    }
+
+    if !problems.is_empty() {
+        let mut msg = format!(
+            "vendor directory `{}` doesn't match `Cargo.lock`:",
+            destination.display()
+        );
+        for problem in problems {
+            msg.push_str(&format!("\n  {problem}"));
+        }
+        bail!("{msg}\nhelp: run `cargo vendor` to update it");
+    }
+    gctx.shell().status(
+        "Verified",
+        format!(
+            "{} vendored packages in `{}`",
+            locked.len(),
+            destination.display()
+        ),
+    )
+}
+
+/// Reads the `[package]` table of the crate vendored in `dir`.
+fn read_vendored_package(dir: &Path) -> CargoResult<toml::Table> {
+    let manifest = dir.join("Cargo.toml");
+    let mut toml: toml::Table = toml::from_str(&paths::read(&manifest)?)
+        .with_context(|| format!("failed to parse `{}`", manifest.display()))?;
+    match toml.remove("package") {
+        Some(toml::Value::Table(package)) => Ok(package),
+        _ => Ok(toml::Table::new()),
+    }
+}
+
 fn cp_sources(
     pkg: &Package,
     src: &Path,
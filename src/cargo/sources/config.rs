@@ -6,7 +6,7 @@
 
 use crate::core::{GitReference, PackageId, SourceId};
 use crate::sources::source::Source;
-use crate::sources::{ReplacedSource, CRATES_IO_REGISTRY};
+use crate::sources::{OverlaySource, ReplacedSource, CRATES_IO_REGISTRY};
 use crate::util::context::{self, ConfigRelativePath, OptValue};
 use crate::util::errors::CargoResult;
 use crate::util::{GlobalContext, IntoUrl};
@@ -24,6 +24,8 @@ pub struct SourceConfigMap<'gctx> {
     cfgs: HashMap<String, SourceConfig>,
     /// Mapping of [`SourceId`] to the source name.
     id2name: HashMap<SourceId, String>,
+    /// Mapping of [`SourceId`] to the local registry overlaid on top of it.
+    overlays: HashMap<SourceId, SourceId>,
     gctx: &'gctx GlobalContext,
 }
 
@@ -90,6 +92,7 @@ impl<'gctx> SourceConfigMap<'gctx> {
         let mut base = SourceConfigMap {
             cfgs: HashMap::new(),
             id2name: HashMap::new(),
+            overlays: HashMap::new(),
             gctx,
         };
         base.add(
@@ -125,6 +128,12 @@ impl<'gctx> SourceConfigMap<'gctx> {
         self.gctx
     }
 
+    /// Overlays the local registry `overlay` on top of the source `id`, see
+    /// [`OverlaySource`].
+    pub fn add_overlay(&mut self, id: SourceId, overlay: SourceId) {
+        self.overlays.insert(id, overlay);
+    }
+
     /// Gets the [`Source`] for a given [`SourceId`].
     ///
     /// * `yanked_whitelist` --- Packages allowed to be used, even if they are yanked.
@@ -132,6 +141,26 @@ impl<'gctx> SourceConfigMap<'gctx> {
         &self,
         id: SourceId,
         yanked_whitelist: &HashSet<PackageId>,
+    ) -> CargoResult<Box<dyn Source + 'gctx>> {
+        let src = self.load_without_overlay(id, yanked_whitelist)?;
+        let Some(&overlay_id) = self.overlays.get(&id) else {
+            return Ok(src);
+        };
+        debug!("overlaying {} on top of {}", overlay_id, id);
+        let overlay = overlay_id.load(
+            self.gctx,
+            &yanked_whitelist
+                .iter()
+                .map(|p| p.map_source(id, overlay_id))
+                .collect(),
+        )?;
+        Ok(Box::new(OverlaySource::new(overlay, src)))
+    }
+
+    fn load_without_overlay(
+        &self,
+        id: SourceId,
+        yanked_whitelist: &HashSet<PackageId>,
     ) -> CargoResult<Box<dyn Source + 'gctx>> {
         debug!("loading: {}", id);
 
@@ -20,6 +20,8 @@
 //!   filesystem.
 //! * [`ReplacedSource`] --- This manages the [source replacement] feature,
 //!   redirecting operations on the original source to the replacement.
+//! * [`OverlaySource`] --- This puts the packages of a local registry on top
+//!   of another source, for verifying packages before they are published.
 //!
 //! This module also contains [`SourceConfigMap`], which is effectively the
 //! representation of the `[source.*]` value in Cargo configuration.
@@ -29,6 +31,7 @@
 pub use self::config::SourceConfigMap;
 pub use self::directory::DirectorySource;
 pub use self::git::GitSource;
+pub use self::overlay::OverlaySource;
 pub use self::path::PathSource;
 pub use self::registry::{
     IndexSummary, RegistrySource, CRATES_IO_DOMAIN, CRATES_IO_INDEX, CRATES_IO_REGISTRY,
@@ -38,6 +41,7 @@ pub use self::replaced::ReplacedSource;
 pub mod config;
 pub mod directory;
 pub mod git;
+pub mod overlay;
 pub mod path;
 pub mod registry;
 pub mod replaced;
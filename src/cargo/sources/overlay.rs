@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+use std::task::{ready, Poll};
+
+use crate::core::{Dependency, Package, PackageId, SourceId};
+use crate::sources::source::MaybePackage;
+use crate::sources::source::QueryKind;
+use crate::sources::source::Source;
+use crate::sources::IndexSummary;
+use crate::util::errors::CargoResult;
+
+/// A source that puts the packages of a local registry on top of another
+/// source, as if they had been published to it.
+///
+/// This is used by `cargo package` to verify packages depending on other
+/// packages being packaged along with them: the freshly created `.crate`
+/// files are added to a temporary local registry, which is overlaid on top
+/// of the registry they will be published to.
+///
+/// A version in the local registry shadows the same version in the other
+/// source, so that what is verified is what is going to be published.
+pub struct OverlaySource<'gctx> {
+    /// The local registry, whose packages take precedence.
+    local: Box<dyn Source + 'gctx>,
+    /// The source being overlaid.
+    remote: Box<dyn Source + 'gctx>,
+    /// The packages returned by queries that come from `local`, with the
+    /// source id of `remote`.
+    local_packages: HashSet<PackageId>,
+}
+
+impl<'gctx> OverlaySource<'gctx> {
+    pub fn new(
+        local: Box<dyn Source + 'gctx>,
+        remote: Box<dyn Source + 'gctx>,
+    ) -> OverlaySource<'gctx> {
+        OverlaySource {
+            local,
+            remote,
+            local_packages: HashSet::new(),
+        }
+    }
+
+    /// Maps `id` to the local registry, if the package comes from it.
+    fn local_id(&self, id: PackageId) -> Option<PackageId> {
+        self.local_packages
+            .contains(&id)
+            .then(|| id.with_source_id(self.local.source_id()))
+    }
+}
+
+impl<'gctx> Source for OverlaySource<'gctx> {
+    fn source_id(&self) -> SourceId {
+        self.remote.source_id()
+    }
+
+    fn replaced_source_id(&self) -> SourceId {
+        self.remote.replaced_source_id()
+    }
+
+    fn supports_checksums(&self) -> bool {
+        self.remote.supports_checksums()
+    }
+
+    fn requires_precise(&self) -> bool {
+        self.remote.requires_precise()
+    }
+
+    fn query(
+        &mut self,
+        dep: &Dependency,
+        kind: QueryKind,
+        f: &mut dyn FnMut(IndexSummary),
+    ) -> Poll<CargoResult<()>> {
+        let local_source = self.local.source_id();
+        let remote_source = self.remote.source_id();
+
+        let local_dep = dep.clone().map_source(remote_source, local_source);
+        let mut shadowed = HashSet::new();
+        let local = self.local.query(&local_dep, kind, &mut |summary| {
+            let summary = summary.map_summary(|s| s.map_source(local_source, remote_source));
+            let id = summary.package_id();
+            shadowed.insert((id.name(), id.version().clone()));
+            self.local_packages.insert(id);
+            f(summary)
+        })?;
+
+        let remote = self.remote.query(dep, kind, &mut |summary| {
+            let id = summary.package_id();
+            if !shadowed.contains(&(id.name(), id.version().clone())) {
+                f(summary)
+            }
+        })?;
+
+        ready!(local);
+        ready!(remote);
+        Poll::Ready(Ok(()))
+    }
+
+    fn invalidate_cache(&mut self) {
+        self.local.invalidate_cache();
+        self.remote.invalidate_cache();
+    }
+
+    fn set_quiet(&mut self, quiet: bool) {
+        self.local.set_quiet(quiet);
+        self.remote.set_quiet(quiet);
+    }
+
+    fn download(&mut self, id: PackageId) -> CargoResult<MaybePackage> {
+        let Some(local_id) = self.local_id(id) else {
+            return self.remote.download(id);
+        };
+        let (local_source, remote_source) = (self.local.source_id(), self.remote.source_id());
+        Ok(match self.local.download(local_id)? {
+            MaybePackage::Ready(pkg) => {
+                MaybePackage::Ready(pkg.map_source(local_source, remote_source))
+            }
+            other @ MaybePackage::Download { .. } => other,
+        })
+    }
+
+    fn finish_download(&mut self, id: PackageId, data: Vec<u8>) -> CargoResult<Package> {
+        let Some(local_id) = self.local_id(id) else {
+            return self.remote.finish_download(id, data);
+        };
+        let pkg = self.local.finish_download(local_id, data)?;
+        Ok(pkg.map_source(self.local.source_id(), self.remote.source_id()))
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        if self.local_packages.contains(&pkg.package_id()) {
+            self.local.fingerprint(pkg)
+        } else {
+            self.remote.fingerprint(pkg)
+        }
+    }
+
+    fn verify(&self, id: PackageId) -> CargoResult<()> {
+        match self.local_id(id) {
+            Some(local_id) => self.local.verify(local_id),
+            None => self.remote.verify(id),
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.remote.describe()
+    }
+
+    fn is_replaced(&self) -> bool {
+        self.remote.is_replaced()
+    }
+
+    fn add_to_yanked_whitelist(&mut self, pkgs: &[PackageId]) {
+        let local_source = self.local.source_id();
+        let local_pkgs = pkgs
+            .iter()
+            .map(|id| id.with_source_id(local_source))
+            .collect::<Vec<_>>();
+        self.local.add_to_yanked_whitelist(&local_pkgs);
+        self.remote.add_to_yanked_whitelist(pkgs);
+    }
+
+    fn is_yanked(&mut self, id: PackageId) -> Poll<CargoResult<bool>> {
+        match self.local_id(id) {
+            Some(local_id) => self.local.is_yanked(local_id),
+            None => self.remote.is_yanked(id),
+        }
+    }
+
+    fn block_until_ready(&mut self) -> CargoResult<()> {
+        self.local.block_until_ready()?;
+        self.remote.block_until_ready()
+    }
+}
@@ -754,6 +754,7 @@ impl<'gctx> Source for PathSource<'gctx> {
                 QueryKind::Exact => dep.matches(s),
                 QueryKind::Alternatives => true,
                 QueryKind::Normalized => dep.matches(s),
+                QueryKind::RejectedVersions => false,
             };
             if matched {
                 f(IndexSummary::Candidate(s.clone()))
@@ -314,11 +314,21 @@ impl<'gctx> HttpRegistry<'gctx> {
             let mut handle = self.multi.remove(handle)?;
             let data = download.data.take();
             let url = self.full_url(&download.path);
+            let is_file = url.starts_with("file:");
             let result = match download.retry.r#try(|| {
+                // A `file://` index reports missing files as errors, and has
+                // no status code for the others.
+                match result {
+                    Err(e) if is_file && e.is_file_couldnt_read_file() => {
+                        return Ok((Vec::new(), StatusCode::NotFound));
+                    }
+                    _ => {}
+                }
                 result.with_context(|| format!("failed to download from `{}`", url))?;
                 let code = handle.response_code()?;
                 // Keep this list of expected status codes in sync with the codes handled in `load`
                 let code = match code {
+                    0 if is_file => StatusCode::Success,
                     200 => StatusCode::Success,
                     304 => StatusCode::NotModified,
                     401 => StatusCode::Unauthorized,
@@ -194,6 +194,7 @@ use std::task::{ready, Poll};
 
 use anyhow::Context as _;
 use cargo_util::paths::{self, exclude_from_backups_and_indexing};
+use cargo_util::registry::make_dep_path;
 use flate2::read::GzDecoder;
 use serde::Deserialize;
 use serde::Serialize;
@@ -210,7 +211,7 @@ use crate::sources::PathSource;
 use crate::util::cache_lock::CacheLockMode;
 use crate::util::interning::InternedString;
 use crate::util::network::PollExt;
-use crate::util::{hex, VersionExt};
+use crate::util::{hex, internal, VersionExt};
 use crate::util::{restricted_names, CargoResult, Filesystem, GlobalContext, LimitErrorReader};
 
 /// The `.cargo-ok` file is used to track if the source is already unpacked.
@@ -452,7 +453,7 @@ mod remote;
 
 /// Generates a unique name for [`SourceId`] to have a unique path to put their
 /// index files.
-fn short_name(id: SourceId, is_shallow: bool) -> String {
+pub(crate) fn short_name(id: SourceId, is_shallow: bool) -> String {
     // CAUTION: This should not change between versions. If you change how
     // this is computed, it will orphan previously cached data, forcing the
     // cache to be rebuilt and potentially wasting significant disk space. If
@@ -548,6 +549,39 @@ impl<'gctx> RegistrySource<'gctx> {
         self.ops.config()
     }
 
+    /// Loads the index file of the package `name` as the registry serves it,
+    /// bypassing the index cache.
+    ///
+    /// Returns `None` if the registry doesn't have the package. This is
+    /// used by `cargo registry mirror` to copy the index entries verbatim.
+    pub fn load_index_file(&mut self, name: &str) -> Poll<CargoResult<Option<Vec<u8>>>> {
+        self.ops.prepare()?;
+        let root = self
+            .ops
+            .assert_index_locked(self.ops.index_path())
+            .to_path_buf();
+        let path = make_dep_path(&name.to_lowercase(), false);
+        match ready!(self.ops.load(&root, path.as_ref(), None)?) {
+            LoadResponse::Data { raw_data, .. } => Poll::Ready(Ok(Some(raw_data))),
+            LoadResponse::NotFound => Poll::Ready(Ok(None)),
+            LoadResponse::CacheValid => {
+                Poll::Ready(Err(internal("no cached index file was given to validate")))
+            }
+        }
+    }
+
+    /// Opens the `.crate` file of `package` if it was already downloaded.
+    pub fn downloaded_crate(
+        &mut self,
+        package: PackageId,
+        checksum: &str,
+    ) -> CargoResult<Option<File>> {
+        Ok(match self.ops.download(package, checksum)? {
+            MaybeLock::Ready(file) => Some(file),
+            MaybeLock::Download { .. } => None,
+        })
+    }
+
     /// Unpacks a downloaded package into a location where it's ready to be
     /// compiled.
     ///
@@ -797,7 +831,7 @@ impl<'gctx> Source for RegistrySource<'gctx> {
                 .index
                 .query_inner(dep.package_name(), &req, &mut *self.ops, &mut |s| {
                     let matched = match kind {
-                        QueryKind::Exact => {
+                        QueryKind::Exact | QueryKind::RejectedVersions => {
                             if req.is_precise() && self.gctx.cli_unstable().unstable_options {
                                 dep.matches_prerelease(s.as_summary())
                             } else {
@@ -810,6 +844,12 @@ impl<'gctx> Source for RegistrySource<'gctx> {
                     if !matched {
                         return;
                     }
+                    if kind == QueryKind::RejectedVersions {
+                        if s.is_yanked() {
+                            callback(s);
+                        }
+                        return;
+                    }
                     // Next filter out all yanked packages. Some yanked packages may
                     // leak through if they're in a whitelist (aka if they were
                     // previously in `Cargo.lock`
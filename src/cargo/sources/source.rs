@@ -187,6 +187,10 @@ pub enum QueryKind {
     /// Match a denpendency in all ways and will normalize the package name.
     /// Each source defines what normalizing means.
     Normalized,
+    /// A query for the packages exactly matching the given dependency
+    /// requirement that an [`QueryKind::Exact`] query rejects, like yanked
+    /// ones.
+    RejectedVersions,
 }
 
 /// A download status that represents if a [`Package`] has already been
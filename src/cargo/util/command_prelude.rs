@@ -307,6 +307,16 @@ pub trait CommandExt: Sized {
         )
     }
 
+    fn arg_explain_rebuild(self) -> Self {
+        self._arg(
+            flag(
+                "explain-rebuild",
+                "Explain why each unit was rebuilt (unstable)",
+            )
+            .help_heading(heading::COMPILATION_OPTIONS),
+        )
+    }
+
     fn arg_new_opts(self) -> Self {
         self._arg(
             opt(
@@ -713,6 +723,9 @@ Run `{cmd}` to see possible targets."
         build_config.requested_profile = self.get_profile_name(gctx, "dev", profile_checking)?;
         build_config.build_plan = self.flag("build-plan");
         build_config.unit_graph = self.flag("unit-graph");
+        build_config.explain_rebuild = self.flag("explain-rebuild");
+        build_config.build_events = build_config.emit_json() && gctx.cli_unstable().build_events;
+        gctx.shell().set_build_events(build_config.build_events);
         build_config.future_incompat_report = self.flag("future-incompat-report");
 
         if self._contains("timings") {
@@ -748,6 +761,10 @@ Run `{cmd}` to see possible targets."
             gctx.cli_unstable()
                 .fail_if_stable_opt("--unit-graph", 8002)?;
         }
+        if build_config.explain_rebuild {
+            gctx.cli_unstable()
+                .fail_if_stable_opt("--explain-rebuild", None)?;
+        }
 
         let opts = CompileOptions {
             build_config,
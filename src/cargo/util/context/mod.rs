@@ -108,6 +108,11 @@ pub use path::{ConfigRelativePath, PathAndArgs};
 mod target;
 pub use target::{TargetCfgConfig, TargetConfig};
 
+mod schema;
+pub use schema::UnknownKey;
+
+mod when;
+
 mod environment;
 use environment::Env;
 
@@ -224,6 +229,7 @@ pub struct GlobalContext {
     future_incompat_config: LazyCell<CargoFutureIncompatConfig>,
     net_config: LazyCell<CargoNetConfig>,
     build_config: LazyCell<CargoBuildConfig>,
+    build_scripts_config: LazyCell<CargoBuildScriptsConfig>,
     target_cfgs: LazyCell<Vec<(String, TargetCfgConfig)>>,
     doc_extern_map: LazyCell<RustdocExternMap>,
     progress_config: ProgressConfig,
@@ -318,6 +324,7 @@ impl GlobalContext {
             future_incompat_config: LazyCell::new(),
             net_config: LazyCell::new(),
             build_config: LazyCell::new(),
+            build_scripts_config: LazyCell::new(),
             target_cfgs: LazyCell::new(),
             doc_extern_map: LazyCell::new(),
             progress_config: ProgressConfig::default(),
@@ -1081,21 +1088,40 @@ impl GlobalContext {
             self.cli_config = Some(cli_config.iter().map(|s| s.to_string()).collect());
             self.merge_cli_args()?;
         }
-        if self.unstable_flags.config_include {
+        let when_applied = self.unstable_flags.config_when;
+        if self.unstable_flags.config_include || self.unstable_flags.config_when {
             // If the config was already loaded (like when fetching the
-            // `[alias]` table), it was loaded with includes disabled because
-            // the `unstable_flags` hadn't been set up, yet. Any values
-            // fetched before this step will not process includes, but that
-            // should be fine (`[alias]` is one of the only things loaded
-            // before configure). This can be removed when stabilized.
+            // `[alias]` table), it was loaded with includes and `[when]`
+            // tables disabled because the `unstable_flags` hadn't been set
+            // up, yet. Any values fetched before this step will not process
+            // them, but that should be fine (`[alias]` is one of the only
+            // things loaded before configure). This can be removed when
+            // stabilized.
             self.reload_rooted_at(self.cwd.clone())?;
         }
 
         self.load_unstable_flags_from_config()?;
+        if self.unstable_flags.config_when && !when_applied {
+            // `config-when` was enabled in the `[unstable]` table, after the
+            // config was loaded without the `[when]` tables.
+            self.reload_rooted_at(self.cwd.clone())?;
+        }
+
+        if self.unstable_flags.config_check {
+            for unknown in self.unknown_config_keys()? {
+                self.shell().warn(unknown)?;
+            }
+        }
 
         Ok(())
     }
 
+    /// Finds the keys of the configuration that Cargo doesn't know, which are
+    /// otherwise ignored, to catch typos in them.
+    pub fn unknown_config_keys(&self) -> CargoResult<Vec<UnknownKey>> {
+        Ok(schema::unknown_keys(self.values()?))
+    }
+
     fn load_unstable_flags_from_config(&mut self) -> CargoResult<()> {
         // If nightly features are enabled, allow setting Z-flags from config
         // using the `unstable` table. Ignore that block otherwise.
@@ -1131,6 +1157,12 @@ impl GlobalContext {
         self.offline
     }
 
+    /// Forbids network access for the rest of the session, as if `--offline`
+    /// was passed, for commands that must only use what is cached locally.
+    pub fn set_offline(&mut self) {
+        self.offline = true;
+    }
+
     pub fn frozen(&self) -> bool {
         self.frozen
     }
@@ -1251,12 +1283,15 @@ impl GlobalContext {
             WhyLoad::Cli => Definition::Cli(Some(path.into())),
             WhyLoad::FileDiscovery => Definition::Path(path.into()),
         };
-        let value = CV::from_toml(def, toml::Value::Table(toml)).with_context(|| {
+        let mut value = CV::from_toml(def, toml::Value::Table(toml)).with_context(|| {
             format!(
                 "failed to load TOML configuration from `{}`",
                 path.display()
             )
         })?;
+        if self.cli_unstable().config_when {
+            value = when::apply(self, value)?;
+        }
         if includes {
             self.load_includes(value, seen, why_load)
         } else {
@@ -1497,6 +1532,9 @@ impl GlobalContext {
                 .merge(tmp_table, true)
                 .with_context(|| format!("failed to merge --config argument `{arg}`"))?;
         }
+        if self.cli_unstable().config_when {
+            loaded_args = when::apply(self, loaded_args)?;
+        }
         Ok(loaded_args)
     }
 
@@ -1821,6 +1859,14 @@ impl GlobalContext {
             .try_borrow_with(|| self.get::<CargoBuildConfig>("build"))
     }
 
+    pub fn build_scripts_config(&self) -> CargoResult<&CargoBuildScriptsConfig> {
+        self.build_scripts_config.try_borrow_with(|| {
+            Ok(self
+                .get::<Option<CargoBuildScriptsConfig>>("build-scripts")?
+                .unwrap_or_default())
+        })
+    }
+
     pub fn progress_config(&self) -> &ProgressConfig {
         &self.progress_config
     }
@@ -1983,6 +2029,23 @@ impl GlobalContext {
         self.package_cache_lock.try_lock(self, mode)
     }
 
+    /// Like [`GlobalContext::acquire_package_cache_lock`], but reports
+    /// waiting on the lock with the JSON messages of `-Zbuild-events`.
+    pub fn acquire_package_cache_lock_reporting_wait(
+        &self,
+        mode: CacheLockMode,
+    ) -> CargoResult<CacheLock<'_>> {
+        let build_events = self.shell().build_events();
+        crate::util::acquire_reporting_wait(
+            self,
+            build_events,
+            &self.home().as_path_unlocked().join(".package-cache"),
+            "package cache",
+            || self.try_acquire_package_cache_lock(mode),
+            || self.acquire_package_cache_lock(mode),
+        )
+    }
+
     /// Returns a reference to the shared [`GlobalCacheTracker`].
     ///
     /// The package cache lock must be held to call this function (and to use
@@ -2613,6 +2676,30 @@ impl<'de> Deserialize<'de> for BuildTargetConfigInner {
     }
 }
 
+/// Configuration for the `[build-scripts]` table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CargoBuildScriptsConfig {
+    /// Package ID specs of the packages allowed to run build scripts and
+    /// proc-macros, used by `-Zbuild-script-allowlist`.
+    pub allow: Option<Vec<String>>,
+    pub sandbox: Option<BuildScriptSandboxConfig>,
+}
+
+/// Configuration for `build-scripts.sandbox`, used by
+/// `-Zbuild-script-sandbox`.
+///
+/// ```toml
+/// [build-scripts.sandbox]
+/// read = ["/opt/sdk", "../shared"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildScriptSandboxConfig {
+    /// Extra paths build scripts can read from.
+    pub read: Option<Vec<ConfigRelativePath>>,
+}
+
 impl BuildTargetConfig {
     /// Gets values of `build.target` as a list of strings.
     pub fn values(&self, gctx: &GlobalContext) -> CargoResult<Vec<String>> {
@@ -2651,6 +2738,7 @@ pub struct CargoResolverConfig {
 pub enum CargoResolverPrecedence {
     SomethingLikeMaximum,
     SomethingLikeRustVersion,
+    SomethingLikePerMemberRustVersion,
 }
 
 #[derive(Deserialize, Default)]
@@ -0,0 +1,354 @@
+//! The keys Cargo knows in its configuration, to catch typos in them.
+//!
+//! This only describes the tables and their keys: the types of the values
+//! are checked when they are deserialized. Keep it in sync with the
+//! [configuration reference] when adding a config key, including unstable
+//! ones.
+//!
+//! [configuration reference]: https://doc.rust-lang.org/nightly/cargo/reference/config.html
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{ConfigKey, ConfigValue as CV, Definition};
+use crate::util::edit_distance;
+
+/// The schema of a config value.
+pub(super) enum Schema {
+    /// Any value, like the entries of `[alias]`.
+    Any,
+    /// A table with any keys, like the `links` overrides of `[target]`.
+    AnyTable,
+    /// A table with the given keys, and the other keys of `rest`, when it
+    /// accepts their value.
+    Table(&'static [(&'static str, Schema)], Option<&'static Schema>),
+}
+
+use Schema::{Any, AnyTable, Table};
+
+impl Schema {
+    fn accepts(&self, cv: &CV) -> bool {
+        match self {
+            Any => true,
+            AnyTable | Table(..) => matches!(cv, CV::Table(..)),
+        }
+    }
+}
+
+static ROOT: Schema = Table(
+    &[
+        ("alias", Any),
+        (
+            "build",
+            Table(
+                &[
+                    ("dep-info-basedir", Any),
+                    ("incremental", Any),
+                    ("jobs", Any),
+                    ("out-dir", Any),
+                    ("pipelining", Any),
+                    ("rustc", Any),
+                    ("rustc-wrapper", Any),
+                    ("rustc-workspace-wrapper", Any),
+                    ("rustdoc", Any),
+                    ("rustdocflags", Any),
+                    ("rustflags", Any),
+                    ("target", Any),
+                    ("target-dir", Any),
+                ],
+                None,
+            ),
+        ),
+        ("build-cache", Table(&[("path", Any)], None)),
+        (
+            "build-scripts",
+            Table(
+                &[("allow", Any), ("sandbox", Table(&[("read", Any)], None))],
+                None,
+            ),
+        ),
+        (
+            "cargo-new",
+            Table(&[("email", Any), ("name", Any), ("vcs", Any)], None),
+        ),
+        ("credential-alias", Any),
+        (
+            "doc",
+            Table(
+                &[
+                    ("browser", Any),
+                    (
+                        "extern-map",
+                        Table(&[("registries", Any), ("std", Any)], None),
+                    ),
+                ],
+                None,
+            ),
+        ),
+        ("env", Any),
+        ("future-incompat-report", Table(&[("frequency", Any)], None)),
+        (
+            "gc",
+            Table(
+                &[(
+                    "auto",
+                    Table(
+                        &[
+                            ("frequency", Any),
+                            ("max-crate-age", Any),
+                            ("max-git-co-age", Any),
+                            ("max-git-db-age", Any),
+                            ("max-index-age", Any),
+                            ("max-src-age", Any),
+                        ],
+                        None,
+                    ),
+                )],
+                None,
+            ),
+        ),
+        ("host", Table(TARGET_KEYS, Some(&AnyTable))),
+        (
+            "http",
+            Table(
+                &[
+                    ("cainfo", Any),
+                    ("check-revoke", Any),
+                    ("debug", Any),
+                    ("low-speed-limit", Any),
+                    ("multiplexing", Any),
+                    ("proxy", Any),
+                    ("ssl-version", Any),
+                    ("timeout", Any),
+                    ("user-agent", Any),
+                ],
+                None,
+            ),
+        ),
+        ("include", Any),
+        (
+            "install",
+            Table(
+                &[
+                    ("prebuilt-dir", Any),
+                    ("prebuilt-store", Any),
+                    ("root", Any),
+                ],
+                None,
+            ),
+        ),
+        (
+            "net",
+            Table(
+                &[
+                    ("git-fetch-with-cli", Any),
+                    ("offline", Any),
+                    ("retry", Any),
+                    ("ssh", Table(&[("known-hosts", Any)], None)),
+                ],
+                None,
+            ),
+        ),
+        ("patch", Any),
+        ("paths", Any),
+        (
+            "policy",
+            Table(
+                &[
+                    ("allowed-licenses", Any),
+                    ("allowed-sources", Any),
+                    ("denied-packages", Any),
+                ],
+                None,
+            ),
+        ),
+        ("profile", Table(&[], Some(&PROFILE))),
+        ("publish", Table(&[("timeout", Any)], None)),
+        ("registries", Table(&[], Some(&REGISTRY))),
+        (
+            "registry",
+            Table(
+                &[
+                    ("credential-provider", Any),
+                    ("default", Any),
+                    ("global-credential-providers", Any),
+                    ("index", Any),
+                    ("secret-key", Any),
+                    ("secret-key-subject", Any),
+                    ("token", Any),
+                ],
+                None,
+            ),
+        ),
+        (
+            "resolver",
+            Table(&[("something-like-precedence", Any)], None),
+        ),
+        ("script", Table(&[("share-target-dir", Any)], None)),
+        ("source", Table(&[], Some(&SOURCE))),
+        ("target", Table(&[], Some(&TARGET))),
+        ("target-applies-to-host", Any),
+        (
+            "term",
+            Table(
+                &[
+                    ("color", Any),
+                    ("hyperlinks", Any),
+                    ("progress", Table(&[("when", Any), ("width", Any)], None)),
+                    ("quiet", Any),
+                    ("unicode", Any),
+                    ("verbose", Any),
+                ],
+                None,
+            ),
+        ),
+        ("unstable", Any),
+        ("when", Table(&[], Some(&ROOT))),
+    ],
+    None,
+);
+
+/// The schema of a profile, or of its `package` and `build-override` tables,
+/// with the keys of `extra` on top of the settings of the profile.
+macro_rules! profile {
+    ($($extra:expr),*) => {
+        Table(
+            &[
+                $($extra,)*
+                ("codegen-backend", Any),
+                ("codegen-units", Any),
+                ("debug", Any),
+                ("debug-assertions", Any),
+                ("dir-name", Any),
+                ("incremental", Any),
+                ("inherits", Any),
+                ("lto", Any),
+                ("opt-level", Any),
+                ("overflow-checks", Any),
+                ("panic", Any),
+                ("rpath", Any),
+                ("rustflags", Any),
+                ("split-debuginfo", Any),
+                ("strip", Any),
+                ("trim-paths", Any),
+            ],
+            None,
+        )
+    };
+}
+
+static PROFILE: Schema = profile!(
+    ("build-override", profile!()),
+    ("package", Table(&[], Some(&PROFILE_PACKAGE)))
+);
+
+static PROFILE_PACKAGE: Schema = profile!();
+
+static REGISTRY: Schema = Table(
+    &[
+        ("credential-provider", Any),
+        ("index", Any),
+        ("protocol", Any),
+        ("secret-key", Any),
+        ("secret-key-subject", Any),
+        ("token", Any),
+    ],
+    None,
+);
+
+static SOURCE: Schema = Table(
+    &[
+        ("branch", Any),
+        ("directory", Any),
+        ("git", Any),
+        ("local-registry", Any),
+        ("registry", Any),
+        ("replace-with", Any),
+        ("rev", Any),
+        ("tag", Any),
+    ],
+    None,
+);
+
+/// A `[target.<triple>]` or `[target.<cfg>]` table, whose other keys are
+/// the tables overriding build scripts by their `links` value.
+static TARGET: Schema = Table(TARGET_KEYS, Some(&AnyTable));
+
+static TARGET_KEYS: &[(&str, Schema)] = &[
+    ("ar", Any),
+    ("linker", Any),
+    ("runner", Any),
+    ("rustdocflags", Any),
+    ("rustflags", Any),
+];
+
+/// A key of the configuration that Cargo doesn't know.
+pub struct UnknownKey {
+    pub key: ConfigKey,
+    pub definition: Definition,
+    /// The known key closest to it, if any is close enough to be a typo.
+    pub suggestion: Option<ConfigKey>,
+}
+
+impl fmt::Display for UnknownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown config key `{}` in `{}`",
+            self.key, self.definition
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n\n\tDid you mean `{}`?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds the keys of `values` that aren't in the schema of the
+/// configuration, sorted by key.
+pub(super) fn unknown_keys(values: &HashMap<String, CV>) -> Vec<UnknownKey> {
+    let mut unknown = Vec::new();
+    check_table(&ROOT, &ConfigKey::new(), values, &mut unknown);
+    unknown
+}
+
+fn check(schema: &Schema, key: &ConfigKey, cv: &CV, unknown: &mut Vec<UnknownKey>) {
+    // Values of the wrong type are reported when they are deserialized.
+    if let (Table(..), CV::Table(table, _def)) = (schema, cv) {
+        check_table(schema, key, table, unknown);
+    }
+}
+
+fn check_table(
+    schema: &Schema,
+    key: &ConfigKey,
+    table: &HashMap<String, CV>,
+    unknown: &mut Vec<UnknownKey>,
+) {
+    let Table(keys, rest) = schema else {
+        unreachable!("only tables have keys");
+    };
+    let mut entries: Vec<_> = table.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, cv) in entries {
+        let mut subkey = key.clone();
+        subkey.push(name);
+        if let Some((_, schema)) = keys.iter().find(|(known, _)| *known == name) {
+            check(schema, &subkey, cv, unknown);
+        } else if let Some(rest) = rest.filter(|rest| rest.accepts(cv)) {
+            check(rest, &subkey, cv, unknown);
+        } else {
+            let suggestion =
+                edit_distance::closest(name, keys.iter(), |(known, _)| known).map(|(known, _)| {
+                    let mut suggestion = key.clone();
+                    suggestion.push(known);
+                    suggestion
+                });
+            unknown.push(UnknownKey {
+                key: subkey,
+                definition: cv.definition().clone(),
+                suggestion,
+            });
+        }
+    }
+}
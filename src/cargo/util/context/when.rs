@@ -0,0 +1,195 @@
+//! Conditional config tables, with `[when.<condition>]`.
+//!
+//! The tables of `when` are merged into the file defining them when their
+//! condition matches, like `[when.'cfg(windows)'.build]`. Conditions are
+//! evaluated against the host Cargo runs on, rather than the target of the
+//! build, since the target itself comes from the configuration.
+//!
+//! A condition is either a target triple, matching the host triple, or a
+//! `cfg` expression like the ones of `[target.'cfg(..)']`. On top of the
+//! `cfg` values of the host, `cfg` expressions support `env = "NAME"`, which
+//! matches when the environment variable `NAME` is set. The other `cfg`
+//! values, which would need to ask `rustc`, are rejected rather than never
+//! matching.
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context as _};
+use cargo_platform::{Cfg, CfgExpr, Platform};
+
+use super::{ConfigValue as CV, GlobalContext};
+use crate::util::errors::CargoResult;
+
+/// Merges the tables of `when` in `value` whose condition matches, and
+/// removes `when` from it.
+///
+/// The conditional values override the other values of the same file,
+/// arrays included. When several conditions match, they are merged in the
+/// order of their keys.
+pub(super) fn apply(gctx: &GlobalContext, mut value: CV) -> CargoResult<CV> {
+    let CV::Table(table, _def) = &mut value else {
+        return Ok(value);
+    };
+    let Some(when) = table.remove("when") else {
+        return Ok(value);
+    };
+    let CV::Table(conditions, _def) = when else {
+        bail!(
+            "expected a table for `when` in {}, but found {}",
+            when.definition(),
+            when.desc()
+        );
+    };
+    let mut conditions: Vec<_> = conditions.into_iter().collect();
+    conditions.sort_by(|a, b| a.0.cmp(&b.0));
+    let cfgs = host_cfgs();
+    for (condition, cv) in conditions {
+        let platform = Platform::from_str(&condition).with_context(|| {
+            format!(
+                "failed to parse the condition `{}` of `when` in {}",
+                condition,
+                cv.definition()
+            )
+        })?;
+        let matches = match &platform {
+            Platform::Name(triple) => triple == env!("RUST_HOST_TARGET"),
+            Platform::Cfg(expr) => {
+                check_cfgs(expr).with_context(|| {
+                    format!(
+                        "failed to parse the condition `{}` of `when` in {}",
+                        condition,
+                        cv.definition()
+                    )
+                })?;
+                matches(gctx, expr, &cfgs)
+            }
+        };
+        if !matches {
+            continue;
+        }
+        if !matches!(cv, CV::Table(..)) {
+            bail!(
+                "expected a table for `when.'{}'` in {}, but found {}",
+                condition,
+                cv.definition(),
+                cv.desc()
+            );
+        }
+        let cv = apply(gctx, cv)?;
+        override_with(&mut value, cv);
+    }
+    Ok(value)
+}
+
+/// Sets the values of `cv` in `value`, merging tables but replacing
+/// everything else, unlike `ConfigValue::merge` which concatenates arrays.
+fn override_with(value: &mut CV, cv: CV) {
+    match (value, cv) {
+        (CV::Table(old, _), CV::Table(new, _)) => {
+            for (key, cv) in new {
+                match old.get_mut(&key) {
+                    Some(value) => override_with(value, cv),
+                    None => {
+                        old.insert(key, cv);
+                    }
+                }
+            }
+        }
+        (value, cv) => *value = cv,
+    }
+}
+
+/// The `cfg` keys known without asking `rustc`, on top of `env`.
+const CFG_KEYS: &[&str] = &[
+    "target_arch",
+    "target_endian",
+    "target_env",
+    "target_family",
+    "target_os",
+    "target_pointer_width",
+    "target_vendor",
+];
+
+/// Rejects the `cfg` values that aren't known for the host.
+fn check_cfgs(expr: &CfgExpr) -> CargoResult<()> {
+    match expr {
+        CfgExpr::Not(e) => check_cfgs(e),
+        CfgExpr::All(e) | CfgExpr::Any(e) => e.iter().try_for_each(check_cfgs),
+        CfgExpr::Value(Cfg::KeyPair(key, _)) => {
+            if key != "env" && !CFG_KEYS.contains(&key.as_str()) {
+                bail!(
+                    "unsupported `cfg` key `{key}`, only `env`, `{}` are supported",
+                    CFG_KEYS.join("`, `")
+                );
+            }
+            Ok(())
+        }
+        CfgExpr::Value(Cfg::Name(name)) => {
+            if name != "unix" && name != "windows" {
+                bail!("unsupported `cfg` name `{name}`, only `unix` and `windows` are supported");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn matches(gctx: &GlobalContext, expr: &CfgExpr, cfgs: &[Cfg]) -> bool {
+    match expr {
+        CfgExpr::Not(e) => !matches(gctx, e, cfgs),
+        CfgExpr::All(e) => e.iter().all(|e| matches(gctx, e, cfgs)),
+        CfgExpr::Any(e) => e.iter().any(|e| matches(gctx, e, cfgs)),
+        CfgExpr::Value(Cfg::KeyPair(key, name)) if key == "env" => {
+            gctx.env.get_env_os(name).is_some()
+        }
+        CfgExpr::Value(cfg) => cfgs.contains(cfg),
+    }
+}
+
+/// The `cfg` values of the host, known without asking `rustc`.
+fn host_cfgs() -> Vec<Cfg> {
+    use std::env::consts::{ARCH, FAMILY, OS};
+
+    let key_pair = |key: &str, value: &str| Cfg::KeyPair(key.to_string(), value.to_string());
+    let target_env = if cfg!(target_env = "gnu") {
+        "gnu"
+    } else if cfg!(target_env = "musl") {
+        "musl"
+    } else if cfg!(target_env = "msvc") {
+        "msvc"
+    } else {
+        ""
+    };
+    let target_vendor = if cfg!(target_vendor = "apple") {
+        "apple"
+    } else if cfg!(target_vendor = "pc") {
+        "pc"
+    } else if cfg!(target_vendor = "fortanix") {
+        "fortanix"
+    } else if cfg!(target_vendor = "uwp") {
+        "uwp"
+    } else if cfg!(target_vendor = "wrs") {
+        "wrs"
+    } else if cfg!(target_vendor = "sun") {
+        "sun"
+    } else {
+        "unknown"
+    };
+    let target_endian = if cfg!(target_endian = "big") {
+        "big"
+    } else {
+        "little"
+    };
+    let mut cfgs = vec![
+        key_pair("target_arch", ARCH),
+        key_pair("target_endian", target_endian),
+        key_pair("target_env", target_env),
+        key_pair("target_os", OS),
+        key_pair("target_pointer_width", &usize::BITS.to_string()),
+        key_pair("target_vendor", target_vendor),
+    ];
+    if !FAMILY.is_empty() {
+        cfgs.push(key_pair("target_family", FAMILY));
+        cfgs.push(Cfg::Name(FAMILY.to_string()));
+    }
+    cfgs
+}
@@ -11,8 +11,10 @@ use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Display, Path, PathBuf};
+use std::time::Instant;
 
 use crate::util::errors::CargoResult;
+use crate::util::machine_message::{self, Message as _};
 use crate::util::style;
 use crate::util::GlobalContext;
 use anyhow::Context as _;
@@ -407,6 +409,37 @@ fn acquire(
     Ok(())
 }
 
+/// Acquires a lock with `lock`, reporting the wait with the `lock-wait-*`
+/// JSON messages of `-Zbuild-events` if `build_events` is set.
+///
+/// `try_lock` is attempted first, and returns `None` if the lock is held
+/// elsewhere. `path` and `description` describe the lock in the messages.
+pub fn acquire_reporting_wait<T>(
+    gctx: &GlobalContext,
+    build_events: bool,
+    path: &Path,
+    description: &str,
+    try_lock: impl FnOnce() -> CargoResult<Option<T>>,
+    lock: impl FnOnce() -> CargoResult<T>,
+) -> CargoResult<T> {
+    if !build_events {
+        return lock();
+    }
+    if let Some(lock) = try_lock()? {
+        return Ok(lock);
+    }
+    let started = machine_message::LockWaitStarted { path, description };
+    writeln!(gctx.shell().out(), "{}", started.to_json_string())?;
+    let start = Instant::now();
+    let lock = lock()?;
+    let finished = machine_message::LockWaitFinished {
+        path,
+        duration: start.elapsed().as_secs_f64(),
+    };
+    writeln!(gctx.shell().out(), "{}", finished.to_json_string())?;
+    Ok(lock)
+}
+
 #[cfg(all(target_os = "linux", not(target_env = "musl")))]
 fn is_on_nfs_mount(path: &Path) -> bool {
     use std::ffi::CString;
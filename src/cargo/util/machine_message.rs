@@ -103,6 +103,150 @@ impl<'a> Message for TimingInfo<'a> {
     }
 }
 
+#[derive(Serialize)]
+pub struct UnitRebuilt<'a> {
+    pub package_id: PackageIdSpec,
+    pub target: &'a Target,
+    pub mode: CompileMode,
+    pub cause: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependency: Option<RebuiltDependency<'a>>,
+}
+
+/// The dependency whose rebuild caused a [`UnitRebuilt`].
+#[derive(Serialize)]
+pub struct RebuiltDependency<'a> {
+    pub package_id: PackageIdSpec,
+    pub target: &'a Target,
+    pub mode: CompileMode,
+}
+
+impl<'a> Message for UnitRebuilt<'a> {
+    fn reason(&self) -> &str {
+        "unit-rebuilt"
+    }
+}
+
+#[derive(Serialize)]
+pub struct UnitStarted<'a> {
+    pub package_id: PackageIdSpec,
+    pub target: &'a Target,
+    pub mode: CompileMode,
+    /// Whether the unit is up to date, in which case nothing is run.
+    pub fresh: bool,
+    /// Why the unit needs to be rebuilt, when it isn't fresh.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dirty_reason: Option<&'static str>,
+}
+
+impl<'a> Message for UnitStarted<'a> {
+    fn reason(&self) -> &str {
+        "unit-started"
+    }
+}
+
+#[derive(Serialize)]
+pub struct UnitFinished<'a> {
+    pub package_id: PackageIdSpec,
+    pub target: &'a Target,
+    pub mode: CompileMode,
+    pub success: bool,
+}
+
+impl<'a> Message for UnitFinished<'a> {
+    fn reason(&self) -> &str {
+        "unit-finished"
+    }
+}
+
+/// The number of units in each state of the job queue, emitted when it
+/// changes. These are the same counts as the concurrency graph of
+/// `--timings`.
+#[derive(Serialize, PartialEq, Eq, Clone, Copy)]
+pub struct JobQueueConcurrency {
+    /// Units being built.
+    pub active: usize,
+    /// Units ready to be built, waiting for a jobserver token.
+    pub waiting: usize,
+    /// Units waiting for their dependencies to be built.
+    pub inactive: usize,
+}
+
+impl Message for JobQueueConcurrency {
+    fn reason(&self) -> &str {
+        "job-queue-concurrency"
+    }
+}
+
+#[derive(Serialize)]
+pub struct DownloadStarted<'a> {
+    pub package_id: PackageIdSpec,
+    pub url: &'a str,
+}
+
+impl<'a> Message for DownloadStarted<'a> {
+    fn reason(&self) -> &str {
+        "download-started"
+    }
+}
+
+/// The overall progress of the downloads in flight, emitted at most every
+/// 100ms.
+#[derive(Serialize)]
+pub struct DownloadProgress {
+    /// Number of crates still being downloaded.
+    pub pending: usize,
+    /// Bytes left to download for the crates whose size is known.
+    pub remaining_bytes: u64,
+}
+
+impl Message for DownloadProgress {
+    fn reason(&self) -> &str {
+        "download-progress"
+    }
+}
+
+#[derive(Serialize)]
+pub struct DownloadFinished {
+    pub package_id: PackageIdSpec,
+    pub bytes: u64,
+    /// Time spent downloading, in seconds.
+    pub duration: f64,
+}
+
+impl Message for DownloadFinished {
+    fn reason(&self) -> &str {
+        "download-finished"
+    }
+}
+
+#[derive(Serialize)]
+pub struct LockWaitStarted<'a> {
+    pub path: &'a Path,
+    /// What the lock protects, as shown in the `Blocking` status.
+    pub description: &'a str,
+}
+
+impl<'a> Message for LockWaitStarted<'a> {
+    fn reason(&self) -> &str {
+        "lock-wait-started"
+    }
+}
+
+#[derive(Serialize)]
+pub struct LockWaitFinished<'a> {
+    pub path: &'a Path,
+    /// Time spent waiting for the lock, in seconds.
+    pub duration: f64,
+}
+
+impl<'a> Message for LockWaitFinished<'a> {
+    fn reason(&self) -> &str {
+        "lock-wait-finished"
+    }
+}
+
 #[derive(Serialize)]
 pub struct BuildFinished {
     pub success: bool,
@@ -113,3 +257,117 @@ impl Message for BuildFinished {
         "build-finished"
     }
 }
+
+#[derive(Serialize)]
+pub struct TestSuiteStarted<'a> {
+    pub package_id: PackageIdSpec,
+    pub target: &'a Target,
+    pub kind: &'static str,
+}
+
+impl<'a> Message for TestSuiteStarted<'a> {
+    fn reason(&self) -> &str {
+        "test-suite-started"
+    }
+}
+
+#[derive(Serialize)]
+pub struct TestStarted<'a> {
+    pub package_id: PackageIdSpec,
+    pub target: &'a Target,
+    pub name: &'a str,
+}
+
+impl<'a> Message for TestStarted<'a> {
+    fn reason(&self) -> &str {
+        "test-started"
+    }
+}
+
+#[derive(Serialize)]
+pub struct TestFinished<'a> {
+    pub package_id: PackageIdSpec,
+    pub target: &'a Target,
+    pub name: &'a str,
+    pub outcome: TestOutcome,
+    /// Execution time in seconds, only known when libtest reports it
+    /// (`--report-time`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exec_time: Option<f64>,
+    /// The reason given to `#[ignore = "..."]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<&'a str>,
+    /// Output captured by the harness for a failed test.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bench: Option<BenchResult>,
+}
+
+impl<'a> Message for TestFinished<'a> {
+    fn reason(&self) -> &str {
+        "test-finished"
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestOutcome {
+    Ok,
+    Failed,
+    Ignored,
+    Bench,
+}
+
+/// Measurement reported by the libtest bench harness, in nanoseconds per
+/// iteration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BenchResult {
+    pub median: u64,
+    pub deviation: u64,
+}
+
+#[derive(Serialize)]
+pub struct TestSuiteFinished<'a> {
+    pub package_id: PackageIdSpec,
+    pub target: &'a Target,
+    pub kind: &'static str,
+    pub success: bool,
+    /// Wall-clock time in seconds spent running the suite.
+    pub duration: f64,
+    /// The counts from libtest's `test result:` line, absent for targets
+    /// without a harness.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<TestSummary>,
+}
+
+impl<'a> Message for TestSuiteFinished<'a> {
+    fn reason(&self) -> &str {
+        "test-suite-finished"
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TestSummary {
+    pub passed: u32,
+    pub failed: u32,
+    pub ignored: u32,
+    pub measured: u32,
+    pub filtered_out: u32,
+}
+
+#[derive(Serialize)]
+pub struct PolicyViolation<'a> {
+    pub package_id: PackageIdSpec,
+    /// The key of the `[policy]` table that was violated.
+    pub rule: &'static str,
+    pub message: &'a str,
+    /// The packages from a workspace member to this package.
+    pub path: Vec<PackageIdSpec>,
+}
+
+impl<'a> Message for PolicyViolation<'a> {
+    fn reason(&self) -> &str {
+        "policy-violation"
+    }
+}
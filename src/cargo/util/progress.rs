@@ -55,7 +55,9 @@ pub enum ProgressStyle {
     Indeterminate,
 }
 
-struct Throttle {
+/// Limits how often something is updated: at most every 100ms, and not
+/// before 500ms have passed.
+pub(crate) struct Throttle {
     first: bool,
     last_update: Instant,
 }
@@ -237,14 +239,14 @@ impl<'gctx> Progress<'gctx> {
 }
 
 impl Throttle {
-    fn new() -> Throttle {
+    pub(crate) fn new() -> Throttle {
         Throttle {
             first: true,
             last_update: Instant::now(),
         }
     }
 
-    fn allowed(&mut self) -> bool {
+    pub(crate) fn allowed(&mut self) -> bool {
         if self.first {
             let delay = Duration::from_millis(500);
             if self.last_update.elapsed() < delay {
@@ -52,7 +52,10 @@ pub(super) fn expand_manifest(
             writeln!(hacked_source)?;
         }
         writeln!(hacked_source)?; // close
-        writeln!(hacked_source, "{}", source.content)?;
+        let script_dir = path
+            .parent()
+            .expect("always a parent for embedded manifests");
+        hacked_source.push_str(&add_module_paths(source.content, script_dir));
         if let Some(parent) = hacked_path.parent() {
             cargo_util::paths::create_dir_all(parent)?;
         }
@@ -151,6 +154,252 @@ fn expand_manifest_(
     Ok(manifest)
 }
 
+/// Adds a `#[path]` to each `mod foo;` at the root of a script in
+/// `script_dir`.
+///
+/// As the script is compiled from a copy without its manifest, the modules
+/// it declares are pointed to the files next to it. Everything else is left
+/// untouched, so that the lines of the copy match the script.
+///
+/// The modules declared in the body of an inline module are in its
+/// directory, like `a/b.rs` for `mod a { mod b; }`.
+fn add_module_paths(content: &str, script_dir: &std::path::Path) -> String {
+    let mut inserts = Vec::new();
+    let mut item = Vec::new();
+    let mut depth = 0usize;
+    // The directory of each inline module we are in, with the depth of its
+    // body, and `None` for those whose modules are left untouched.
+    let mut modules = vec![(Some(script_dir.to_path_buf()), 0)];
+    let mut tokens = Tokens::new(content);
+    while let Some((start, token)) = tokens.next() {
+        let (dir, body) = modules.last().expect("always the script's module");
+        let at_item = depth == *body;
+        match token {
+            Token::Open(c) => {
+                if at_item && c == '{' && item.iter().any(|(_, t)| *t == Token::Ident("mod")) {
+                    let dir = dir.as_ref().and_then(|dir| {
+                        declared_module(content, &item).map(|(_, name)| dir.join(name))
+                    });
+                    modules.push((dir, depth + 1));
+                    item.clear();
+                } else if at_item {
+                    item.push((start, Token::Open(c)));
+                }
+                depth += 1;
+            }
+            Token::Close(c) => {
+                depth = depth.saturating_sub(1);
+                if modules.len() > 1 && depth < *body {
+                    modules.pop();
+                    item.clear();
+                } else if depth == *body && c == '}' {
+                    item.clear();
+                }
+            }
+            Token::Punct(';') if at_item => {
+                if let (Some(dir), Some((at, name))) = (dir, declared_module(content, &item)) {
+                    inserts.push((at, module_path(name, dir)));
+                }
+                item.clear();
+            }
+            token if at_item => item.push((start, token)),
+            _ => {}
+        }
+    }
+
+    let mut source = String::with_capacity(content.len());
+    let mut last = 0;
+    for (at, path) in inserts {
+        source.push_str(&content[last..at]);
+        source.push_str(&format!("#[path = {path:?}] "));
+        last = at;
+    }
+    source.push_str(&content[last..]);
+    source
+}
+
+/// The offset to insert a `#[path]` at and the name of the module, if the
+/// tokens of `item` declare a module in another file without a `#[path]`.
+fn declared_module<'s>(content: &'s str, item: &[(usize, Token<'s>)]) -> Option<(usize, &'s str)> {
+    let mut rest = item;
+    // Outer attributes, and the inner ones of the crate before the first item
+    while let [(_, Token::Punct('#')), tail @ ..] = rest {
+        rest = match tail {
+            [(_, Token::Punct('!')), (_, Token::Open('[')), tail @ ..] => tail,
+            [(attr, Token::Open('[')), tail @ ..] => {
+                let attr = content[attr + 1..].trim_start();
+                if attr.starts_with("path") && !attr[4..].starts_with(is_ident_char) {
+                    return None;
+                }
+                tail
+            }
+            _ => return None,
+        };
+    }
+    let at = rest.first()?.0;
+    if let [(_, Token::Ident("pub")), tail @ ..] = rest {
+        rest = match tail {
+            [(_, Token::Open('(')), tail @ ..] => tail,
+            _ => tail,
+        };
+    }
+    match rest {
+        [(_, Token::Ident("mod")), (_, Token::Ident(name))] => {
+            Some((at, name.strip_prefix("r#").unwrap_or(name)))
+        }
+        _ => None,
+    }
+}
+
+/// The file of the module `name` of a script in `script_dir`.
+fn module_path(name: &str, script_dir: &std::path::Path) -> String {
+    let file = script_dir.join(format!("{name}.rs"));
+    let mod_rs = script_dir.join(name).join("mod.rs");
+    let path = if !file.exists() && mod_rs.exists() {
+        mod_rs
+    } else {
+        file
+    };
+    path.to_string_lossy().into_owned()
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// A token of Rust source, as far as finding the modules of a script goes.
+#[derive(Debug, PartialEq)]
+enum Token<'s> {
+    Ident(&'s str),
+    Open(char),
+    Close(char),
+    Punct(char),
+}
+
+/// Splits Rust source in [`Token`]s, skipping whitespace, comments and
+/// literals.
+struct Tokens<'s> {
+    content: &'s str,
+    pos: usize,
+}
+
+impl<'s> Tokens<'s> {
+    fn new(content: &'s str) -> Self {
+        Self { content, pos: 0 }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.content[self.pos..]
+    }
+
+    /// Moves past the next `end`, or to the end of the content.
+    fn skip_past(&mut self, end: &str) {
+        self.pos = match self.rest().find(end) {
+            Some(i) => self.pos + i + end.len(),
+            None => self.content.len(),
+        };
+    }
+
+    /// Skips a block comment, which may be nested, after its `/*`.
+    fn skip_block_comment(&mut self) {
+        let mut depth = 1;
+        while depth > 0 {
+            let rest = self.rest();
+            let Some(c) = rest.chars().next() else {
+                return;
+            };
+            if rest.starts_with("/*") {
+                depth += 1;
+                self.pos += 2;
+            } else if rest.starts_with("*/") {
+                depth -= 1;
+                self.pos += 2;
+            } else {
+                self.pos += c.len_utf8();
+            }
+        }
+    }
+
+    /// Skips a string literal, after its opening quote.
+    fn skip_string(&mut self) {
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => {
+                    self.pos += i + 1;
+                    return;
+                }
+                _ => {}
+            }
+        }
+        self.pos = self.content.len();
+    }
+
+    /// Skips a char literal after its opening quote, or nothing for the
+    /// quote of a lifetime.
+    fn skip_char(&mut self) {
+        let mut chars = self.rest().char_indices();
+        match (chars.next(), chars.next()) {
+            (Some((_, '\\')), Some((i, escaped))) => {
+                self.pos += i + escaped.len_utf8();
+                self.skip_past("'");
+            }
+            (Some(_), Some((i, '\''))) => self.pos += i + 1,
+            _ => {}
+        }
+    }
+}
+
+impl<'s> Iterator for Tokens<'s> {
+    type Item = (usize, Token<'s>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = self.rest();
+            let start = self.pos;
+            let c = rest.chars().next()?;
+            self.pos += c.len_utf8();
+            match c {
+                '/' if rest.starts_with("//") => self.skip_past("\n"),
+                '/' if rest.starts_with("/*") => {
+                    self.pos += 1;
+                    self.skip_block_comment();
+                }
+                '"' => self.skip_string(),
+                '\'' => self.skip_char(),
+                '(' | '[' | '{' => return Some((start, Token::Open(c))),
+                ')' | ']' | '}' => return Some((start, Token::Close(c))),
+                c if is_ident_char(c) => {
+                    let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+                    let ident = &rest[..len];
+                    let after = &rest[len..];
+                    self.pos = start + len;
+                    if matches!(ident, "r" | "br" | "cr") {
+                        let hashes = after.len() - after.trim_start_matches('#').len();
+                        if after[hashes..].starts_with('"') {
+                            self.pos += hashes + 1;
+                            self.skip_past(&format!("\"{}", "#".repeat(hashes)));
+                            continue;
+                        }
+                        if ident == "r" && hashes == 1 && after[1..].starts_with(is_ident_char) {
+                            let raw = &after[1..];
+                            let raw_len = raw.find(|c| !is_ident_char(c)).unwrap_or(raw.len());
+                            self.pos += 1 + raw_len;
+                            return Some((start, Token::Ident(&rest[..len + 1 + raw_len])));
+                        }
+                    }
+                    return Some((start, Token::Ident(ident)));
+                }
+                c if c.is_whitespace() => {}
+                c => return Some((start, Token::Punct(c))),
+            }
+        }
+    }
+}
+
 /// Ensure the package name matches the validation from `ops::cargo_new::check_name`
 fn sanitize_name(name: &str) -> String {
     let placeholder = if name.contains('_') {
@@ -364,4 +613,64 @@ fn main() {}
 "#),
         );
     }
+
+    #[test]
+    fn test_add_module_paths() {
+        let dir = std::path::Path::new("/home/me");
+        let path = |name: &str| format!("#[path = {:?}] ", dir.join(name).to_string_lossy());
+        let foo = path("foo.rs");
+        assert_eq!(
+            add_module_paths("mod foo;\n", dir),
+            format!("{foo}mod foo;\n")
+        );
+        assert_eq!(
+            add_module_paths("pub mod foo  ;", dir),
+            format!("{foo}pub mod foo  ;")
+        );
+        assert_eq!(
+            add_module_paths("#[cfg(test)]\npub(crate) mod r#type;", dir),
+            format!("#[cfg(test)]\n{}pub(crate) mod r#type;", path("type.rs"))
+        );
+        assert_eq!(
+            add_module_paths("#![allow(dead_code)]\n\nmod foo; mod bar;", dir),
+            format!(
+                "#![allow(dead_code)]\n\n{foo}mod foo; {}mod bar;",
+                path("bar.rs")
+            )
+        );
+        assert_eq!(
+            add_module_paths("mod\n    foo\n;", dir),
+            format!("{foo}mod\n    foo\n;")
+        );
+        let literals = "const C: char = '{'; const D: char = '\\''; fn f<'a>(_: &'a str) {}\n";
+        assert_eq!(
+            add_module_paths(&format!("{literals}mod foo;"), dir),
+            format!("{literals}{foo}mod foo;")
+        );
+        assert_eq!(
+            add_module_paths(
+                "mod foo { mod bar; pub mod baz { mod qux; } }\nmod quux;",
+                dir
+            ),
+            format!(
+                "mod foo {{ {}mod bar; pub mod baz {{ {}mod qux; }} }}\n{}mod quux;",
+                path("foo/bar.rs"),
+                path("foo/baz/qux.rs"),
+                path("quux.rs"),
+            )
+        );
+        for untouched in [
+            "mod foo {}",
+            "#[path = \"other\"] mod foo { mod bar; }",
+            "fn main() { mod foo; }",
+            "#[path = \"other.rs\"] mod foo;",
+            "// mod foo;",
+            "/* /* */ mod foo; */",
+            "const S: &str = \"mod foo;\";",
+            "const S: &str = r#\"\"mod foo;\"#;",
+            "model;",
+        ] {
+            assert_eq!(add_module_paths(untouched, dir), untouched);
+        }
+    }
 }
@@ -0,0 +1,371 @@
+//! Tests for `-Zbuild-script-allowlist` and `cargo approve-build-scripts`.
+
+use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_manifest, git, project};
+
+#[cargo_test]
+fn gated() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("approve-build-scripts -Zbuild-script-allowlist")
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the `-Z` flag is only accepted on the nightly channel of Cargo[..]",
+        )
+        .run();
+    p.cargo("approve-build-scripts")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo approve-build-scripts` command is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+    p.cargo("approve-build-scripts")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo approve-build-scripts` command is unstable, pass `-Z build-script-allowlist` to enable it
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn unlisted_packages_are_refused() {
+    Package::new("bs", "1.0.0")
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("pm", "1.0.0").proc_macro(true).publish();
+    Package::new("plain", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bs = "1.0"
+                pm = "1.0"
+                plain = "1.0"
+                local = { path = "local" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}")
+        .file("local/Cargo.toml", &basic_manifest("local", "0.1.0"))
+        .file("local/build.rs", "fn main() {}")
+        .file("local/src/lib.rs", "")
+        .build();
+    p.cargo("check -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] the following packages would run code at build time without being allowed by `build-scripts.allow`:
+  bs v1.0.0 (build script)
+  pm v1.0.0 (proc-macro)
+Review them and run `cargo approve-build-scripts -Zbuild-script-allowlist` to allow them
+",
+        )
+        .with_stderr_does_not_contain("[COMPILING][..]")
+        .run();
+
+    // Without the flag, nothing is checked.
+    p.cargo("check").run();
+}
+
+#[cargo_test]
+fn allow_config() {
+    Package::new("bs", "1.0.0")
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("pm", "1.0.0").proc_macro(true).publish();
+    Package::new("plain", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bs = "1.0"
+                pm = "1.0"
+                plain = "1.0"
+                local = { path = "local" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}")
+        .file("local/Cargo.toml", &basic_manifest("local", "0.1.0"))
+        .file("local/build.rs", "fn main() {}")
+        .file("local/src/lib.rs", "")
+        .build();
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build-scripts]
+            allow = ["bs@1.0.0"]
+        "#,
+    );
+    p.cargo("check -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] the following package would run code at build time without being allowed by `build-scripts.allow`:
+  pm v1.0.0 (proc-macro)
+",
+        )
+        .run();
+
+    // Specs without a version allow every version.
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build-scripts]
+            allow = ["bs@1.0.0", "pm"]
+        "#,
+    );
+    p.cargo("check -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .run();
+
+    // A new version needs to be approved again.
+    Package::new("bs", "1.0.1")
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "")
+        .publish();
+    p.cargo("update bs").run();
+    p.cargo("check -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] the following package would run code at build time without being allowed by `build-scripts.allow`:
+  bs v1.0.1 (build script)
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn invalid_allow_spec() {
+    Package::new("bs", "1.0.0")
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("pm", "1.0.0").proc_macro(true).publish();
+    Package::new("plain", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bs = "1.0"
+                pm = "1.0"
+                plain = "1.0"
+                local = { path = "local" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}")
+        .file("local/Cargo.toml", &basic_manifest("local", "0.1.0"))
+        .file("local/build.rs", "fn main() {}")
+        .file("local/src/lib.rs", "")
+        .build();
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build-scripts]
+            allow = ["bs@not-a-version"]
+        "#,
+    );
+    p.cargo("check -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_status(101)
+        .with_stderr_contains("[ERROR] invalid package ID spec in `build-scripts.allow`: [..]")
+        .run();
+}
+
+#[cargo_test]
+fn approve() {
+    Package::new("bs", "1.0.0")
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("pm", "1.0.0").proc_macro(true).publish();
+    Package::new("plain", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bs = "1.0"
+                pm = "1.0"
+                plain = "1.0"
+                local = { path = "local" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}")
+        .file("local/Cargo.toml", &basic_manifest("local", "0.1.0"))
+        .file("local/build.rs", "fn main() {}")
+        .file("local/src/lib.rs", "")
+        .build();
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build-scripts.sandbox]
+            read = ["/opt/sdk"]
+        "#,
+    );
+
+    p.cargo("approve-build-scripts -Zbuild-script-allowlist --dry-run")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[LOCKING] 5 packages to latest compatible versions
+[DOWNLOADING] crates ...
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+   Approving bs v1.0.0 (build script)
+   Approving pm v1.0.0 (proc-macro)
+[WARNING] not recording approvals due to dry run
+",
+        )
+        .run();
+
+    p.cargo("approve-build-scripts -Zbuild-script-allowlist pm")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_stderr(
+            "   Approving pm v1.0.0 (proc-macro)
+    Recorded 1 approval in `[ROOT]/foo/.cargo/config.toml`
+",
+        )
+        .run();
+
+    p.cargo("approve-build-scripts -Zbuild-script-allowlist pm")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] package ID specification `pm` did not match any package that needs to be approved
+",
+        )
+        .run();
+
+    p.cargo("approve-build-scripts -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_stderr(
+            "   Approving bs v1.0.0 (build script)
+    Recorded 1 approval in `[ROOT]/foo/.cargo/config.toml`
+",
+        )
+        .run();
+    assert_eq!(
+        p.read_file(".cargo/config.toml"),
+        r#"[build-scripts]
+allow = [
+    "registry+https://github.com/rust-lang/crates.io-index#pm@1.0.0",
+    "registry+https://github.com/rust-lang/crates.io-index#bs@1.0.0",
+]
+
+            [build-scripts.sandbox]
+            read = ["/opt/sdk"]
+        "#
+    );
+
+    p.cargo("approve-build-scripts -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_stderr("[NOTE] all build scripts and proc-macros are already allowed\n")
+        .run();
+    p.cargo("check -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .run();
+}
+
+#[cargo_test]
+fn approval_is_tied_to_the_source() {
+    Package::new("bs", "1.0.0")
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "")
+        .publish();
+    let git_project = git::new("bs", |project| {
+        project
+            .file("Cargo.toml", &basic_manifest("bs", "1.0.0"))
+            .file("build.rs", "fn main() {}")
+            .file("src/lib.rs", "")
+    });
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bs = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("approve-build-scripts -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_stderr_contains("   Approving bs v1.0.0 (build script)")
+        .run();
+    p.cargo("check -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .run();
+
+    // The same name and version from a git repository needs its own approval.
+    p.change_file(
+        "Cargo.toml",
+        &format!(
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bs = {{ git = '{}' }}
+            "#,
+            git_project.url()
+        ),
+    );
+    p.cargo("check -Zbuild-script-allowlist")
+        .masquerade_as_nightly_cargo(&["build-script-allowlist"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] the following package would run code at build time without being allowed by `build-scripts.allow`:
+  bs v1.0.0 (file://[..]/bs#[..]) (build script)
+",
+        )
+        .run();
+}
@@ -0,0 +1,501 @@
+//! Tests for the `cargo audit-policy` command.
+
+use cargo_test_support::paths::CargoPathExt;
+use cargo_test_support::registry::{registry_path, Package};
+use cargo_test_support::{basic_manifest, git, project};
+
+#[cargo_test]
+fn gated() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("audit-policy")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo audit-policy` command is unstable, pass `-Z unstable-options` to enable it
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn requires_policy_and_lockfile() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("audit-policy -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] no `[policy]` table was found in the configuration
+See https://doc.rust-lang.org/nightly/cargo/reference/unstable.html#audit-policy for how to define one.
+",
+        )
+        .run();
+
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [policy]
+            allowed-licenses = ["MIT"]
+        "#,
+    );
+    p.cargo("audit-policy -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `cargo audit-policy` requires a `Cargo.lock` file, run `cargo generate-lockfile` to create one
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn licenses() {
+    Package::new("b", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "b"
+                version = "1.0.0"
+                license = "MIT"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("a", "1.0.0")
+        .dep("b", "1.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "1.0.0"
+                license = "MIT OR Apache-2.0"
+
+                [dependencies]
+                b = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("c", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "c"
+                version = "1.0.0"
+                license = "GPL-3.0-only"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                a = "1.0"
+                c = { version = "1.0", optional = true }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                allowed-licenses = ["MIT"]
+            "#,
+        )
+        .build();
+    p.cargo("generate-lockfile").run();
+    p.cargo("fetch").run();
+
+    // `c` is only checked once the feature enabling it is.
+    p.cargo("audit-policy -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr("     Audited 2 packages against the policy\n")
+        .run();
+
+    p.cargo("audit-policy -Zunstable-options --features c")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `c v1.0.0` is licensed under `GPL-3.0-only`, which is not allowed by `policy.allowed-licenses`
+[NOTE] required by foo v0.1.0 ([ROOT]/foo) -> c v1.0.0
+[ERROR] 1 package does not comply with the policy
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn missing_license() {
+    Package::new("nolicense", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                nolicense = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                allowed-licenses = ["MIT"]
+            "#,
+        )
+        .build();
+    p.cargo("generate-lockfile").run();
+    p.cargo("fetch").run();
+
+    p.cargo("audit-policy -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `nolicense v1.0.0` has no license
+[NOTE] required by foo v0.1.0 ([ROOT]/foo) -> nolicense v1.0.0
+[ERROR] 1 package does not comply with the policy
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn denied_packages() {
+    Package::new("b", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "b"
+                version = "1.0.0"
+                license = "MIT"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("a", "1.0.0")
+        .dep("b", "1.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "1.0.0"
+                license = "MIT OR Apache-2.0"
+
+                [dependencies]
+                b = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("c", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "c"
+                version = "1.0.0"
+                license = "GPL-3.0-only"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                a = "1.0"
+                c = { version = "1.0", optional = true }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                denied-packages = ["b@1", "c"]
+            "#,
+        )
+        .build();
+    p.cargo("generate-lockfile").run();
+    p.cargo("fetch").run();
+
+    p.cargo("audit-policy -Zunstable-options --features c")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `c v1.0.0` is denied by `policy.denied-packages` (`c`)
+[NOTE] required by foo v0.1.0 ([ROOT]/foo) -> c v1.0.0
+[ERROR] `b v1.0.0` is denied by `policy.denied-packages` (`b@1`)
+[NOTE] required by foo v0.1.0 ([ROOT]/foo) -> a v1.0.0 -> b v1.0.0
+[ERROR] 2 packages do not comply with the policy
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn allowed_sources() {
+    Package::new("reg", "1.0.0").publish();
+    let git_project = git::new("gitdep", |project| {
+        project
+            .file("Cargo.toml", &basic_manifest("gitdep", "1.0.0"))
+            .file("src/lib.rs", "")
+    });
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.1.0"
+                    edition = "2015"
+
+                    [dependencies]
+                    reg = "1.0"
+                    gitdep = {{ git = '{}' }}
+                    local = {{ path = "local" }}
+                "#,
+                git_project.url()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .file("local/Cargo.toml", &basic_manifest("local", "1.0.0"))
+        .file("local/src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                allowed-sources = ["crates-io"]
+            "#,
+        )
+        .build();
+    p.cargo("generate-lockfile").run();
+    p.cargo("fetch").run();
+
+    p.cargo("audit-policy -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `gitdep v1.0.0 (file://[..]/gitdep#[..])` comes from `file://[..]/gitdep`, which is not in `policy.allowed-sources`
+[NOTE] required by foo v0.1.0 ([ROOT]/foo) -> gitdep v1.0.0 (file://[..]/gitdep#[..])
+[ERROR] 1 package does not comply with the policy
+",
+        )
+        .run();
+
+    // URL prefixes are allowed too.
+    p.change_file(
+        ".cargo/config.toml",
+        &format!(
+            r#"
+                [policy]
+                allowed-sources = ["crates-io", "{}"]
+            "#,
+            git_project.url()
+        ),
+    );
+    p.cargo("audit-policy -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr("     Audited 3 packages against the policy\n")
+        .run();
+}
+
+#[cargo_test]
+fn json_messages() {
+    Package::new("b", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "b"
+                version = "1.0.0"
+                license = "MIT"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("a", "1.0.0")
+        .dep("b", "1.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "1.0.0"
+                license = "MIT OR Apache-2.0"
+
+                [dependencies]
+                b = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("c", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "c"
+                version = "1.0.0"
+                license = "GPL-3.0-only"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                a = "1.0"
+                c = { version = "1.0", optional = true }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                allowed-licenses = ["Apache-2.0"]
+            "#,
+        )
+        .build();
+    p.cargo("generate-lockfile").run();
+    p.cargo("fetch").run();
+
+    p.cargo("audit-policy -Zunstable-options --message-format=json")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_json(
+            r#"
+                {
+                    "reason": "policy-violation",
+                    "package_id": "registry+https://github.com/rust-lang/crates.io-index#b@1.0.0",
+                    "rule": "allowed-licenses",
+                    "message": "`b v1.0.0` is licensed under `MIT`, which is not allowed by `policy.allowed-licenses`",
+                    "path": [
+                        "path+file://[..]/foo#0.1.0",
+                        "registry+https://github.com/rust-lang/crates.io-index#a@1.0.0",
+                        "registry+https://github.com/rust-lang/crates.io-index#b@1.0.0"
+                    ]
+                }
+            "#,
+        )
+        .with_stderr(
+            "\
+[ERROR] 1 package does not comply with the policy
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn works_offline() {
+    Package::new("b", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "b"
+                version = "1.0.0"
+                license = "MIT"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("a", "1.0.0")
+        .dep("b", "1.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "1.0.0"
+                license = "MIT OR Apache-2.0"
+
+                [dependencies]
+                b = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("c", "1.0.0")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "c"
+                version = "1.0.0"
+                license = "GPL-3.0-only"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                a = "1.0"
+                c = { version = "1.0", optional = true }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            r#"
+                [policy]
+                allowed-licenses = ["MIT"]
+            "#,
+        )
+        .build();
+    p.cargo("generate-lockfile").run();
+    p.cargo("fetch").run();
+
+    // Removing the registry must not matter once everything is cached.
+    registry_path().rm_rf();
+    p.cargo("audit-policy -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stderr("     Audited 2 packages against the policy\n")
+        .run();
+}
@@ -0,0 +1,417 @@
+//! Tests for `-Zbuild-cache`.
+
+use cargo_test_support::paths::CargoPathExt;
+use cargo_test_support::registry::Package;
+use cargo_test_support::{paths, project};
+
+#[cargo_test]
+fn gated() {
+    Package::new("bar", "1.0.0")
+        .file("src/lib.rs", "pub fn value() -> u32 { 1 }")
+        .publish();
+    let p = project()
+        .at("foo")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+
+    p.cargo("build").run();
+    assert!(!paths::home().join(".cargo/build-cache").exists());
+}
+
+#[cargo_test]
+fn shared_between_workspaces() {
+    Package::new("bar", "1.0.0")
+        .file("src/lib.rs", "pub fn value() -> u32 { 1 }")
+        .publish();
+    let foo = project()
+        .at("foo")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+    let foo2 = project()
+        .at("foo2")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo2"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+
+    foo.cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .with_stderr_contains("[RUNNING] `rustc --crate-name bar [..]")
+        .with_stderr_does_not_contain("[..]Restored[..]")
+        .run();
+    assert!(paths::home().join(".cargo/build-cache").is_dir());
+
+    foo2.cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .with_stderr_contains("[..]Restored bar v1.0.0 from the build cache")
+        .with_stderr_does_not_contain("[RUNNING] `rustc --crate-name bar [..]")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name foo2 [..]")
+        .run();
+    foo2.rename_run("foo2", "foo2_restored")
+        .with_stdout("1")
+        .run();
+
+    // Restored units are fresh afterwards.
+    foo2.cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .with_stderr_contains("[FRESH] bar v1.0.0")
+        .with_stderr_does_not_contain("[..]Restored[..]")
+        .run();
+}
+
+#[cargo_test]
+fn custom_path() {
+    Package::new("bar", "1.0.0")
+        .file("src/lib.rs", "pub fn value() -> u32 { 1 }")
+        .publish();
+    let foo = project()
+        .at("foo")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+    let foo2 = project()
+        .at("foo2")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo2"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+    let config = r#"
+        [build-cache]
+        path = "../shared-cache"
+    "#;
+    foo.change_file(".cargo/config.toml", config);
+    foo2.change_file(".cargo/config.toml", config);
+
+    foo.cargo("build -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .run();
+    assert!(paths::root().join("shared-cache").is_dir());
+    assert!(!paths::home().join(".cargo/build-cache").exists());
+
+    foo2.cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .with_stderr_contains("[..]Restored bar v1.0.0 from the build cache")
+        .run();
+}
+
+#[cargo_test]
+fn build_script_output_is_part_of_key() {
+    Package::new("bar", "1.0.0")
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    println!("cargo:rerun-if-env-changed=BAR_VALUE");
+                    let value = std::env::var("BAR_VALUE").unwrap();
+                    println!("cargo:rustc-env=BAR_VALUE={value}");
+                    let out = std::env::var("OUT_DIR").unwrap();
+                    std::fs::write(format!("{out}/value.rs"), value).unwrap();
+                }
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"
+                pub fn value() -> u32 {
+                    let out_dir: u32 = include!(concat!(env!("OUT_DIR"), "/value.rs"));
+                    assert_eq!(out_dir.to_string(), env!("BAR_VALUE"));
+                    out_dir
+                }
+            "#,
+        )
+        .publish();
+    let foo = project()
+        .at("foo")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+    let foo2 = project()
+        .at("foo2")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo2"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+    let foo3 = project()
+        .at("foo3")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo3"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+
+    foo.cargo("build -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .env("BAR_VALUE", "1")
+        .run();
+
+    // A different build script output can't reuse the entry.
+    foo2.cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .env("BAR_VALUE", "2")
+        .with_stderr_contains("[RUNNING] `[..]build-script-build`")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name bar [..]")
+        .run();
+    foo2.rename_run("foo2", "foo2_built").with_stdout("2").run();
+
+    // The same output can, even though the script still runs.
+    foo3.cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .env("BAR_VALUE", "1")
+        .with_stderr_contains("[RUNNING] `[..]build-script-build`")
+        .with_stderr_contains("[..]Restored bar v1.0.0 from the build cache")
+        .with_stderr_does_not_contain("[RUNNING] `rustc --crate-name bar [..]")
+        .run();
+    foo3.rename_run("foo3", "foo3_restored")
+        .with_stdout("1")
+        .run();
+}
+
+#[cargo_test]
+fn local_packages_are_not_cached() {
+    let bar = project()
+        .at("bar")
+        .file("Cargo.toml", &cargo_test_support::basic_lib_manifest("bar"))
+        .file("src/lib.rs", "pub fn value() -> u32 { 1 }")
+        .build();
+    let foo = project()
+        .at("foo")
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.1.0"
+                    edition = "2015"
+
+                    [dependencies]
+                    bar = {{ path = "{}" }}
+                "#,
+                bar.root().display()
+            ),
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+    let foo2 = project()
+        .at("foo2")
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo2"
+                    version = "0.1.0"
+                    edition = "2015"
+
+                    [dependencies]
+                    bar = {{ path = "{}" }}
+                "#,
+                bar.root().display()
+            ),
+        )
+        .file(
+            "src/main.rs",
+            r#"fn main() { println!("{}", bar::value()); }"#,
+        )
+        .build();
+
+    foo.cargo("build -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .run();
+    foo2.cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .with_stderr_contains("[RUNNING] `rustc --crate-name bar [..]")
+        .with_stderr_does_not_contain("[..]Restored[..]")
+        .run();
+    let cache = paths::home().join(".cargo/build-cache");
+    assert!(!cache.exists() || cache.ls_r().is_empty());
+}
+
+#[cargo_test]
+fn env_deps_are_part_of_key() {
+    Package::new("bar", "1.0.0")
+        .file(
+            "src/lib.rs",
+            r#"pub fn value() -> &'static str { option_env!("BAR_VALUE").unwrap_or("unset") }"#,
+        )
+        .publish();
+    let mut ps = Vec::new();
+    for name in ["foo", "foo2", "foo3", "foo4"] {
+        ps.push(
+            project()
+                .at(name)
+                .file(
+                    "Cargo.toml",
+                    &format!(
+                        r#"
+                            [package]
+                            name = "{name}"
+                            version = "0.1.0"
+                            edition = "2015"
+
+                            [dependencies]
+                            bar = "1.0"
+                        "#
+                    ),
+                )
+                .file(
+                    "src/main.rs",
+                    r#"fn main() { println!("{}", bar::value()); }"#,
+                )
+                .build(),
+        );
+    }
+
+    ps[0]
+        .cargo("build -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .env("BAR_VALUE", "1")
+        .run();
+
+    // The entry was compiled with another value.
+    ps[1]
+        .cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .env("BAR_VALUE", "2")
+        .with_stderr_contains("[RUNNING] `rustc --crate-name bar [..]")
+        .with_stderr_does_not_contain("[..]Restored[..]")
+        .run();
+    ps[1]
+        .rename_run("foo2", "foo2_built")
+        .with_stdout("2")
+        .run();
+
+    // Both values have an entry of their own.
+    ps[2]
+        .cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .env("BAR_VALUE", "1")
+        .with_stderr_contains("[..]Restored bar v1.0.0 from the build cache")
+        .run();
+    ps[2]
+        .rename_run("foo3", "foo3_restored")
+        .with_stdout("1")
+        .run();
+    ps[3]
+        .cargo("build -v -Zbuild-cache")
+        .masquerade_as_nightly_cargo(&["build-cache"])
+        .env("BAR_VALUE", "2")
+        .with_stderr_contains("[..]Restored bar v1.0.0 from the build cache")
+        .run();
+    ps[3]
+        .rename_run("foo4", "foo4_restored")
+        .with_stdout("2")
+        .run();
+}
@@ -0,0 +1,214 @@
+//! Tests for `-Zbuild-events`.
+
+use std::process::Stdio;
+
+use crate::config::GlobalContextBuilder;
+use cargo::util::cache_lock::CacheLockMode;
+use cargo_test_support::registry::Package;
+use cargo_test_support::{basic_lib_manifest, project, sleep_ms, thread_wait_timeout};
+
+#[cargo_test]
+fn gated() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("check --message-format=json -Zbuild-events")
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the `-Z` flag is only accepted on the nightly channel of Cargo[..]",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn not_emitted_by_default() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("check --message-format=json")
+        .with_stdout_does_not_contain("[..]\"reason\":\"unit-started\"[..]")
+        .with_stdout_does_not_contain("[..]\"reason\":\"job-queue-concurrency\"[..]")
+        .run();
+
+    // The events are only emitted as JSON messages.
+    p.cargo("check -Zbuild-events")
+        .masquerade_as_nightly_cargo(&["build-events"])
+        .with_stdout("")
+        .run();
+}
+
+#[cargo_test]
+fn units() {
+    let p = project()
+        .file("Cargo.toml", &basic_lib_manifest("foo"))
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check --message-format=json -Zbuild-events")
+        .masquerade_as_nightly_cargo(&["build-events"])
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "unit-started",
+                    "package_id": "path+file:///[..]/foo#0.5.0",
+                    "target": "{...}",
+                    "mode": "check",
+                    "fresh": false,
+                    "dirty_reason": "fresh-build"
+                }
+
+                {
+                    "reason": "job-queue-concurrency",
+                    "active": 1,
+                    "waiting": 0,
+                    "inactive": 0
+                }
+
+                {
+                    "reason": "unit-finished",
+                    "package_id": "path+file:///[..]/foo#0.5.0",
+                    "target": "{...}",
+                    "mode": "check",
+                    "success": true
+                }
+
+                {
+                    "reason": "job-queue-concurrency",
+                    "active": 0,
+                    "waiting": 0,
+                    "inactive": 0
+                }
+            "#,
+        )
+        .run();
+
+    p.cargo("check --message-format=json -Zbuild-events")
+        .masquerade_as_nightly_cargo(&["build-events"])
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "unit-started",
+                    "package_id": "path+file:///[..]/foo#0.5.0",
+                    "target": "{...}",
+                    "mode": "check",
+                    "fresh": true
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn failed_unit() {
+    let p = project()
+        .file("Cargo.toml", &basic_lib_manifest("foo"))
+        .file("src/lib.rs", "invalid")
+        .build();
+
+    p.cargo("check --message-format=json -Zbuild-events")
+        .masquerade_as_nightly_cargo(&["build-events"])
+        .with_status(101)
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "unit-finished",
+                    "package_id": "path+file:///[..]/foo#0.5.0",
+                    "target": "{...}",
+                    "mode": "check",
+                    "success": false
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn downloads() {
+    Package::new("bar", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("check --message-format=json -Zbuild-events")
+        .masquerade_as_nightly_cargo(&["build-events"])
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "download-started",
+                    "package_id": "registry+https://github.com/rust-lang/crates.io-index#bar@1.0.0",
+                    "url": "[..]/dl/bar/1.0.0/download"
+                }
+
+                {
+                    "reason": "download-finished",
+                    "package_id": "registry+https://github.com/rust-lang/crates.io-index#bar@1.0.0",
+                    "bytes": "{...}",
+                    "duration": "{...}"
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn lock_wait() {
+    Package::new("bar", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    let gctx = GlobalContextBuilder::new().build();
+    let lock = gctx
+        .acquire_package_cache_lock(CacheLockMode::DownloadExclusive)
+        .unwrap();
+    let mut cmd = p
+        .cargo("check --message-format=json -Zbuild-events")
+        .masquerade_as_nightly_cargo(&["build-events"])
+        .build_command();
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let child = cmd.spawn().unwrap();
+
+    // Give cargo a chance to block on the lock.
+    sleep_ms(500);
+    drop(lock);
+
+    let thread = std::thread::spawn(|| child.wait_with_output().unwrap());
+    let output = thread_wait_timeout(100, thread);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let events: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .filter(|msg: &serde_json::Value| {
+            msg["reason"]
+                .as_str()
+                .is_some_and(|reason| reason.starts_with("lock-wait-"))
+        })
+        .collect();
+    assert_eq!(events.len(), 2, "unexpected lock events in {stdout}");
+    assert_eq!(events[0]["reason"], "lock-wait-started");
+    assert_eq!(events[0]["description"], "package cache");
+    assert_eq!(events[1]["reason"], "lock-wait-finished");
+    assert_eq!(events[0]["path"], events[1]["path"]);
+    assert!(events[1]["duration"].as_f64().unwrap() > 0.0);
+}
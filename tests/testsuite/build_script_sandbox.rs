@@ -0,0 +1,276 @@
+//! Tests for `-Zbuild-script-sandbox`.
+
+#![cfg(target_os = "linux")]
+
+use cargo_test_support::{paths, project};
+
+/// Whether the sandbox can't run here, on other architectures or kernels
+/// without Landlock.
+fn sandbox_disabled() -> bool {
+    if !cfg!(any(target_arch = "x86_64", target_arch = "aarch64")) {
+        return true;
+    }
+    // Asks for the Landlock ABI version, like cargo does.
+    // SAFETY: with `LANDLOCK_CREATE_RULESET_VERSION`, the attributes must be
+    // null and their size 0, and nothing is created.
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<u8>(),
+            0,
+            1u32,
+        )
+    };
+    abi < 1
+}
+
+#[cargo_test]
+fn gated() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file("build.rs", "fn main() {}")
+        .build();
+    p.cargo("build -Zbuild-script-sandbox")
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the `-Z` flag is only accepted on the nightly channel of Cargo[..]",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn allowed_accesses() {
+    if sandbox_disabled() {
+        return;
+    }
+
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"
+                use std::env;
+                use std::fs;
+                use std::path::Path;
+                use std::process::Command;
+
+                fn main() {
+                    let out_dir = env::var("OUT_DIR").unwrap();
+                    fs::write(Path::new(&out_dir).join("generated.rs"), "").unwrap();
+                    let tmp = env::temp_dir().join("scratch");
+                    fs::write(&tmp, "scratch").unwrap();
+                    fs::remove_file(&tmp).unwrap();
+                    fs::read_to_string("src/lib.rs").unwrap();
+                    let rustc = env::var("RUSTC").unwrap();
+                    let status = Command::new(rustc).arg("--version").status().unwrap();
+                    assert!(status.success());
+                }
+            "#,
+        )
+        .build();
+    p.cargo("build -Zbuild-script-sandbox")
+        .masquerade_as_nightly_cargo(&["build-script-sandbox"])
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([ROOT]/foo)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn write_outside_out_dir() {
+    if sandbox_disabled() {
+        return;
+    }
+
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    std::fs::write("generated.rs", "").unwrap();
+                }
+            "#,
+        )
+        .build();
+    p.cargo("build -Zbuild-script-sandbox")
+        .masquerade_as_nightly_cargo(&["build-script-sandbox"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] build script of `foo v0.0.1 ([ROOT]/foo)` was denied access outside of its sandbox:
+  it tried to write to `[ROOT]/foo/generated.rs`
+note: build scripts can only write to `[ROOT]/foo/target/debug/build/foo-[..]/out` and `[ROOT]/foo/target/debug/build/foo-[..]/tmp`, and read from paths declared in `build-scripts.sandbox.read`
+
+Caused by:
+  failed to run custom build command for `foo v0.0.1 ([ROOT]/foo)`
+",
+        )
+        .run();
+    assert!(!p.root().join("generated.rs").exists());
+
+    // The build still fails when the build script ignores the error.
+    p.change_file(
+        "build.rs",
+        r#"
+            fn main() {
+                let _ = std::fs::write("generated.rs", "");
+            }
+        "#,
+    );
+    p.cargo("build -Zbuild-script-sandbox")
+        .masquerade_as_nightly_cargo(&["build-script-sandbox"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] build script of `foo v0.0.1 ([ROOT]/foo)` was denied access outside of its sandbox:
+  it tried to write to `[ROOT]/foo/generated.rs`
+",
+        )
+        .with_stderr_does_not_contain("[..]Caused by[..]")
+        .run();
+
+    // Without the sandbox, the build script can write anywhere.
+    p.cargo("build").run();
+    assert!(p.root().join("generated.rs").exists());
+}
+
+#[cargo_test]
+fn network() {
+    if sandbox_disabled() {
+        return;
+    }
+
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    let _ = std::net::TcpStream::connect("127.0.0.1:1");
+                }
+            "#,
+        )
+        .build();
+    p.cargo("build -Zbuild-script-sandbox")
+        .masquerade_as_nightly_cargo(&["build-script-sandbox"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] build script of `foo v0.0.1 ([ROOT]/foo)` was denied access outside of its sandbox:
+  it tried to access the network
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn declared_read_paths() {
+    if sandbox_disabled() {
+        return;
+    }
+
+    std::fs::write(paths::root().join("secret.txt"), "secret").unwrap();
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    std::fs::read_to_string("../secret.txt").unwrap();
+                }
+            "#,
+        )
+        .build();
+    p.cargo("build -Zbuild-script-sandbox")
+        .masquerade_as_nightly_cargo(&["build-script-sandbox"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] build script of `foo v0.0.1 ([ROOT]/foo)` was denied access outside of its sandbox:
+  it tried to read from `[ROOT]/secret.txt`
+",
+        )
+        .run();
+
+    // Paths are relative to the directory containing the `.cargo` directory.
+    p.change_file(
+        ".cargo/config.toml",
+        r#"
+            [build-scripts.sandbox]
+            read = ["../secret.txt"]
+        "#,
+    );
+    p.cargo("build -Zbuild-script-sandbox")
+        .masquerade_as_nightly_cargo(&["build-script-sandbox"])
+        .run();
+}
+
+#[cargo_test]
+fn denied_read_is_a_warning() {
+    if sandbox_disabled() {
+        return;
+    }
+
+    std::fs::write(paths::root().join("secret.txt"), "secret").unwrap();
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            r#"
+                fn main() {
+                    let _ = std::fs::read_to_string("../secret.txt");
+                }
+            "#,
+        )
+        .build();
+    p.cargo("build -Zbuild-script-sandbox")
+        .masquerade_as_nightly_cargo(&["build-script-sandbox"])
+        .with_stderr(
+            "\
+[COMPILING] foo v0.0.1 ([ROOT]/foo)
+[WARNING] build script was denied access outside of its sandbox: it tried to read from `[ROOT]/secret.txt`
+[WARNING] `foo` (build script) generated 1 warning
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn cargo_home_credentials() {
+    if sandbox_disabled() {
+        return;
+    }
+
+    let credentials = paths::home().join(".cargo/credentials.toml");
+    std::fs::create_dir_all(credentials.parent().unwrap()).unwrap();
+    std::fs::write(&credentials, "[registry]\ntoken = \"secret\"\n").unwrap();
+    let p = project()
+        .file("src/lib.rs", "")
+        .file(
+            "build.rs",
+            &format!(
+                r#"
+                    fn main() {{
+                        std::fs::read_to_string({:?}).unwrap();
+                    }}
+                "#,
+                credentials
+            ),
+        )
+        .build();
+    p.cargo("build -Zbuild-script-sandbox")
+        .masquerade_as_nightly_cargo(&["build-script-sandbox"])
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] build script of `foo v0.0.1 ([ROOT]/foo)` was denied access outside of its sandbox:
+  it tried to read from `[ROOT]/home/.cargo/credentials.toml`
+",
+        )
+        .run();
+}
@@ -298,7 +298,13 @@ fn show_origin_toml_cli() {
         .cwd(&sub_folder.parent().unwrap())
         .masquerade_as_nightly_cargo(&["cargo-config"])
         .env("CARGO_BUILD_JOBS", "1")
-        .with_stdout("build.jobs = 123 # --config cli option")
+        .with_stdout(
+            "\
+build.jobs = 123 # --config cli option
+# build.jobs = 1 # environment variable `CARGO_BUILD_JOBS` (overridden)
+# build.jobs = 99 # [ROOT]/home/.cargo/config.toml (overridden)
+",
+        )
         .with_stderr("")
         .run();
 
@@ -323,14 +329,133 @@ build.rustflags = [
         .run();
 }
 
+#[cargo_test]
+fn show_origin_toml_overridden() {
+    let sub_folder = common_setup();
+    write_config_at(
+        sub_folder.join("config.toml"),
+        "
+        [build]
+        jobs = 4
+        rustflags = [\"--flag-directory\"]
+        ",
+    );
+    cargo_process("config get --show-origin build -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stdout(
+            "\
+build.jobs = 4 # [ROOT]/foo/.cargo/config.toml
+# build.jobs = 99 # [ROOT]/home/.cargo/config.toml (overridden)
+build.rustflags = [
+    \"--flag-global\", # [ROOT]/home/.cargo/config.toml
+    \"--flag-directory\", # [ROOT]/foo/.cargo/config.toml
+]
+",
+        )
+        .with_stderr("")
+        .run();
+
+    // Environment variables are shown for the values of tables.
+    cargo_process("config get --show-origin build -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .env("CARGO_BUILD_JOBS", "100")
+        .with_stdout(
+            "\
+build.jobs = 100 # environment variable `CARGO_BUILD_JOBS`
+# build.jobs = 4 # [ROOT]/foo/.cargo/config.toml (overridden)
+# build.jobs = 99 # [ROOT]/home/.cargo/config.toml (overridden)
+build.rustflags = [
+    \"--flag-global\", # [ROOT]/home/.cargo/config.toml
+    \"--flag-directory\", # [ROOT]/foo/.cargo/config.toml
+]
+# The following environment variables may affect the loaded values.
+# CARGO_BUILD_JOBS=100
+",
+        )
+        .with_stderr("")
+        .run();
+}
+
 #[cargo_test]
 fn show_origin_json() {
     let sub_folder = common_setup();
-    cargo_process("config get --show-origin --format=json -Zunstable-options")
+    cargo_process("config get --show-origin --format=json build -Zunstable-options")
+        .arg("--config=build.rustflags=[\"--flag-cli\"]")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .env("CARGO_BUILD_JOBS", "100")
+        .with_json(
+            r#"
+            {
+              "version": 1,
+              "values": [
+                {
+                  "key": "build.jobs",
+                  "value": 100,
+                  "definition": {
+                    "kind": "environment",
+                    "name": "CARGO_BUILD_JOBS"
+                  },
+                  "overridden": [
+                    {
+                      "value": 99,
+                      "definition": {
+                        "kind": "file",
+                        "path": "[ROOT]/home/.cargo/config.toml"
+                      }
+                    }
+                  ]
+                },
+                {
+                  "key": "build.rustflags",
+                  "value": ["--flag-global", "--flag-directory", "--flag-cli"],
+                  "elements": [
+                    {
+                      "value": "--flag-global",
+                      "definition": {
+                        "kind": "file",
+                        "path": "[ROOT]/home/.cargo/config.toml"
+                      }
+                    },
+                    {
+                      "value": "--flag-directory",
+                      "definition": {
+                        "kind": "file",
+                        "path": "[ROOT]/foo/.cargo/config.toml"
+                      }
+                    },
+                    {
+                      "value": "--flag-cli",
+                      "definition": {
+                        "kind": "cli",
+                        "path": null
+                      }
+                    }
+                  ],
+                  "overridden": []
+                }
+              ]
+            }
+            "#,
+        )
+        .with_stderr(
+            "\
+note: The following environment variables may affect the loaded values.
+CARGO_BUILD_JOBS=100
+",
+        )
+        .run();
+
+    cargo_process("config get --show-origin --format=json-value -Zunstable-options")
         .cwd(&sub_folder.parent().unwrap())
         .masquerade_as_nightly_cargo(&["cargo-config"])
         .with_status(101)
-        .with_stderr("error: the `json` format does not support --show-origin, try the `toml` format instead")
+        .with_stderr(
+            "error: the `json-value` format does not support --show-origin, \
+             try the `toml` or `json` format instead",
+        )
         .run();
 }
 
@@ -520,3 +645,72 @@ target.\"cfg(target_os = \\\"linux\\\")\".runner = \"runme\"
         .with_stderr("")
         .run();
 }
+
+#[cargo_test]
+fn check() {
+    let sub_folder = common_setup();
+    cargo_process("config check -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_status(101)
+        .with_stderr(
+            "\
+error: unknown config key `extra-table` in `[ROOT]/home/.cargo/config.toml`
+error: the configuration has 1 unknown key
+",
+        )
+        .run();
+
+    write_config_at(
+        paths::home().join(".cargo/config.toml"),
+        "
+        [build]
+        jobs = 99
+        ",
+    );
+    cargo_process("config check -Zunstable-options")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_stdout("")
+        .with_stderr("")
+        .run();
+
+    cargo_process("config check -Zunstable-options --config build.rustflag=[]")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config"])
+        .with_status(101)
+        .with_stderr(
+            "\
+error: unknown config key `build.rustflag` in `--config cli option`
+
+<tab>Did you mean `build.rustflags`?
+error: the configuration has 1 unknown key
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn when() {
+    let sub_folder = common_setup();
+    write_config_at(
+        sub_folder.join("config.toml"),
+        "
+        [when.'cfg(all())'.build]
+        jobs = 4
+        [when.'cfg(any())'.build]
+        jobs = 8
+        ",
+    );
+    cargo_process("config get --show-origin build.jobs -Zunstable-options -Zconfig-when")
+        .cwd(&sub_folder.parent().unwrap())
+        .masquerade_as_nightly_cargo(&["cargo-config", "config-when"])
+        .with_stdout(
+            "\
+build.jobs = 4 # [ROOT]/foo/.cargo/config.toml
+# build.jobs = 99 # [ROOT]/home/.cargo/config.toml (overridden)
+",
+        )
+        .with_stderr("")
+        .run();
+}
@@ -0,0 +1,386 @@
+//! Tests for `cargo clean --stale` and `--max-age` with `-Zclean-stale`.
+
+use cargo_test_support::registry::{registry_path, Package};
+use cargo_test_support::{project, Project};
+
+/// Sets every entry of the last-use file of the `dev` profile to the epoch.
+fn forget_last_uses(p: &Project) {
+    let path = p.build_dir().join("debug/.last-use");
+    let last_use: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let last_use: serde_json::Map<_, _> =
+        last_use.into_iter().map(|(k, _)| (k, 0.into())).collect();
+    std::fs::write(&path, serde_json::to_string(&last_use).unwrap()).unwrap();
+}
+
+fn last_use_entries(p: &Project) -> usize {
+    let path = p.build_dir().join("debug/.last-use");
+    let last_use: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    last_use.len()
+}
+
+#[cargo_test]
+fn gated() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("clean --stale")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--stale` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+    p.cargo("clean --stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--stale` flag is unstable, pass `-Z clean-stale` to enable it
+",
+        )
+        .run();
+    p.cargo("clean --max-age=1day")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--max-age` flag is unstable, pass `-Z clean-stale` to enable it
+",
+        )
+        .run();
+    p.cargo("clean --stale -p foo -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_status(1)
+        .with_stderr_contains(
+            "[ERROR] the argument '--stale' cannot be used with '--package [<SPEC>]'",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn stale() {
+    Package::new("bar", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("build").run();
+    Package::new("bar", "1.0.1").publish();
+    p.cargo("update").run();
+    p.cargo("build").run();
+    assert_eq!(p.glob("target/debug/.fingerprint/bar-*").count(), 2);
+
+    p.cargo("clean --stale -Zclean-stale --dry-run")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr(
+            "\
+[SUMMARY] [..] files, [..] total
+[WARNING] no files deleted due to --dry-run
+",
+        )
+        .run();
+    assert_eq!(p.glob("target/debug/.fingerprint/bar-*").count(), 2);
+
+    // The units of `bar` 1.0.0, and of `foo` depending on it, are gone.
+    p.cargo("clean --stale -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] [..] files, [..] total\n")
+        .run();
+    assert_eq!(p.glob("target/debug/.fingerprint/bar-*").count(), 1);
+    assert_eq!(p.glob("target/debug/.fingerprint/foo-*").count(), 1);
+    assert_eq!(p.glob("target/debug/deps/libbar-*.rlib").count(), 1);
+    p.cargo("build")
+        .with_stderr("[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]\n")
+        .run();
+}
+
+#[cargo_test]
+fn other_builds_are_kept() {
+    Package::new("bar", "1.0.0").publish();
+    Package::new("baz", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["foo", "qux"]
+            "#,
+        )
+        .file(
+            "foo/Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = { version = "1.0", optional = true }
+                baz = "1.0"
+
+                [features]
+                extra = ["bar"]
+            "#,
+        )
+        .file("foo/src/lib.rs", "")
+        .file(
+            "qux/Cargo.toml",
+            r#"
+                [package]
+                name = "qux"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                baz = { version = "1.0", features = [] }
+            "#,
+        )
+        .file("qux/src/main.rs", "fn main() {}")
+        .build();
+    let commands = [
+        "build",
+        "build -p foo",
+        "check --all-targets",
+        "test --no-run",
+        "doc",
+    ];
+    for command in commands {
+        p.cargo(command).run();
+    }
+
+    // Whatever the command that built them, none of the units are stale.
+    p.cargo("clean --stale -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] 0 files\n")
+        .run();
+    for command in commands {
+        p.cargo(command)
+            .with_stderr_does_not_contain("[COMPILING] [..]")
+            .with_stderr_does_not_contain("[CHECKING] [..]")
+            .with_stderr_does_not_contain("[DOCUMENTING] [..]")
+            .run();
+    }
+}
+
+#[cargo_test]
+fn disabled_dependency_feature() {
+    Package::new("bar", "1.0.0")
+        .feature("extra", &[])
+        .file("src/lib.rs", "")
+        .publish();
+    Package::new("baz", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = { version = "1.0", features = ["extra"] }
+                baz = { version = "1.0", optional = true }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("build").run();
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "1.0"
+            baz = { version = "1.0", optional = true }
+        "#,
+    );
+    p.cargo("build").run();
+    p.cargo("build --features baz").run();
+    assert_eq!(p.glob("target/debug/deps/libbar-*.rlib").count(), 2);
+
+    // The build of `bar` with its `extra` feature is gone, and `baz` is only
+    // kept when its feature is enabled.
+    p.cargo("clean --stale -Zclean-stale --features baz")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] [..] files, [..] total\n")
+        .run();
+    assert_eq!(p.glob("target/debug/deps/libbar-*.rlib").count(), 1);
+    assert_eq!(p.glob("target/debug/deps/libbaz-*.rlib").count(), 1);
+    p.cargo("build --features baz")
+        .with_stderr("[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]\n")
+        .run();
+
+    p.cargo("clean --stale -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] [..] files, [..] total\n")
+        .run();
+    assert_eq!(p.glob("target/debug/deps/libbar-*.rlib").count(), 1);
+    assert_eq!(p.glob("target/debug/deps/libbaz-*.rlib").count(), 0);
+    p.cargo("build")
+        .with_stderr(
+            "\
+[COMPILING] foo v0.1.0 ([ROOT]/foo)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+",
+        )
+        .run();
+
+    p.cargo("clean --features baz")
+        .with_status(101)
+        .with_stderr("[ERROR] feature selection flags can only be used with `--stale`\n")
+        .run();
+}
+
+#[cargo_test]
+fn stale_only_reads_the_lockfile() {
+    Package::new("bar", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("build").run();
+
+    // Neither the index nor the network are needed.
+    std::fs::remove_dir_all(registry_path()).unwrap();
+    p.cargo("clean --stale -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] 0 files\n")
+        .run();
+
+    std::fs::remove_file(p.root().join("Cargo.lock")).unwrap();
+    p.cargo("clean --stale -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `cargo clean --stale` needs a lockfile to tell which artifacts are stale, run `cargo generate-lockfile` to create one
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn max_age() {
+    Package::new("bar", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = { version = "1.0", optional = true }
+
+                [features]
+                extra = ["bar"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("build --features extra -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .run();
+    p.cargo("build -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .run();
+    assert_eq!(last_use_entries(&p), 3);
+
+    // Only the units used since then are kept.
+    forget_last_uses(&p);
+    p.cargo("build -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .run();
+    p.cargo("clean --max-age=1day -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] [..] files, [..] total\n")
+        .run();
+    assert_eq!(p.glob("target/debug/deps/libbar-*").count(), 0);
+    assert_eq!(last_use_entries(&p), 1);
+    p.cargo("build")
+        .with_stderr("[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]\n")
+        .run();
+
+    // Artifacts recently used by untracked builds are kept.
+    p.cargo("build --features extra").run();
+    p.cargo("clean --max-age=1day -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] 0 files\n")
+        .run();
+}
+
+#[cargo_test]
+fn stale_and_max_age() {
+    Package::new("bar", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("build -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .run();
+    Package::new("bar", "1.0.1").publish();
+    p.cargo("update").run();
+    p.cargo("build -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .run();
+
+    // The units of `bar` 1.0.0 are stale, but used recently.
+    p.cargo("clean --stale --max-age=1day -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] 0 files\n")
+        .run();
+
+    forget_last_uses(&p);
+    p.cargo("clean --stale --max-age=1day -Zclean-stale")
+        .masquerade_as_nightly_cargo(&["clean-stale"])
+        .with_stderr("[REMOVED] [..] files, [..] total\n")
+        .run();
+    assert_eq!(p.glob("target/debug/deps/libbar-*.rlib").count(), 1);
+    // The current units are kept, even though they weren't used recently.
+    p.cargo("build")
+        .with_stderr("[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]\n")
+        .run();
+}
@@ -6,7 +6,7 @@ use cargo::util::context::{
 };
 use cargo::CargoResult;
 use cargo_test_support::compare;
-use cargo_test_support::{paths, project, symlink_supported, t};
+use cargo_test_support::{paths, project, rustc_host, symlink_supported, t};
 use cargo_util_schemas::manifest::TomlTrimPaths;
 use cargo_util_schemas::manifest::TomlTrimPathsValue;
 use cargo_util_schemas::manifest::{self as cargo_toml, TomlDebugInfo, VecStringOrBool as VSOB};
@@ -435,6 +435,256 @@ warning: unused config key `S.unused` in `[..]/.cargo/config.toml`
     compare::assert_match_exact(expected, &output);
 }
 
+#[cargo_test]
+fn config_check_unknown_keys() {
+    write_config_toml(
+        "\
+[buid]
+jobs = 1
+
+[build]
+target-dri = 'foo'
+
+[alias]
+anything = 'build'
+
+[profile.dev.package.bar]
+opt-levl = 1
+
+[target.x86_64-unknown-linux-gnu]
+linkr = 'cc'
+
+[target.x86_64-unknown-linux-gnu.foo]
+rustc-link-lib = ['foo']
+",
+    );
+
+    // Unknown keys are ignored without the flag.
+    let gctx = GlobalContextBuilder::new().build();
+    assert_eq!(read_output(gctx), "");
+
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-check")
+        .config_arg("net.retyr=2")
+        .build();
+    let output = read_output(gctx);
+    let expected = "\
+warning: unknown config key `buid` in `[..]/.cargo/config.toml`
+
+<tab>Did you mean `build`?
+warning: unknown config key `build.target-dri` in `[..]/.cargo/config.toml`
+
+<tab>Did you mean `build.target-dir`?
+warning: unknown config key `net.retyr` in `--config cli option`
+
+<tab>Did you mean `net.retry`?
+warning: unknown config key `profile.dev.package.bar.opt-levl` in `[..]/.cargo/config.toml`
+
+<tab>Did you mean `profile.dev.package.bar.opt-level`?
+warning: unknown config key `target.x86_64-unknown-linux-gnu.linkr` in `[..]/.cargo/config.toml`
+
+<tab>Did you mean `target.x86_64-unknown-linux-gnu.linker`?
+";
+    compare::assert_match_exact(expected, &output);
+}
+
+#[cargo_test]
+fn config_when() {
+    write_config_toml(&format!(
+        "\
+[build]
+jobs = 1
+rustflags = ['--base']
+
+[when.'cfg(unix)'.build]
+jobs = 2
+
+[when.'cfg(windows)'.build]
+jobs = 3
+
+[when.'cfg(env = \"CARGO_TEST_CI\")'.build]
+rustflags = ['--ci']
+
+[when.'{host}'.alias]
+on-host = 'build'
+
+[when.'unknown-unknown-unknown'.alias]
+on-other = 'build'
+",
+        host = rustc_host()
+    ));
+
+    // The `when` tables are ignored without the flag.
+    let gctx = GlobalContextBuilder::new().build();
+    assert_eq!(gctx.get::<i32>("build.jobs").unwrap(), 1);
+    assert_eq!(gctx.get::<Option<String>>("alias.on-host").unwrap(), None);
+
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-when")
+        .build();
+    let jobs = if cfg!(windows) { 3 } else { 2 };
+    assert_eq!(gctx.get::<i32>("build.jobs").unwrap(), jobs);
+    assert_eq!(
+        gctx.get::<Vec<String>>("build.rustflags").unwrap(),
+        ["--base"]
+    );
+    assert_eq!(
+        gctx.get::<Option<String>>("alias.on-host")
+            .unwrap()
+            .as_deref(),
+        Some("build")
+    );
+    assert_eq!(gctx.get::<Option<String>>("alias.on-other").unwrap(), None);
+
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-when")
+        .env("CARGO_TEST_CI", "1")
+        .build();
+    // Arrays are replaced, not appended to.
+    assert_eq!(
+        gctx.get::<Vec<String>>("build.rustflags").unwrap(),
+        ["--ci"]
+    );
+}
+
+#[cargo_test]
+fn config_when_from_unstable_table() {
+    write_config_toml(
+        "\
+[unstable]
+config-when = true
+
+[build]
+jobs = 1
+
+[when.'cfg(all())'.build]
+jobs = 2
+",
+    );
+    let gctx = GlobalContextBuilder::new().build();
+    assert_eq!(gctx.get::<i32>("build.jobs").unwrap(), 1);
+
+    let gctx = GlobalContextBuilder::new()
+        .nightly_features_allowed(true)
+        .build();
+    assert_eq!(gctx.get::<i32>("build.jobs").unwrap(), 2);
+}
+
+#[cargo_test]
+fn config_when_target_vendor() {
+    let vendor = if cfg!(target_vendor = "apple") {
+        "apple"
+    } else if cfg!(target_vendor = "pc") {
+        "pc"
+    } else {
+        "unknown"
+    };
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-when")
+        .config_arg("build.jobs=1")
+        .config_arg(format!(
+            "when.'cfg(target_vendor = \"{vendor}\")'.build.jobs=2"
+        ))
+        .build();
+    assert_eq!(gctx.get::<i32>("build.jobs").unwrap(), 2);
+}
+
+#[cargo_test]
+fn config_when_cli() {
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-when")
+        .config_arg("build.jobs=1")
+        .config_arg("when.'cfg(all())'.build.jobs=2")
+        .config_arg("when.'cfg(any())'.build.jobs=3")
+        .build();
+    assert_eq!(gctx.get::<i32>("build.jobs").unwrap(), 2);
+}
+
+#[cargo_test]
+fn config_when_invalid() {
+    write_config_toml(
+        "\
+[when.'cfg(unix'.build]
+jobs = 2
+",
+    );
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-when")
+        .build_err();
+    assert_error(
+        gctx.unwrap_err(),
+        "\
+could not load Cargo configuration
+
+Caused by:
+  failed to parse the condition `cfg(unix` of `when` in [..]/.cargo/config.toml
+
+Caused by:
+  failed to parse `cfg(unix` as a cfg expression: invalid target specifier: unexpected `(` character, cfg expressions must start with `cfg(`",
+    );
+
+    write_config_toml(
+        "\
+[when]
+'cfg(unix)' = 2
+'cfg(windows)' = 2
+",
+    );
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-when")
+        .build_err();
+    assert_error(
+        gctx.unwrap_err(),
+        "\
+could not load Cargo configuration
+
+Caused by:
+  expected a table for `when.'cfg([..])'` in [..]/.cargo/config.toml, but found integer",
+    );
+
+    write_config_toml(
+        "\
+[when.'cfg(any(unix, debug_assertions))'.build]
+jobs = 2
+",
+    );
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-when")
+        .build_err();
+    assert_error(
+        gctx.unwrap_err(),
+        "\
+could not load Cargo configuration
+
+Caused by:
+  failed to parse the condition `cfg(any(unix, debug_assertions))` of `when` in [..]/.cargo/config.toml
+
+Caused by:
+  unsupported `cfg` name `debug_assertions`, only `unix` and `windows` are supported",
+    );
+
+    write_config_toml(
+        "\
+[when.'cfg(target_feature = \"sse2\")'.build]
+jobs = 2
+",
+    );
+    let gctx = GlobalContextBuilder::new()
+        .unstable_flag("config-when")
+        .build_err();
+    assert_error(
+        gctx.unwrap_err(),
+        "\
+could not load Cargo configuration
+
+Caused by:
+  failed to parse the condition `cfg(target_feature = \"sse2\")` of `when` in [..]/.cargo/config.toml
+
+Caused by:
+  unsupported `cfg` key `target_feature`, only `env`, `target_arch`, `target_endian`, `target_env`, `target_family`, `target_os`, `target_pointer_width`, `target_vendor` are supported",
+    );
+}
+
 #[cargo_test]
 fn config_load_toml_profile() {
     write_config_toml(
@@ -0,0 +1,147 @@
+//! Tests for `--explain-rebuild`.
+
+use cargo_test_support::{basic_manifest, project, sleep_ms};
+
+#[cargo_test]
+fn gated() {
+    let p = project().file("src/main.rs", "fn main() {}").build();
+    p.cargo("build --explain-rebuild")
+        .masquerade_as_nightly_cargo(&["explain-rebuild"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--explain-rebuild` flag is unstable, pass `-Z unstable-options` to enable it
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn dependency_chain() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/main.rs", "fn main() { bar::bar(); }")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .build();
+    p.cargo("build").run();
+
+    p.cargo("build --explain-rebuild -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["explain-rebuild"])
+        .with_stderr(
+            "\
+[NOTE] no units were rebuilt
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    sleep_ms(1000);
+    p.change_file("bar/src/lib.rs", "pub fn bar() { }");
+    p.cargo("build --explain-rebuild -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["explain-rebuild"])
+        .with_stderr(
+            "\
+[COMPILING] bar v0.1.0 ([..])
+[COMPILING] foo v0.1.0 ([..])
+     Rebuilt bar v0.1.0: the file `bar/src/lib.rs` has changed ([..])
+     Rebuilt foo v0.1.0 bin \"foo\": the dependency bar was rebuilt
+[NOTE] bar v0.1.0 was rebuilt because the file `bar/src/lib.rs` has changed ([..])
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn json_messages() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                bar = { path = "bar" }
+            "#,
+        )
+        .file("src/main.rs", "fn main() { bar::bar(); }")
+        .file("bar/Cargo.toml", &basic_manifest("bar", "0.1.0"))
+        .file("bar/src/lib.rs", "pub fn bar() {}")
+        .build();
+    p.cargo("build").run();
+
+    p.cargo("build --explain-rebuild -Zunstable-options --message-format=json")
+        .masquerade_as_nightly_cargo(&["explain-rebuild"])
+        .env("RUSTFLAGS", "-Cdebug-assertions")
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "unit-rebuilt",
+                    "package_id": "path+file:///[..]/foo/bar#0.1.0",
+                    "target": "{...}",
+                    "mode": "build",
+                    "cause": "rustflags-changed",
+                    "message": "the rustflags changed"
+                }
+
+                {
+                    "reason": "unit-rebuilt",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "mode": "build",
+                    "cause": "rustflags-changed",
+                    "message": "the rustflags changed"
+                }
+            "#,
+        )
+        .run();
+
+    // Only `bar` changes by itself, `foo` points at it.
+    sleep_ms(1000);
+    p.change_file("bar/src/lib.rs", "pub fn bar() { }");
+    p.cargo("build --explain-rebuild -Zunstable-options --message-format=json")
+        .masquerade_as_nightly_cargo(&["explain-rebuild"])
+        .env("RUSTFLAGS", "-Cdebug-assertions")
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "unit-rebuilt",
+                    "package_id": "path+file:///[..]/foo/bar#0.1.0",
+                    "target": "{...}",
+                    "mode": "build",
+                    "cause": "file-changed",
+                    "message": "the file `bar/src/lib.rs` has changed ([..])"
+                }
+
+                {
+                    "reason": "unit-rebuilt",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "mode": "build",
+                    "cause": "stale-dependency-fingerprint",
+                    "message": "the dependency bar was rebuilt",
+                    "dependency": {
+                        "package_id": "path+file:///[..]/foo/bar#0.1.0",
+                        "target": "{...}",
+                        "mode": "build"
+                    }
+                }
+            "#,
+        )
+        .run();
+}
@@ -0,0 +1,220 @@
+//! Tests for `cargo install --from`.
+
+use cargo_test_support::install::{
+    assert_has_installed_exe, assert_has_not_installed_exe, cargo_home,
+};
+use cargo_test_support::registry::{self, Package};
+use cargo_test_support::{basic_manifest, cargo_process, git, paths};
+
+#[cargo_test]
+fn gated() {
+    registry::init();
+    std::fs::write(paths::root().join("tools.toml"), "[tools]\nfoo = \"1\"\n").unwrap();
+
+    cargo_process("install --from tools.toml")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--from` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+    cargo_process("install --from tools.toml")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .with_status(101)
+        .with_stderr("[ERROR] the `--from` flag is unstable, pass `-Z install-from` to enable it")
+        .run();
+}
+
+#[cargo_test]
+fn conflicts_with_a_single_crate() {
+    std::fs::write(paths::root().join("tools.toml"), "[tools]\nfoo = \"1\"\n").unwrap();
+
+    for (args, arg) in [
+        ("--version 1.0.0", "--version <VERSION>"),
+        ("--git https://example.com/foo", "--git <URL>"),
+        ("--path foo", "--path <PATH>"),
+    ] {
+        cargo_process(&format!("install --from tools.toml {args} -Zinstall-from"))
+            .masquerade_as_nightly_cargo(&["install-from"])
+            .with_status(1)
+            .with_stderr_contains(&format!(
+                "[ERROR] the argument '--from <FILE>' cannot be used with '{arg}'"
+            ))
+            .run();
+    }
+}
+
+#[cargo_test]
+fn installs_and_locks() {
+    registry::init();
+    Package::new("foo", "1.0.0")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    Package::new("foo", "1.1.0")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    Package::new("bar", "0.2.0")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    std::fs::write(
+        paths::root().join("tools.toml"),
+        r#"
+            [tools]
+            foo = "1.0"
+            bar = { version = "0.2", bins = ["bar"] }
+        "#,
+    )
+    .unwrap();
+
+    cargo_process("install --from tools.toml -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .with_stderr_contains("[INSTALLED] package `bar v0.2.0` [..]")
+        .with_stderr_contains("[INSTALLED] package `foo v1.1.0` [..]")
+        .with_stderr_contains("[SUMMARY] Successfully installed bar, foo!")
+        .run();
+    assert_has_installed_exe(cargo_home(), "foo");
+    assert_has_installed_exe(cargo_home(), "bar");
+    assert_eq!(
+        std::fs::read_to_string(paths::root().join("tools.lock")).unwrap(),
+        r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+[[tool]]
+name = "bar"
+version = "0.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[tool]]
+name = "foo"
+version = "1.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#
+    );
+
+    // Another machine with the same lockfile gets the same versions, even
+    // if newer ones were published since.
+    Package::new("foo", "1.2.0")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    cargo_process("uninstall foo bar").run();
+    cargo_process("install --from tools.toml --locked -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .with_stderr_contains("[INSTALLED] package `foo v1.1.0` [..]")
+        .run();
+    assert!(std::fs::read_to_string(paths::root().join("tools.lock"))
+        .unwrap()
+        .contains("version = \"1.1.0\""));
+
+    // Nothing to do when everything is installed.
+    cargo_process("install --from tools.toml -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .with_stderr(
+            "\
+[IGNORED] package `bar v0.2.0` is already installed, use --force to override
+[IGNORED] package `foo v1.1.0` is already installed, use --force to override
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn updates_and_removes() {
+    registry::init();
+    Package::new("foo", "1.0.0")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    Package::new("foo", "2.0.0")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    Package::new("bar", "0.2.0")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    std::fs::write(
+        paths::root().join("tools.toml"),
+        "[tools]\nfoo = \"1\"\nbar = \"0.2\"\n",
+    )
+    .unwrap();
+    cargo_process("install --from tools.toml -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .run();
+
+    // Changing the requirement installs a new version, and dropping a tool
+    // uninstalls it.
+    std::fs::write(paths::root().join("tools.toml"), "[tools]\nfoo = \"2\"\n").unwrap();
+    cargo_process("install --from tools.toml --locked -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the lock file [ROOT]/tools.lock needs to be updated for tool `foo` \
+             but --locked was passed to prevent this",
+        )
+        .run();
+    cargo_process("install --from tools.toml -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .with_stderr_contains(
+            "[REPLACED] package `foo v1.0.0` with `foo v2.0.0` (executable `foo[EXE]`)",
+        )
+        .with_stderr_contains("[REMOVING] [CWD]/home/.cargo/bin/bar[EXE]")
+        .run();
+    assert_has_installed_exe(cargo_home(), "foo");
+    assert_has_not_installed_exe(cargo_home(), "bar");
+    let lock = std::fs::read_to_string(paths::root().join("tools.lock")).unwrap();
+    assert!(lock.contains("version = \"2.0.0\""));
+    assert!(!lock.contains("bar"));
+}
+
+#[cargo_test]
+fn git_tool_is_pinned() {
+    let (p, repo) = git::new_repo("foo", |p| {
+        p.file("Cargo.toml", &basic_manifest("foo", "0.1.0"))
+            .file("src/main.rs", "fn main() {}")
+    });
+    std::fs::write(
+        paths::root().join("tools.toml"),
+        &format!("[tools]\nfoo = {{ git = '{}' }}\n", p.url()),
+    )
+    .unwrap();
+
+    cargo_process("install --from tools.toml -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .run();
+    let rev = repo.revparse_single("HEAD").unwrap().id().to_string();
+    assert!(std::fs::read_to_string(paths::root().join("tools.lock"))
+        .unwrap()
+        .contains(&format!("#{rev}\"")));
+
+    // A new commit isn't installed while the lockfile pins the old one.
+    p.change_file("Cargo.toml", &basic_manifest("foo", "0.2.0"));
+    git::add(&repo);
+    git::commit(&repo);
+    cargo_process("install --from tools.toml -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .with_stderr_contains("[IGNORED] package `foo v0.1.0 [..]` is already installed[..]")
+        .run();
+    assert!(std::fs::read_to_string(paths::root().join("tools.lock"))
+        .unwrap()
+        .contains(&format!("#{rev}\"")));
+}
+
+#[cargo_test]
+fn invalid_file() {
+    std::fs::write(
+        paths::root().join("tools.toml"),
+        "[tools]\nfoo = { path = \"foo\", git = \"https://example.com/foo\" }\n",
+    )
+    .unwrap();
+
+    cargo_process("install --from tools.toml -Zinstall-from")
+        .masquerade_as_nightly_cargo(&["install-from"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] invalid specification of tool `foo`
+
+Caused by:
+  only one of `registry`, `index`, `git` or `path` can be specified
+",
+        )
+        .run();
+}
@@ -0,0 +1,194 @@
+//! Tests for `cargo install` with `-Zinstall-prebuilt`.
+
+use std::fs;
+
+use cargo_test_support::install::{assert_has_installed_exe, cargo_home};
+use cargo_test_support::registry::{self, Package};
+use cargo_test_support::{cargo_process, paths};
+
+#[cargo_test]
+fn ignored_without_flag() {
+    registry::init();
+    Package::new("foo", "0.0.1")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+
+    cargo_process("install foo")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .with_stderr_does_not_contain("[STORING] [..]")
+        .run();
+    assert!(!paths::root().join("prebuilt").exists());
+}
+
+#[cargo_test]
+fn store_then_reuse() {
+    registry::init();
+    Package::new("foo", "0.0.1")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+
+    cargo_process("install foo -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .with_stderr_contains("[COMPILING] foo v0.0.1")
+        .with_stderr_contains("[STORING] prebuilt binaries in `[ROOT]/prebuilt/foo-0.0.1-[..]`")
+        .run();
+    let entry = fs::read_dir(paths::root().join("prebuilt"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    assert!(entry
+        .join(format!("foo{}", std::env::consts::EXE_SUFFIX))
+        .is_file());
+    let metadata = fs::read_to_string(entry.join("prebuilt.json")).unwrap();
+    assert!(metadata.contains(r#""profile": "release""#), "{metadata}");
+
+    cargo_process("uninstall foo").run();
+    cargo_process("install foo -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .with_stderr_contains("[REUSING] prebuilt binaries from `[ROOT]/prebuilt/foo-0.0.1-[..]`")
+        .with_stderr_does_not_contain("[COMPILING] [..]")
+        .with_stderr_contains("[INSTALLED] package `foo v0.0.1` [..]")
+        .run();
+    assert_has_installed_exe(cargo_home(), "foo");
+    // The prebuilt binaries are copied, not moved.
+    assert!(entry
+        .join(format!("foo{}", std::env::consts::EXE_SUFFIX))
+        .is_file());
+}
+
+#[cargo_test]
+fn different_features_are_not_reused() {
+    registry::init();
+    Package::new("foo", "0.0.1")
+        .feature("extra", &[])
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+
+    cargo_process("install foo -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .run();
+    cargo_process("install foo --features extra -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .with_stderr_does_not_contain("[REUSING] [..]")
+        .with_stderr_contains("[COMPILING] foo v0.0.1")
+        .run();
+    assert_eq!(
+        fs::read_dir(paths::root().join("prebuilt"))
+            .unwrap()
+            .count(),
+        2
+    );
+}
+
+#[cargo_test]
+fn other_rustc_builds_from_source() {
+    registry::init();
+    Package::new("foo", "0.0.1")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+
+    cargo_process("install foo -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .run();
+    cargo_process("uninstall foo").run();
+    let metadata_path = fs::read_dir(paths::root().join("prebuilt"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path()
+        .join("prebuilt.json");
+    let metadata = fs::read_to_string(&metadata_path).unwrap();
+    let rustc = metadata
+        .lines()
+        .find(|line| line.contains(r#""rustc": "#))
+        .unwrap();
+    fs::write(
+        &metadata_path,
+        metadata.replace(rustc, r#"  "rustc": "0000000000000000","#),
+    )
+    .unwrap();
+
+    cargo_process("install foo -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .with_stderr_contains(
+            "[WARNING] ignoring the prebuilt binaries of `foo v0.0.1` in \
+             `[ROOT]/prebuilt/foo-0.0.1-[..]`: `[ROOT]/prebuilt/foo-0.0.1-[..]/prebuilt.json` \
+             describes another build, building from source instead",
+        )
+        .with_stderr_contains("[COMPILING] foo v0.0.1")
+        .run();
+    assert_has_installed_exe(cargo_home(), "foo");
+}
+
+#[cargo_test]
+fn checksum_mismatch_builds_from_source() {
+    registry::init();
+    Package::new("foo", "0.0.1")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+
+    cargo_process("install foo -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .run();
+    cargo_process("uninstall foo").run();
+    let exe = fs::read_dir(paths::root().join("prebuilt"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path()
+        .join(format!("foo{}", std::env::consts::EXE_SUFFIX));
+    fs::write(&exe, "not a binary").unwrap();
+
+    cargo_process("install foo -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", paths::root().join("prebuilt"))
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .with_stderr_contains(
+            "[WARNING] ignoring the prebuilt binaries of `foo v0.0.1` in \
+             `[ROOT]/prebuilt/foo-0.0.1-[..]`: `foo[EXE]` doesn't match its checksum, \
+             building from source instead",
+        )
+        .with_stderr_contains("[COMPILING] foo v0.0.1")
+        .with_stderr_does_not_contain("[STORING] [..]")
+        .run();
+    assert_has_installed_exe(cargo_home(), "foo");
+}
+
+#[cargo_test]
+fn file_url() {
+    registry::init();
+    Package::new("foo", "0.0.1")
+        .file("src/main.rs", "fn main() {}")
+        .publish();
+    let url = url::Url::from_file_path(paths::root().join("prebuilt")).unwrap();
+
+    cargo_process("install foo -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", url.as_str())
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .with_stderr_contains("[STORING] prebuilt binaries in `[ROOT]/prebuilt/foo-0.0.1-[..]`")
+        .run();
+    cargo_process("install foo --force -Zinstall-prebuilt")
+        .env("CARGO_INSTALL_PREBUILT_DIR", url.as_str())
+        .env("CARGO_INSTALL_PREBUILT_STORE", "true")
+        .masquerade_as_nightly_cargo(&["install-prebuilt"])
+        .with_stderr_contains("[REUSING] prebuilt binaries from `[ROOT]/prebuilt/foo-0.0.1-[..]`")
+        .run();
+}
@@ -0,0 +1,612 @@
+//! Tests for resolving merge conflicts in `Cargo.lock` with `-Zlockfile-merge`.
+
+use cargo_test_support::project;
+use cargo_test_support::registry::Package;
+
+fn assert_locked(lockfile: &str, name: &str, version: &str) {
+    assert!(
+        lockfile.contains(&format!("name = \"{name}\"\nversion = \"{version}\"")),
+        "`{name} v{version}` isn't locked in:\n{lockfile}"
+    );
+}
+
+#[cargo_test]
+fn conflict_is_an_error_without_flag() {
+    let a100 = Package::new("a", "1.0.0").publish();
+    let a101 = Package::new("a", "1.0.1").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            a = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "Cargo.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+<<<<<<< HEAD
+version = "1.0.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a101}"
+=======
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a100}"
+>>>>>>> theirs
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+]
+"#
+            ),
+        )
+        .build();
+
+    p.cargo("check")
+        .with_status(101)
+        .with_stderr_contains("[ERROR] failed to parse lock file at: [ROOT]/foo/Cargo.lock")
+        .run();
+}
+
+#[cargo_test]
+fn resolves_conflict() {
+    let a100 = Package::new("a", "1.0.0").publish();
+    let a101 = Package::new("a", "1.0.1").publish();
+    let b100 = Package::new("b", "1.0.0").publish();
+    let b110 = Package::new("b", "1.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            a = "1.0"
+            b = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "Cargo.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+<<<<<<< HEAD
+version = "1.0.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a101}"
+=======
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a100}"
+>>>>>>> theirs
+
+[[package]]
+name = "b"
+<<<<<<< HEAD
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b100}"
+=======
+version = "1.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b110}"
+>>>>>>> theirs
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+ "b",
+]
+"#
+            ),
+        )
+        .build();
+
+    p.cargo("check -Zlockfile-merge")
+        .masquerade_as_nightly_cargo(&["lockfile-merge"])
+        .with_stderr_contains("[..]Resolving merge conflict in `[ROOT]/foo/Cargo.lock`")
+        .with_stderr_contains("[CHECKING] a v1.0.1")
+        .with_stderr_contains("[CHECKING] b v1.1.0")
+        .run();
+    let lockfile = p.read_lockfile();
+    assert!(!lockfile.contains("<<<<<<<"), "{lockfile}");
+    assert_locked(&lockfile, "a", "1.0.1");
+    assert_locked(&lockfile, "b", "1.1.0");
+}
+
+#[cargo_test]
+fn resolves_diff3_conflict() {
+    let a100 = Package::new("a", "1.0.0").publish();
+    let a101 = Package::new("a", "1.0.1").publish();
+    let b100 = Package::new("b", "1.0.0").publish();
+    let b110 = Package::new("b", "1.1.0").publish();
+    // `a` was downgraded on our side and `b` updated on theirs.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            a = "1.0"
+            b = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "Cargo.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+<<<<<<< HEAD
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a100}"
+||||||| base
+version = "1.0.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a101}"
+=======
+version = "1.0.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a101}"
+>>>>>>> theirs
+
+[[package]]
+name = "b"
+<<<<<<< HEAD
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b100}"
+||||||| base
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b100}"
+=======
+version = "1.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b110}"
+>>>>>>> theirs
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+ "b",
+]
+"#
+            ),
+        )
+        .build();
+
+    p.cargo("check -Zlockfile-merge")
+        .masquerade_as_nightly_cargo(&["lockfile-merge"])
+        .with_stderr_contains("[..]Resolving merge conflict in `[ROOT]/foo/Cargo.lock`")
+        .run();
+    let lockfile = p.read_lockfile();
+    assert_locked(&lockfile, "a", "1.0.0");
+    assert_locked(&lockfile, "b", "1.1.0");
+}
+
+#[cargo_test]
+fn conflict_with_locked() {
+    let a100 = Package::new("a", "1.0.0").publish();
+    let a101 = Package::new("a", "1.0.1").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            a = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "Cargo.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+<<<<<<< HEAD
+version = "1.0.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a101}"
+=======
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a100}"
+>>>>>>> theirs
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+]
+"#
+            ),
+        )
+        .build();
+
+    p.cargo("check --locked -Zlockfile-merge")
+        .masquerade_as_nightly_cargo(&["lockfile-merge"])
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the lock file [ROOT]/foo/Cargo.lock needs to be updated but --locked was passed to prevent this",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn unterminated_conflict() {
+    let a100 = Package::new("a", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            a = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "Cargo.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+<<<<<<< HEAD
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a100}"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+]
+"#
+            ),
+        )
+        .build();
+
+    p.cargo("check -Zlockfile-merge")
+        .masquerade_as_nightly_cargo(&["lockfile-merge"])
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] failed to resolve the merge conflict in lock file at: [ROOT]/foo/Cargo.lock",
+        )
+        .with_stderr_contains("  unterminated merge conflict")
+        .run();
+}
+
+#[cargo_test]
+fn merge_driver_requires_flag() {
+    Package::new("a", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            a = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+    let lockfile = p.read_lockfile();
+    p.change_file("base.lock", &lockfile);
+    p.change_file("ours.lock", &lockfile);
+    p.change_file("theirs.lock", &lockfile);
+
+    p.cargo("generate-lockfile --merge base.lock ours.lock theirs.lock")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--merge` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+
+    p.cargo("generate-lockfile --merge base.lock ours.lock theirs.lock")
+        .masquerade_as_nightly_cargo(&["lockfile-merge"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--merge` flag is unstable, pass `-Z lockfile-merge` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn merge_driver() {
+    let a100 = Package::new("a", "1.0.0").publish();
+    let a101 = Package::new("a", "1.0.1").publish();
+    let b100 = Package::new("b", "1.0.0").publish();
+    let b110 = Package::new("b", "1.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            a = "1.0"
+            b = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "base.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a100}"
+
+[[package]]
+name = "b"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b100}"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+ "b",
+]
+"#
+            ),
+        )
+        .file(
+            "ours.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+version = "1.0.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a101}"
+
+[[package]]
+name = "b"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b100}"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+ "b",
+]
+"#
+            ),
+        )
+        .file(
+            "theirs.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a100}"
+
+[[package]]
+name = "b"
+version = "1.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b110}"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+ "b",
+]
+"#
+            ),
+        )
+        .build();
+
+    p.cargo("generate-lockfile --merge base.lock ours.lock theirs.lock -Zlockfile-merge")
+        .masquerade_as_nightly_cargo(&["lockfile-merge"])
+        .run();
+    let merged = p.read_file("ours.lock");
+    assert_locked(&merged, "a", "1.0.1");
+    assert_locked(&merged, "b", "1.1.0");
+}
+
+#[cargo_test]
+fn merge_driver_keeps_downgrade() {
+    let a100 = Package::new("a", "1.0.0").publish();
+    let a101 = Package::new("a", "1.0.1").publish();
+    let b100 = Package::new("b", "1.0.0").publish();
+    let b110 = Package::new("b", "1.1.0").publish();
+    // `a` was downgraded on our side and `b` updated on theirs.
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            a = "1.0"
+            b = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "base.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+version = "1.0.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a101}"
+
+[[package]]
+name = "b"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b100}"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+ "b",
+]
+"#
+            ),
+        )
+        .file(
+            "ours.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a100}"
+
+[[package]]
+name = "b"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b100}"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+ "b",
+]
+"#
+            ),
+        )
+        .file(
+            "theirs.lock",
+            &format!(
+                r#"# This file is automatically @generated by Cargo.
+# It is not intended for manual editing.
+version = 3
+
+[[package]]
+name = "a"
+version = "1.0.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{a101}"
+
+[[package]]
+name = "b"
+version = "1.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{b110}"
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "a",
+ "b",
+]
+"#
+            ),
+        )
+        .build();
+
+    p.cargo("generate-lockfile --merge base.lock ours.lock theirs.lock -Zlockfile-merge")
+        .masquerade_as_nightly_cargo(&["lockfile-merge"])
+        .run();
+    let merged = p.read_file("ours.lock");
+    assert_locked(&merged, "a", "1.0.0");
+    assert_locked(&merged, "b", "1.1.0");
+}
@@ -7,16 +7,21 @@ extern crate cargo_test_macro;
 
 mod advanced_env;
 mod alt_registry;
+mod approve_build_scripts;
 mod artifact_dep;
+mod audit_policy;
 mod bad_config;
 mod bad_manifest_path;
 mod bench;
 mod binary_name;
 mod build;
+mod build_cache;
+mod build_events;
 mod build_plan;
 mod build_script;
 mod build_script_env;
 mod build_script_extra_link_arg;
+mod build_script_sandbox;
 mod cache_lock;
 mod cache_messages;
 mod cargo;
@@ -67,6 +72,7 @@ mod cfg;
 mod check;
 mod check_cfg;
 mod clean;
+mod clean_stale;
 mod collisions;
 mod concurrent;
 mod config;
@@ -86,6 +92,7 @@ mod doc;
 mod docscrape;
 mod edition;
 mod error;
+mod explain_rebuild;
 mod features;
 mod features2;
 mod features_namespaced;
@@ -105,6 +112,8 @@ mod help;
 mod https;
 mod inheritable_workspace_fields;
 mod install;
+mod install_from;
+mod install_prebuilt;
 mod install_upgrade;
 mod jobserver;
 mod lints;
@@ -113,6 +122,7 @@ mod list_availables;
 mod local_registry;
 mod locate_project;
 mod lockfile_compat;
+mod lockfile_merge;
 mod login;
 mod logout;
 mod lto;
@@ -152,6 +162,8 @@ mod publish_lockfile;
 mod read_manifest;
 mod registry;
 mod registry_auth;
+mod registry_mirror;
+mod registry_serve;
 mod rename_deps;
 mod replace;
 mod required_features;
@@ -166,11 +178,13 @@ mod rustflags;
 mod rustup;
 mod script;
 mod search;
+mod semver_check;
 mod shell_quoting;
 mod source_replacement;
 mod ssh;
 mod standard_lib;
 mod test;
+mod test_events;
 mod timings;
 mod tool_paths;
 mod tree;
@@ -4928,3 +4928,96 @@ path = "src/lib.rs"
         )],
     );
 }
+
+#[cargo_test]
+fn workspace_verified_against_packaged_deps() {
+    // `dep` is published at 0.1.0, and `main`, a binary, depends on its
+    // unpublished 0.2.0.
+    Package::new("dep", "0.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["dep", "main"]
+            "#,
+        )
+        .file(
+            "dep/Cargo.toml",
+            r#"
+                [package]
+                name = "dep"
+                version = "0.2.0"
+                edition = "2015"
+                license = "MIT"
+                description = "dep"
+                homepage = "https://example.com"
+            "#,
+        )
+        .file("dep/src/lib.rs", "pub fn new_in_0_2() {}")
+        .file(
+            "main/Cargo.toml",
+            r#"
+                [package]
+                name = "main"
+                version = "0.1.0"
+                edition = "2015"
+                license = "MIT"
+                description = "main"
+                homepage = "https://example.com"
+
+                [dependencies]
+                dep = { path = "../dep", version = "0.2.0" }
+            "#,
+        )
+        .file("main/src/main.rs", "fn main() { dep::new_in_0_2() }")
+        .build();
+
+    p.cargo("package --workspace")
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] failed to prepare local package for uploading
+
+Caused by:
+  failed to select a version for the requirement `dep = \"^0.2.0\"`
+  candidate versions found which didn't match: 0.1.0
+[..]",
+        )
+        .run();
+
+    p.cargo("package --workspace -Zpackage-workspace")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .with_stderr(
+            "\
+[PACKAGING] dep v0.2.0 ([ROOT]/foo/dep)
+[VERIFYING] dep v0.2.0 ([ROOT]/foo/dep)
+[COMPILING] dep v0.2.0 ([ROOT]/foo/target/package/dep-0.2.0)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[PACKAGED] [..] files, [..] ([..] compressed)
+[PACKAGING] main v0.1.0 ([ROOT]/foo/main)
+[UPDATING] `dummy-registry` index
+[VERIFYING] main v0.1.0 ([ROOT]/foo/main)
+[UNPACKING] dep v0.2.0 (registry `[ROOT]/foo/target/package/tmp-registry`)
+[COMPILING] dep v0.2.0
+[COMPILING] main v0.1.0 ([ROOT]/foo/target/package/main-0.1.0)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[PACKAGED] [..] files, [..] ([..] compressed)
+",
+        )
+        .run();
+
+    // The lock file of `main` refers to the registry it will be published to.
+    let lock = p.read_file("target/package/main-0.1.0/Cargo.lock");
+    assert!(lock.contains(
+        r#"name = "dep"
+version = "0.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index""#
+    ));
+
+    // Changes to `dep` are picked up by the next run.
+    p.change_file("dep/src/lib.rs", "pub fn renamed_in_0_2() {}");
+    p.change_file("main/src/main.rs", "fn main() { dep::renamed_in_0_2() }");
+    p.cargo("package --workspace -Zpackage-workspace")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .run();
+}
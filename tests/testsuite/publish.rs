@@ -3474,3 +3474,284 @@ You may press ctrl-c to skip waiting; the crate should be available shortly.
 
     validate_upload_foo();
 }
+
+/// A virtual workspace where `c` depends on `b`, which depends on `a`, and
+/// `a` has a dev-dependency on `c`.
+fn workspace_with_chain() -> cargo_test_support::Project {
+    let member = |name: &str, deps: &str| {
+        format!(
+            r#"
+                [package]
+                name = "{name}"
+                version = "0.0.1"
+                edition = "2015"
+                authors = []
+                license = "MIT"
+                description = "{name}"
+                homepage = "{name}"
+
+                {deps}
+            "#
+        )
+    };
+    project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["a", "b", "c", "internal"]
+            "#,
+        )
+        .file(
+            "a/Cargo.toml",
+            &member("a", "[dev-dependencies]\nc = { path = \"../c\" }"),
+        )
+        .file("a/src/lib.rs", "pub fn a() {}")
+        .file(
+            "b/Cargo.toml",
+            &member(
+                "b",
+                "[dependencies]\na = { path = \"../a\", version = \"0.0.1\" }",
+            ),
+        )
+        .file("b/src/lib.rs", "pub fn b() { a::a() }")
+        .file(
+            "c/Cargo.toml",
+            &member(
+                "c",
+                "[dependencies]\nb = { path = \"../b\", version = \"0.0.1\" }",
+            ),
+        )
+        .file("c/src/lib.rs", "pub fn c() { b::b() }")
+        .file(
+            "internal/Cargo.toml",
+            r#"
+                [package]
+                name = "internal"
+                version = "0.0.1"
+                edition = "2015"
+                publish = false
+            "#,
+        )
+        .file("internal/src/lib.rs", "")
+        .build()
+}
+
+#[cargo_test]
+fn workspace_gated() {
+    let registry = RegistryBuilder::new().http_api().http_index().build();
+    let p = workspace_with_chain();
+
+    p.cargo("publish --workspace")
+        .replace_crates_io(registry.index_url())
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--workspace` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+    p.cargo("publish --workspace")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .replace_crates_io(registry.index_url())
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--workspace` flag is unstable, pass `-Z package-workspace` to enable it
+",
+        )
+        .run();
+    p.cargo("publish -p a -p b")
+        .replace_crates_io(registry.index_url())
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `-p` argument must be specified to select a single package to publish
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn workspace_in_dependency_order() {
+    let registry = RegistryBuilder::new().http_api().http_index().build();
+    let p = workspace_with_chain();
+
+    p.cargo("publish --workspace -Zpackage-workspace")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .replace_crates_io(registry.index_url())
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+[PACKAGING] a v0.0.1 ([ROOT]/foo/a)
+[VERIFYING] a v0.0.1 ([ROOT]/foo/a)
+[COMPILING] a v0.0.1 ([ROOT]/foo/target/package/a-0.0.1)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] a v0.0.1 ([ROOT]/foo/a)
+[UPLOADED] a v0.0.1 to registry `crates-io`
+[NOTE] waiting for `a v0.0.1` to be available at registry `crates-io`.
+You may press ctrl-c to skip waiting; the crate should be available shortly.
+[PUBLISHED] a v0.0.1 at registry `crates-io`
+[UPDATING] crates.io index
+[PACKAGING] b v0.0.1 ([ROOT]/foo/b)
+[VERIFYING] b v0.0.1 ([ROOT]/foo/b)
+[UPDATING] crates.io index
+[DOWNLOADING] crates ...
+[DOWNLOADED] a v0.0.1
+[COMPILING] a v0.0.1
+[COMPILING] b v0.0.1 ([ROOT]/foo/target/package/b-0.0.1)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] b v0.0.1 ([ROOT]/foo/b)
+[UPLOADED] b v0.0.1 to registry `crates-io`
+[NOTE] waiting for `b v0.0.1` to be available at registry `crates-io`.
+You may press ctrl-c to skip waiting; the crate should be available shortly.
+[PUBLISHED] b v0.0.1 at registry `crates-io`
+[UPDATING] crates.io index
+[PACKAGING] c v0.0.1 ([ROOT]/foo/c)
+[VERIFYING] c v0.0.1 ([ROOT]/foo/c)
+[UPDATING] crates.io index
+[DOWNLOADING] crates ...
+[DOWNLOADED] b v0.0.1
+[COMPILING] a v0.0.1
+[COMPILING] b v0.0.1
+[COMPILING] c v0.0.1 ([ROOT]/foo/target/package/c-0.0.1)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] c v0.0.1 ([ROOT]/foo/c)
+[UPLOADED] c v0.0.1 to registry `crates-io`
+[NOTE] waiting for `c v0.0.1` to be available at registry `crates-io`.
+You may press ctrl-c to skip waiting; the crate should be available shortly.
+[PUBLISHED] c v0.0.1 at registry `crates-io`
+",
+        )
+        .run();
+
+    // Selecting `internal` by name is an error, since it can't be published.
+    p.cargo("publish -p c -p internal -Zpackage-workspace")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .replace_crates_io(registry.index_url())
+        .with_status(101)
+        .with_stderr_contains("[ERROR] `internal` cannot be published.")
+        .run();
+}
+
+#[cargo_test]
+fn workspace_skips_published_versions() {
+    let registry = RegistryBuilder::new().http_api().http_index().build();
+    let p = workspace_with_chain();
+
+    p.cargo("publish -p a --no-verify")
+        .replace_crates_io(registry.index_url())
+        .run();
+
+    p.cargo("publish -p a -p b --no-verify -Zpackage-workspace")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .replace_crates_io(registry.index_url())
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+    Skipping a v0.0.1, already published at registry `crates-io`
+[UPDATING] crates.io index
+[PACKAGING] b v0.0.1 ([ROOT]/foo/b)
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] b v0.0.1 ([ROOT]/foo/b)
+[UPLOADED] b v0.0.1 to registry `crates-io`
+[NOTE] waiting for `b v0.0.1` to be available at registry `crates-io`.
+You may press ctrl-c to skip waiting; the crate should be available shortly.
+[PUBLISHED] b v0.0.1 at registry `crates-io`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn workspace_resume_after_failure() {
+    let registry = RegistryBuilder::new().http_api().http_index().build();
+    let p = workspace_with_chain();
+    p.change_file("c/src/lib.rs", "pub fn c() { b::missing() }");
+
+    p.cargo("publish --workspace -Zpackage-workspace")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .replace_crates_io(registry.index_url())
+        .with_status(101)
+        .with_stderr_contains("[PUBLISHED] b v0.0.1 at registry `crates-io`")
+        .with_stderr_contains(
+            "\
+[NOTE] the 2 packages before `c v0.0.1` are published, run the command again to resume from it
+[ERROR] failed to verify package tarball
+",
+        )
+        .with_stderr_does_not_contain("[UPLOADING] c v0.0.1 [..]")
+        .run();
+
+    p.change_file("c/src/lib.rs", "pub fn c() { b::b() }");
+    p.cargo("publish --workspace -Zpackage-workspace --no-verify")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .replace_crates_io(registry.index_url())
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+    Skipping a v0.0.1, already published at registry `crates-io`
+[UPDATING] crates.io index
+    Skipping b v0.0.1, already published at registry `crates-io`
+[UPDATING] crates.io index
+[PACKAGING] c v0.0.1 ([ROOT]/foo/c)
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] c v0.0.1 ([ROOT]/foo/c)
+[UPLOADED] c v0.0.1 to registry `crates-io`
+[NOTE] waiting for `c v0.0.1` to be available at registry `crates-io`.
+You may press ctrl-c to skip waiting; the crate should be available shortly.
+[PUBLISHED] c v0.0.1 at registry `crates-io`
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn workspace_dry_run() {
+    let registry = RegistryBuilder::new().http_api().http_index().build();
+    let p = workspace_with_chain();
+
+    p.cargo("publish --workspace --dry-run -Zpackage-workspace")
+        .masquerade_as_nightly_cargo(&["package-workspace"])
+        .replace_crates_io(registry.index_url())
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+[PACKAGING] a v0.0.1 ([ROOT]/foo/a)
+[VERIFYING] a v0.0.1 ([ROOT]/foo/a)
+[COMPILING] a v0.0.1 ([ROOT]/foo/target/package/a-0.0.1)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] a v0.0.1 ([ROOT]/foo/a)
+[WARNING] aborting upload due to dry run
+[UPDATING] crates.io index
+[PACKAGING] b v0.0.1 ([ROOT]/foo/b)
+[VERIFYING] b v0.0.1 ([ROOT]/foo/b)
+[UPDATING] crates.io index
+[UNPACKING] a v0.0.1 (registry `[ROOT]/foo/target/package/tmp-registry`)
+[COMPILING] a v0.0.1
+[COMPILING] b v0.0.1 ([ROOT]/foo/target/package/b-0.0.1)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] b v0.0.1 ([ROOT]/foo/b)
+[WARNING] aborting upload due to dry run
+[UPDATING] crates.io index
+[PACKAGING] c v0.0.1 ([ROOT]/foo/c)
+[VERIFYING] c v0.0.1 ([ROOT]/foo/c)
+[UPDATING] crates.io index
+[UNPACKING] b v0.0.1 (registry `[ROOT]/foo/target/package/tmp-registry`)
+[COMPILING] a v0.0.1
+[COMPILING] b v0.0.1
+[COMPILING] c v0.0.1 ([ROOT]/foo/target/package/c-0.0.1)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[PACKAGED] [..] files, [..] ([..] compressed)
+[UPLOADING] c v0.0.1 ([ROOT]/foo/c)
+[WARNING] aborting upload due to dry run
+",
+        )
+        .run();
+}
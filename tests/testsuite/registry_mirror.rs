@@ -0,0 +1,278 @@
+//! Tests for the `cargo registry mirror` command.
+
+use cargo_test_support::registry::{Package, RegistryBuilder};
+use cargo_test_support::{basic_manifest, project};
+
+#[cargo_test]
+fn requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("registry mirror mirror")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo registry` command is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+
+    p.cargo("registry mirror mirror")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo registry` command is unstable, pass `-Z unstable-options` to enable it
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn mirror_lockfile() {
+    let registry = RegistryBuilder::new().http_index().build();
+    Package::new("baz", "1.0.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "1.0").publish();
+    Package::new("bar", "0.2.0").dep("baz", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("registry mirror mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .with_stdout(
+            "\
+[source.crates-io]
+replace-with = \"mirrored-registry\"
+
+[source.mirrored-registry]
+registry = \"sparse+file://[ROOT]/foo/mirror/\"
+",
+        )
+        .with_stderr(
+            "\
+[UPDATING] crates.io index
+[LOCKING] 3 packages to latest compatible versions
+[ADDING] bar v0.1.0 (latest: v0.2.0)
+[DOWNLOADING] crates ...
+[DOWNLOADED] [..]
+[DOWNLOADED] [..]
+[MIRRORING] bar v0.1.0
+[MIRRORING] baz v1.0.0
+[MIRRORED] 2 packages to [ROOT]/foo/mirror
+To use the mirror, add this to your .cargo/config.toml:
+
+",
+        )
+        .run();
+
+    let bar = p.read_file("mirror/3/b/bar");
+    assert_eq!(bar.lines().count(), 1, "{bar}");
+    assert!(bar.contains(r#""vers":"0.1.0""#), "{bar}");
+    let baz = p.read_file("mirror/3/b/baz");
+    assert_eq!(baz.lines().count(), 1, "{baz}");
+    assert!(baz.contains(r#""vers":"1.0.0""#), "{baz}");
+    assert!(p.root().join("mirror/crates/bar/bar-0.1.0.crate").is_file());
+    assert!(p.root().join("mirror/crates/baz/baz-1.0.0.crate").is_file());
+    let config: serde_json::Value =
+        serde_json::from_str(&p.read_file("mirror/config.json")).unwrap();
+    assert_eq!(
+        config["dl"].as_str().unwrap(),
+        format!(
+            "{}/crates/{{crate}}/{{crate}}-{{version}}.crate",
+            url::Url::from_directory_path(p.root().join("mirror"))
+                .unwrap()
+                .as_str()
+                .trim_end_matches('/')
+        )
+    );
+}
+
+#[cargo_test]
+fn build_from_mirror() {
+    let registry = RegistryBuilder::new().http_index().build();
+    Package::new("baz", "1.0.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "1.0").publish();
+    Package::new("bar", "0.2.0").dep("baz", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("registry mirror mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .run();
+    registry.join();
+
+    // The registry is gone, everything comes from the mirror.
+    let consumer = project()
+        .at("consumer")
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "consumer"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                [source.crates-io]
+                replace-with = "mirrored-registry"
+
+                [source.mirrored-registry]
+                registry = "sparse+{}"
+                "#,
+                url::Url::from_directory_path(p.root().join("mirror")).unwrap()
+            ),
+        )
+        .build();
+    consumer
+        .cargo("check")
+        .with_stderr(
+            "\
+[UPDATING] `mirrored-registry` index
+[LOCKING] 3 packages to latest compatible versions
+[DOWNLOADING] crates ...
+[DOWNLOADED] [..] (registry `mirrored-registry`)
+[DOWNLOADED] [..] (registry `mirrored-registry`)
+[CHECKING] baz v1.0.0
+[CHECKING] bar v0.1.0
+[CHECKING] consumer v0.1.0 ([ROOT]/consumer)
+[FINISHED] [..]
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn mirror_packages_adds_versions() {
+    let registry = RegistryBuilder::new().http_index().build();
+    Package::new("baz", "1.0.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "1.0").publish();
+    Package::new("bar", "0.2.0").dep("baz", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("registry mirror mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .run();
+
+    p.cargo("registry mirror mirror -Zunstable-options -p bar@0.2.0")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .with_stderr_contains("[MIRRORING] bar v0.2.0")
+        .with_stderr_contains("[MIRRORED] 2 packages to [ROOT]/foo/mirror")
+        .with_stderr_does_not_contain("[MIRRORING] baz v1.0.0")
+        .run();
+
+    let bar = p.read_file("mirror/3/b/bar");
+    assert_eq!(bar.lines().count(), 2, "{bar}");
+    assert!(bar.contains(r#""vers":"0.1.0""#), "{bar}");
+    assert!(bar.contains(r#""vers":"0.2.0""#), "{bar}");
+    let baz = p.read_file("mirror/3/b/baz");
+    assert_eq!(baz.lines().count(), 1, "{baz}");
+    assert!(baz.contains(r#""vers":"1.0.0""#), "{baz}");
+    assert!(p.root().join("mirror/crates/bar/bar-0.1.0.crate").is_file());
+    assert!(p.root().join("mirror/crates/bar/bar-0.2.0.crate").is_file());
+}
+
+#[cargo_test]
+fn mirror_invalid_spec() {
+    let registry = RegistryBuilder::new().http_index().build();
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("registry mirror mirror -Zunstable-options -p bar@foo")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] invalid version requirement in package spec `bar@foo`
+
+Caused by:
+  unexpected character 'f' while parsing major version number
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn skips_other_sources() {
+    let registry = RegistryBuilder::new().http_index().build();
+    Package::new("baz", "1.0.0").publish();
+    let git = cargo_test_support::git::new("dep", |p| {
+        p.file("Cargo.toml", &basic_manifest("dep", "0.1.0"))
+            .file("src/lib.rs", "")
+    });
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [dependencies]
+                baz = "1.0"
+                dep = {{ git = "{}" }}
+                "#,
+                git.url()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("registry mirror mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .with_stderr_contains(
+            "[WARNING] skipping `dep v0.1.0 ([..])`, it doesn't come from crates-io",
+        )
+        .with_stderr_contains("[MIRRORED] 1 package to [ROOT]/foo/mirror")
+        .run();
+}
@@ -0,0 +1,400 @@
+//! Tests for the `cargo registry serve` command.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Stdio};
+
+use cargo_test_support::registry::{Package, RegistryBuilder};
+use cargo_test_support::{project, Project};
+
+/// A running `cargo registry serve`, stopped when dropped.
+struct Server {
+    child: Child,
+    /// The index URL, like `sparse+http://127.0.0.1:1234/`.
+    index: String,
+    /// The address it listens on, like `127.0.0.1:1234`.
+    addr: String,
+}
+
+impl Server {
+    fn start(p: &Project, args: &str) -> Server {
+        let mut cmd = p
+            .cargo(&format!(
+                "registry serve mirror --address 127.0.0.1:0 -Zunstable-options {args}"
+            ))
+            .masquerade_as_nightly_cargo(&["unstable-options"])
+            .build_command();
+        cmd.stdout(Stdio::null()).stderr(Stdio::piped());
+        let mut child = cmd.spawn().unwrap();
+        let mut stderr = BufReader::new(child.stderr.take().unwrap());
+        let mut line = String::new();
+        stderr.read_line(&mut line).unwrap();
+        let index = line
+            .trim()
+            .rsplit_once(" at ")
+            .unwrap_or_else(|| panic!("unexpected output `{line}`"))
+            .1
+            .to_string();
+        let addr = index
+            .trim_start_matches("sparse+http://")
+            .trim_end_matches('/')
+            .to_string();
+        Server { child, index, addr }
+    }
+
+    /// Sends a request for `path` with `headers`, returning the status code,
+    /// the headers and the body of the response.
+    fn request(&self, method: &str, path: &str, headers: &[&str]) -> (u16, Vec<String>, String) {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: {}\r\n",
+            self.addr
+        )
+        .unwrap();
+        for header in headers {
+            write!(stream, "{header}\r\n").unwrap();
+        }
+        write!(stream, "Connection: close\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let mut lines = head.lines();
+        let code = lines.next().unwrap().split(' ').nth(1).unwrap();
+        (
+            code.parse().unwrap(),
+            lines.map(str::to_string).collect(),
+            body.to_string(),
+        )
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn header<'a>(headers: &'a [String], name: &str) -> &'a str {
+    headers
+        .iter()
+        .find_map(|h| h.strip_prefix(name)?.strip_prefix(": "))
+        .unwrap_or_else(|| panic!("no `{name}` header in {headers:?}"))
+}
+
+#[cargo_test]
+fn requires_unstable_options() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("registry serve mirror")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo registry` command is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+
+    p.cargo("registry serve mirror")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo registry` command is unstable, pass `-Z unstable-options` to enable it
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn not_a_directory() {
+    let p = project().file("src/lib.rs", "").build();
+    p.cargo("registry serve mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] `mirror` is not a directory
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn build_from_served_mirror() {
+    let registry = RegistryBuilder::new().http_index().build();
+    Package::new("baz", "1.0.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("registry mirror mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .run();
+    registry.join();
+    let server = Server::start(&p, "");
+
+    let consumer = project()
+        .at("consumer")
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "consumer"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                [source.crates-io]
+                replace-with = "served"
+
+                [source.served]
+                registry = "{}"
+                "#,
+                server.index
+            ),
+        )
+        .build();
+    consumer
+        .cargo("check")
+        .with_stderr(
+            "\
+[UPDATING] `served` index
+[LOCKING] 3 packages to latest compatible versions
+[DOWNLOADING] crates ...
+[DOWNLOADED] [..] (registry `served`)
+[DOWNLOADED] [..] (registry `served`)
+[CHECKING] baz v1.0.0
+[CHECKING] bar v0.1.0
+[CHECKING] consumer v0.1.0 ([ROOT]/consumer)
+[FINISHED] [..]
+",
+        )
+        .run();
+
+    // The index is up-to-date, and revalidated with conditional requests.
+    consumer
+        .cargo("update")
+        .with_stderr(
+            "\
+[UPDATING] `served` index
+[LOCKING] 0 packages to latest compatible versions
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn conditional_requests() {
+    let registry = RegistryBuilder::new().http_index().build();
+    Package::new("baz", "1.0.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("registry mirror mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .run();
+    registry.join();
+    let server = Server::start(&p, "");
+
+    let (code, headers, body) = server.request("GET", "/3/b/bar", &[]);
+    assert_eq!(code, 200);
+    assert_eq!(body, p.read_file("mirror/3/b/bar"));
+    let etag = header(&headers, "ETag").to_string();
+    let last_modified = header(&headers, "Last-Modified").to_string();
+    assert!(last_modified.ends_with(" GMT"), "{last_modified}");
+
+    let if_none_match = format!("If-None-Match: {etag}");
+    let (code, _, body) = server.request("GET", "/3/b/bar", &[&if_none_match]);
+    assert_eq!(code, 304);
+    assert_eq!(body, "");
+    let (code, _, _) = server.request("GET", "/3/b/bar", &["If-None-Match: \"other\""]);
+    assert_eq!(code, 200);
+    let index = p.read_file("mirror/3/b/bar");
+    p.change_file("mirror/3/b/bar", &format!("{index}\n"));
+    let (code, headers, _) = server.request("GET", "/3/b/bar", &[&if_none_match]);
+    assert_eq!(code, 200);
+    assert_ne!(header(&headers, "ETag"), etag);
+    p.change_file("mirror/3/b/bar", &index);
+
+    let if_modified_since = format!("If-Modified-Since: {last_modified}");
+    let (code, _, _) = server.request("GET", "/3/b/bar", &[&if_modified_since]);
+    assert_eq!(code, 304);
+    let (code, _, _) = server.request(
+        "GET",
+        "/3/b/bar",
+        &["If-Modified-Since: Thu, 01 Jan 1970 00:00:00 GMT"],
+    );
+    assert_eq!(code, 200);
+
+    let (code, headers, body) = server.request("HEAD", "/crates/bar/bar-0.1.0.crate", &[]);
+    assert_eq!(code, 200);
+    assert_eq!(body, "");
+    assert_eq!(header(&headers, "Content-Type"), "application/octet-stream");
+
+    let (code, _, body) = server.request("GET", "/config.json", &[]);
+    assert_eq!(code, 200);
+    let config: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        config["dl"],
+        format!(
+            "http://{}/crates/{{crate}}/{{crate}}-{{version}}.crate",
+            server.addr
+        )
+    );
+    assert!(config.get("auth-required").is_none());
+
+    for path in [
+        "/3/b/missing",
+        "/../foo/Cargo.toml",
+        "/.cargo/config.toml",
+        "/3/b",
+    ] {
+        let (code, _, _) = server.request("GET", path, &[]);
+        assert_eq!(code, 404, "{path}");
+    }
+    let (code, headers, _) = server.request("POST", "/3/b/bar", &[]);
+    assert_eq!(code, 405);
+    assert_eq!(header(&headers, "Allow"), "GET, HEAD");
+}
+
+#[cargo_test]
+fn token_auth() {
+    let registry = RegistryBuilder::new().http_index().build();
+    Package::new("baz", "1.0.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("registry mirror mirror -Zunstable-options")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .replace_crates_io(registry.index_url())
+        .run();
+    registry.join();
+    p.change_file("token", "sekrit\n");
+    let server = Server::start(&p, "--token-file token");
+
+    let (code, headers, _) = server.request("GET", "/config.json", &[]);
+    assert_eq!(code, 401);
+    assert_eq!(header(&headers, "WWW-Authenticate"), "Cargo");
+    let (code, _, _) = server.request("GET", "/3/b/bar", &["Authorization: wrong"]);
+    assert_eq!(code, 401);
+    let (code, _, body) = server.request("GET", "/config.json", &["Authorization: sekrit"]);
+    assert_eq!(code, 200);
+    let config: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(config["auth-required"], true);
+
+    let consumer = project()
+        .at("consumer")
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "consumer"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            bar = { version = "0.1", registry = "served" }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            ".cargo/config.toml",
+            &format!(
+                r#"
+                [registry]
+                global-credential-providers = ["cargo:token"]
+
+                [registries.served]
+                index = "{}"
+                "#,
+                server.index
+            ),
+        )
+        .build();
+    consumer
+        .cargo("check")
+        .with_status(101)
+        .with_stderr_contains(
+            "  no token found for `served`, please run `cargo login --registry served`",
+        )
+        .run();
+    consumer
+        .cargo("check")
+        .env("CARGO_REGISTRIES_SERVED_TOKEN", "sekrit")
+        .with_stderr_contains("[DOWNLOADED] bar v0.1.0 (registry `served`)")
+        .with_stderr_contains("[CHECKING] consumer v0.1.0 ([ROOT]/consumer)")
+        .run();
+}
+
+#[cargo_test]
+fn idle_connections_dont_block_requests() {
+    let p = project()
+        .file("src/lib.rs", "")
+        .file("mirror/config.json", "{}")
+        .build();
+    let server = Server::start(&p, "");
+
+    // More idle connections than the server has workers.
+    let idle: Vec<_> = (0..33)
+        .map(|_| TcpStream::connect(&server.addr).unwrap())
+        .collect();
+    let start = std::time::Instant::now();
+    let (code, _, _) = server.request("GET", "/config.json", &[]);
+    assert_eq!(code, 200);
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(5),
+        "served after {:?}",
+        start.elapsed()
+    );
+    drop(idle);
+}
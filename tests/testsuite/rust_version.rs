@@ -535,6 +535,138 @@ higher v0.0.1 ([CWD])
         .run();
 }
 
+#[cargo_test]
+fn resolve_with_per_member_rust_versions() {
+    Package::new("only-newer", "1.6.0")
+        .rust_version("1.65.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+    Package::new("newer-and-older", "1.5.0")
+        .rust_version("1.45.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+    Package::new("newer-and-older", "1.5.1")
+        .rust_version("1.55.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+    Package::new("newer-and-older", "1.6.0")
+        .rust_version("1.65.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+    Package::new("lower-dep", "1.0.0")
+        .rust_version("1.45.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+    Package::new("lower-dep", "1.1.0")
+        .rust_version("1.65.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+    Package::new("leaf", "1.0.0")
+        .rust_version("1.55.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+    Package::new("leaf", "1.1.0")
+        .rust_version("1.65.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+    Package::new("middle", "1.0.0")
+        .dep("leaf", "1.0.0")
+        .file("src/lib.rs", "fn other_stuff() {}")
+        .publish();
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [workspace]
+            members = ["lower"]
+
+            [package]
+            name = "higher"
+            version = "0.0.1"
+            edition = "2015"
+            authors = []
+            rust-version = "1.60.0"
+
+            [dependencies]
+            newer-and-older = "1.0.0"
+            middle = "1.0.0"
+        "#,
+        )
+        .file("src/main.rs", "fn main() {}")
+        .file(
+            "lower/Cargo.toml",
+            r#"
+            [package]
+            name = "lower"
+            version = "0.0.1"
+            edition = "2015"
+            authors = []
+            rust-version = "1.50.0"
+
+            [dependencies]
+            lower-dep = "1.0.0"
+            only-newer = "1.0.0"
+        "#,
+        )
+        .file("lower/src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("generate-lockfile")
+        .env(
+            "CARGO_RESOLVER_SOMETHING_LIKE_PRECEDENCE",
+            "something-like-per-member-rust-version",
+        )
+        .arg("-Zmsrv-policy")
+        .masquerade_as_nightly_cargo(&["msrv-policy"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[LOCKING] 7 packages to latest per-member Rust compatible versions
+[ADDING] leaf v1.0.0 (latest: v1.1.0)
+[ADDING] lower-dep v1.0.0 (latest: v1.1.0)
+[ADDING] newer-and-older v1.5.1 (latest: v1.6.0)
+[WARNING] only-newer v1.6.0 requires Rust 1.65.0, which is newer than the rust-version of `lower` (1.50.0)
+[NOTE] `lower` depends on it through lower -> only-newer
+[NOTE] no version of `only-newer` matching `^1.0.0` supports Rust 1.50.0
+",
+        )
+        .run();
+    p.cargo("tree --workspace")
+        .with_stdout(
+            "\
+higher v0.0.1 ([CWD])
+├── middle v1.0.0
+│   └── leaf v1.0.0
+└── newer-and-older v1.5.1
+
+lower v0.0.1 ([CWD]/lower)
+├── lower-dep v1.0.0
+└── only-newer v1.6.0
+",
+        )
+        .run();
+
+    // `--ignore-rust-version` turns it off like any other MSRV-aware resolving.
+    p.cargo("update --ignore-rust-version")
+        .env(
+            "CARGO_RESOLVER_SOMETHING_LIKE_PRECEDENCE",
+            "something-like-per-member-rust-version",
+        )
+        .arg("-Zmsrv-policy")
+        .masquerade_as_nightly_cargo(&["msrv-policy"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+[LOCKING] 3 packages to latest compatible versions
+[UPDATING] leaf v1.0.0 -> v1.1.0
+[UPDATING] lower-dep v1.0.0 -> v1.1.0
+[UPDATING] newer-and-older v1.5.1 -> v1.6.0
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn resolve_unstable_config_on_stable() {
     Package::new("only-newer", "1.6.0")
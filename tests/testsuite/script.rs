@@ -1,4 +1,5 @@
 use cargo_test_support::basic_manifest;
+use cargo_test_support::paths;
 use cargo_test_support::registry::Package;
 
 const ECHO_SCRIPT: &str = r#"#!/usr/bin/env cargo
@@ -1096,6 +1097,114 @@ fn cmd_generate_lockfile_with_embedded() {
         .run();
 }
 
+#[cargo_test]
+fn cmd_generate_lockfile_lock_with_embedded() {
+    Package::new("dep", "1.0.0").publish();
+    let script = r#"#!/usr/bin/env cargo
+---
+[dependencies]
+dep = "1.0.0"
+---
+
+fn main() {}"#;
+    let p = cargo_test_support::project()
+        .file("script.rs", script)
+        .build();
+
+    p.cargo("-Zscript generate-lockfile --manifest-path script.rs --lock")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stderr(
+            "\
+[WARNING] `package.edition` is unspecified, defaulting to `2021`
+[UPDATING] `dummy-registry` index
+[LOCKING] 2 packages to latest compatible versions
+",
+        )
+        .run();
+    let lock = p.read_file("script.lock");
+    assert!(
+        lock.contains("name = \"dep\"\nversion = \"1.0.0\""),
+        "{lock}"
+    );
+
+    // Running the script honors its lockfile, even once newer versions
+    // are published.
+    Package::new("dep", "1.0.1").publish();
+    p.cargo("-Zscript -v script.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stderr_contains("[COMPILING] dep v1.0.0")
+        .run();
+
+    p.cargo("-Zscript update --manifest-path script.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stderr_contains("[UPDATING] dep v1.0.0 -> v1.0.1")
+        .run();
+    let lock = p.read_file("script.lock");
+    assert!(
+        lock.contains("name = \"dep\"\nversion = \"1.0.1\""),
+        "{lock}"
+    );
+}
+
+#[cargo_test]
+fn lockfile_next_to_script_with_locked() {
+    Package::new("dep", "1.0.0").publish();
+    let script = r#"#!/usr/bin/env cargo
+---
+[dependencies]
+dep = "1.0.0"
+---
+
+fn main() {}"#;
+    let p = cargo_test_support::project()
+        .file("script.rs", script)
+        .build();
+
+    p.cargo("-Zscript generate-lockfile --manifest-path script.rs --lock")
+        .masquerade_as_nightly_cargo(&["script"])
+        .run();
+    p.change_file(
+        "script.rs",
+        &script.replace("dep = \"1.0.0\"", "dep = \"1.0.0\"\nother = \"1.0.0\""),
+    );
+    Package::new("other", "1.0.0").publish();
+
+    p.cargo("-Zscript check --manifest-path script.rs --locked")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the lock file [ROOT]/foo/script.lock needs to be updated but --locked was passed to prevent this",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn cmd_generate_lockfile_lock_without_embedded() {
+    let p = cargo_test_support::project()
+        .file("src/main.rs", "fn main() {}")
+        .build();
+
+    p.cargo("generate-lockfile --lock")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--lock` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+See https://github.com/rust-lang/cargo/issues/12207 for more information about the `--lock` flag.
+",
+        )
+        .run();
+    p.cargo("-Zscript generate-lockfile --lock")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--lock` flag can only be used with a script, whose lockfile is otherwise kept in its target directory
+",
+        )
+        .run();
+}
+
 #[cargo_test]
 fn cmd_metadata_with_embedded() {
     let script = ECHO_SCRIPT;
@@ -1372,3 +1481,225 @@ fn cmd_publish_with_embedded() {
         )
         .run();
 }
+
+#[cargo_test]
+fn mod_sibling_files() {
+    let script = r#"#!/usr/bin/env cargo
+---
+[package]
+edition = "2021"
+---
+
+mod util;
+pub(crate) mod nested;
+mod outer {
+    pub mod leaf;
+}
+
+fn main() {
+    println!("{} {} {}", util::NAME, nested::inner::NAME, outer::leaf::NAME);
+}"#;
+    let p = cargo_test_support::project()
+        .file("script.rs", script)
+        .file("util.rs", r#"pub const NAME: &str = "util";"#)
+        .file("nested/mod.rs", "pub mod inner;")
+        .file("nested/inner.rs", r#"pub const NAME: &str = "inner";"#)
+        .file("outer/leaf.rs", r#"pub const NAME: &str = "leaf";"#)
+        .build();
+
+    p.cargo("-Zscript -v script.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("util inner leaf\n")
+        .run();
+
+    // Changes to the modules are picked up.
+    p.change_file("util.rs", r#"pub const NAME: &str = "changed";"#);
+    p.cargo("-Zscript -v script.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("changed inner leaf\n")
+        .run();
+}
+
+#[cargo_test]
+fn share_target_dir() {
+    Package::new("dep", "1.0.0")
+        .file("src/lib.rs", "pub fn dep() {}")
+        .publish();
+    let script = r#"#!/usr/bin/env cargo
+---
+[package]
+edition = "2021"
+
+[dependencies]
+dep = "1.0.0"
+---
+
+fn main() {
+    dep::dep();
+}"#;
+    let p = cargo_test_support::project()
+        .file(".cargo/config.toml", "script.share-target-dir = true")
+        .file("first.rs", script)
+        .file("second.rs", script)
+        .build();
+
+    p.cargo("-Zscript -v first.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stderr_contains("[COMPILING] dep v1.0.0")
+        .with_stderr_contains("[RUNNING] `[ROOT]/home/.cargo/target/shared/[..]/debug/first[EXE]`")
+        .run();
+    p.cargo("-Zscript -v second.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stderr_does_not_contain("[COMPILING] dep v1.0.0")
+        .with_stderr_contains("[COMPILING] second v0.0.0 ([ROOT]/foo)")
+        .with_stderr_contains("[RUNNING] `[ROOT]/home/.cargo/target/shared/[..]/debug/second[EXE]`")
+        .run();
+
+    // Each script keeps its own lockfile.
+    assert!(!p.root().join("Cargo.lock").exists());
+    let shared = paths::home().join(".cargo/target/shared");
+    let shared = std::fs::read_dir(shared)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    assert!(!shared.join("Cargo.lock").exists());
+}
+
+#[cargo_test]
+fn share_target_dir_same_name() {
+    Package::new("dep", "1.0.0")
+        .file("src/lib.rs", "pub fn dep() {}")
+        .publish();
+    let script = |name| {
+        format!(
+            r#"#!/usr/bin/env cargo
+---
+[package]
+edition = "2021"
+
+[dependencies]
+dep = "1.0.0"
+---
+
+fn main() {{
+    dep::dep();
+    println!("{name}");
+}}"#
+        )
+    };
+    let p = cargo_test_support::project()
+        .file(".cargo/config.toml", "script.share-target-dir = true")
+        .file("a/script.rs", &script("a"))
+        .file("b/script.rs", &script("b"))
+        .build();
+
+    p.cargo("-Zscript a/script.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("a\n")
+        .run();
+    p.cargo("-Zscript b/script.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("b\n")
+        .run();
+    p.cargo("-Zscript -v a/script.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("a\n")
+        .with_stderr_does_not_contain("[COMPILING] [..]")
+        .run();
+    p.cargo("-Zscript -v b/script.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("b\n")
+        .with_stderr_does_not_contain("[COMPILING] [..]")
+        .run();
+
+    // Their binaries have the same name, they don't share a directory.
+    let shared = paths::home().join(".cargo/target/shared");
+    assert_eq!(std::fs::read_dir(shared).unwrap().count(), 2);
+}
+
+#[cargo_test]
+fn cmd_script_requires_flag() {
+    cargo_test_support::cargo_process("script --list")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo script` command is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+See https://github.com/rust-lang/cargo/issues/12207 for more information about the `cargo script` command.
+",
+        )
+        .run();
+
+    cargo_test_support::cargo_process("script --list")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `cargo script` command is unstable, pass `-Z script` to enable it
+See https://github.com/rust-lang/cargo/issues/12207 for more information about the `cargo script` command.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+#[cfg(unix)]
+fn cmd_script_leaves_name_to_plugin() {
+    let p = cargo_test_support::project()
+        .executable(
+            std::path::Path::new("path-test").join("cargo-script"),
+            "#!/bin/sh\necho external $@",
+        )
+        .build();
+
+    let mut path = path();
+    path.push(p.root().join("path-test"));
+    let path = std::env::join_paths(path.iter()).unwrap();
+
+    p.cargo("script foo.rs --bar")
+        .env("PATH", &path)
+        .with_stdout("external script foo.rs --bar")
+        .run();
+
+    p.cargo("-Zscript script --list")
+        .env("PATH", &path)
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout("")
+        .run();
+}
+
+#[cargo_test]
+fn cmd_script_list_and_clean() {
+    let p = cargo_test_support::project()
+        .file("kept.rs", ECHO_SCRIPT)
+        .file("deleted.rs", ECHO_SCRIPT)
+        .build();
+
+    p.cargo("-Zscript kept.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .run();
+    p.cargo("-Zscript deleted.rs")
+        .masquerade_as_nightly_cargo(&["script"])
+        .run();
+    std::fs::remove_file(p.root().join("deleted.rs")).unwrap();
+
+    p.cargo("-Zscript script --list")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout_contains("[ROOT]/home/.cargo/target/[..] ([..])")
+        .with_stdout_contains("    [ROOT]/foo/kept.rs")
+        .with_stdout_contains("    [ROOT]/foo/deleted.rs (deleted)")
+        .run();
+
+    p.cargo("-Zscript script --clean")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stderr("[REMOVED] [..] files, [..] total")
+        .run();
+
+    p.cargo("-Zscript script --list")
+        .masquerade_as_nightly_cargo(&["script"])
+        .with_stdout_contains("    [ROOT]/foo/kept.rs")
+        .with_stdout_does_not_contain("[..]deleted.rs[..]")
+        .run();
+}
@@ -0,0 +1,396 @@
+//! Tests for `cargo package --semver-check` and `cargo publish --semver-check`.
+
+use cargo_test_support::registry::{self, Package};
+use cargo_test_support::{basic_manifest, paths, project};
+
+#[cargo_test]
+fn gated() {
+    registry::init();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+                license = "MIT"
+                description = "foo"
+                homepage = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("package --semver-check")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--semver-check` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+    p.cargo("package --semver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--semver-check` flag is unstable, pass `-Z semver-check` to enable it
+",
+        )
+        .run();
+    p.cargo("publish --semver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--semver-check` flag is unstable, pass `-Z semver-check` to enable it
+",
+        )
+        .run();
+}
+
+#[cargo_test(nightly, reason = "rustdoc JSON output is unstable")]
+fn major_change_in_patch_release() {
+    registry::init();
+    Package::new("foo", "0.1.0")
+        .file(
+            "src/lib.rs",
+            "pub fn f() {} pub struct S { pub a: u8 } pub trait T {}",
+        )
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.1"
+                edition = "2015"
+                license = "MIT"
+                description = "foo"
+                homepage = "foo"
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            "pub fn f(_x: u8) {} pub struct S { pub a: u8, pub b: u8 } pub trait T { fn t(); }",
+        )
+        .build();
+
+    p.cargo("package --semver-check --no-verify -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[PACKAGING] foo v0.1.1 ([ROOT]/foo)
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] foo v0.1.0 (registry `dummy-registry`)
+[COMPARING] foo v0.1.1 ([ROOT]/foo) to v0.1.0
+[DOCUMENTING] foo v0.1.0
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[DOCUMENTING] foo v0.1.1 ([ROOT]/foo)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[ERROR] `foo v0.1.1` only allows minor changes after v0.1.0, but its public API has changes that need a major release:
+  major (struct-add-public-field-when-no-private): field `foo::S::b` was added to a struct with no private fields
+  major (trait-object-safety): `foo::T` is no longer dyn compatible
+  major (trait-new-item-no-default): trait item `foo::T::t` was added without a default
+  major (fn-change-arity): the number of parameters of `foo::f` changed
+help: set the version to 0.2.0 or later, and see https://doc.rust-lang.org/cargo/reference/semver.html for more information about each kind of change
+",
+        )
+        .run();
+}
+
+#[cargo_test(nightly, reason = "rustdoc JSON output is unstable")]
+fn minor_change_in_minor_release() {
+    registry::init();
+    Package::new("foo", "1.0.0")
+        .file("src/lib.rs", "pub fn f() {}")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "1.1.0"
+                edition = "2015"
+                license = "MIT"
+                description = "foo"
+                homepage = "foo"
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            "pub fn f() {} pub fn g() {} #[non_exhaustive] pub enum E { A }",
+        )
+        .build();
+
+    p.cargo("package --semver-check --no-verify -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_stderr(
+            "\
+[PACKAGING] foo v1.1.0 ([ROOT]/foo)
+[UPDATING] `dummy-registry` index
+[DOWNLOADING] crates ...
+[DOWNLOADED] foo v1.0.0 (registry `dummy-registry`)
+[COMPARING] foo v1.1.0 ([ROOT]/foo) to v1.0.0
+[DOCUMENTING] foo v1.0.0
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[DOCUMENTING] foo v1.1.0 ([ROOT]/foo)
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[NOTE] the public API of `foo` changed since v1.0.0:
+  minor (item-new): enum `foo::E` was added
+  minor (item-new): function `foo::g` was added
+[PACKAGED] 3 files, [..] ([..] compressed)
+",
+        )
+        .run();
+
+    // The baseline downloaded before is used offline.
+    p.cargo("package --semver-check --no-verify --offline -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_stderr(
+            "\
+[PACKAGING] foo v1.1.0 ([ROOT]/foo)
+[COMPARING] foo v1.1.0 ([ROOT]/foo) to v1.0.0
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[FINISHED] `dev` profile [unoptimized + debuginfo] target(s) in [..]
+[NOTE] the public API of `foo` changed since v1.0.0:
+  minor (item-new): enum `foo::E` was added
+  minor (item-new): function `foo::g` was added
+[PACKAGED] 3 files, [..] ([..] compressed)
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn nothing_published() {
+    registry::init();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+                license = "MIT"
+                description = "foo"
+                homepage = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "pub fn f() {}")
+        .build();
+
+    p.cargo("package --semver-check --no-verify -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_stderr(
+            "\
+[PACKAGING] foo v0.1.0 ([ROOT]/foo)
+[UPDATING] `dummy-registry` index
+[NOTE] skipping the SemVer check of `foo`, since no earlier version of it is published
+[PACKAGED] 3 files, [..] ([..] compressed)
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn nothing_cached_offline() {
+    registry::init();
+    Package::new("foo", "0.1.0")
+        .file("src/lib.rs", "pub fn f() {}")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.1"
+                edition = "2015"
+                license = "MIT"
+                description = "foo"
+                homepage = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("package --semver-check --no-verify --offline -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_stderr(
+            "\
+[PACKAGING] foo v0.1.1 ([ROOT]/foo)
+[NOTE] skipping the SemVer check of `foo`, since no earlier version of it is in the local registry cache
+[PACKAGED] 3 files, [..] ([..] compressed)
+",
+        )
+        .run();
+}
+
+#[cargo_test(nightly, reason = "rustdoc JSON output is unstable")]
+fn features_are_passed_through() {
+    registry::init();
+    Package::new("foo", "1.0.0")
+        .feature("extra", &[])
+        .file(
+            "src/lib.rs",
+            r#"#[cfg(feature = "extra")] pub fn extra() {}"#,
+        )
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "1.0.1"
+                edition = "2015"
+                license = "MIT"
+                description = "foo"
+                homepage = "foo"
+
+                [features]
+                extra = []
+                new = []
+            "#,
+        )
+        .file(
+            "src/lib.rs",
+            r#"#[cfg(feature = "extra")] pub fn extra() {}"#,
+        )
+        .build();
+
+    // `extra()` is only public with the feature, and `new` is ignored for
+    // the baseline, which doesn't have it.
+    p.cargo("package --semver-check --no-verify -Zsemver-check --features extra,new")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_stderr_contains("[COMPATIBLE] no changes to the public API since v1.0.0")
+        .run();
+    p.change_file("src/lib.rs", "");
+    p.cargo("package --semver-check --no-verify -Zsemver-check --features extra")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .with_status(101)
+        .with_stderr_contains("  major (item-remove): function `foo::extra` was removed")
+        .run();
+}
+
+#[cargo_test]
+fn required_version_with_stub_rustdoc() {
+    // Outputs the `api.json` next to the documented crate root as its JSON
+    // documentation, so that the versions can be compared on stable.
+    let rustdoc = project()
+        .at(paths::root().join("stub-rustdoc"))
+        .file("Cargo.toml", &basic_manifest("stub-rustdoc", "1.0.0"))
+        .file(
+            "src/main.rs",
+            r#"
+                use std::path::Path;
+
+                fn main() {
+                    let args: Vec<String> = std::env::args().skip(1).collect();
+                    if !args.iter().any(|arg| arg == "--output-format=json") {
+                        let status = std::process::Command::new("rustdoc")
+                            .args(&args)
+                            .status()
+                            .unwrap();
+                        std::process::exit(status.code().unwrap_or(1));
+                    }
+                    let value = |flag: &str| {
+                        let i = args.iter().position(|arg| arg == flag).unwrap();
+                        args[i + 1].clone()
+                    };
+                    let root = args.iter().find(|arg| arg.ends_with(".rs")).unwrap();
+                    let api = Path::new(root).with_file_name("api.json");
+                    let out = Path::new(&value("-o")).join(format!("{}.json", value("--crate-name")));
+                    std::fs::create_dir_all(out.parent().unwrap()).unwrap();
+                    std::fs::copy(api, out).unwrap();
+                }
+            "#,
+        )
+        .build();
+    rustdoc.cargo("build").run();
+
+    registry::init();
+    Package::new("foo", "1.0.0")
+        .file("src/lib.rs", "pub fn f() {}")
+        .file(
+            "src/api.json",
+            r#"{
+                "format_version": 40,
+                "root": 0,
+                "index": {
+                    "0": {"name": "foo", "visibility": "public", "inner": {"module": {"items": [1]}}},
+                    "1": {"name": "f", "visibility": "public", "inner": {"function": {}}}
+                }
+            }"#,
+        )
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "1.1.0"
+                edition = "2015"
+                license = "MIT"
+                description = "foo"
+                homepage = "foo"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "src/api.json",
+            r#"{
+                "format_version": 40,
+                "root": 0,
+                "index": {
+                    "0": {"name": "foo", "visibility": "public", "inner": {"module": {"items": []}}}
+                }
+            }"#,
+        )
+        .build();
+
+    p.cargo("package --semver-check --no-verify -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .env("RUSTDOC", rustdoc.bin("stub-rustdoc"))
+        .with_status(101)
+        .with_stderr_contains(
+            "\
+[ERROR] `foo v1.1.0` only allows minor changes after v1.0.0, but its public API has changes that need a major release:
+  major (item-remove): function `foo::f` was removed
+help: set the version to 2.0.0 or later, and see https://doc.rust-lang.org/cargo/reference/semver.html for more information about each kind of change
+",
+        )
+        .run();
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "2.0.0"
+            edition = "2015"
+            license = "MIT"
+            description = "foo"
+            homepage = "foo"
+        "#,
+    );
+    p.cargo("package --semver-check --no-verify -Zsemver-check")
+        .masquerade_as_nightly_cargo(&["semver-check"])
+        .env("RUSTDOC", rustdoc.bin("stub-rustdoc"))
+        .with_stderr_contains(
+            "\
+[NOTE] the public API of `foo` changed since v1.0.0:
+  major (item-remove): function `foo::f` was removed
+",
+        )
+        .run();
+}
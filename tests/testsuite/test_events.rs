@@ -0,0 +1,210 @@
+//! Tests for `-Ztest-events`.
+
+use cargo_test_support::{basic_manifest, project};
+
+#[cargo_test]
+fn not_enabled() {
+    let p = project()
+        .file("src/lib.rs", "#[test] fn passes() {}")
+        .build();
+
+    // Without the flag, libtest's output is passed through untouched.
+    p.cargo("test --message-format=json")
+        .with_stdout_contains("test passes ... ok")
+        .with_stdout_does_not_contain("[..]test-finished[..]")
+        .run();
+}
+
+#[cargo_test]
+fn unit_and_doc_tests() {
+    let p = project()
+        .file("Cargo.toml", &basic_manifest("foo", "0.1.0"))
+        .file(
+            "src/lib.rs",
+            r#"
+                /// ```
+                /// assert_eq!(foo::two(), 2);
+                /// ```
+                pub fn two() -> u32 { 2 }
+
+                #[test]
+                fn passes() {}
+
+                #[test]
+                fn fails() {
+                    println!("some output");
+                    panic!("oh no");
+                }
+
+                #[test]
+                #[ignore = "not today"]
+                fn skipped() {}
+            "#,
+        )
+        .build();
+
+    p.cargo("test --no-fail-fast --message-format=json -Ztest-events")
+        .masquerade_as_nightly_cargo(&["test-events"])
+        .with_status(101)
+        .with_stderr_contains("test passes ... ok")
+        .with_stdout_contains(r#"[..]"stdout":"some output\n[..]oh no[..]"#)
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "test-suite-started",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "kind": "test"
+                }
+
+                {
+                    "reason": "test-finished",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "name": "passes",
+                    "outcome": "ok"
+                }
+
+                {
+                    "reason": "test-finished",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "name": "skipped",
+                    "outcome": "ignored",
+                    "message": "not today"
+                }
+
+                {
+                    "reason": "test-finished",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "name": "fails",
+                    "outcome": "failed",
+                    "stdout": "{...}"
+                }
+
+                {
+                    "reason": "test-suite-finished",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "kind": "test",
+                    "success": false,
+                    "duration": "{...}",
+                    "summary": {
+                        "passed": 1,
+                        "failed": 1,
+                        "ignored": 1,
+                        "measured": 0,
+                        "filtered_out": 0
+                    }
+                }
+
+                {
+                    "reason": "test-finished",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "name": "src/lib.rs - two (line 2)",
+                    "outcome": "ok"
+                }
+
+                {
+                    "reason": "test-suite-finished",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "kind": "doctest",
+                    "success": true,
+                    "duration": "{...}",
+                    "summary": {
+                        "passed": 1,
+                        "failed": 0,
+                        "ignored": 0,
+                        "measured": 0,
+                        "filtered_out": 0
+                    }
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn no_harness() {
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+                edition = "2015"
+
+                [[test]]
+                name = "custom"
+                harness = false
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file(
+            "tests/custom.rs",
+            r#"fn main() { println!("custom output"); }"#,
+        )
+        .build();
+
+    p.cargo("test --test custom --message-format=json -Ztest-events")
+        .masquerade_as_nightly_cargo(&["test-events"])
+        .with_stderr_contains("custom output")
+        .with_json_contains_unordered(
+            r#"
+                {
+                    "reason": "test-suite-finished",
+                    "package_id": "path+file:///[..]/foo#0.1.0",
+                    "target": "{...}",
+                    "kind": "test",
+                    "success": true,
+                    "duration": "{...}"
+                }
+            "#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn without_json_warns() {
+    let p = project()
+        .file("src/lib.rs", "#[test] fn passes() {}")
+        .build();
+
+    p.cargo("test -Ztest-events")
+        .masquerade_as_nightly_cargo(&["test-events"])
+        .with_stderr_contains(
+            "[WARNING] `-Ztest-events` has no effect without `--message-format=json`",
+        )
+        .with_stdout_contains("test passes ... ok")
+        .run();
+}
+
+#[cargo_test]
+fn harness_format_rejected() {
+    let p = project()
+        .file("src/lib.rs", "#[test] fn passes() {}")
+        .build();
+
+    p.cargo("test --message-format=json -Ztest-events -- -Zunstable-options --format=json")
+        .masquerade_as_nightly_cargo(&["test-events"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the test harness argument `--format=json` cannot be used with `-Ztest-events`
+Test events are read from the harness's default output format.
+",
+        )
+        .run();
+
+    p.cargo("test --message-format=json -Ztest-events -- --quiet")
+        .masquerade_as_nightly_cargo(&["test-events"])
+        .with_status(101)
+        .with_stderr_contains(
+            "[ERROR] the test harness argument `--quiet` cannot be used with `-Ztest-events`",
+        )
+        .run();
+}
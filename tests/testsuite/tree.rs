@@ -2199,3 +2199,235 @@ foo v1.0.0 ([ROOT]/foo)
         )
         .run();
 }
+
+#[cargo_test]
+fn output_format_is_unstable() {
+    let p = make_simple_proj();
+
+    p.cargo("tree --output-format=dot")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--output-format` flag is unstable, pass `-Z unstable-options` to enable it
+",
+        )
+        .run();
+
+    p.cargo("tree --output-format=dot")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--output-format` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn output_format_dot() {
+    let p = make_simple_proj();
+
+    p.cargo("tree --output-format=dot -Zunstable-options -e normal,build --format={p}")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_stdout(
+            r#"digraph {
+    0 [label="foo v0.1.0 ([..]/foo)"];
+    1 [label="a v1.0.0"];
+    2 [label="c v1.0.0"];
+    3 [label="bdep v1.0.0"];
+    4 [label="b v1.0.0"];
+    0 -> 1;
+    0 -> 2;
+    0 -> 3 [label="build"];
+    1 -> 4;
+    3 -> 4;
+    4 -> 2;
+}
+"#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn output_format_json() {
+    let p = make_simple_proj();
+
+    p.cargo("tree --output-format=json -Zunstable-options --prune a")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+  "version": 1,
+  "inverted": false,
+  "roots": [0],
+  "nodes": [
+    {
+      "kind": "package",
+      "package_id": "path+file:///[..]/foo#0.1.0",
+      "name": "foo",
+      "version": "0.1.0",
+      "features": [],
+      "target": null
+    },
+    {
+      "kind": "package",
+      "package_id": "registry+https://github.com/rust-lang/crates.io-index#c@1.0.0",
+      "name": "c",
+      "version": "1.0.0",
+      "features": [],
+      "target": null
+    },
+    {
+      "kind": "package",
+      "package_id": "registry+https://github.com/rust-lang/crates.io-index#bdep@1.0.0",
+      "name": "bdep",
+      "version": "1.0.0",
+      "features": [],
+      "target": null
+    },
+    {
+      "kind": "package",
+      "package_id": "registry+https://github.com/rust-lang/crates.io-index#devdep@1.0.0",
+      "name": "devdep",
+      "version": "1.0.0",
+      "features": [],
+      "target": null
+    },
+    {
+      "kind": "package",
+      "package_id": "registry+https://github.com/rust-lang/crates.io-index#b@1.0.0",
+      "name": "b",
+      "version": "1.0.0",
+      "features": [],
+      "target": null
+    }
+  ],
+  "edges": [
+    { "from": 0, "to": 1, "kind": "normal" },
+    { "from": 0, "to": 2, "kind": "build" },
+    { "from": 0, "to": 3, "kind": "dev" },
+    { "from": 2, "to": 4, "kind": "normal" },
+    { "from": 3, "to": 4, "kind": "normal" },
+    { "from": 4, "to": 1, "kind": "normal" }
+  ]
+}
+"#,
+        )
+        .run();
+
+    // Inverting just changes the direction of the edges.
+    p.cargo("tree --output-format=json -Zunstable-options -e normal -i c")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+  "version": 1,
+  "inverted": true,
+  "roots": [0],
+  "nodes": [
+    {
+      "kind": "package",
+      "package_id": "registry+https://github.com/rust-lang/crates.io-index#c@1.0.0",
+      "name": "c",
+      "version": "1.0.0",
+      "features": [],
+      "target": null
+    },
+    {
+      "kind": "package",
+      "package_id": "registry+https://github.com/rust-lang/crates.io-index#b@1.0.0",
+      "name": "b",
+      "version": "1.0.0",
+      "features": [],
+      "target": null
+    },
+    {
+      "kind": "package",
+      "package_id": "path+file:///[..]/foo#0.1.0",
+      "name": "foo",
+      "version": "0.1.0",
+      "features": [],
+      "target": null
+    },
+    {
+      "kind": "package",
+      "package_id": "registry+https://github.com/rust-lang/crates.io-index#a@1.0.0",
+      "name": "a",
+      "version": "1.0.0",
+      "features": [],
+      "target": null
+    }
+  ],
+  "edges": [
+    { "from": 0, "to": 1, "kind": "normal" },
+    { "from": 0, "to": 2, "kind": "normal" },
+    { "from": 1, "to": 3, "kind": "normal" },
+    { "from": 3, "to": 2, "kind": "normal" }
+  ]
+}
+"#,
+        )
+        .run();
+}
+
+#[cargo_test]
+fn output_format_json_features() {
+    Package::new("optdep", "1.0.0")
+        .feature("default", &[])
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            optdep = { version = "1.0", optional = true }
+
+            [features]
+            default = ["optdep"]
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("tree --output-format=json -Zunstable-options -e features")
+        .masquerade_as_nightly_cargo(&["unstable-options"])
+        .with_json(
+            r#"
+{
+  "version": 1,
+  "inverted": false,
+  "roots": [0],
+  "nodes": [
+    {
+      "kind": "package",
+      "package_id": "path+file:///[..]/foo#0.1.0",
+      "name": "foo",
+      "version": "0.1.0",
+      "features": ["default", "optdep"],
+      "target": null
+    },
+    { "kind": "feature", "package": 2, "name": "default" },
+    {
+      "kind": "package",
+      "package_id": "registry+https://github.com/rust-lang/crates.io-index#optdep@1.0.0",
+      "name": "optdep",
+      "version": "1.0.0",
+      "features": ["default"],
+      "target": null
+    }
+  ],
+  "edges": [
+    { "from": 0, "to": 1, "kind": "normal" },
+    { "from": 1, "to": 2, "kind": "feature" }
+  ]
+}
+"#,
+        )
+        .run();
+}
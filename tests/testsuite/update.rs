@@ -1659,3 +1659,270 @@ fn update_with_missing_feature() {
         )
         .run();
 }
+
+#[cargo_test]
+fn explain_requires_flag() {
+    Package::new("dep", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            dep = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("update dep --explain")
+        .with_status(101)
+        .with_stderr(
+            "\
+[ERROR] the `--explain` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+",
+        )
+        .run();
+
+    p.cargo("update dep --explain")
+        .masquerade_as_nightly_cargo(&["update-explain"])
+        .with_status(101)
+        .with_stderr(
+            "[ERROR] the `--explain` flag is unstable, pass `-Z update-explain` to enable it",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn explain_requirements() {
+    Package::new("dep", "1.0.0").feature("extra", &[]).publish();
+    Package::new("middle", "0.1.0").dep("dep", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            dep = { version = "1.0", features = ["extra"] }
+            middle = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("dep", "1.1.0").feature("extra", &[]).publish();
+    Package::new("dep", "2.0.0").publish();
+
+    p.cargo("update dep --explain -Zupdate-explain")
+        .masquerade_as_nightly_cargo(&["update-explain"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+  Explaining dep v1.0.0, the latest version is v2.0.0
+[NOTE] v2.0.0 isn't selected because:
+  - `foo v0.1.0 ([ROOT]/foo)` requires `dep ^1.0`, through foo
+  - `foo v0.1.0 ([ROOT]/foo)` enables its feature `extra`, which it doesn't have, through foo
+  - `middle v0.1.0` requires `dep ^1.0`, through foo -> middle
+[NOTE] v1.1.0 is allowed by the requirements on it, run `cargo update dep` to select it
+",
+        )
+        .run();
+
+    // The lockfile is left untouched.
+    assert!(p
+        .read_lockfile()
+        .contains("name = \"dep\"\nversion = \"1.0.0\""));
+}
+
+#[cargo_test]
+fn explain_resolve_failure() {
+    Package::new("shared", "1.0.0").publish();
+    Package::new("shared", "1.1.0").publish();
+    Package::new("dep", "1.0.0").dep("shared", "1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            dep = "1.0"
+            shared = "=1.0.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("dep", "1.1.0")
+        .dep("shared", "=1.1.0")
+        .publish();
+
+    p.cargo("update dep --explain -Zupdate-explain")
+        .masquerade_as_nightly_cargo(&["update-explain"])
+        .with_stderr_contains("  Explaining dep v1.0.0, the latest version is v1.1.0")
+        .with_stderr_contains("[NOTE] v1.1.0 is allowed by the requirements on it, but:")
+        .with_stderr_contains("  failed to select a version for `shared`.")
+        .run();
+}
+
+#[cargo_test]
+fn explain_yanked_and_prerelease() {
+    Package::new("dep", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            dep = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("dep", "1.1.0").publish();
+    Package::new("dep", "1.2.0").yanked(true).publish();
+    Package::new("dep", "1.3.0-alpha.1").publish();
+
+    p.cargo("update dep --explain -Zupdate-explain")
+        .masquerade_as_nightly_cargo(&["update-explain"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+  Explaining dep v1.0.0, the latest version is v1.2.0 (yanked)
+[NOTE] v1.2.0 isn't selected because:
+  - it was yanked by its author
+[NOTE] v1.1.0 is allowed by the requirements on it, run `cargo update dep` to select it
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn explain_prerelease_allowed_by_requirement() {
+    Package::new("dep", "1.0.0-alpha.1").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            dep = "1.0.0-alpha.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("dep", "1.0.0-alpha.2").publish();
+
+    p.cargo("update dep --explain -Zupdate-explain")
+        .masquerade_as_nightly_cargo(&["update-explain"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+  Explaining dep v1.0.0-alpha.1, the latest version is v1.0.0-alpha.2
+[NOTE] v1.0.0-alpha.2 is allowed by the requirements on it, run `cargo update dep` to select it
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn explain_rust_version_of_dependent() {
+    Package::new("dep", "1.0.0").publish();
+    Package::new("middle", "0.1.0")
+        .rust_version("1.50")
+        .dep("dep", "1.0")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            middle = "0.1"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    Package::new("dep", "1.1.0").rust_version("1.60").publish();
+
+    p.cargo("update dep --explain -Zupdate-explain -Zmsrv-policy")
+        .env(
+            "CARGO_RESOLVER_SOMETHING_LIKE_PRECEDENCE",
+            "something-like-rust-version",
+        )
+        .masquerade_as_nightly_cargo(&["update-explain", "msrv-policy"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+  Explaining dep v1.0.0, the latest version is v1.1.0
+[NOTE] v1.1.0 isn't selected because:
+  - it requires Rust 1.60, which is newer than the rust-version of `middle` (1.50)
+[NOTE] `dep v1.0.0` is the latest version allowed by the requirements on it
+",
+        )
+        .run();
+}
+
+#[cargo_test]
+fn explain_latest() {
+    Package::new("dep", "1.0.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            edition = "2015"
+
+            [dependencies]
+            dep = "1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+    p.cargo("generate-lockfile").run();
+
+    p.cargo("update dep --explain -Zupdate-explain")
+        .masquerade_as_nightly_cargo(&["update-explain"])
+        .with_stderr(
+            "\
+[UPDATING] `dummy-registry` index
+  Explaining dep v1.0.0
+[NOTE] `dep v1.0.0` is the latest version of `dep`
+",
+        )
+        .run();
+}
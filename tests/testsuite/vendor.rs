@@ -774,8 +774,6 @@ fn git_duplicate() {
 [UPDATING] [..]
 [UPDATING] [..]
 [LOCKING] 4 packages to latest compatible versions
-[DOWNLOADING] [..]
-[DOWNLOADED] [..]
 error: failed to sync
 
 Caused by:
@@ -1156,3 +1154,320 @@ fn vendor_crate_with_ws_inherit() {
         .with_stderr_contains("[..]foo/vendor/bar/src/lib.rs[..]")
         .run();
 }
+
+#[cargo_test]
+fn vendor_sync_gated() {
+    Package::new("log", "0.3.5").publish();
+    Package::new("bar", "0.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                log = "0.3.5"
+                bar = "0.1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    for flag in ["--incremental", "--per-source", "--verify"] {
+        p.cargo(&format!("vendor --respect-source-config {flag}"))
+            .with_stderr(&format!(
+                "\
+[ERROR] the `{flag}` flag is unstable, and only available on the nightly channel of Cargo, but this is the `stable` channel
+See https://doc.rust-lang.org/book/appendix-07-nightly-rust.html for more information about Rust release channels.
+"
+            ))
+            .with_status(101)
+            .run();
+        p.cargo(&format!("vendor --respect-source-config {flag}"))
+            .masquerade_as_nightly_cargo(&["vendor-sync"])
+            .with_stderr(&format!(
+                "[ERROR] the `{flag}` flag is unstable, pass `-Z vendor-sync` to enable it"
+            ))
+            .with_status(101)
+            .run();
+    }
+}
+
+#[cargo_test]
+fn incremental_only_copies_changed_crates() {
+    Package::new("log", "0.3.5").publish();
+    Package::new("bar", "0.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                log = "0.3.5"
+                bar = "0.1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("vendor --respect-source-config --incremental -Zvendor-sync")
+        .masquerade_as_nightly_cargo(&["vendor-sync"])
+        .with_stderr_contains("[VENDORING] bar v0.1.0 [..]")
+        .with_stderr_contains("[VENDORING] log v0.3.5 [..]")
+        .run();
+    let manifest = p.read_file("vendor/.vendor-manifest.json");
+    assert!(manifest.contains(r#""name": "log""#));
+    assert!(manifest.contains(r#""path": "log""#));
+    assert!(
+        manifest.contains(r#""source": "registry+https://github.com/rust-lang/crates.io-index""#)
+    );
+
+    // Left alone by the next run, since `bar` doesn't change.
+    p.change_file("vendor/bar/marker", "");
+    Package::new("log", "0.3.6").publish();
+    p.cargo("update log").run();
+
+    p.cargo("vendor --respect-source-config --incremental -Zvendor-sync")
+        .masquerade_as_nightly_cargo(&["vendor-sync"])
+        .with_stderr(
+            "\
+[DOWNLOADING] crates ...
+[DOWNLOADED] log v0.3.6 (registry `dummy-registry`)
+[VENDORING] log v0.3.6 ([..]) to [ROOT]/foo/vendor/log
+To use vendored sources, add this to your .cargo/config.toml for this project:
+
+",
+        )
+        .run();
+    assert!(p.root().join("vendor/bar/marker").exists());
+    assert!(p
+        .read_file("vendor/log/Cargo.toml")
+        .contains(r#"version = "0.3.6""#));
+    assert!(p
+        .read_file("vendor/.vendor-manifest.json")
+        .contains(r#""version": "0.3.6""#));
+
+    p.change_file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [dependencies]
+            log = "0.3.5"
+        "#,
+    );
+    p.cargo("vendor --respect-source-config --incremental -Zvendor-sync")
+        .masquerade_as_nightly_cargo(&["vendor-sync"])
+        .with_stderr(
+            "\
+[REMOVING] [ROOT]/foo/vendor/bar
+To use vendored sources, add this to your .cargo/config.toml for this project:
+
+",
+        )
+        .run();
+    assert!(!p.root().join("vendor/bar").exists());
+    assert!(!p.read_file("vendor/.vendor-manifest.json").contains("bar"));
+
+    add_vendor_config(&p);
+    p.cargo("check").run();
+}
+
+#[cargo_test]
+fn incremental_records_license_of_crates_vendored_before() {
+    Package::new("log", "0.3.5")
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "log"
+                version = "0.3.5"
+                license = "MIT OR Apache-2.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                log = "0.3.5"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    // Vendored before the manifest existed, `log` is recognized by its
+    // checksum, and its license read from its `Cargo.toml`.
+    p.cargo("vendor --respect-source-config").run();
+    p.cargo("vendor --respect-source-config --incremental -Zvendor-sync")
+        .masquerade_as_nightly_cargo(&["vendor-sync"])
+        .with_stderr_does_not_contain("[VENDORING][..]")
+        .run();
+    assert!(p
+        .read_file("vendor/.vendor-manifest.json")
+        .contains(r#""license": "MIT OR Apache-2.0""#));
+}
+
+#[cargo_test]
+fn per_source_crate_named_git() {
+    Package::new("git", "0.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                git = "0.1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    let output = p
+        .cargo("vendor --respect-source-config --per-source -Zvendor-sync")
+        .masquerade_as_nightly_cargo(&["vendor-sync"])
+        .exec_with_output()
+        .unwrap();
+    assert!(p.root().join("vendor/git-0.1.0/Cargo.toml").exists());
+    assert!(!p.root().join("vendor/git").exists());
+
+    p.change_file(
+        ".cargo/config.toml",
+        &String::from_utf8(output.stdout).unwrap(),
+    );
+    p.cargo("check").run();
+}
+
+#[cargo_test]
+fn per_source_layout() {
+    let git = git::new("a", |p| {
+        p.file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "a"
+                version = "0.1.0"
+
+                [dependencies]
+                b = { path = 'b' }
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .file("b/Cargo.toml", &basic_lib_manifest("b"))
+        .file("b/src/lib.rs", "")
+    });
+
+    let p = project()
+        .file(
+            "Cargo.toml",
+            &format!(
+                r#"
+                    [package]
+                    name = "foo"
+                    version = "0.1.0"
+
+                    [dependencies]
+                    a = {{ git = '{}' }}
+                    b = '0.5.0'
+                "#,
+                git.url()
+            ),
+        )
+        .file("src/lib.rs", "")
+        .build();
+    Package::new("b", "0.5.0").publish();
+
+    // The two `b v0.5.0` don't conflict, being in different directory sources.
+    let output = p
+        .cargo("vendor --respect-source-config --per-source -Zvendor-sync")
+        .masquerade_as_nightly_cargo(&["vendor-sync"])
+        .exec_with_output()
+        .unwrap();
+    let config = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        config,
+        format!(
+            r#"[source.crates-io]
+replace-with = "vendored-sources"
+
+[source."git+{url}"]
+git = "{url}"
+replace-with = "vendored-git-sources"
+
+[source.vendored-git-sources]
+directory = "vendor/git"
+
+[source.vendored-sources]
+directory = "vendor"
+"#,
+            url = git.url()
+        )
+    );
+    assert!(p.root().join("vendor/git/a/Cargo.toml").exists());
+    assert!(p.root().join("vendor/git/b/Cargo.toml").exists());
+    assert!(p.root().join("vendor/b/Cargo.toml").exists());
+    assert!(!p.root().join("vendor/a").exists());
+
+    p.change_file(".cargo/config.toml", &config);
+    p.cargo("check").run();
+}
+
+#[cargo_test]
+fn verify() {
+    Package::new("log", "0.3.5").publish();
+    Package::new("bar", "0.1.0").publish();
+    let p = project()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                name = "foo"
+                version = "0.1.0"
+
+                [dependencies]
+                log = "0.3.5"
+                bar = "0.1.0"
+            "#,
+        )
+        .file("src/lib.rs", "")
+        .build();
+
+    p.cargo("vendor --respect-source-config").run();
+    p.cargo("vendor --verify --offline -Zvendor-sync")
+        .masquerade_as_nightly_cargo(&["vendor-sync"])
+        .with_stderr("[VERIFIED] 2 vendored packages in `vendor`")
+        .run();
+
+    p.change_file("vendor/log/src/lib.rs", "// changed");
+    fs::remove_dir_all(p.root().join("vendor/bar")).unwrap();
+    p.change_file("vendor/extra/Cargo.toml", &basic_manifest("extra", "1.0.0"));
+    p.cargo("vendor --verify --offline -Zvendor-sync")
+        .masquerade_as_nightly_cargo(&["vendor-sync"])
+        .with_stderr(
+            "\
+[WARNING] `extra v1.0.0` is vendored but not in `Cargo.lock`
+[ERROR] vendor directory `vendor` doesn't match `Cargo.lock`:
+  `bar v0.1.0` is not vendored
+  `vendor/log/src/lib.rs` was modified
+[HELP] run `cargo vendor` to update it
+",
+        )
+        .with_status(101)
+        .run();
+}